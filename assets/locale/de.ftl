@@ -0,0 +1,117 @@
+# german
+language-name = Deutsch
+
+settings-title = EINSTELLUNGEN - W/S, A/D zum Ändern, Esc zum Verlassen
+settings-fullscreen = Vollbild
+settings-vsync = VSync
+settings-resolution = Auflösung
+settings-integer-scaling = ganzzahlige Skalierung
+settings-volume = Lautstärke
+settings-shadows = Schatten
+settings-bloom = Leuchten
+settings-rumble = Vibration
+settings-language = Sprache
+settings-quit = beenden
+settings-on = an
+settings-off = aus
+settings-low = niedrig
+settings-high = hoch
+settings-native = nativ
+
+keys-move = WASD - bewegen
+keys-shoot = Pfeile - schießen
+keys-sprint = Shift - sprinten
+keys-dash = Strg - ausweichen
+keys-interact = F - benutzen
+keys-items = Q/E - Gegenstand wechseln, Leertaste - benutzen
+keys-inventory = Tab - Inventar
+keys-console = ` - Konsole
+
+shop-title = LADEN - hoch/runter, F zum Kaufen, Tab zum Verlassen
+shop-cant-afford = zu teuer
+shop-bought = { $item } gekauft
+shop-inventory-full = Inventar ist voll
+shop-not-for-sale = { $item } ist nicht zu verkaufen
+
+container-chest = TRUHE
+container-corpse = ÜBERRESTE
+container-inventory = INVENTAR
+container-hint = Gegenstand anklicken zum Verschieben, Tab zum Schließen
+
+equip-armor = Rüstung
+equip-boots = Stiefel
+equip-trinket = Schmuck
+stat-max-hp = max. LP { $value }
+stat-speed = Tempo { $value }
+stat-light = Licht { $value }
+stat-damage = Schaden { $value }
+
+rarity-common = gewöhnlich
+rarity-uncommon = ungewöhnlich
+rarity-rare = selten
+rarity-epic = episch
+item-cooldown = { $seconds } s zwischen Benutzungen
+item-stack = stapelbar bis { $count }
+item-perfectly-generic-item = vollkommen gewöhnlich
+item-test-item = zum Testen, tut nichts
+item-torch = leuchtet bis sie abbrennt, Wasser löscht sie
+item-chemlight = kaltes Leuchten, ein paar Mal knickbar
+item-flashlight = ein Lichtkegel, wohin du zielst
+item-sword = schlägt nach allem in der Nähe
+item-pistol = geht nie leer
+item-shotgun = eine Schrotladung aus der Nähe
+item-automatic = feuert, solange du drückst
+item-bouncer = Schüsse prallen von Wänden ab
+item-flamer = setzt alles in Brand
+item-piercer = Schüsse gehen durch Gegner
+item-launcher = explodiert beim Aufprall
+item-armor = mehr LP, etwas langsamer
+item-boots = schneller laufen
+item-lantern-charm = Licht reicht weiter
+item-war-charm = härter zuschlagen, weniger LP
+
+cutscene-cellar-stirs = Hinter der Kellertür regt sich etwas.
+
+achievement-unlocked = Erfolg freigeschaltet: { $name }
+achievement-first-blood = Erstes Blut
+achievement-exterminator = Kammerjäger
+achievement-trigger-happy = Schießwütig
+achievement-wanderer = Wanderer
+achievement-handy = Geschickt
+
+toast-item-acquired = Gegenstand erhalten: { $name }
+toast-assets-reloaded = Assets neu geladen
+toast-mods-restart = Mods ändern sich nach einem Neustart
+toast-saved = { $path } gespeichert
+
+saves-title = SPIEL LADEN - hoch/runter, F zum Laden, Tab zum Verlassen
+saves-slot = Platz { $slot }
+saves-empty = leer
+saves-autosave = Autospeicherung
+toast-game-saved = in { $slot } gespeichert
+toast-game-loaded = { $slot } geladen
+toast-checkpoint = Kontrollpunkt erreicht
+toast-respawned = zurück am letzten Kontrollpunkt
+
+editor-palette = PALETTE - Q/E
+editor-tile = Feld
+editor-hint = EDITOR - WASD Kamera, Klick zum Setzen, Rechtsklick zum Löschen, Umschalt+Ziehen zum Verschieben, Strg+Ziehen zum Auswählen, Strg+C/X/V kopieren/ausschneiden/stempeln, Pfeile ändern Eigenschaften, Strg+Z/Y rückgängig/wiederholen, F7 zum Verlassen
+
+lobby-title = KOOP - hoch/runter, Enter zum Auswählen
+lobby-play = Spielen
+lobby-host = Spiel hosten
+lobby-join = Beitreten
+lobby-hosting = Host auf Port { $port }
+lobby-connecting = Verbinde mit { $address }...
+lobby-connected = Verbunden
+lobby-error = Fehlgeschlagen: { $error }
+lobby-partner-joined = Ein Partner ist beigetreten
+lobby-partner-left = Dein Partner hat das Spiel verlassen
+lobby-host-left = Der Host hat das Spiel verlassen
+lobby-connection-lost = Verbindung verloren
+lobby-wrong-version = Der Host hat eine andere Version ({ $host })
+lobby-wrong-content = Der Host hat andere Mods oder Assets
+lobby-full = Der Host hat schon einen Partner
+lobby-no-answer = Keine Antwort vom Host
+
+loading = lädt
@@ -0,0 +1,117 @@
+# english, the fallback for every other language
+language-name = English
+
+settings-title = SETTINGS - W/S, A/D to change, esc to leave
+settings-fullscreen = fullscreen
+settings-vsync = vsync
+settings-resolution = resolution
+settings-integer-scaling = integer scaling
+settings-volume = volume
+settings-shadows = shadows
+settings-bloom = bloom
+settings-rumble = rumble
+settings-language = language
+settings-quit = quit
+settings-on = on
+settings-off = off
+settings-low = low
+settings-high = high
+settings-native = native
+
+keys-move = WASD - move
+keys-shoot = arrows - shoot
+keys-sprint = shift - sprint
+keys-dash = ctrl - dash
+keys-interact = F - interact
+keys-items = Q/E - cycle items, space - use
+keys-inventory = tab - inventory
+keys-console = ` - console
+
+shop-title = SHOP - up/down, F to buy, tab to leave
+shop-cant-afford = can't afford that
+shop-bought = bought { $item }
+shop-inventory-full = inventory is full
+shop-not-for-sale = { $item } isn't for sale
+
+container-chest = CHEST
+container-corpse = REMAINS
+container-inventory = INVENTORY
+container-hint = click an item to move it, tab to close
+
+equip-armor = armor
+equip-boots = boots
+equip-trinket = trinket
+stat-max-hp = max hp { $value }
+stat-speed = speed { $value }
+stat-light = light { $value }
+stat-damage = damage { $value }
+
+rarity-common = common
+rarity-uncommon = uncommon
+rarity-rare = rare
+rarity-epic = epic
+item-cooldown = { $seconds }s between uses
+item-stack = stacks up to { $count }
+item-perfectly-generic-item = it's perfectly generic
+item-test-item = for testing, does nothing
+item-torch = lights the way until it burns down, water puts it out
+item-chemlight = a cold glow, a few cracks in each
+item-flashlight = a cone of light where you aim
+item-sword = swings at whatever is close
+item-pistol = never runs out
+item-shotgun = a spread of pellets up close
+item-automatic = fires as fast as you hold it
+item-bouncer = shots bounce off walls
+item-flamer = sets things on fire
+item-piercer = shots go through enemies
+item-launcher = explodes on impact
+item-armor = more hp, a little slower
+item-boots = walk faster
+item-lantern-charm = lights reach further
+item-war-charm = hit harder, less hp
+
+cutscene-cellar-stirs = Something stirs behind the cellar door.
+
+achievement-unlocked = achievement unlocked: { $name }
+achievement-first-blood = First Blood
+achievement-exterminator = Exterminator
+achievement-trigger-happy = Trigger Happy
+achievement-wanderer = Wanderer
+achievement-handy = Handy
+
+toast-item-acquired = item acquired: { $name }
+toast-assets-reloaded = assets reloaded
+toast-mods-restart = mods change after a restart
+toast-saved = saved { $path }
+
+saves-title = LOAD GAME - up/down, F to load, tab to leave
+saves-slot = slot { $slot }
+saves-empty = empty
+saves-autosave = autosave
+toast-game-saved = saved to { $slot }
+toast-game-loaded = loaded { $slot }
+toast-checkpoint = checkpoint reached
+toast-respawned = back at the last checkpoint
+
+editor-palette = PALETTE - Q/E
+editor-tile = tile
+editor-hint = EDITOR - WASD camera, click to place, right click to erase, shift+drag to move, ctrl+drag to select, ctrl+C/X/V copy/cut/stamp, arrows edit properties, ctrl+Z/Y undo/redo, F7 to leave
+
+lobby-title = CO-OP - up/down, enter to pick
+lobby-play = play
+lobby-host = host a game
+lobby-join = join
+lobby-hosting = hosting on port { $port }
+lobby-connecting = connecting to { $address }...
+lobby-connected = connected
+lobby-error = failed: { $error }
+lobby-partner-joined = a partner joined
+lobby-partner-left = your partner left
+lobby-host-left = the host left the game
+lobby-connection-lost = connection lost
+lobby-wrong-version = the host runs another version ({ $host })
+lobby-wrong-content = the host has other mods or assets
+lobby-full = the host already has a partner
+lobby-no-answer = no answer from the host
+
+loading = loading
@@ -0,0 +1,157 @@
+use ecs::{Resource, With, World};
+use rand::Rng;
+use sdl2::pixels::Color;
+
+use crate::{
+    components::{AmbienceZone, Player, Pos},
+    math,
+    rng::GameRng,
+    tween::Ease,
+};
+
+const ZONE_TRANSITION_TICKS: u32 = 90;
+const LIGHTNING_CHANCE: f64 = 1. / 400.;
+const LIGHTNING_TICKS: u32 = 12;
+
+#[derive(Clone, Copy)]
+pub struct DayNightCycle {
+    pub period: u32, // ticks
+    pub tick: u32,
+    pub day: Color,
+    pub night: Color,
+}
+
+#[derive(Resource)]
+pub struct AmbientLight {
+    // used outside of any AmbienceZone
+    pub default_color: Color,
+    pub day_night: Option<DayNightCycle>,
+    pub is_stormy: bool,
+    color: Color,
+    from: Color,
+    target: Color,
+    ease: Ease,
+    duration: u32,
+    ticks_left: u32,
+    flash: Color,
+    flash_duration: u32,
+    flash_ticks_left: u32,
+}
+
+impl AmbientLight {
+    pub fn new(color: Color) -> Self {
+        AmbientLight {
+            default_color: color,
+            day_night: None,
+            is_stormy: false,
+            color,
+            from: color,
+            target: color,
+            ease: Ease::Linear,
+            duration: 0,
+            ticks_left: 0,
+            flash: Color::RGB(0, 0, 0),
+            flash_duration: 0,
+            flash_ticks_left: 0,
+        }
+    }
+
+    pub fn transition_to(&mut self, target: Color, duration: u32, ease: Ease) {
+        if self.target == target {
+            return;
+        }
+
+        self.from = self.color;
+        self.target = target;
+        self.ease = ease;
+        self.duration = duration;
+        self.ticks_left = duration;
+        if duration == 0 {
+            self.color = target;
+        }
+    }
+
+    // added on top of the ambient color, fading out over duration ticks
+    pub fn flash(&mut self, color: Color, duration: u32) {
+        self.flash = color;
+        self.flash_duration = duration;
+        self.flash_ticks_left = duration;
+    }
+
+    pub fn current(&self) -> Color {
+        let mut color = self.color;
+
+        if let Some(cycle) = &self.day_night {
+            // noon at tick 0, midnight half a period later
+            let t = (1.
+                - math::cos(cycle.tick as f32 / cycle.period as f32 * std::f32::consts::TAU))
+                / 2.;
+            let daylight = lerp_color(cycle.day, cycle.night, t);
+            color = Color::RGB(
+                (color.r as u16 * daylight.r as u16 / 255) as u8,
+                (color.g as u16 * daylight.g as u16 / 255) as u8,
+                (color.b as u16 * daylight.b as u16 / 255) as u8,
+            );
+        }
+
+        if self.flash_ticks_left > 0 {
+            let t = self.flash_ticks_left as f32 / self.flash_duration as f32;
+            color = Color::RGB(
+                color.r.saturating_add((self.flash.r as f32 * t) as u8),
+                color.g.saturating_add((self.flash.g as f32 * t) as u8),
+                color.b.saturating_add((self.flash.b as f32 * t) as u8),
+            );
+        }
+
+        color
+    }
+
+    // the fades and the day, a job of its own after update picks the target
+    pub fn tick(&mut self) {
+        if self.ticks_left > 0 {
+            self.ticks_left -= 1;
+            let t = 1. - self.ticks_left as f32 / self.duration as f32;
+            self.color = lerp_color(self.from, self.target, self.ease.apply(t));
+        }
+
+        if let Some(cycle) = &mut self.day_night {
+            cycle.tick = (cycle.tick + 1) % cycle.period.max(1);
+        }
+
+        self.flash_ticks_left = self.flash_ticks_left.saturating_sub(1);
+    }
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+    Color::RGB(lerp(a.r, b.r), lerp(a.g, b.g), lerp(a.b, b.b))
+}
+
+pub fn update(world: &World) {
+    let ambient = world.resource_mut::<AmbientLight>().unwrap();
+    let mut player_pos = Pos::zero();
+    world.run(|pos: &Pos, _: With<Player>| {
+        player_pos = *pos;
+    });
+
+    let mut zone_color = None;
+    let mut is_stormy = false;
+    world.run(|zone: &AmbienceZone, pos: &Pos| {
+        if zone_color.is_none() && zone.contains(pos, &player_pos) {
+            zone_color = Some(zone.color);
+            is_stormy = zone.is_stormy;
+        }
+    });
+
+    ambient.transition_to(
+        zone_color.unwrap_or(ambient.default_color),
+        ZONE_TRANSITION_TICKS,
+        Ease::InOut,
+    );
+    ambient.is_stormy = is_stormy;
+
+    let rng = world.resource_mut::<GameRng>().unwrap();
+    if ambient.is_stormy && rng.gen_bool(LIGHTNING_CHANCE) {
+        ambient.flash(Color::RGB(180, 180, 220), LIGHTNING_TICKS);
+    }
+}
@@ -0,0 +1,232 @@
+// data driven animation switching. a graph is a const list of states, each with an
+// animation and the transitions out of it, the first state is the base state. systems
+// only fill in the parameters and the graph picks the animation and which way it faces
+//
+// directional graphs look for variants of each state's animation named after where the
+// entity faces, "player_walk_up", "player_walk_down_left" and so on. a missing variant
+// falls back to the nearest one that exists and then to the plain animation, flipped
+// to face left or right
+
+use ecs::{Component, World};
+
+use crate::{animation, components::AnimatedSprite, math::Vec2, AnimationRepository};
+
+// below this a direction doesn't turn the sprite around
+const FACING_THRESHOLD: f32 = 0.01;
+
+// variant suffixes to try for each Direction, a diagonal falls back to its two neighbours
+const SUFFIXES: [&[&str]; 8] = [
+    &["right"],
+    &["down_right", "right", "down"],
+    &["down"],
+    &["down_left", "left", "down"],
+    &["left"],
+    &["up_left", "left", "up"],
+    &["up"],
+    &["up_right", "right", "up"],
+];
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Directions {
+    // left and right by flipping
+    Two,
+    Four,
+    Eight,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub enum Cond {
+    Moving,
+    Still,
+    Firing,
+    SpeedAbove(f32),
+    SpeedBelow(f32),
+    // the state's animation played through once
+    Finished,
+}
+
+pub struct Transition {
+    pub to: &'static str,
+    pub when: Cond,
+}
+
+pub struct AnimState {
+    pub name: &'static str,
+    pub anim: &'static str,
+    // ignored by timed animations
+    pub ticks_per_frame: u32,
+    // plays through once and goes back to the base state
+    pub one_shot: bool,
+    // the first one that holds wins
+    pub transitions: &'static [Transition],
+}
+
+pub struct AnimGraphDef {
+    pub states: &'static [AnimState],
+    pub directions: Directions,
+}
+
+impl AnimGraphDef {
+    fn find(&self, name: &str) -> usize {
+        match self.states.iter().position(|s| s.name == name) {
+            Some(idx) => idx,
+            None => panic!("no anim state {}", name),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct AnimParams {
+    pub is_moving: bool,
+    pub is_firing: bool,
+    pub speed: f32,
+    pub move_dir: Vec2<f32>,
+    // wins over move_dir while it's not zero
+    pub aim_dir: Vec2<f32>,
+}
+
+#[derive(Component)]
+pub struct AnimGraph {
+    def: &'static AnimGraphDef,
+    state: usize,
+    // set when the state changes, the sprite is switched on the next update
+    entered: bool,
+    state_ticks: u32,
+    // ticks for one play through of the state's animation
+    state_len: u32,
+    // the last direction that was big enough to count
+    facing: Vec2<f32>,
+    // the first suffix tried for facing, and whether any variant was found
+    suffix: &'static str,
+    has_variant: bool,
+    pub params: AnimParams,
+}
+
+impl AnimGraph {
+    pub fn new(def: &'static AnimGraphDef) -> Self {
+        AnimGraph {
+            def,
+            state: 0,
+            entered: true,
+            state_ticks: 0,
+            state_len: 0,
+            // the way the art faces
+            facing: Vec2::new(-1., 0.),
+            suffix: "",
+            has_variant: false,
+            params: AnimParams {
+                is_moving: false,
+                is_firing: false,
+                speed: 0.,
+                move_dir: Vec2::zero(),
+                aim_dir: Vec2::zero(),
+            },
+        }
+    }
+
+    // jumps straight to the state, for one shots
+    pub fn play(&mut self, state: &'static str) {
+        self.state = self.def.find(state);
+        self.entered = true;
+    }
+
+    fn holds(&self, cond: Cond) -> bool {
+        let params = &self.params;
+        match cond {
+            Cond::Moving => params.is_moving,
+            Cond::Still => !params.is_moving,
+            Cond::Firing => params.is_firing,
+            Cond::SpeedAbove(speed) => params.speed > speed,
+            Cond::SpeedBelow(speed) => params.speed < speed,
+            Cond::Finished => self.state_ticks >= self.state_len,
+        }
+    }
+}
+
+fn suffixes(facing: Vec2<f32>, directions: Directions) -> &'static [&'static str] {
+    let direction = match directions {
+        Directions::Two => return &[],
+        Directions::Four => facing.to_direction4(),
+        Directions::Eight => facing.to_direction8(),
+    };
+    SUFFIXES[direction as usize]
+}
+
+fn play_length(animations: &AnimationRepository, sprite: &AnimatedSprite) -> u32 {
+    let anim = sprite.anim();
+    (0..animations.get_frames(anim).len() as u32)
+        .map(|frame| {
+            animations
+                .frame_ticks(anim, frame)
+                .unwrap_or(sprite.ticks_per_frame)
+                .max(1)
+        })
+        .sum()
+}
+
+pub fn update(world: &World) {
+    let animations = world.resource::<AnimationRepository>().unwrap();
+    world.run(|graph: &mut AnimGraph, sprite: &mut AnimatedSprite| {
+        if !graph.entered {
+            graph.state_ticks += 1;
+            let state = &graph.def.states[graph.state];
+            let next = if state.one_shot {
+                graph.holds(Cond::Finished).then_some(0)
+            } else {
+                state
+                    .transitions
+                    .iter()
+                    .find(|t| graph.holds(t.when))
+                    .map(|t| graph.def.find(t.to))
+            };
+            if let Some(next) = next {
+                graph.state = next;
+                graph.entered = true;
+            }
+        }
+
+        let params = &graph.params;
+        if params.aim_dir.magnitude() > FACING_THRESHOLD {
+            graph.facing = params.aim_dir;
+        } else if params.move_dir.magnitude() > FACING_THRESHOLD {
+            graph.facing = params.move_dir;
+        }
+        let suffixes = suffixes(graph.facing, graph.def.directions);
+        let suffix = suffixes.first().copied().unwrap_or("");
+
+        if graph.entered || suffix != graph.suffix {
+            let state = &graph.def.states[graph.state];
+            let variant = suffixes
+                .iter()
+                .find_map(|suffix| animations.get(&format!("{}_{}", state.anim, suffix)));
+            let anim = variant.unwrap_or_else(|| animation(world, state.anim));
+            graph.suffix = suffix;
+            graph.has_variant = variant.is_some();
+
+            // turning keeps the frame, a new state starts over
+            let frame = sprite.frame;
+            sprite.switch_anim(anim, state.ticks_per_frame);
+            if !graph.entered {
+                sprite.frame = frame % animations.get_frames(anim).len() as u32;
+            }
+            // a state sharing the animation keeps the frame and only changes the speed
+            sprite.ticks_per_frame = state.ticks_per_frame;
+            graph.state_len = play_length(animations, sprite);
+            if graph.entered {
+                graph.entered = false;
+                graph.state_ticks = 0;
+            }
+        }
+
+        // variants are drawn facing their own way, the plain art faces left
+        if graph.has_variant {
+            sprite.flip_horizontal = false;
+        } else if graph.facing.x > FACING_THRESHOLD {
+            sprite.flip_horizontal = true;
+        } else if graph.facing.x < -FACING_THRESHOLD {
+            sprite.flip_horizontal = false;
+        }
+    });
+}
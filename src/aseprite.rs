@@ -0,0 +1,100 @@
+// animations exported from Aseprite (File > Export Sprite Sheet, json data with "Array"
+// frames and tags). every json in ASEPRITE_DIR gets its own sheet, each tag becomes an
+// animation named <file>_<tag> and a file without tags becomes one animation named after
+// the file. frame durations carry over, rounded to ticks
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::{assets, Sprite, TICKS_PER_SECOND};
+
+pub const ASEPRITE_DIR: &str = "textures/aseprite";
+
+pub struct AsepriteSheet {
+    // the exported png, its frame rects are in pixels
+    pub image: String,
+    // name, frames and ticks per frame
+    pub animations: Vec<(String, Vec<Sprite>, Vec<u32>)>,
+}
+
+// sorted so the sheets keep their TextureIds between runs
+pub fn paths() -> Vec<PathBuf> {
+    assets::files(ASEPRITE_DIR, "json")
+}
+
+pub fn load(path: &Path) -> Result<AsepriteSheet, String> {
+    let error = |what: &str| format!("{}: {}", path.display(), what);
+    let data = std::fs::read_to_string(path).map_err(|e| error(&e.to_string()))?;
+    let json: Value = serde_json::from_str(&data).map_err(|e| error(&e.to_string()))?;
+
+    let Some(frames) = json["frames"].as_array() else {
+        return Err(error(
+            "frames aren't an array, export with \"Array\" instead of \"Hash\"",
+        ));
+    };
+    let mut sprites = Vec::new();
+    let mut ticks = Vec::new();
+    for frame in frames {
+        let rect = &frame["frame"];
+        let field = |name: &str| {
+            rect[name]
+                .as_u64()
+                .map(|v| v as u16)
+                .ok_or_else(|| error(&format!("frame without {}", name)))
+        };
+        sprites.push(Sprite(field("x")?, field("y")?, field("w")?, field("h")?));
+        let ms = frame["duration"].as_u64().unwrap_or(100) as u32;
+        ticks.push(((ms * TICKS_PER_SECOND + 500) / 1000).max(1));
+    }
+
+    let meta = &json["meta"];
+    let Some(image) = meta["image"].as_str() else {
+        return Err(error("no meta.image"));
+    };
+    let image = path.with_file_name(image).to_string_lossy().into_owned();
+
+    let stem = path.file_stem().unwrap().to_string_lossy();
+    let tags = meta["frameTags"]
+        .as_array()
+        .map(|t| t.as_slice())
+        .unwrap_or(&[]);
+    if tags.is_empty() {
+        return Ok(AsepriteSheet {
+            image,
+            animations: vec![(stem.into_owned(), sprites, ticks)],
+        });
+    }
+
+    let mut animations = Vec::new();
+    for tag in tags {
+        let (Some(name), Some(from), Some(to)) = (
+            tag["name"].as_str(),
+            tag["from"].as_u64(),
+            tag["to"].as_u64(),
+        ) else {
+            return Err(error("tag without a name, from or to"));
+        };
+        let (from, to) = (from as usize, to as usize);
+        if from > to || to >= sprites.len() {
+            return Err(error(&format!("tag {} is out of range", name)));
+        }
+        let mut order: Vec<usize> = (from..=to).collect();
+        match tag["direction"].as_str().unwrap_or("forward") {
+            "reverse" => order.reverse(),
+            // back down without repeating either end
+            "pingpong" => order.extend((from + 1..to).rev()),
+            "pingpong_reverse" => {
+                order.reverse();
+                order.extend(from + 1..to);
+            }
+            _ => {}
+        }
+        animations.push((
+            format!("{}_{}", stem, name),
+            order.iter().map(|idx| sprites[*idx]).collect(),
+            order.iter().map(|idx| ticks[*idx]).collect(),
+        ));
+    }
+    Ok(AsepriteSheet { image, animations })
+}
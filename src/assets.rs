@@ -0,0 +1,102 @@
+// every asset is looked up by its path under BASE_DIR. mods are directories in MOD_DIR laid
+// out the same way, the enabled ones are layered over the base assets in the order of the
+// mod list in the settings, the last one winning:
+//
+//   mods/hard_mode/data/tuning.toml     replaces assets/data/tuning.toml
+//   mods/hard_mode/scripts/traps.rhai   loads next to the base scripts
+//
+// a file replaces the one at the same path in the layers below, directories merge. prefabs
+// are code, mods place them in their rooms and drive them from their scripts
+//
+// the layers are set once at startup, a changed mod list applies after a restart
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+pub const BASE_DIR: &str = "assets";
+pub const MOD_DIR: &str = "mods";
+
+// enabled mods, lowest priority first
+static MODS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+// mods that aren't installed are skipped
+pub fn set_mods(mods: &[String]) {
+    let installed = installed();
+    let mut enabled = MODS.write().unwrap();
+    enabled.clear();
+    for name in mods {
+        if installed.contains(name) {
+            println!("mod {}", name);
+            enabled.push(name.clone());
+        } else {
+            println!("no mod {} in {}", name, MOD_DIR);
+        }
+    }
+}
+
+// lowest priority first
+pub fn enabled() -> Vec<String> {
+    MODS.read().unwrap().clone()
+}
+
+// every directory in MOD_DIR, sorted
+pub fn installed() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(MOD_DIR) else {
+        return Vec::new();
+    };
+    let mut mods: Vec<_> = entries
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    mods.sort();
+    mods
+}
+
+// the top layer first
+fn layers() -> Vec<PathBuf> {
+    let mods = MODS.read().unwrap();
+    mods.iter()
+        .rev()
+        .map(|name| Path::new(MOD_DIR).join(name))
+        .chain([PathBuf::from(BASE_DIR)])
+        .collect()
+}
+
+// the top-most layer that has the asset, or the base path so errors still name it
+pub fn path(asset: &str) -> String {
+    let path = layers()
+        .into_iter()
+        .map(|layer| layer.join(asset))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| Path::new(BASE_DIR).join(asset));
+    path.to_string_lossy().into_owned()
+}
+
+// the files with the extension in dir across all layers, one per file name, sorted by it
+pub fn files(dir: &str, extension: &str) -> Vec<PathBuf> {
+    let mut files = BTreeMap::new();
+    // bottom up so the upper layers overwrite
+    for layer in layers().into_iter().rev() {
+        let Ok(entries) = std::fs::read_dir(layer.join(dir)) else {
+            continue;
+        };
+        for entry in entries {
+            // an entry that can't be read is one file less, not a reason to stop
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(e) => {
+                    println!("can't read {}: {}", layer.join(dir).display(), e);
+                    continue;
+                }
+            };
+            if path.extension().is_some_and(|ext| ext == extension) {
+                files.insert(path.file_name().unwrap().to_os_string(), path);
+            }
+        }
+    }
+    files.into_values().collect()
+}
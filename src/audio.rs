@@ -0,0 +1,309 @@
+// sound effects. every wav in SOUND_DIR is loaded at startup, converted to the device's
+// rate, and played by name through a small software mixer running in SDL's audio
+// callback. a missing sound is reported once and otherwise ignored
+//
+// sounds played at a position are panned and attenuated relative to the listener, the
+// player, and can't be heard past their radius. SoundEmitters loop while in range
+//
+// there is no device when running headless, playing is a no-op then
+
+use std::{
+    collections::{HashMap, HashSet},
+    f32::consts::FRAC_PI_4,
+    sync::Arc,
+};
+
+use ecs::{Entity, Resource, World};
+use sdl2::{
+    audio::{AudioCVT, AudioCallback, AudioDevice, AudioFormat, AudioSpecDesired, AudioSpecWAV},
+    AudioSubsystem,
+};
+
+use crate::{
+    assets,
+    components::{Light, ParticleEmitter, Pos, SoundEmitter},
+    Ctx,
+};
+
+pub const SOUND_DIR: &str = "sounds";
+// sounds past this many are dropped
+const MAX_VOICES: usize = 24;
+// how far one shot sounds can be heard
+pub const DEFAULT_RADIUS: f32 = 400.;
+// sideways distance at which a sound is all the way in one ear
+const PAN_WIDTH: f32 = 300.;
+
+struct Voice {
+    id: u32,
+    samples: Arc<Vec<f32>>,
+    at: usize,
+    volume: f32,
+    // -1 is left, 1 right
+    pan: f32,
+    is_looping: bool,
+}
+
+struct Mixer {
+    voices: Vec<Voice>,
+    master: f32,
+}
+
+impl AudioCallback for Mixer {
+    type Channel = f32;
+
+    // interleaved stereo
+    fn callback(&mut self, out: &mut [f32]) {
+        out.fill(0.);
+        for voice in &mut self.voices {
+            // equal power, so a sound doesn't dip as it crosses the middle
+            let angle = (voice.pan.clamp(-1., 1.) + 1.) * FRAC_PI_4;
+            let gain = voice.volume * self.master;
+            let (left, right) = (angle.cos() * gain, angle.sin() * gain);
+            for frame in out.chunks_exact_mut(2) {
+                if voice.at >= voice.samples.len() {
+                    if !voice.is_looping || voice.samples.is_empty() {
+                        break;
+                    }
+                    voice.at = 0;
+                }
+                let sample = voice.samples[voice.at];
+                voice.at += 1;
+                frame[0] += sample * left;
+                frame[1] += sample * right;
+            }
+        }
+        self.voices
+            .retain(|voice| voice.is_looping || voice.at < voice.samples.len());
+        for sample in out.iter_mut() {
+            *sample = sample.clamp(-1., 1.);
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Audio {
+    // None when running headless or without a sound card
+    device: Option<AudioDevice<Mixer>>,
+    // mono, at the device's rate
+    sounds: HashMap<String, Arc<Vec<f32>>>,
+    reported: HashSet<String>,
+    next_id: u32,
+    pub listener: Pos,
+    // the looping voice of every SoundEmitter in range
+    loops: HashMap<Entity, u32>,
+}
+
+impl Audio {
+    pub fn new(subsystem: Option<AudioSubsystem>) -> Self {
+        let mut audio = Audio {
+            device: None,
+            sounds: HashMap::new(),
+            reported: HashSet::new(),
+            next_id: 0,
+            listener: Pos::zero(),
+            loops: HashMap::new(),
+        };
+        let Some(subsystem) = subsystem else {
+            return audio;
+        };
+        let desired = AudioSpecDesired {
+            freq: Some(44100),
+            channels: Some(2),
+            samples: Some(1024),
+        };
+        let device = match subsystem.open_playback(None, &desired, |_| Mixer {
+            voices: Vec::new(),
+            master: 1.,
+        }) {
+            Ok(device) => device,
+            Err(e) => {
+                println!("no audio: {}", e);
+                return audio;
+            }
+        };
+        audio.load_sounds(device.spec().freq);
+        device.resume();
+        audio.device = Some(device);
+        audio
+    }
+
+    fn load_sounds(&mut self, rate: i32) {
+        for path in assets::files(SOUND_DIR, "wav") {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            match load_wav(&path, rate) {
+                Ok(samples) => {
+                    self.sounds.insert(name, Arc::new(samples));
+                }
+                Err(e) => println!("failed to load {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    // 0 to 1, scales everything
+    pub fn set_volume(&mut self, volume: f32) {
+        if let Some(device) = self.device.as_mut() {
+            device.lock().master = volume.clamp(0., 1.);
+        }
+    }
+
+    pub fn play(&mut self, name: &str, volume: f32) {
+        self.start(name, volume, 0., false);
+    }
+
+    // nothing past radius
+    pub fn play_at(&mut self, name: &str, volume: f32, pos: Pos, radius: f32) {
+        if let Some((gain, pan)) = self.spatialize(pos, radius) {
+            self.start(name, volume * gain, pan, false);
+        }
+    }
+
+    // volume falls off with the square of the distance and reaches 0 at radius
+    fn spatialize(&self, pos: Pos, radius: f32) -> Option<(f32, f32)> {
+        let distance = self.listener.distance(&pos);
+        if distance >= radius {
+            return None;
+        }
+        let falloff = 1. - distance / radius;
+        let pan = (pos.x - self.listener.x) / PAN_WIDTH;
+        Some((falloff * falloff, pan.clamp(-1., 1.)))
+    }
+
+    // looping and centered, all in the same callback so they stay in time
+    pub fn play_loops(&mut self, names: &[String], volume: f32) -> Vec<Option<u32>> {
+        let voices: Vec<_> = names
+            .iter()
+            .map(|name| self.voice(name, volume, 0., true))
+            .collect();
+        let Some(device) = self.device.as_mut() else {
+            return vec![None; names.len()];
+        };
+        let mut mixer = device.lock();
+        voices
+            .into_iter()
+            .map(|voice| {
+                let voice = voice.filter(|_| mixer.voices.len() < MAX_VOICES)?;
+                let id = voice.id;
+                mixer.voices.push(voice);
+                Some(id)
+            })
+            .collect()
+    }
+
+    fn start(&mut self, name: &str, volume: f32, pan: f32, is_looping: bool) -> Option<u32> {
+        let voice = self.voice(name, volume, pan, is_looping)?;
+        let mut mixer = self.device.as_mut()?.lock();
+        if mixer.voices.len() >= MAX_VOICES {
+            return None;
+        }
+        let id = voice.id;
+        mixer.voices.push(voice);
+        Some(id)
+    }
+
+    fn voice(&mut self, name: &str, volume: f32, pan: f32, is_looping: bool) -> Option<Voice> {
+        self.device.as_ref()?;
+        let Some(samples) = self.sounds.get(name) else {
+            if self.reported.insert(name.to_string()) {
+                println!("no sound {}", name);
+            }
+            return None;
+        };
+        self.next_id = self.next_id.wrapping_add(1);
+        Some(Voice {
+            id: self.next_id,
+            samples: samples.clone(),
+            at: 0,
+            volume,
+            pan,
+            is_looping,
+        })
+    }
+
+    pub fn update_voice(&mut self, id: u32, volume: f32, pan: f32) {
+        if let Some(device) = self.device.as_mut() {
+            if let Some(voice) = device.lock().voices.iter_mut().find(|v| v.id == id) {
+                voice.volume = volume;
+                voice.pan = pan;
+            }
+        }
+    }
+
+    pub fn stop(&mut self, id: u32) {
+        if let Some(device) = self.device.as_mut() {
+            device.lock().voices.retain(|voice| voice.id != id);
+        }
+    }
+}
+
+fn load_wav(path: &std::path::Path, rate: i32) -> Result<Vec<f32>, String> {
+    let wav = AudioSpecWAV::load_wav(path)?;
+    let cvt = AudioCVT::new(
+        wav.format,
+        wav.channels,
+        wav.freq,
+        AudioFormat::F32LSB,
+        1,
+        rate,
+    )?;
+    let bytes = cvt.convert(wav.buffer().to_vec());
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+pub fn play(world: &World, name: &str, volume: f32) {
+    world.resource_mut::<Audio>().unwrap().play(name, volume);
+}
+
+pub fn play_at(world: &World, name: &str, volume: f32, pos: Pos) {
+    world
+        .resource_mut::<Audio>()
+        .unwrap()
+        .play_at(name, volume, pos, DEFAULT_RADIUS);
+}
+
+// starts, moves and stops the emitter loops as the listener walks around
+pub fn update(world: &World) {
+    let audio = world.resource_mut::<Audio>().unwrap();
+    audio.listener = world.resource::<Ctx>().unwrap().player_pos;
+
+    let mut heard = HashSet::new();
+    world.run(|entity: &Entity, emitter: &SoundEmitter, pos: &Pos| {
+        // unlit torches don't crackle and idle emitters don't hum
+        let is_silent = world
+            .component::<Light>(*entity)
+            .is_some_and(|light| light.intensity <= 0.)
+            || world
+                .component::<ParticleEmitter>(*entity)
+                .is_some_and(|particles| !particles.is_active);
+        let Some((gain, pan)) = audio
+            .spatialize(*pos, emitter.radius)
+            .filter(|_| !is_silent)
+        else {
+            return;
+        };
+        heard.insert(*entity);
+        let volume = emitter.volume * gain;
+        match audio.loops.get(entity) {
+            Some(id) => audio.update_voice(*id, volume, pan),
+            None => {
+                if let Some(id) = audio.start(emitter.sound, volume, pan, true) {
+                    audio.loops.insert(*entity, id);
+                }
+            }
+        }
+    });
+
+    // out of range, silenced or despawned
+    let gone: Vec<_> = audio
+        .loops
+        .iter()
+        .filter(|(entity, _)| !heard.contains(*entity))
+        .map(|(entity, id)| (*entity, *id))
+        .collect();
+    for (entity, id) in gone {
+        audio.stop(id);
+        audio.loops.remove(&entity);
+    }
+}
@@ -0,0 +1,58 @@
+// uniform grid over rects, every rect is listed in each cell it overlaps. only narrows things
+// down, what comes back still has to be tested against the actual shapes
+
+use std::collections::HashMap;
+
+use sdl2::rect::Rect;
+
+const CELL_SIZE: i32 = 64;
+
+pub struct Grid {
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        Grid {
+            cells: HashMap::new(),
+        }
+    }
+
+    // keeps the cell allocations around between rebuilds
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+    }
+
+    fn cells(rect: Rect) -> impl Iterator<Item = (i32, i32)> {
+        let min = (
+            rect.left().div_euclid(CELL_SIZE),
+            rect.top().div_euclid(CELL_SIZE),
+        );
+        let max = (
+            rect.right().div_euclid(CELL_SIZE),
+            rect.bottom().div_euclid(CELL_SIZE),
+        );
+        (min.0..=max.0).flat_map(move |cx| (min.1..=max.1).map(move |cy| (cx, cy)))
+    }
+
+    pub fn insert(&mut self, idx: usize, rect: Rect) {
+        for cell in Self::cells(rect) {
+            self.cells.entry(cell).or_default().push(idx);
+        }
+    }
+
+    // every idx in a cell rect touches, sorted and without duplicates
+    pub fn query(&self, rect: Rect) -> Vec<usize> {
+        let mut found = Vec::new();
+        for cell in Self::cells(rect) {
+            if let Some(cell) = self.cells.get(&cell) {
+                found.extend_from_slice(cell);
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
@@ -0,0 +1,296 @@
+// F10 screenshots and F11 gif clips of the last few seconds
+
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ecs::{Resource, World};
+use sdl2::{image::SaveSurface, pixels::PixelFormatEnum, surface::Surface};
+
+use crate::{locale::tr_args, toasts, Ctx};
+
+const OUTPUT_DIR: &str = "screenshots";
+const CLIP_SECONDS: u32 = 5;
+const CLIP_FPS: u32 = 20;
+// clip frames are downscaled to keep the ring buffer and the gif small
+const CLIP_SCALE: usize = 2;
+
+// 6 red, 7 green and 6 blue levels, the eye is most sensitive to green
+const LEVELS: [usize; 3] = [6, 7, 6];
+
+struct ClipFrame {
+    width: usize,
+    height: usize,
+    // palette indices
+    pixels: Vec<u8>,
+}
+
+#[derive(Resource)]
+pub struct Capture {
+    frames: VecDeque<ClipFrame>,
+    last_frame: Instant,
+    pub screenshot_requested: bool,
+    pub clip_requested: bool,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Capture {
+            frames: VecDeque::new(),
+            last_frame: Instant::now(),
+            screenshot_requested: false,
+            clip_requested: false,
+        }
+    }
+}
+
+// called after the ui is drawn and before present
+pub fn update(world: &World) {
+    let ctx = world.resource::<Ctx>().unwrap();
+    let capture = world.resource_mut::<Capture>().unwrap();
+
+    let now = Instant::now();
+    let clip_due = now.duration_since(capture.last_frame) >= Duration::from_secs(1) / CLIP_FPS;
+    if !clip_due && !capture.screenshot_requested {
+        return;
+    }
+
+    let (width, height) = ctx.canvas.output_size().unwrap();
+    let mut pixels = ctx
+        .canvas
+        .read_pixels(None, PixelFormatEnum::RGB24)
+        .unwrap();
+
+    if capture.screenshot_requested {
+        capture.screenshot_requested = false;
+        let path = output_path("png");
+        let surface = Surface::from_data(
+            &mut pixels,
+            width,
+            height,
+            width * 3,
+            PixelFormatEnum::RGB24,
+        )
+        .unwrap();
+        match surface.save(&path) {
+            Ok(()) => toasts::push(world, &tr_args("toast-saved", &[("path", &path)])),
+            Err(e) => println!("failed to save {}: {}", path, e),
+        }
+    }
+
+    if clip_due {
+        capture.last_frame = now;
+        let frame = downscale(&pixels, width as usize, height as usize);
+        // frames of different sizes can't share a gif, so a resize starts a new clip
+        if capture
+            .frames
+            .back()
+            .is_some_and(|last| (last.width, last.height) != (frame.width, frame.height))
+        {
+            capture.frames.clear();
+        }
+        capture.frames.push_back(frame);
+        if capture.frames.len() > (CLIP_SECONDS * CLIP_FPS) as usize {
+            capture.frames.pop_front();
+        }
+    }
+
+    if capture.clip_requested {
+        capture.clip_requested = false;
+        let frames: Vec<ClipFrame> = capture.frames.drain(..).collect();
+        let path = output_path("gif");
+        // encoding takes a while, so don't stall the game on it
+        std::thread::spawn(move || {
+            let data = encode_gif(&frames, (100 / CLIP_FPS) as u16);
+            match std::fs::write(&path, data) {
+                Ok(()) => println!("saved {} ({} frames)", path, frames.len()),
+                Err(e) => println!("failed to save {}: {}", path, e),
+            }
+        });
+    }
+}
+
+fn output_path(extension: &str) -> String {
+    std::fs::create_dir_all(OUTPUT_DIR).unwrap();
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    format!(
+        "{}/{}_{:03}.{}",
+        OUTPUT_DIR,
+        time.as_secs(),
+        time.subsec_millis(),
+        extension
+    )
+}
+
+fn downscale(rgb: &[u8], width: usize, height: usize) -> ClipFrame {
+    let (w, h) = (width / CLIP_SCALE, height / CLIP_SCALE);
+    let mut pixels = Vec::with_capacity(w * h);
+    for y in 0..h {
+        for x in 0..w {
+            let i = ((y * CLIP_SCALE) * width + x * CLIP_SCALE) * 3;
+            pixels.push(palette_index(rgb[i], rgb[i + 1], rgb[i + 2]));
+        }
+    }
+    ClipFrame {
+        width: w,
+        height: h,
+        pixels,
+    }
+}
+
+fn palette_index(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8, levels: usize| v as usize * levels / 256;
+    let (r, g, b) = (
+        level(r, LEVELS[0]),
+        level(g, LEVELS[1]),
+        level(b, LEVELS[2]),
+    );
+    ((r * LEVELS[1] + g) * LEVELS[2] + b) as u8
+}
+
+fn palette() -> Vec<u8> {
+    let value = |level: usize, levels: usize| (level * 255 / (levels - 1)) as u8;
+    let mut colors = Vec::with_capacity(256 * 3);
+    for r in 0..LEVELS[0] {
+        for g in 0..LEVELS[1] {
+            for b in 0..LEVELS[2] {
+                colors.extend([
+                    value(r, LEVELS[0]),
+                    value(g, LEVELS[1]),
+                    value(b, LEVELS[2]),
+                ]);
+            }
+        }
+    }
+    // the table has to be a power of two long
+    colors.resize(256 * 3, 0);
+    colors
+}
+
+// delay is in hundredths of a second
+fn encode_gif(frames: &[ClipFrame], delay: u16) -> Vec<u8> {
+    let mut out = Vec::new();
+    let (width, height) = frames
+        .first()
+        .map_or((0, 0), |frame| (frame.width as u16, frame.height as u16));
+
+    out.extend(b"GIF89a");
+    out.extend(width.to_le_bytes());
+    out.extend(height.to_le_bytes());
+    // global color table with 256 entries, 8 bit color resolution
+    out.extend([0xf7, 0, 0]);
+    out.extend(palette());
+    // loop forever
+    out.extend([0x21, 0xff, 0x0b]);
+    out.extend(b"NETSCAPE2.0");
+    out.extend([0x03, 0x01, 0x00, 0x00, 0x00]);
+
+    for frame in frames {
+        // graphic control extension
+        out.extend([0x21, 0xf9, 0x04, 0x00]);
+        out.extend(delay.to_le_bytes());
+        out.extend([0x00, 0x00]);
+        // image descriptor
+        out.push(0x2c);
+        out.extend([0, 0, 0, 0]);
+        out.extend(width.to_le_bytes());
+        out.extend(height.to_le_bytes());
+        out.push(0x00);
+        lzw_encode(&frame.pixels, &mut out);
+    }
+
+    out.push(0x3b);
+    out
+}
+
+const MIN_CODE_SIZE: u32 = 8;
+const MAX_CODE: u16 = 4096;
+
+fn lzw_encode(indices: &[u8], out: &mut Vec<u8>) {
+    let clear = 1 << MIN_CODE_SIZE;
+    let end = clear + 1;
+
+    let mut bits = BitWriter::new();
+    let mut dict: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = MIN_CODE_SIZE + 1;
+    let mut next_code = end + 1;
+
+    bits.write(clear, code_size);
+    let mut prefix: Option<u16> = None;
+    for &index in indices {
+        let Some(p) = prefix else {
+            prefix = Some(index as u16);
+            continue;
+        };
+        if let Some(&code) = dict.get(&(p, index)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        bits.write(p, code_size);
+        if next_code < MAX_CODE {
+            if next_code == 1 << code_size {
+                code_size += 1;
+            }
+            dict.insert((p, index), next_code);
+            next_code += 1;
+        } else {
+            // table is full, start over
+            bits.write(clear, code_size);
+            dict.clear();
+            code_size = MIN_CODE_SIZE + 1;
+            next_code = end + 1;
+        }
+        prefix = Some(index as u16);
+    }
+    if let Some(p) = prefix {
+        bits.write(p, code_size);
+        if next_code < MAX_CODE && next_code == 1 << code_size {
+            code_size += 1;
+        }
+    }
+    bits.write(end, code_size);
+
+    out.push(MIN_CODE_SIZE as u8);
+    // data goes in sub-blocks of at most 255 bytes
+    for block in bits.finish().chunks(255) {
+        out.push(block.len() as u8);
+        out.extend(block);
+    }
+    out.push(0);
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            bytes: Vec::new(),
+            current: 0,
+            count: 0,
+        }
+    }
+
+    // least significant bit first
+    fn write(&mut self, code: u16, size: u32) {
+        self.current |= (code as u32) << self.count;
+        self.count += size;
+        while self.count >= 8 {
+            self.bytes.push(self.current as u8);
+            self.current >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.current as u8);
+        }
+        self.bytes
+    }
+}
@@ -0,0 +1,138 @@
+// where the player comes back after dying, and the autosaves that go with it. touching
+// a checkpoint moves the respawn point and autosaves, a timer autosaves in between
+
+use ecs::{Entity, Resource, With, World};
+use sdl2::pixels::Color;
+
+use crate::{
+    animation,
+    components::{
+        AnimatedSprite, Checkpoint, Health, Player, Pos, PrevPos, StatusEffects, StatusKind,
+    },
+    events::{EventReader, Events, PlayerDied},
+    locale::tr,
+    saves::{SaveSlots, AUTOSAVE_SLOT},
+    toasts,
+    transition::{self, Style},
+    tween::{Ease, LightIntensity, Tween},
+    TICKS_PER_SECOND,
+};
+
+const AUTOSAVE_TICKS: u32 = 5 * 60 * TICKS_PER_SECOND;
+const RESPAWN_TICKS: u32 = 90;
+const LIGHT_FADE_TICKS: u32 = 30;
+const REVIVE_FADE_TICKS: u32 = 30;
+const DEATH_FADE_COLOR: Color = Color::RGB(40, 0, 0);
+
+#[derive(Resource)]
+pub struct Checkpoints {
+    pub respawn_pos: Pos,
+    // counts down while the player is dead
+    respawn_in: Option<u32>,
+    autosave_in: u32,
+    player_died: EventReader<PlayerDied>,
+}
+
+impl Checkpoints {
+    pub fn new() -> Self {
+        Checkpoints {
+            respawn_pos: Pos::zero(),
+            respawn_in: None,
+            autosave_in: AUTOSAVE_TICKS,
+            player_died: EventReader::new(),
+        }
+    }
+
+    pub fn is_player_dead(&self) -> bool {
+        self.respawn_in.is_some()
+    }
+}
+
+pub fn on_checkpoint_enter(world: &World, me: Entity, other: Entity) {
+    if !world.has_component::<Player>(other) {
+        return;
+    }
+    if world.component::<Checkpoint>(me).unwrap().is_active {
+        return;
+    }
+
+    // only the newest one stays lit
+    world.run(
+        |entity: &Entity,
+         checkpoint: &mut Checkpoint,
+         sprite: &mut AnimatedSprite,
+         light: &mut Tween<LightIntensity>| {
+            checkpoint.is_active = *entity == me;
+            let (anim, intensity) = if checkpoint.is_active {
+                ("checkpoint_on", 1.)
+            } else {
+                ("checkpoint_off", 0.)
+            };
+            sprite.switch_anim(animation(world, anim), 0);
+            light.to(LightIntensity(intensity), LIGHT_FADE_TICKS, Ease::InOut);
+        },
+    );
+
+    let checkpoints = world.resource_mut::<Checkpoints>().unwrap();
+    checkpoints.respawn_pos = *world.component::<Pos>(me).unwrap();
+    autosave(world);
+    toasts::push(world, tr("toast-checkpoint"));
+}
+
+fn autosave(world: &World) {
+    world.resource_mut::<Checkpoints>().unwrap().autosave_in = AUTOSAVE_TICKS;
+    world
+        .resource_mut::<SaveSlots>()
+        .unwrap()
+        .request_save(Some(AUTOSAVE_SLOT));
+}
+
+pub fn update(world: &World) {
+    let checkpoints = world.resource_mut::<Checkpoints>().unwrap();
+
+    let died = checkpoints
+        .player_died
+        .read(world.resource::<Events<PlayerDied>>().unwrap())
+        .count()
+        > 0;
+    if died && checkpoints.respawn_in.is_none() {
+        checkpoints.respawn_in = Some(RESPAWN_TICKS);
+        world.run(|sprite: &mut AnimatedSprite, _: With<Player>| sprite.alpha = 0);
+        // fades out for as long as the player is dead
+        transition::cover(world, Style::Fade(DEATH_FADE_COLOR), RESPAWN_TICKS);
+    }
+
+    if let Some(ticks) = checkpoints.respawn_in.as_mut() {
+        *ticks -= 1;
+        if *ticks == 0 {
+            checkpoints.respawn_in = None;
+            respawn(world, checkpoints.respawn_pos);
+        }
+        // no autosaving a dead player
+        return;
+    }
+
+    checkpoints.autosave_in -= 1;
+    if checkpoints.autosave_in == 0 {
+        autosave(world);
+    }
+}
+
+fn respawn(world: &World, at: Pos) {
+    world.run(
+        |pos: &mut Pos,
+         prev_pos: &mut PrevPos,
+         health: &mut Health,
+         status: &mut StatusEffects,
+         sprite: &mut AnimatedSprite,
+         _: With<Player>| {
+            *pos = at;
+            prev_pos.0 = at;
+            health.hp = health.max_hp;
+            status.remove(StatusKind::Burning);
+            sprite.alpha = 255;
+        },
+    );
+    transition::uncover(world, REVIVE_FADE_TICKS);
+    toasts::push(world, tr("toast-respawned"));
+}
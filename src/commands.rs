@@ -0,0 +1,60 @@
+// structural changes queued while systems iterate, applied at the end of the tick
+
+use ecs::{Component, Entity, Resource, World};
+
+type DeferredFn = Box<dyn FnOnce(&World)>;
+
+#[derive(Resource)]
+pub struct CommandBuffer {
+    spawns: Vec<Vec<Box<dyn Component>>>,
+    despawns: Vec<Entity>,
+    deferred: Vec<DeferredFn>,
+}
+
+impl CommandBuffer {
+    pub fn new() -> Self {
+        CommandBuffer {
+            spawns: Vec::new(),
+            despawns: Vec::new(),
+            deferred: Vec::new(),
+        }
+    }
+
+    pub fn spawn(&mut self, components: Vec<Box<dyn Component>>) {
+        self.spawns.push(components);
+    }
+
+    // despawning the same entity twice in a tick is fine
+    pub fn despawn(&mut self, entity: Entity) {
+        if !self.despawns.contains(&entity) {
+            self.despawns.push(entity);
+        }
+    }
+
+    // the ecs can't add or remove components on a live entity,
+    // anything along those lines goes through here
+    pub fn defer(&mut self, f: impl FnOnce(&World) + 'static) {
+        self.deferred.push(Box::new(f));
+    }
+}
+
+// commands queued while applying wait for the next sync point
+pub fn apply(world: &World) {
+    let commands = world.resource_mut::<CommandBuffer>().unwrap();
+    let spawns = std::mem::take(&mut commands.spawns);
+    let deferred = std::mem::take(&mut commands.deferred);
+    let despawns = std::mem::take(&mut commands.despawns);
+
+    for components in spawns {
+        let components: Vec<&dyn Component> = components.iter().map(|c| c.as_ref()).collect();
+        world.spawn(&components);
+    }
+
+    for f in deferred {
+        f(world);
+    }
+
+    for entity in despawns {
+        world.despawn(entity);
+    }
+}
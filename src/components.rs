@@ -1,9 +1,20 @@
-// TODO move inventory related stuff elsewhere since inventory is not a component
+// TODO move inventory related stuff elsewhere
 
 use std::ops::{Deref, DerefMut};
 
-use crate::{math::Vec2, AnimationId, Ctx, Sprite};
-use ecs::{Component, Entity, With, World};
+use crate::{
+    animation,
+    equipment::PlayerStats,
+    events::{self, ItemUsed},
+    game,
+    layers::CollisionLayers,
+    math::{Transform, Vec2},
+    tuning::Tuning,
+    tween::{Ease, Lerp, Tween},
+    AnimationId, Ctx,
+};
+use ecs::{Component, Entity, World};
+use rand::Rng;
 use sdl2::{
     pixels::Color,
     rect::{Point, Rect},
@@ -22,7 +33,25 @@ impl Pos {
     }
 
     pub fn distance(&self, other: &Pos) -> f32 {
-        f32::sqrt((self.0.x - other.x).powi(2) + (self.0.y - other.y).powi(2))
+        let (dx, dy) = (self.0.x - other.x, self.0.y - other.y);
+        f32::sqrt(dx * dx + dy * dy)
+    }
+
+    pub fn lerp(&self, other: &Pos, t: f32) -> Pos {
+        Pos::new(
+            self.0.x + (other.x - self.0.x) * t,
+            self.0.y + (other.y - self.0.y) * t,
+        )
+    }
+
+    // the pixel it lands on. sprites, colliders and lights all snap through here so they
+    // never disagree by one. halves go up on both sides of zero, f32::round rounds them away
+    // from it, which would snap something differently after moving it by whole pixels
+    pub fn pixel(&self) -> (i32, i32) {
+        (
+            (self.0.x + 0.5).floor() as i32,
+            (self.0.y + 0.5).floor() as i32,
+        )
     }
 }
 
@@ -52,6 +81,17 @@ impl From<(i32, i32)> for Pos {
     }
 }
 
+// position at the start of the current tick, used to interpolate rendering
+#[derive(Component)]
+pub struct PrevPos(pub Pos);
+
+#[derive(Clone, Copy)]
+pub struct ScaleAnim {
+    pub from: Vec2<f32>,
+    pub ticks_left: u16,
+    pub duration: u16,
+}
+
 #[derive(Component)]
 pub struct AnimatedSprite {
     // TODO u16
@@ -65,6 +105,18 @@ pub struct AnimatedSprite {
     pub x_offset: i16,
     pub y_offset: i16,
     pub z_offset: Option<i16>,
+    // degrees clockwise, around rotation_origin or the sprite center if None
+    pub rotation: f32,
+    pub rotation_origin: Option<(i16, i16)>,
+    // scales around Pos, offsets included
+    pub scale: Vec2<f32>,
+    pub scale_anim: Option<ScaleAnim>,
+    pub color_mod: Color,
+    pub alpha: u8,
+    // fades from 1 to 0 after a hit
+    pub flash: Tween<f32>,
+    // drawn again after the lightmap so it glows in the dark
+    pub is_emissive: bool,
 }
 
 impl AnimatedSprite {
@@ -85,9 +137,74 @@ impl AnimatedSprite {
             ticks_per_frame,
             flip_horizontal: false,
             z_offset,
+            rotation: 0.,
+            rotation_origin: None,
+            scale: Vec2::new(1., 1.),
+            scale_anim: None,
+            color_mod: Color::WHITE,
+            alpha: 255,
+            flash: Tween::idle(0.),
+            is_emissive: false,
+        }
+    }
+
+    pub fn current_scale(&self) -> Vec2<f32> {
+        match self.scale_anim {
+            Some(anim) => {
+                let t = 1. - anim.ticks_left as f32 / anim.duration as f32;
+                Vec2::new(
+                    anim.from.x + (self.scale.x - anim.from.x) * t,
+                    anim.from.y + (self.scale.y - anim.from.y) * t,
+                )
+            }
+            None => self.scale,
+        }
+    }
+
+    // eases from the given scale back to self.scale over duration ticks
+    pub fn animate_scale_from(&mut self, from: Vec2<f32>, duration: u16) {
+        self.scale_anim = Some(ScaleAnim {
+            from,
+            ticks_left: duration,
+            duration,
+        });
+    }
+
+    pub fn pop_in(&mut self, duration: u16) {
+        self.animate_scale_from(Vec2::zero(), duration);
+    }
+
+    pub fn squash(&mut self, amount: f32, duration: u16) {
+        self.animate_scale_from(
+            Vec2::new(self.scale.x * (1. + amount), self.scale.y * (1. - amount)),
+            duration,
+        );
+    }
+
+    pub fn tick_scale_anim(&mut self) {
+        if let Some(anim) = &mut self.scale_anim {
+            anim.ticks_left = anim.ticks_left.saturating_sub(1);
+            if anim.ticks_left == 0 {
+                self.scale_anim = None;
+            }
         }
     }
 
+    pub fn flash(&mut self, duration: u32) {
+        self.flash = Tween::new(1., 0., duration, Ease::InOut);
+    }
+
+    pub fn tint(&self) -> Color {
+        let flash = self.flash.value();
+        let channel = |from: u8, to: u8| (from as f32).lerp(to as f32, flash).round() as u8;
+        Color::RGBA(
+            channel(self.color_mod.r, 255),
+            channel(self.color_mod.g, 60),
+            channel(self.color_mod.b, 60),
+            self.alpha,
+        )
+    }
+
     pub fn anim(&self) -> AnimationId {
         self.anim
     }
@@ -102,9 +219,14 @@ impl AnimatedSprite {
     }
 }
 
-pub const CH_NONE: usize = 0;
-pub const CH_NAV: usize = 1;
-pub const CH_HITBOX: usize = 1 << 1;
+// a side of a collider's bounds
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
 
 #[derive(Clone, Copy)]
 pub struct Collider {
@@ -113,11 +235,16 @@ pub struct Collider {
     pub x_offset: i32,
     pub y_offset: i32,
     pub bounds: Rect,
+    pub is_enabled: bool,
     pub is_colliding: bool,
     pub left: bool,
     pub right: bool,
     pub top: bool,
     pub bottom: bool,
+    // overlaps are reported but nothing is pushed apart or flagged, on either side
+    pub is_sensor: bool,
+    // only pushes things out through this side, they pass through from the others
+    pub one_way: Option<Side>,
     pub on_collide: Option<fn(&World, Entity, Entity)>,
 }
 
@@ -134,15 +261,38 @@ impl Collider {
             x_offset: rect.0,
             y_offset: rect.1,
             bounds: Rect::new(0, 0, rect.2, rect.3),
+            is_enabled: true,
             is_colliding: false,
             left: false,
             right: false,
             top: false,
             bottom: false,
+            is_sensor: false,
+            one_way: None,
             on_collide,
         }
     }
 
+    // on a layer from layers.txt, which also says what it collides with
+    pub fn on_layer(layers: &CollisionLayers, layer: &str, rect: (i32, i32, u32, u32)) -> Self {
+        Collider::new(rect, layers.bit(layer), layers.collides_with(layer), None)
+    }
+
+    pub fn with_on_collide(mut self, on_collide: fn(&World, Entity, Entity)) -> Self {
+        self.on_collide = Some(on_collide);
+        self
+    }
+
+    pub fn as_sensor(mut self) -> Self {
+        self.is_sensor = true;
+        self
+    }
+
+    pub fn one_way(mut self, side: Side) -> Self {
+        self.one_way = Some(side);
+        self
+    }
+
     pub fn set_pos(&mut self, x: i32, y: i32) {
         self.bounds.set_x(x);
         self.bounds.set_y(y);
@@ -155,324 +305,1882 @@ pub struct ColliderGroup {
     pub hitbox: Option<Collider>,
 }
 
+// lit while it's the respawn point
 #[derive(Component)]
-pub struct LightOccluder {
-    pub line: (Point, Point),
+pub struct Checkpoint {
+    pub is_active: bool,
 }
 
 #[derive(Component)]
-pub struct LightOccluderGroup {
-    pub occluders: [Option<LightOccluder>; 4],
+pub struct Ambush {
+    pub points: [Option<Pos>; 4],
 }
 
+pub const MAX_TRIGGER_OCCUPANTS: usize = 8;
+
+// reports colliders entering and leaving, nothing gets pushed out
 #[derive(Component)]
-pub struct Player {
-    pub fire_cooldown: usize,
-    pub can_fire_in: usize,
+pub struct TriggerZone {
+    pub x_offset: i32,
+    pub y_offset: i32,
+    pub bounds: Rect,
+    // collider channels that set the trigger off
+    pub channels: usize,
+    pub is_enabled: bool,
+    pub on_enter: Option<fn(&World, Entity, Entity)>,
+    pub on_exit: Option<fn(&World, Entity, Entity)>,
+    // runs on enter after on_enter, see scripts.rs
+    pub script: Option<&'static str>,
+    pub occupants: [Option<Entity>; MAX_TRIGGER_OCCUPANTS],
 }
 
-#[derive(Component)]
-pub struct Enemy {}
+impl TriggerZone {
+    pub fn new(
+        rect: (i32, i32, u32, u32),
+        channels: usize,
+        on_enter: Option<fn(&World, Entity, Entity)>,
+        on_exit: Option<fn(&World, Entity, Entity)>,
+    ) -> Self {
+        TriggerZone {
+            x_offset: rect.0,
+            y_offset: rect.1,
+            bounds: Rect::new(0, 0, rect.2, rect.3),
+            channels,
+            is_enabled: true,
+            on_enter,
+            on_exit,
+            script: None,
+            occupants: [None; MAX_TRIGGER_OCCUPANTS],
+        }
+    }
+}
 
 #[derive(Component)]
-pub struct Projectile {
-    pub velocity: Vec2<f32>,
-    pub ticks_left: usize,
+pub struct LightOccluder {
+    pub line: (Point, Point),
 }
 
 #[derive(Component)]
-pub struct Light {
-    pub radius: u16,
-    pub color: Color,
-    pub intensity: f32,
+pub struct LightOccluderGroup {
+    pub is_enabled: bool,
+    pub occluders: [Option<LightOccluder>; 4],
 }
 
-#[derive(Component)]
-pub struct Floor {}
+// offsets from Pos
+#[derive(Clone, Copy)]
+pub enum OccluderShape {
+    Circle(u16),
+    Rect(i16, i16, u16, u16),
+}
 
+// shadows from things that move around. walls use LightOccluderGroup
 #[derive(Component)]
-pub struct Wall {}
+pub struct Occluder {
+    pub shape: OccluderShape,
+    // a soft partial shadow instead of blocking the light
+    pub is_blob: bool,
+    pub is_enabled: bool,
+}
 
-#[derive(Component)]
-pub struct Prop {}
+impl Occluder {
+    pub fn blob(radius: u16) -> Self {
+        Occluder {
+            shape: OccluderShape::Circle(radius),
+            is_blob: true,
+            is_enabled: true,
+        }
+    }
 
-#[derive(Component)]
-pub struct Interactable {
-    pub on_interact: fn(&World, Entity),
+    pub fn solid(shape: OccluderShape) -> Self {
+        Occluder {
+            shape,
+            is_blob: false,
+            is_enabled: true,
+        }
+    }
 }
 
+// slot 0 plays on keyboard and mouse, 1 is the second local player
 #[derive(Component)]
-pub struct ProximityIndicator {
-    pub range: f32,
-    pub sprite: AnimatedSprite,
+pub struct Player {
+    pub slot: u8,
 }
 
+// the second player in a networked game, driven by the client's input, see net.rs.
+// only has a pistol
 #[derive(Component)]
-pub struct ParticleEmitter {
-    pub is_active: bool,
-    pub particle_cooldown: u32,
-    pub particle_ticks_left: u32,
+pub struct Partner {
+    pub fire_cooldown: Timer,
 }
 
 #[derive(Component)]
-pub struct Static {}
+pub struct Enemy {}
 
-pub trait Item {
-    fn name(&self) -> &'static str;
-    fn sprite(&self) -> Sprite;
-    fn on_tick(&mut self, is_active: bool, world: &World) -> InventoryCmd;
-    fn on_use(&mut self, world: &World) -> InventoryCmd;
-    fn on_select(&mut self, world: &World);
-    fn on_deselect(&mut self, world: &World);
+// a short burst of speed in one direction, the player's dodge
+#[derive(Component)]
+pub struct Dash {
+    pub direction: Vec2<f32>,
+    pub active: Timer,
+    pub cooldown: Timer,
 }
 
-pub struct PerfectlyGenericItem {}
-impl Item for PerfectlyGenericItem {
-    fn name(&self) -> &'static str {
-        "perfectly_generic_item"
+impl Dash {
+    pub fn new(duration: u32, cooldown: u32) -> Self {
+        Dash {
+            direction: Vec2::zero(),
+            active: Timer::ready(duration),
+            cooldown: Timer::ready(cooldown),
+        }
     }
 
-    fn sprite(&self) -> Sprite {
-        (14, 0, 1, 1).into()
+    pub fn is_dashing(&self) -> bool {
+        !self.active.is_finished()
     }
 
-    fn on_tick(&mut self, _is_active: bool, _world: &World) -> InventoryCmd {
-        InventoryCmd::None
+    // 0 right after a dash, 1 when it can be used again
+    pub fn readiness(&self) -> f32 {
+        self.cooldown.elapsed.min(self.cooldown.duration) as f32 / self.cooldown.duration as f32
     }
+}
 
-    fn on_use(&mut self, _world: &World) -> InventoryCmd {
-        InventoryCmd::None
-    }
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BossAttack {
+    // a ring of slow bullets in every direction
+    Ring,
+    // a dash towards where the player stood when the telegraph ended
+    Charge,
+}
 
-    fn on_select(&mut self, _world: &World) {}
+#[derive(Clone, Copy)]
+pub enum BossState {
+    Idle,
+    // winding up, so the player can read what's coming
+    Telegraph(BossAttack),
+    Charging(Vec2<f32>),
+    Recover,
+}
 
-    fn on_deselect(&mut self, _world: &World) {}
+#[derive(Component)]
+pub struct Boss {
+    // index into the phase table, only ever goes up
+    pub phase: usize,
+    pub state: BossState,
+    pub state_timer: Timer,
+    pub attacks_done: usize,
+    // signalled on death
+    pub door_channel: Option<u16>,
 }
 
-pub struct TestItem {}
-impl Item for TestItem {
-    fn name(&self) -> &'static str {
-        "test_item"
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TimerMode {
+    Once,
+    Repeating,
+}
+
+// counts fixed timestep ticks
+#[derive(Clone, Copy)]
+pub struct Timer {
+    pub duration: u32,
+    pub elapsed: u32,
+    pub mode: TimerMode,
+    // repeating timers are only finished on the tick they wrap around
+    finished: bool,
+}
+
+impl Timer {
+    pub fn once(duration: u32) -> Self {
+        Timer {
+            duration,
+            elapsed: 0,
+            mode: TimerMode::Once,
+            finished: duration == 0,
+        }
     }
 
-    fn sprite(&self) -> Sprite {
-        (10, 0, 1, 1).into()
+    pub fn repeating(duration: u32) -> Self {
+        Timer {
+            mode: TimerMode::Repeating,
+            finished: false,
+            ..Timer::once(duration)
+        }
     }
 
-    fn on_tick(&mut self, _is_active: bool, _world: &World) -> InventoryCmd {
-        InventoryCmd::None
+    // a once timer that has already run out, for cooldowns
+    pub fn ready(duration: u32) -> Self {
+        Timer {
+            elapsed: duration,
+            finished: true,
+            ..Timer::once(duration)
+        }
     }
 
-    fn on_use(&mut self, _world: &World) -> InventoryCmd {
-        InventoryCmd::None
+    // returns whether the timer finished on this tick
+    pub fn tick(&mut self) -> bool {
+        match self.mode {
+            TimerMode::Once => {
+                if self.finished {
+                    return false;
+                }
+                self.elapsed += 1;
+                self.finished = self.elapsed >= self.duration;
+            }
+            TimerMode::Repeating => {
+                self.elapsed += 1;
+                self.finished = self.elapsed >= self.duration;
+                if self.finished {
+                    self.elapsed = 0;
+                }
+            }
+        }
+        self.finished
     }
 
-    fn on_select(&mut self, _world: &World) {}
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
 
-    fn on_deselect(&mut self, _world: &World) {}
+    pub fn reset(&mut self) {
+        self.elapsed = 0;
+        self.finished = self.duration == 0;
+    }
 }
 
-pub struct Torch {
-    pub is_lit: bool,
-    pub ticks_max: usize,
-    pub ticks_left: usize,
+pub const MAX_TIMERS: usize = 4;
+
+// named timers ticked by the timers system
+#[derive(Component)]
+pub struct Timers {
+    timers: [Option<(&'static str, Timer)>; MAX_TIMERS],
 }
 
-impl Torch {
+impl Timers {
     pub fn new() -> Self {
-        Torch {
-            is_lit: false,
-            ticks_max: 3600,
-            ticks_left: 3600,
+        Timers {
+            timers: [None; MAX_TIMERS],
         }
     }
-}
 
-impl Item for Torch {
-    fn name(&self) -> &'static str {
-        "torch"
+    pub fn with(mut self, name: &'static str, timer: Timer) -> Self {
+        self.insert(name, timer);
+        self
     }
 
-    fn sprite(&self) -> Sprite {
-        (10, 1, 1, 1).into()
+    pub fn insert(&mut self, name: &'static str, timer: Timer) {
+        let slot = match self
+            .timers
+            .iter()
+            .position(|t| matches!(t, Some((n, _)) if *n == name))
+        {
+            Some(idx) => idx,
+            None => self.timers.iter().position(|t| t.is_none()).unwrap(),
+        };
+        self.timers[slot] = Some((name, timer));
     }
 
-    fn on_tick(&mut self, _is_active: bool, world: &World) -> InventoryCmd {
-        if self.ticks_left == 0 {
-            world.run(|light: &mut Light, _: With<Player>| {
-                light.radius = 0;
-            });
-            return InventoryCmd::Remove;
-        } else {
-            world.run(|light: &mut Light, _: With<Player>| {
-                light.radius = (100. * self.ticks_left as f32 / self.ticks_max as f32) as u16 + 20;
-            });
-        }
-
-        if self.is_lit {
-            self.ticks_left = self.ticks_left.saturating_sub(1);
-        }
-
-        InventoryCmd::None
+    pub fn is_finished(&self, name: &str) -> bool {
+        self.timers
+            .iter()
+            .flatten()
+            .any(|(n, timer)| *n == name && timer.is_finished())
     }
 
-    fn on_use(&mut self, world: &World) -> InventoryCmd {
-        self.is_lit = true;
-        world.run(|light: &mut Light, _: With<Player>| {
-            light.color = Color::RGB(255, 255, 100);
-            light.radius = 150;
-            light.intensity = 1.;
-        });
-        InventoryCmd::None
+    pub fn tick(&mut self) {
+        for (_, timer) in self.timers.iter_mut().flatten() {
+            timer.tick();
+        }
     }
+}
 
-    fn on_select(&mut self, _world: &World) {}
+#[derive(Component)]
+pub struct Projectile {
+    pub velocity: Vec2<f32>,
+    pub damage: u16,
+    pub bounces_left: u8,
+    // targets it can pass through before stopping
+    pub pierces_left: u8,
+    pub last_hit: Option<Entity>,
+    pub effect: Option<StatusEffect>,
+}
 
-    fn on_deselect(&mut self, _world: &World) {}
+// blows up on impact, damage and knockback fall off towards the edge
+#[derive(Component)]
+pub struct Explosive {
+    pub radius: f32,
+    pub damage: u16,
+    pub knockback: f32,
+    // camera trauma, 0 to 1
+    pub shake: f32,
 }
 
-pub struct Chemlight {
-    pub uses_left: u16,
+// a light that flares up and fades out, despawned when done
+#[derive(Component)]
+pub struct LightFlash {
+    pub radius: u16,
+    pub intensity: f32,
+    pub timer: Timer,
 }
 
-impl Chemlight {
-    pub fn new() -> Self {
-        Chemlight { uses_left: 5 }
+impl LightFlash {
+    pub fn new(radius: u16, duration: u32) -> Self {
+        LightFlash {
+            radius,
+            intensity: 2.,
+            timer: Timer::once(duration),
+        }
     }
 }
 
-impl Item for Chemlight {
-    fn name(&self) -> &'static str {
-        "chemlight"
+#[derive(Component)]
+pub struct Health {
+    pub hp: i32,
+    pub max_hp: i32,
+}
+
+impl Health {
+    pub fn new(max_hp: i32) -> Self {
+        Health { hp: max_hp, max_hp }
     }
+}
 
-    fn sprite(&self) -> Sprite {
-        (12, 1, 1, 1).into()
+#[derive(Clone, Copy, PartialEq)]
+pub enum StatusKind {
+    Burning,
+    Slow,
+    Poison,
+    Invulnerable,
+    Wet,
+}
+
+#[derive(Clone, Copy)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub ticks_left: u32,
+    // damage is dealt every damage_interval ticks, 0 counts as 1
+    pub damage: u16,
+    pub damage_interval: u32,
+    pub speed_multiplier: f32,
+    pub tint: Option<Color>,
+}
+
+impl StatusEffect {
+    pub fn burning(ticks: u32) -> Self {
+        StatusEffect {
+            kind: StatusKind::Burning,
+            ticks_left: ticks,
+            damage: 1,
+            damage_interval: 30,
+            speed_multiplier: 1.,
+            tint: Some(Color::RGB(255, 140, 60)),
+        }
     }
 
-    fn on_tick(&mut self, _is_active: bool, _world: &World) -> InventoryCmd {
-        InventoryCmd::None
+    pub fn slow(ticks: u32) -> Self {
+        StatusEffect {
+            kind: StatusKind::Slow,
+            ticks_left: ticks,
+            damage: 0,
+            damage_interval: 0,
+            speed_multiplier: 0.5,
+            tint: Some(Color::RGB(140, 170, 255)),
+        }
     }
 
-    fn on_use(&mut self, world: &World) -> InventoryCmd {
-        let ctx = world.resource::<Ctx>().unwrap();
-        world.spawn(&[
-            &ctx.player_pos,
-            &AnimatedSprite::new(
-                (-16, -16, 32, 32),
-                0,
-                ctx.animations.get("chemlight").unwrap(),
-                None,
-            ),
-            &Light {
-                radius: 120,
-                color: Color::RGB(0, 255, 0),
-                intensity: 1.,
-            },
-        ]);
-        self.uses_left -= 1;
-        if self.uses_left == 0 {
-            InventoryCmd::Remove
-        } else {
-            InventoryCmd::None
+    pub fn poison(ticks: u32) -> Self {
+        StatusEffect {
+            kind: StatusKind::Poison,
+            ticks_left: ticks,
+            damage: 1,
+            damage_interval: 60,
+            speed_multiplier: 0.8,
+            tint: Some(Color::RGB(120, 255, 120)),
         }
     }
 
-    fn on_select(&mut self, _world: &World) {}
+    // wading through water, refreshed every tick while in it
+    pub fn wet(ticks: u32) -> Self {
+        StatusEffect {
+            kind: StatusKind::Wet,
+            ticks_left: ticks,
+            damage: 0,
+            damage_interval: 0,
+            speed_multiplier: 0.6,
+            tint: None,
+        }
+    }
 
-    fn on_deselect(&mut self, _world: &World) {}
+    // i-frames, damage and knockback are ignored
+    pub fn invulnerable(ticks: u32) -> Self {
+        StatusEffect {
+            kind: StatusKind::Invulnerable,
+            ticks_left: ticks,
+            damage: 0,
+            damage_interval: 0,
+            speed_multiplier: 1.,
+            tint: None,
+        }
+    }
 }
 
-pub enum InventoryCmd {
-    None,
-    Remove,
-}
+pub const MAX_STATUS_EFFECTS: usize = 4;
 
-// FIXME awful everything
-pub struct Inventory {
-    items: [Option<Box<dyn Item>>; 8],
-    num_items: u16,
-    active_item_idx: u16,
+#[derive(Component)]
+pub struct StatusEffects {
+    pub effects: [Option<StatusEffect>; MAX_STATUS_EFFECTS],
 }
 
-impl Inventory {
+impl StatusEffects {
     pub fn new() -> Self {
-        Inventory {
-            // TODO there's supposed to be a way to do this
-            // items: [None; 8],
-            items: [None, None, None, None, None, None, None, None],
-            num_items: 0,
-            active_item_idx: 0,
+        StatusEffects {
+            effects: [None; MAX_STATUS_EFFECTS],
         }
     }
 
-    pub fn insert(&mut self, item: impl Item + 'static, world: &World) -> bool {
-        if self.num_items < 8 {
-            for slot in self.items.iter_mut() {
-                if slot.is_none() {
-                    let item = slot.insert(Box::new(item));
-                    if self.num_items == 0 {
-                        item.on_select(world)
-                    }
-                    self.num_items += 1;
-                    return true;
-                }
-            }
-            false
-        } else {
-            false
+    // reapplying an effect refreshes its duration instead of stacking
+    pub fn apply(&mut self, effect: StatusEffect) {
+        if let Some(existing) = self
+            .effects
+            .iter_mut()
+            .flatten()
+            .find(|e| e.kind == effect.kind)
+        {
+            existing.ticks_left = existing.ticks_left.max(effect.ticks_left);
+        } else if let Some(slot) = self.effects.iter_mut().find(|e| e.is_none()) {
+            *slot = Some(effect);
         }
     }
 
-    pub fn has_item(&self, name: &'static str) -> bool {
-        if self.num_items > 0 {
-            for item in self.items.iter().flatten() {
-                if item.name() == name {
-                    return true;
-                }
-            }
-        }
-        false
+    pub fn speed_multiplier(&self) -> f32 {
+        self.effects
+            .iter()
+            .flatten()
+            .map(|e| e.speed_multiplier)
+            .product()
     }
 
-    pub fn active_item(&self) -> Option<&dyn Item> {
-        self.items[self.active_item_idx as usize].as_deref()
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().flatten().any(|e| e.kind == kind)
     }
 
-    fn next_idx_right(&self) -> Option<u16> {
-        let mut idx = self.active_item_idx;
-        let mut i = 0;
-        while i < 7 {
-            if self.items[idx as usize].is_some() && idx != self.active_item_idx {
-                return Some(idx);
+    pub fn remove(&mut self, kind: StatusKind) {
+        for slot in self.effects.iter_mut() {
+            if slot.is_some_and(|e| e.kind == kind) {
+                *slot = None;
             }
-            idx = (idx + 1) % 8;
-            i += 1;
         }
-        None
     }
 
-    fn next_idx_left(&self) -> Option<u16> {
-        let mut idx = self.active_item_idx;
-        let mut i = 0;
-        while i < 7 {
-            if self.items[idx as usize].is_some() && idx != self.active_item_idx {
-                return Some(idx);
-            }
-            idx = (idx.wrapping_sub(1)) % 8;
-            i += 1;
-        }
-        None
+    pub fn is_invulnerable(&self) -> bool {
+        self.has(StatusKind::Invulnerable)
     }
 
-    pub fn tick(&mut self, world: &World) {
-        for i in 0..8 {
-            if let Some(item) = self.items[i].as_mut() {
-                let cmd = item.on_tick(i == self.active_item_idx as usize, world);
+    pub fn has_tint(&self) -> bool {
+        self.effects.iter().flatten().any(|e| e.tint.is_some())
+    }
+
+    pub fn tint(&self) -> Color {
+        self.effects
+            .iter()
+            .flatten()
+            .find_map(|e| e.tint)
+            .unwrap_or(Color::WHITE)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum LootDrop {
+    Nothing,
+    Coins(u32),
+    Prefab(&'static str),
+    // by the names the give command uses, left in the corpse instead of on the floor
+    Item(&'static str),
+}
+
+#[derive(Clone, Copy)]
+pub struct LootEntry {
+    pub weight: u32,
+    pub drop: LootDrop,
+}
+
+pub const MAX_LOOT_ENTRIES: usize = 6;
+
+// rolled on death, every roll picks one entry by weight
+#[derive(Component)]
+pub struct LootTable {
+    pub rolls: u32,
+    pub entries: [Option<LootEntry>; MAX_LOOT_ENTRIES],
+}
+
+impl LootTable {
+    pub fn new(rolls: u32, entries: &[LootEntry]) -> Self {
+        let mut slots = [None; MAX_LOOT_ENTRIES];
+        for (slot, entry) in slots.iter_mut().zip(entries) {
+            *slot = Some(*entry);
+        }
+        LootTable {
+            rolls,
+            entries: slots,
+        }
+    }
+
+    pub fn roll(&self, rng: &mut impl Rng) -> LootDrop {
+        let total: u32 = self.entries.iter().flatten().map(|e| e.weight).sum();
+        if total == 0 {
+            return LootDrop::Nothing;
+        }
+
+        let mut pick = rng.gen_range(0..total);
+        for entry in self.entries.iter().flatten() {
+            if pick < entry.weight {
+                return entry.drop;
+            }
+            pick -= entry.weight;
+        }
+        LootDrop::Nothing
+    }
+}
+
+// lying on the floor until the player walks over it
+#[derive(Component)]
+pub struct Pickup {
+    pub velocity: Vec2<f32>,
+    pub coins: u32,
+    // floats above the player when taken
+    pub label: Option<&'static str>,
+    // (the player taking it), returns false when the pickup can't be taken right now
+    pub on_pickup: Option<fn(&World, Entity) -> bool>,
+}
+
+#[derive(Component)]
+pub struct Light {
+    pub radius: u16,
+    pub color: Color,
+    pub intensity: f32,
+    pub cone: Option<LightCone>, // None is omnidirectional
+}
+
+#[derive(Clone, Copy)]
+pub struct LightCone {
+    pub direction: f32, // radians
+    pub angle: f32,     // full width, radians
+}
+
+// ambient light the lightmap fades to while the player is inside, see ambient.rs
+#[derive(Component)]
+pub struct AmbienceZone {
+    pub width: u32,
+    pub height: u32,
+    pub color: Color,
+    pub is_stormy: bool,
+}
+
+impl AmbienceZone {
+    pub fn contains(&self, zone_pos: &Pos, pos: &Pos) -> bool {
+        (pos.x - zone_pos.x).abs() <= self.width as f32 / 2.
+            && (pos.y - zone_pos.y).abs() <= self.height as f32 / 2.
+    }
+}
+
+// perturbs a Light around the base values every tick
+#[derive(Component)]
+pub struct LightAnimator {
+    pub base: Light,
+    // fraction of the base intensity
+    pub flicker_amplitude: f32,
+    // noise features per second
+    pub flicker_frequency: f32,
+    // fraction of the base radius
+    pub pulse_amplitude: f32,
+    pub pulse_period: u32,
+    // cycles from the base color to this one and back
+    pub cycle_color: Option<Color>,
+    pub cycle_period: u32,
+    pub ticks: u32,
+    pub flicker: f32,
+    // picked the first tick
+    pub seed: Option<u32>,
+}
+
+impl LightAnimator {
+    pub fn new(base: Light) -> Self {
+        LightAnimator {
+            base,
+            flicker_amplitude: 0.,
+            flicker_frequency: 0.,
+            pulse_amplitude: 0.,
+            pulse_period: 1,
+            cycle_color: None,
+            cycle_period: 1,
+            ticks: 0,
+            flicker: 0.,
+            seed: None,
+        }
+    }
+
+    pub fn fire(base: Light) -> Self {
+        LightAnimator {
+            flicker_amplitude: 0.25,
+            flicker_frequency: 12.,
+            pulse_amplitude: 0.05,
+            pulse_period: 90,
+            cycle_color: Some(Color::RGB(255, 140, 0)),
+            cycle_period: 150,
+            ..LightAnimator::new(base)
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Floor {}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SurfaceKind {
+    Stone,
+    Wood,
+    Water,
+}
+
+impl SurfaceKind {
+    // footstep sounds and dust presets are named footstep_<name>
+    pub fn name(self) -> &'static str {
+        match self {
+            SurfaceKind::Stone => "stone",
+            SurfaceKind::Wood => "wood",
+            SurfaceKind::Water => "water",
+        }
+    }
+}
+
+// a looping sound that plays while the listener is within radius, see audio.rs
+#[derive(Component)]
+pub struct SoundEmitter {
+    pub sound: &'static str,
+    pub volume: f32,
+    pub radius: f32,
+}
+
+// what a floor tile is made of, floors without one are stone
+#[derive(Component)]
+pub struct Surface {
+    pub kind: SurfaceKind,
+}
+
+#[derive(Component)]
+pub struct Wall {}
+
+// covers a run of wall tiles, see walls.rs
+#[derive(Component)]
+pub struct WallCollider {}
+
+#[derive(Component)]
+pub struct Prop {}
+
+pub const MAX_MOVER_WAYPOINTS: usize = 8;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum MoverMode {
+    Loop,
+    PingPong,
+}
+
+// walks an entity along its waypoints, anything touching it gets carried along
+#[derive(Component)]
+pub struct Mover {
+    pub waypoints: [Option<Pos>; MAX_MOVER_WAYPOINTS],
+    pub speed: f32,
+    pub mode: MoverMode,
+    pub target: usize,
+    pub is_reversed: bool,
+    // how far the mover went this tick
+    pub delta: Vec2<f32>,
+}
+
+impl Mover {
+    pub fn new(waypoints: &[Pos], speed: f32, mode: MoverMode) -> Self {
+        let mut slots = [None; MAX_MOVER_WAYPOINTS];
+        for (slot, waypoint) in slots.iter_mut().zip(waypoints) {
+            *slot = Some(*waypoint);
+        }
+
+        Mover {
+            waypoints: slots,
+            speed,
+            mode,
+            target: 0,
+            is_reversed: false,
+            delta: Vec2::new(0., 0.),
+        }
+    }
+
+    pub fn num_waypoints(&self) -> usize {
+        self.waypoints.iter().flatten().count()
+    }
+
+    pub fn advance(&mut self) {
+        let last = self.num_waypoints() - 1;
+        match self.mode {
+            MoverMode::Loop => {
+                self.target = if self.target == last {
+                    0
+                } else {
+                    self.target + 1
+                }
+            }
+            MoverMode::PingPong => {
+                if self.target == last {
+                    self.is_reversed = true;
+                } else if self.target == 0 {
+                    self.is_reversed = false;
+                }
+
+                if self.is_reversed {
+                    self.target -= 1;
+                } else {
+                    self.target += 1;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Conveyor {
+    pub velocity: Vec2<f32>,
+}
+
+// velocity carried between ticks, friction below 1 makes it drift
+#[derive(Component)]
+pub struct Momentum {
+    pub velocity: Vec2<f32>,
+    pub friction: f32,
+}
+
+impl Momentum {
+    pub fn new() -> Self {
+        Momentum {
+            velocity: Vec2::new(0., 0.),
+            friction: 1.,
+        }
+    }
+
+    pub fn steer(&mut self, desired: Vec2<f32>) -> Vec2<f32> {
+        self.velocity = self.velocity.lerp(desired, self.friction);
+        self.velocity
+    }
+}
+
+// steering between nearby agents of the same kind, weights are relative to the chase speed
+#[derive(Component)]
+pub struct Flocking {
+    pub radius: f32,
+    // pushes away from neighbours so agents don't pile up
+    pub separation: f32,
+    // matches the neighbours' velocity
+    pub alignment: f32,
+    // pulls towards the neighbours' center
+    pub cohesion: f32,
+}
+
+impl Flocking {
+    pub fn separation(radius: f32, separation: f32) -> Self {
+        Flocking {
+            radius,
+            separation,
+            alignment: 0.,
+            cohesion: 0.,
+        }
+    }
+
+    pub fn with_flocking(mut self, alignment: f32, cohesion: f32) -> Self {
+        self.alignment = alignment;
+        self.cohesion = cohesion;
+        self
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HazardKind {
+    Spikes,
+    Lava,
+    Ice,
+    Water,
+}
+
+#[derive(Component)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    // spikes retract while inactive
+    pub is_active: bool,
+    pub ticks: u32,
+}
+
+// weighs down pressure plates
+#[derive(Component)]
+pub struct Heavy {}
+
+// shoved around by the player, velocity bleeds off every tick
+#[derive(Component)]
+pub struct Pushable {
+    pub velocity: Vec2<f32>,
+}
+
+// picked up with the interact key and thrown the way the player faces
+#[derive(Component)]
+pub struct Throwable {
+    pub damage: u16,
+    pub is_carried: bool,
+}
+
+// keeps to where its parent is, local places it relative to the parent. nothing happens while
+// there's no parent, and a parent that's despawned leaves it where it was
+#[derive(Component)]
+pub struct Attachment {
+    pub parent: Option<Entity>,
+    pub local: Transform,
+}
+
+#[derive(Component)]
+pub struct PressurePlate {}
+
+#[derive(Component)]
+pub struct Door {
+    pub is_open: bool,
+    pub ticks_until_open: u32,
+}
+
+#[derive(Component)]
+pub struct Interactable {
+    // (me, the player interacting)
+    pub on_interact: fn(&World, Entity, Entity),
+    // runs after on_interact, see scripts.rs
+    pub script: Option<&'static str>,
+}
+
+// holds items, players carry theirs in one and chests, corpses and vendors keep theirs. the
+// inventory isn't Copy like the other components, so a container is spawned empty and filled
+// through component_mut, see containers.rs
+pub struct Container {
+    // locale key
+    pub title: &'static str,
+    pub inventory: Inventory,
+}
+
+impl Component for Container {}
+
+#[derive(Component)]
+pub struct SignalEmitter {
+    pub channel: u16,
+    pub is_on: bool,
+}
+
+#[derive(Component)]
+pub struct SignalReceiver {
+    pub channel: u16,
+    pub on_signal: fn(&World, Entity, bool),
+}
+
+#[derive(Component)]
+pub struct ProximityIndicator {
+    pub range: f32,
+    pub sprite: AnimatedSprite,
+}
+
+// see particles.rs, made with particles::emitter
+#[derive(Component)]
+pub struct ParticleEmitter {
+    pub is_active: bool,
+    // index into ParticlePresets
+    pub preset: usize,
+    pub cadence: Timer,
+    // burst presets go off once each time the emitter turns on
+    pub has_burst: bool,
+}
+
+// moved and faded by particles::update, despawned when its life runs out
+#[derive(Component)]
+pub struct Particle {
+    pub preset: usize,
+    pub velocity: Vec2<f32>,
+    pub age: u32,
+    pub lifetime: u32,
+}
+
+#[derive(Component)]
+pub struct Static {}
+
+pub trait Item {
+    fn name(&self) -> &'static str;
+    fn sprite(&self) -> &'static str;
+    fn on_tick(&mut self, is_active: bool, holder: Entity, world: &World) -> InventoryCmd;
+    fn on_use(&mut self, holder: Entity, world: &World) -> InventoryCmd;
+    fn on_select(&mut self, holder: Entity, world: &World);
+    fn on_deselect(&mut self, holder: Entity, world: &World);
+    fn meta(&self) -> ItemMeta;
+
+    fn as_weapon_mut(&mut self) -> Option<&mut Weapon> {
+        None
+    }
+
+    // gear goes into an equip slot instead of being used
+    fn equip_slot(&self) -> Option<EquipSlot> {
+        None
+    }
+
+    fn bonus(&self) -> StatBonus {
+        StatBonus::default()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+}
+
+impl Rarity {
+    // locale key
+    pub fn name(self) -> &'static str {
+        match self {
+            Rarity::Common => "rarity-common",
+            Rarity::Uncommon => "rarity-uncommon",
+            Rarity::Rare => "rarity-rare",
+            Rarity::Epic => "rarity-epic",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Rarity::Common => Color::RGB(220, 220, 220),
+            Rarity::Uncommon => Color::RGB(80, 220, 80),
+            Rarity::Rare => Color::RGB(80, 140, 255),
+            Rarity::Epic => Color::RGB(200, 80, 255),
+        }
+    }
+}
+
+// what the tooltip says about an item
+#[derive(Clone, Copy)]
+pub struct ItemMeta {
+    // locale key
+    pub description: &'static str,
+    pub rarity: Rarity,
+    // nothing stacks in the inventory yet, this is how many would share a slot
+    pub stack_size: u16,
+    // ticks between uses, 0 for items without one
+    pub use_cooldown: u32,
+}
+
+impl ItemMeta {
+    pub fn new(description: &'static str, rarity: Rarity) -> Self {
+        ItemMeta {
+            description,
+            rarity,
+            stack_size: 1,
+            use_cooldown: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EquipSlot {
+    Armor,
+    Boots,
+    Trinket,
+}
+
+impl EquipSlot {
+    pub const ALL: [EquipSlot; 3] = [EquipSlot::Armor, EquipSlot::Boots, EquipSlot::Trinket];
+
+    // locale key
+    pub fn name(self) -> &'static str {
+        match self {
+            EquipSlot::Armor => "equip-armor",
+            EquipSlot::Boots => "equip-boots",
+            EquipSlot::Trinket => "equip-trinket",
+        }
+    }
+}
+
+// what gear adds on top of the base stats, fractions except for max_hp
+#[derive(Clone, Copy, Default)]
+pub struct StatBonus {
+    pub speed: f32,
+    pub max_hp: i32,
+    pub light_radius: f32,
+    pub damage: f32,
+}
+
+impl StatBonus {
+    fn add(self, other: StatBonus) -> StatBonus {
+        StatBonus {
+            speed: self.speed + other.speed,
+            max_hp: self.max_hp + other.max_hp,
+            light_radius: self.light_radius + other.light_radius,
+            damage: self.damage + other.damage,
+        }
+    }
+}
+
+// does nothing on its own, it only counts while equipped
+pub struct Gear {
+    name: &'static str,
+    sprite: &'static str,
+    meta: ItemMeta,
+    slot: EquipSlot,
+    bonus: StatBonus,
+}
+
+impl Gear {
+    pub fn armor() -> Self {
+        Gear {
+            name: "armor",
+            sprite: "item_armor",
+            meta: ItemMeta::new("item-armor", Rarity::Uncommon),
+            slot: EquipSlot::Armor,
+            bonus: StatBonus {
+                max_hp: 4,
+                speed: -0.1,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn boots() -> Self {
+        Gear {
+            name: "boots",
+            sprite: "item_boots",
+            meta: ItemMeta::new("item-boots", Rarity::Uncommon),
+            slot: EquipSlot::Boots,
+            bonus: StatBonus {
+                speed: 0.25,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn lantern_charm() -> Self {
+        Gear {
+            name: "lantern_charm",
+            sprite: "item_lantern_charm",
+            meta: ItemMeta::new("item-lantern-charm", Rarity::Rare),
+            slot: EquipSlot::Trinket,
+            bonus: StatBonus {
+                light_radius: 0.5,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn war_charm() -> Self {
+        Gear {
+            name: "war_charm",
+            sprite: "item_war_charm",
+            meta: ItemMeta::new("item-war-charm", Rarity::Epic),
+            slot: EquipSlot::Trinket,
+            bonus: StatBonus {
+                damage: 0.5,
+                max_hp: -2,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Item for Gear {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn sprite(&self) -> &'static str {
+        self.sprite
+    }
+
+    fn meta(&self) -> ItemMeta {
+        self.meta
+    }
+
+    fn on_tick(&mut self, _is_active: bool, _holder: Entity, _world: &World) -> InventoryCmd {
+        InventoryCmd::None
+    }
+
+    fn on_use(&mut self, _holder: Entity, _world: &World) -> InventoryCmd {
+        InventoryCmd::None
+    }
+
+    fn on_select(&mut self, _holder: Entity, _world: &World) {}
+
+    fn on_deselect(&mut self, _holder: Entity, _world: &World) {}
+
+    fn equip_slot(&self) -> Option<EquipSlot> {
+        Some(self.slot)
+    }
+
+    fn bonus(&self) -> StatBonus {
+        self.bonus
+    }
+}
+
+// a player's worn gear, one item per EquipSlot. not Copy either, see Container
+pub struct Equipment {
+    slots: [Option<Box<dyn Item>>; EquipSlot::ALL.len()],
+}
+
+impl Component for Equipment {}
+
+impl Equipment {
+    pub fn new() -> Self {
+        Equipment {
+            slots: std::array::from_fn(|_| None),
+        }
+    }
+
+    pub fn get(&self, slot: EquipSlot) -> Option<&dyn Item> {
+        self.slots[slot as usize].as_deref()
+    }
+
+    // hands back whatever was in the slot
+    pub fn equip(&mut self, item: Box<dyn Item>) -> Option<Box<dyn Item>> {
+        let slot = item.equip_slot().expect("only gear can be equipped");
+        self.slots[slot as usize].replace(item)
+    }
+
+    pub fn take(&mut self, slot: EquipSlot) -> Option<Box<dyn Item>> {
+        self.slots[slot as usize].take()
+    }
+
+    pub fn bonus(&self) -> StatBonus {
+        self.slots
+            .iter()
+            .flatten()
+            .fold(StatBonus::default(), |bonus, item| bonus.add(item.bonus()))
+    }
+}
+
+pub struct PerfectlyGenericItem {}
+impl Item for PerfectlyGenericItem {
+    fn name(&self) -> &'static str {
+        "perfectly_generic_item"
+    }
+
+    fn sprite(&self) -> &'static str {
+        "item_generic"
+    }
+
+    fn meta(&self) -> ItemMeta {
+        ItemMeta::new("item-perfectly-generic-item", Rarity::Common)
+    }
+
+    fn on_tick(&mut self, _is_active: bool, _holder: Entity, _world: &World) -> InventoryCmd {
+        InventoryCmd::None
+    }
+
+    fn on_use(&mut self, _holder: Entity, _world: &World) -> InventoryCmd {
+        InventoryCmd::None
+    }
+
+    fn on_select(&mut self, _holder: Entity, _world: &World) {}
+
+    fn on_deselect(&mut self, _holder: Entity, _world: &World) {}
+}
+
+pub struct TestItem {}
+impl Item for TestItem {
+    fn name(&self) -> &'static str {
+        "test_item"
+    }
+
+    fn sprite(&self) -> &'static str {
+        "item_test"
+    }
+
+    fn meta(&self) -> ItemMeta {
+        ItemMeta::new("item-test-item", Rarity::Common)
+    }
+
+    fn on_tick(&mut self, _is_active: bool, _holder: Entity, _world: &World) -> InventoryCmd {
+        InventoryCmd::None
+    }
+
+    fn on_use(&mut self, _holder: Entity, _world: &World) -> InventoryCmd {
+        InventoryCmd::None
+    }
+
+    fn on_select(&mut self, _holder: Entity, _world: &World) {}
+
+    fn on_deselect(&mut self, _holder: Entity, _world: &World) {}
+}
+
+pub struct Torch {
+    pub is_lit: bool,
+    pub ticks_max: usize,
+    pub ticks_left: usize,
+}
+
+impl Torch {
+    pub fn new() -> Self {
+        Torch {
+            is_lit: false,
+            ticks_max: 3600,
+            ticks_left: 3600,
+        }
+    }
+}
+
+impl Item for Torch {
+    fn name(&self) -> &'static str {
+        "torch"
+    }
+
+    fn sprite(&self) -> &'static str {
+        "item_torch"
+    }
+
+    fn meta(&self) -> ItemMeta {
+        ItemMeta::new("item-torch", Rarity::Common)
+    }
+
+    fn on_tick(&mut self, _is_active: bool, holder: Entity, world: &World) -> InventoryCmd {
+        let (Some(light), Some(stats)) = (
+            world.component_mut::<Light>(holder),
+            world.component::<PlayerStats>(holder),
+        ) else {
+            return InventoryCmd::None;
+        };
+        if self.ticks_left == 0 {
+            light.radius = 0;
+            return InventoryCmd::Remove;
+        }
+        if !self.is_lit {
+            return InventoryCmd::None;
+        }
+
+        // goes out in water, but can be lit again
+        let in_water = world
+            .component::<StatusEffects>(holder)
+            .is_some_and(|status| status.has(StatusKind::Wet));
+        if in_water {
+            self.is_lit = false;
+            light.radius = 0;
+            return InventoryCmd::None;
+        }
+
+        let radius = (100. * self.ticks_left as f32 / self.ticks_max as f32) as u16 + 20;
+        light.radius = stats.light(radius);
+        self.ticks_left = self.ticks_left.saturating_sub(1);
+
+        InventoryCmd::None
+    }
+
+    fn on_use(&mut self, holder: Entity, world: &World) -> InventoryCmd {
+        let (Some(light), Some(stats)) = (
+            world.component_mut::<Light>(holder),
+            world.component::<PlayerStats>(holder),
+        ) else {
+            return InventoryCmd::None;
+        };
+        self.is_lit = true;
+        light.color = Color::RGB(255, 255, 100);
+        light.radius = stats.light(150);
+        light.intensity = 1.;
+        light.cone = None;
+        InventoryCmd::None
+    }
+
+    fn on_select(&mut self, _holder: Entity, _world: &World) {}
+
+    fn on_deselect(&mut self, _holder: Entity, _world: &World) {}
+}
+
+pub struct Chemlight {
+    pub uses_left: u16,
+}
+
+impl Chemlight {
+    pub fn new() -> Self {
+        Chemlight { uses_left: 5 }
+    }
+}
+
+impl Item for Chemlight {
+    fn name(&self) -> &'static str {
+        "chemlight"
+    }
+
+    fn sprite(&self) -> &'static str {
+        "item_chemlight"
+    }
+
+    fn meta(&self) -> ItemMeta {
+        ItemMeta::new("item-chemlight", Rarity::Common)
+    }
+
+    fn on_tick(&mut self, _is_active: bool, _holder: Entity, _world: &World) -> InventoryCmd {
+        InventoryCmd::None
+    }
+
+    fn on_use(&mut self, holder: Entity, world: &World) -> InventoryCmd {
+        let Some(pos) = world.component::<Pos>(holder) else {
+            return InventoryCmd::None;
+        };
+        world.spawn(&[
+            pos,
+            &AnimatedSprite::new((-16, -16, 32, 32), 0, animation(world, "chemlight"), None),
+            &Light {
+                radius: 120,
+                color: Color::RGB(0, 255, 0),
+                intensity: 1.,
+                cone: None,
+            },
+        ]);
+        self.uses_left -= 1;
+        if self.uses_left == 0 {
+            InventoryCmd::Remove
+        } else {
+            InventoryCmd::None
+        }
+    }
+
+    fn on_select(&mut self, _holder: Entity, _world: &World) {}
+
+    fn on_deselect(&mut self, _holder: Entity, _world: &World) {}
+}
+
+pub struct Flashlight {
+    pub is_on: bool,
+    // the holder's light from before it was switched on, put back when it's switched off
+    saved: Option<Light>,
+}
+
+impl Flashlight {
+    pub fn new() -> Self {
+        Flashlight {
+            is_on: false,
+            saved: None,
+        }
+    }
+
+    // the holder's light, a cone while it's on and back to what it was when it's off. facing
+    // is the holder's while the player systems run, see Ctx::swap_players
+    fn update_light(&mut self, holder: Entity, world: &World) {
+        let facing = world.resource::<Ctx>().unwrap().player_facing;
+        let (Some(light), Some(stats)) = (
+            world.component_mut::<Light>(holder),
+            world.component::<PlayerStats>(holder),
+        ) else {
+            return;
+        };
+        if self.is_on {
+            self.saved = Some(*light);
+            light.color = Color::RGB(255, 250, 220);
+            light.radius = stats.light(300);
+            light.intensity = 1.;
+            light.cone = Some(LightCone {
+                direction: facing.angle(),
+                angle: 0.8,
+            });
+        } else if let Some(saved) = self.saved.take() {
+            *light = saved;
+        }
+    }
+}
+
+impl Item for Flashlight {
+    fn name(&self) -> &'static str {
+        "flashlight"
+    }
+
+    fn sprite(&self) -> &'static str {
+        "item_flashlight"
+    }
+
+    fn meta(&self) -> ItemMeta {
+        ItemMeta::new("item-flashlight", Rarity::Uncommon)
+    }
+
+    fn on_tick(&mut self, _is_active: bool, holder: Entity, world: &World) -> InventoryCmd {
+        if self.is_on {
+            let facing = world.resource::<Ctx>().unwrap().player_facing;
+            if let Some(cone) = world
+                .component_mut::<Light>(holder)
+                .and_then(|light| light.cone.as_mut())
+            {
+                cone.direction = facing.angle();
+            }
+        }
+        InventoryCmd::None
+    }
+
+    fn on_use(&mut self, holder: Entity, world: &World) -> InventoryCmd {
+        self.is_on = !self.is_on;
+        self.update_light(holder, world);
+        InventoryCmd::None
+    }
+
+    fn on_select(&mut self, _holder: Entity, _world: &World) {}
+
+    // switched off when put away, the cone would otherwise stay with whatever's held next
+    fn on_deselect(&mut self, holder: Entity, world: &World) {
+        if self.is_on {
+            self.is_on = false;
+            self.update_light(holder, world);
+        }
+    }
+}
+
+// a hit arc in front of the player
+pub struct MeleeSwing {
+    pub range: f32,
+    // radians, centered on the facing direction
+    pub arc: f32,
+    pub damage: u16,
+    pub knockback: f32,
+}
+
+pub struct Sword {
+    pub swing: MeleeSwing,
+    pub cooldown: Timer,
+}
+
+impl Sword {
+    pub fn new() -> Self {
+        Sword {
+            swing: MeleeSwing {
+                range: 48.,
+                arc: 2.,
+                damage: 2,
+                knockback: 16.,
+            },
+            cooldown: Timer::ready(25),
+        }
+    }
+}
+
+impl Item for Sword {
+    fn name(&self) -> &'static str {
+        "sword"
+    }
+
+    fn sprite(&self) -> &'static str {
+        "item_sword"
+    }
+
+    fn meta(&self) -> ItemMeta {
+        ItemMeta {
+            use_cooldown: self.cooldown.duration,
+            ..ItemMeta::new("item-sword", Rarity::Uncommon)
+        }
+    }
+
+    fn on_tick(&mut self, _is_active: bool, _holder: Entity, _world: &World) -> InventoryCmd {
+        self.cooldown.tick();
+        InventoryCmd::None
+    }
+
+    fn on_use(&mut self, holder: Entity, world: &World) -> InventoryCmd {
+        if self.cooldown.is_finished() {
+            self.cooldown.reset();
+            game::swing(world, holder, &self.swing);
+        }
+        InventoryCmd::None
+    }
+
+    fn on_select(&mut self, _holder: Entity, _world: &World) {}
+
+    fn on_deselect(&mut self, _holder: Entity, _world: &World) {}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WeaponKind {
+    Pistol,
+    Shotgun,
+    Automatic,
+    Bouncer,
+    Flamer,
+    Piercer,
+    Launcher,
+}
+
+pub struct Weapon {
+    pub kind: WeaponKind,
+    pub cooldown: Timer,
+    pub projectile_count: u16,
+    pub spread: f32, // radians
+    pub damage: u16,
+    pub bounces: u8,
+    pub pierces: u8,
+    pub explosive: Option<Explosive>,
+    pub ammo: Option<u16>, // None is infinite
+    pub effect: Option<StatusEffect>,
+}
+
+impl Weapon {
+    // its cooldown is player_fire_cooldown, see try_fire
+    pub fn pistol() -> Self {
+        Weapon {
+            kind: WeaponKind::Pistol,
+            cooldown: Timer::ready(0),
+            projectile_count: 1,
+            spread: 0.,
+            damage: 1,
+            bounces: 0,
+            pierces: 0,
+            explosive: None,
+            ammo: None,
+            effect: None,
+        }
+    }
+
+    pub fn shotgun() -> Self {
+        Weapon {
+            kind: WeaponKind::Shotgun,
+            cooldown: Timer::ready(45),
+            projectile_count: 5,
+            spread: 0.6,
+            damage: 1,
+            bounces: 0,
+            pierces: 0,
+            explosive: None,
+            ammo: Some(24),
+            effect: None,
+        }
+    }
+
+    pub fn automatic() -> Self {
+        Weapon {
+            kind: WeaponKind::Automatic,
+            cooldown: Timer::ready(6),
+            projectile_count: 1,
+            spread: 0.15,
+            damage: 1,
+            bounces: 0,
+            pierces: 0,
+            explosive: None,
+            ammo: Some(200),
+            effect: None,
+        }
+    }
+
+    pub fn bouncer() -> Self {
+        Weapon {
+            kind: WeaponKind::Bouncer,
+            cooldown: Timer::ready(30),
+            projectile_count: 1,
+            spread: 0.,
+            damage: 2,
+            bounces: 3,
+            pierces: 0,
+            explosive: None,
+            ammo: Some(40),
+            effect: None,
+        }
+    }
+
+    pub fn flamer() -> Self {
+        Weapon {
+            kind: WeaponKind::Flamer,
+            cooldown: Timer::ready(4),
+            projectile_count: 1,
+            spread: 0.3,
+            damage: 0,
+            bounces: 0,
+            pierces: 0,
+            explosive: None,
+            ammo: Some(150),
+            effect: Some(StatusEffect::burning(150)),
+        }
+    }
+
+    pub fn piercer() -> Self {
+        Weapon {
+            kind: WeaponKind::Piercer,
+            cooldown: Timer::ready(40),
+            projectile_count: 1,
+            spread: 0.,
+            damage: 2,
+            bounces: 0,
+            pierces: 3,
+            explosive: None,
+            ammo: Some(30),
+            effect: None,
+        }
+    }
+
+    pub fn launcher() -> Self {
+        Weapon {
+            kind: WeaponKind::Launcher,
+            cooldown: Timer::ready(60),
+            projectile_count: 1,
+            spread: 0.,
+            damage: 0,
+            bounces: 0,
+            pierces: 0,
+            explosive: Some(Explosive {
+                radius: 72.,
+                damage: 4,
+                knockback: 24.,
+                shake: 0.6,
+            }),
+            ammo: Some(12),
+            effect: None,
+        }
+    }
+
+    pub fn try_fire(&mut self, tuning: &Tuning) -> bool {
+        // read every shot so a reload or the set command applies to pistols already held
+        if self.kind == WeaponKind::Pistol {
+            self.cooldown.duration = tuning.player_fire_cooldown;
+        }
+        if !self.cooldown.is_finished() || self.ammo == Some(0) {
+            return false;
+        }
+
+        if let Some(ammo) = self.ammo.as_mut() {
+            *ammo -= 1;
+        }
+        self.cooldown.reset();
+        true
+    }
+}
+
+impl Item for Weapon {
+    fn name(&self) -> &'static str {
+        match self.kind {
+            WeaponKind::Pistol => "pistol",
+            WeaponKind::Shotgun => "shotgun",
+            WeaponKind::Automatic => "automatic",
+            WeaponKind::Bouncer => "bouncer",
+            WeaponKind::Flamer => "flamer",
+            WeaponKind::Piercer => "piercer",
+            WeaponKind::Launcher => "launcher",
+        }
+    }
+
+    fn sprite(&self) -> &'static str {
+        match self.kind {
+            WeaponKind::Pistol => "item_pistol",
+            WeaponKind::Shotgun => "item_shotgun",
+            WeaponKind::Automatic => "item_automatic",
+            WeaponKind::Bouncer => "item_bouncer",
+            WeaponKind::Flamer => "item_flamer",
+            WeaponKind::Piercer => "item_piercer",
+            WeaponKind::Launcher => "item_launcher",
+        }
+    }
+
+    fn meta(&self) -> ItemMeta {
+        let (description, rarity) = match self.kind {
+            WeaponKind::Pistol => ("item-pistol", Rarity::Common),
+            WeaponKind::Shotgun => ("item-shotgun", Rarity::Uncommon),
+            WeaponKind::Automatic => ("item-automatic", Rarity::Rare),
+            WeaponKind::Bouncer => ("item-bouncer", Rarity::Uncommon),
+            WeaponKind::Flamer => ("item-flamer", Rarity::Rare),
+            WeaponKind::Piercer => ("item-piercer", Rarity::Rare),
+            WeaponKind::Launcher => ("item-launcher", Rarity::Epic),
+        };
+        ItemMeta {
+            use_cooldown: self.cooldown.duration,
+            ..ItemMeta::new(description, rarity)
+        }
+    }
+
+    fn on_tick(&mut self, _is_active: bool, _holder: Entity, _world: &World) -> InventoryCmd {
+        self.cooldown.tick();
+        InventoryCmd::None
+    }
+
+    fn on_use(&mut self, _holder: Entity, _world: &World) -> InventoryCmd {
+        InventoryCmd::None
+    }
+
+    fn on_select(&mut self, _holder: Entity, _world: &World) {}
+
+    fn on_deselect(&mut self, _holder: Entity, _world: &World) {}
+
+    fn as_weapon_mut(&mut self) -> Option<&mut Weapon> {
+        Some(self)
+    }
+}
+
+pub const INVENTORY_SIZE: usize = 8;
+
+pub enum InventoryCmd {
+    None,
+    Remove,
+}
+
+// FIXME awful everything
+pub struct Inventory {
+    // INVENTORY_SIZE for anything carried
+    items: Vec<Option<Box<dyn Item>>>,
+    num_items: u16,
+    active_item_idx: u16,
+    // the player carrying it, the items act on them. None for containers, moving items in and
+    // out of them doesn't select anything
+    holder: Option<Entity>,
+}
+
+impl Inventory {
+    pub fn carried_by(holder: Entity) -> Self {
+        Inventory {
+            items: (0..INVENTORY_SIZE).map(|_| None).collect(),
+            num_items: 0,
+            active_item_idx: 0,
+            holder: Some(holder),
+        }
+    }
+
+    // for chests and the like, see containers.rs
+    pub fn container(size: usize) -> Self {
+        Inventory {
+            items: (0..size).map(|_| None).collect(),
+            num_items: 0,
+            active_item_idx: 0,
+            holder: None,
+        }
+    }
+
+    pub fn insert(&mut self, item: impl Item + 'static, world: &World) -> bool {
+        self.insert_boxed(Box::new(item), world).is_ok()
+    }
+
+    // hands the item back when there's no room
+    pub fn insert_boxed(
+        &mut self,
+        item: Box<dyn Item>,
+        world: &World,
+    ) -> Result<(), Box<dyn Item>> {
+        let Some(idx) = self.items.iter().position(|slot| slot.is_none()) else {
+            return Err(item);
+        };
+        let item = self.items[idx].insert(item);
+        if let Some(holder) = self.holder.filter(|_| self.num_items == 0) {
+            self.active_item_idx = idx as u16;
+            item.on_select(holder, world)
+        }
+        self.num_items += 1;
+        Ok(())
+    }
+
+    // the active item is deselected on the way out
+    pub fn take(&mut self, idx: usize, world: &World) -> Option<Box<dyn Item>> {
+        let mut item = self.items.get_mut(idx)?.take()?;
+        if let Some(holder) = self.holder.filter(|_| idx == self.active_item_idx as usize) {
+            item.on_deselect(holder, world);
+        }
+        self.num_items -= 1;
+        Some(item)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.num_items as usize >= self.items.len()
+    }
+
+    // (idx, item) of the slots that aren't empty
+    pub fn items(&self) -> impl Iterator<Item = (usize, &dyn Item)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| Some((idx, item.as_deref()?)))
+    }
+
+    pub fn has_item(&self, name: &'static str) -> bool {
+        if self.num_items > 0 {
+            for item in self.items.iter().flatten() {
+                if item.name() == name {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn active_item(&self) -> Option<&dyn Item> {
+        self.items[self.active_item_idx as usize].as_deref()
+    }
+
+    pub fn active_weapon_mut(&mut self) -> Option<&mut Weapon> {
+        self.items[self.active_item_idx as usize]
+            .as_mut()?
+            .as_weapon_mut()
+    }
+
+    pub fn active_idx(&self) -> usize {
+        self.active_item_idx as usize
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&dyn Item> {
+        self.items.get(idx)?.as_deref()
+    }
+
+    pub fn set_active(&mut self, idx: usize, world: &World) {
+        let Some(holder) = self.holder else {
+            return;
+        };
+        if idx == self.active_item_idx as usize || self.get(idx).is_none() {
+            return;
+        }
+
+        if let Some(item) = self.items[self.active_item_idx as usize].as_mut() {
+            item.on_deselect(holder, world);
+        }
+
+        self.active_item_idx = idx as u16;
+
+        if let Some(item) = self.items[idx].as_mut() {
+            item.on_select(holder, world);
+        }
+    }
+
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.items.swap(a, b);
+
+        // the active item follows the move
+        if self.active_item_idx as usize == a {
+            self.active_item_idx = b as u16;
+        } else if self.active_item_idx as usize == b {
+            self.active_item_idx = a as u16;
+        }
+    }
+
+    fn next_idx_right(&self) -> Option<u16> {
+        let mut idx = self.active_item_idx;
+        let mut i = 0;
+        while i < INVENTORY_SIZE - 1 {
+            if self.items[idx as usize].is_some() && idx != self.active_item_idx {
+                return Some(idx);
+            }
+            idx = (idx + 1) % INVENTORY_SIZE as u16;
+            i += 1;
+        }
+        None
+    }
+
+    fn next_idx_left(&self) -> Option<u16> {
+        let mut idx = self.active_item_idx;
+        let mut i = 0;
+        while i < INVENTORY_SIZE - 1 {
+            if self.items[idx as usize].is_some() && idx != self.active_item_idx {
+                return Some(idx);
+            }
+            idx = (idx + INVENTORY_SIZE as u16 - 1) % INVENTORY_SIZE as u16;
+            i += 1;
+        }
+        None
+    }
+
+    pub fn tick(&mut self, world: &World) {
+        let Some(holder) = self.holder else {
+            return;
+        };
+        for i in 0..INVENTORY_SIZE {
+            if let Some(item) = self.items[i].as_mut() {
+                let cmd = item.on_tick(i == self.active_item_idx as usize, holder, world);
                 match cmd {
                     InventoryCmd::None => (),
                     InventoryCmd::Remove => {
                         *self.items.get_mut(i).unwrap() = None;
+                        self.num_items -= 1;
                     }
                 }
             }
@@ -481,30 +2189,34 @@ impl Inventory {
 
     pub fn set_active_offset(&mut self, offset: i16, world: &World) {
         let mut i = 0;
-        if offset == 0 {
+        let Some(holder) = self.holder.filter(|_| offset != 0) else {
             return;
-        }
+        };
 
         if let Some(item) = self.items.get_mut(self.active_item_idx as usize).unwrap() {
-            item.on_deselect(world);
+            item.on_deselect(holder, world);
         }
 
         if offset > 0 {
-            self.active_item_idx = (self.active_item_idx as i16 + offset) as u16 % 8;
-            while i < 8 && self.items[self.active_item_idx as usize].is_none() {
+            self.active_item_idx =
+                (self.active_item_idx as i16 + offset) as u16 % INVENTORY_SIZE as u16;
+            while i < INVENTORY_SIZE && self.items[self.active_item_idx as usize].is_none() {
                 i += 1;
-                self.active_item_idx = (self.active_item_idx + 1) % 8;
+                self.active_item_idx = (self.active_item_idx + 1) % INVENTORY_SIZE as u16;
             }
         } else {
-            self.active_item_idx = (self.active_item_idx as i16 + 8 + offset) as u16 % 8;
-            while i < 8 && self.items[self.active_item_idx as usize].is_none() {
+            self.active_item_idx = (self.active_item_idx as i16 + INVENTORY_SIZE as i16 + offset)
+                as u16
+                % INVENTORY_SIZE as u16;
+            while i < INVENTORY_SIZE && self.items[self.active_item_idx as usize].is_none() {
                 i += 1;
-                self.active_item_idx = self.active_item_idx.wrapping_sub(1) % 8;
+                self.active_item_idx =
+                    (self.active_item_idx + INVENTORY_SIZE as u16 - 1) % INVENTORY_SIZE as u16;
             }
         }
 
         if let Some(item) = self.items.get_mut(self.active_item_idx as usize).unwrap() {
-            item.on_select(world);
+            item.on_select(holder, world);
         }
     }
 
@@ -523,12 +2235,17 @@ impl Inventory {
     }
 
     pub fn do_use(&mut self, world: &World) {
+        let Some(holder) = self.holder else {
+            return;
+        };
         if let Some(item) = self.items.get_mut(self.active_item_idx as usize).unwrap() {
-            let cmd = item.on_use(world);
+            let cmd = item.on_use(holder, world);
+            events::send(world, ItemUsed { item: item.name() });
             match cmd {
                 InventoryCmd::None => (),
                 InventoryCmd::Remove => {
                     *self.items.get_mut(self.active_item_idx as usize).unwrap() = None;
+                    self.num_items -= 1;
                 }
             }
         }
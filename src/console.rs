@@ -0,0 +1,196 @@
+use ecs::{Resource, World};
+use sdl2::{event::Event, keyboard::Keycode, pixels::Color};
+
+use crate::ui::{Anchor, Ui};
+
+const MAX_LOG_LINES: usize = 200;
+const LINE_HEIGHT: i32 = 20;
+
+// returns the line to log, errors are logged in red
+pub type CommandFn = fn(&World, &[&str]) -> Result<String, String>;
+
+pub struct Command {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub run: CommandFn,
+}
+
+#[derive(Resource)]
+pub struct Console {
+    pub is_open: bool,
+    input: String,
+    history: Vec<String>,
+    // index into history while browsing with up/down
+    history_idx: Option<usize>,
+    log: Vec<(String, Color)>,
+    commands: Vec<Command>,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Console {
+            is_open: false,
+            input: String::new(),
+            history: Vec::new(),
+            history_idx: None,
+            log: Vec::new(),
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, name: &'static str, usage: &'static str, run: CommandFn) {
+        self.commands.push(Command { name, usage, run });
+    }
+
+    pub fn print(&mut self, line: impl Into<String>, color: Color) {
+        self.log.push((line.into(), color));
+        if self.log.len() > MAX_LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    fn browse_history(&mut self, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        self.history_idx = match (self.history_idx, older) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => None,
+            (Some(idx), true) => Some(idx.saturating_sub(1)),
+            (Some(idx), false) if idx + 1 < self.history.len() => Some(idx + 1),
+            (Some(_), false) => None,
+        };
+
+        self.input = match self.history_idx {
+            Some(idx) => self.history[idx].clone(),
+            None => String::new(),
+        };
+    }
+}
+
+// returns true if the console consumed the event
+pub fn handle_event(world: &World, event: &Event) -> bool {
+    let console = world.resource_mut::<Console>().unwrap();
+
+    match event {
+        Event::KeyDown {
+            keycode: Some(Keycode::Backquote),
+            ..
+        } => {
+            console.is_open = !console.is_open;
+            true
+        }
+        _ if !console.is_open => false,
+        Event::KeyDown {
+            keycode: Some(Keycode::Escape),
+            ..
+        } => {
+            console.is_open = false;
+            true
+        }
+        Event::TextInput { text, .. } => {
+            console.input.extend(text.chars().filter(|c| *c != '`'));
+            true
+        }
+        Event::KeyDown {
+            keycode: Some(Keycode::Backspace),
+            ..
+        } => {
+            console.input.pop();
+            true
+        }
+        Event::KeyDown {
+            keycode: Some(Keycode::Up),
+            ..
+        } => {
+            console.browse_history(true);
+            true
+        }
+        Event::KeyDown {
+            keycode: Some(Keycode::Down),
+            ..
+        } => {
+            console.browse_history(false);
+            true
+        }
+        Event::KeyDown {
+            keycode: Some(Keycode::Return),
+            ..
+        } => {
+            let line = std::mem::take(&mut console.input);
+            console.history_idx = None;
+            if !line.trim().is_empty() {
+                console.history.push(line.clone());
+                execute(world, &line);
+            }
+            true
+        }
+        Event::KeyDown { .. } | Event::KeyUp { .. } => true,
+        _ => false,
+    }
+}
+
+pub fn execute(world: &World, line: &str) {
+    let console = world.resource_mut::<Console>().unwrap();
+    console.print(format!("> {}", line), Color::RGB(150, 150, 150));
+
+    let args: Vec<&str> = line.split_whitespace().collect();
+    let Some((name, args)) = args.split_first() else {
+        return;
+    };
+
+    match *name {
+        "help" => {
+            for idx in 0..console.commands.len() {
+                let usage = console.commands[idx].usage;
+                console.print(usage, Color::RGB(255, 255, 255));
+            }
+        }
+        "clear" => console.log.clear(),
+        _ => match console.commands.iter().find(|cmd| cmd.name == *name) {
+            // the command may print to the console itself
+            Some(cmd) => match (cmd.run)(world, args) {
+                Ok(out) if out.is_empty() => {}
+                Ok(out) => console.print(out, Color::RGB(255, 255, 255)),
+                Err(err) => console.print(err, Color::RGB(255, 80, 80)),
+            },
+            None => console.print(
+                format!("unknown command '{}', try help", name),
+                Color::RGB(255, 80, 80),
+            ),
+        },
+    }
+}
+
+pub fn draw(console: &Console, ui: &mut Ui) {
+    let viewport = ui.viewport();
+    let height = viewport.height() / 2;
+    let visible_lines = (height as i32 / LINE_HEIGHT - 1).max(0) as usize;
+
+    ui.fill(
+        Anchor::TopLeft,
+        (0, 0),
+        (viewport.width(), height),
+        Color::RGBA(0, 0, 0, 200),
+        |ui| {
+            for (idx, (line, color)) in console.log.iter().rev().take(visible_lines).enumerate() {
+                ui.label(
+                    Anchor::BottomLeft,
+                    (8, -8 - LINE_HEIGHT * (idx as i32 + 1)),
+                    line,
+                    *color,
+                    None,
+                );
+            }
+
+            ui.label(
+                Anchor::BottomLeft,
+                (8, -8),
+                &format!("] {}_", console.input),
+                Color::RGB(255, 255, 0),
+                None,
+            );
+        },
+    );
+}
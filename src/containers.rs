@@ -0,0 +1,189 @@
+// the transfer screen between a Container and the player who opened it. interacting opens
+// the container next to that player's own, clicking an item moves it to the other side
+//
+// the mouse does the clicking, so it's the first player's input either way
+
+use ecs::{Entity, Resource, World};
+use sdl2::{pixels::Color, rect::Rect};
+
+use crate::{
+    components::{Container, Inventory, Pos, INVENTORY_SIZE},
+    locale::tr,
+    tooltip,
+    ui::{Anchor, Ui},
+    Ctx, DrawOptions, SpriteRegistry,
+};
+
+const SLOT_SIZE: i32 = 48;
+// between the container's row and the player's
+const GAP: i32 = 40;
+// walking further away than this closes it
+const RANGE: f32 = 48.;
+
+#[derive(Resource)]
+pub struct Containers {
+    // (the container, the player looting it)
+    pub open: Option<(Entity, Entity)>,
+}
+
+impl Containers {
+    pub fn new() -> Self {
+        Containers { open: None }
+    }
+}
+
+// the player's own items, what they carry is a Container too
+pub fn inventory(world: &World, entity: Entity) -> Option<&Inventory> {
+    Some(&world.component::<Container>(entity)?.inventory)
+}
+
+pub fn inventory_mut(world: &World, entity: Entity) -> Option<&mut Inventory> {
+    Some(&mut world.component_mut::<Container>(entity)?.inventory)
+}
+
+pub fn open(world: &World, entity: Entity, looter: Entity) {
+    if world.has_component::<Container>(looter) {
+        world.resource_mut::<Containers>().unwrap().open = Some((entity, looter));
+    }
+}
+
+// (is_container, idx) under the point
+fn slot_at(point: (i32, i32), viewport: Rect) -> Option<(bool, usize)> {
+    [true, false].into_iter().find_map(|is_container| {
+        (0..INVENTORY_SIZE)
+            .find(|idx| slot_rect(is_container, *idx, viewport).contains_point(point))
+            .map(|idx| (is_container, idx))
+    })
+}
+
+// one row each, the container's above the player's
+fn slot_rect(is_container: bool, idx: usize, viewport: Rect) -> Rect {
+    let w = INVENTORY_SIZE as i32 * SLOT_SIZE;
+    let y = if is_container {
+        -SLOT_SIZE - GAP / 2
+    } else {
+        GAP / 2
+    };
+    Rect::new(
+        viewport.width() as i32 / 2 - w / 2 + idx as i32 * SLOT_SIZE,
+        viewport.height() as i32 / 2 + y,
+        SLOT_SIZE as u32,
+        SLOT_SIZE as u32,
+    )
+}
+
+pub fn update(world: &World) {
+    let containers = world.resource_mut::<Containers>().unwrap();
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    let Some((entity, looter)) = containers.open else {
+        return;
+    };
+
+    let is_near = world
+        .component::<Pos>(entity)
+        .zip(world.component::<Pos>(looter))
+        .is_some_and(|(pos, looter_pos)| pos.distance(looter_pos) < RANGE);
+    if ctx.input.just_pressed.tab || !is_near {
+        containers.open = None;
+        return;
+    }
+
+    if !ctx.input.just_pressed.mouse_left {
+        return;
+    }
+    let Some((is_container, idx)) = slot_at(ctx.input.mouse_pos, ctx.canvas.viewport()) else {
+        return;
+    };
+    let (Some(container), Some(own)) = (inventory_mut(world, entity), inventory_mut(world, looter))
+    else {
+        return;
+    };
+    let (from, to) = if is_container {
+        (container, own)
+    } else {
+        (own, container)
+    };
+    if to.is_full() {
+        return;
+    }
+    if let Some(item) = from.take(idx, world) {
+        // there was room, it can't come back
+        let _ = to.insert_boxed(item, world);
+    }
+}
+
+// closes it when either side is gone
+pub fn prune(world: &World) {
+    let containers = world.resource_mut::<Containers>().unwrap();
+    if containers.open.is_some_and(|(entity, looter)| {
+        !world.has_component::<Container>(entity) || !world.has_component::<Container>(looter)
+    }) {
+        containers.open = None;
+    }
+}
+
+pub fn draw(world: &World, ui: &mut Ui, sprites: &SpriteRegistry) {
+    let containers = world.resource::<Containers>().unwrap();
+    let ctx = world.resource::<Ctx>().unwrap();
+    let Some((entity, looter)) = containers.open else {
+        return;
+    };
+    let (Some(container), Some(own)) = (
+        world.component::<Container>(entity),
+        inventory(world, looter),
+    ) else {
+        return;
+    };
+    let viewport = ui.viewport();
+    let spritesheet = ui.spritesheet();
+    let hovered = slot_at(ctx.input.mouse_pos, viewport);
+
+    for (is_container, inventory) in [(true, &container.inventory), (false, own)] {
+        for idx in 0..INVENTORY_SIZE {
+            let slot = slot_rect(is_container, idx, viewport);
+            ui.canvas()
+                .set_draw_color(if hovered == Some((is_container, idx)) {
+                    Color::RGB(200, 200, 200)
+                } else {
+                    Color::RGB(100, 100, 100)
+                });
+            ui.canvas().draw_rect(slot).unwrap();
+            if let Some(item) = inventory.get(idx) {
+                spritesheet.draw_at(
+                    ui.canvas(),
+                    sprites.get(item.sprite()),
+                    (slot.x() + 8, slot.y() + 8),
+                    DrawOptions::default(),
+                );
+            }
+        }
+    }
+
+    let top = slot_rect(true, 0, viewport);
+    ui.label(
+        Anchor::TopLeft,
+        (top.x(), top.y() - 24),
+        tr(container.title),
+        Color::WHITE,
+        None,
+    );
+    let bottom = slot_rect(false, 0, viewport);
+    ui.label(
+        Anchor::TopLeft,
+        (bottom.x(), bottom.bottom() + 8),
+        tr("container-hint"),
+        Color::RGB(200, 200, 200),
+        None,
+    );
+
+    let hovered_item = hovered.and_then(|(is_container, idx)| {
+        if is_container {
+            container.inventory.get(idx)
+        } else {
+            own.get(idx)
+        }
+    });
+    if let Some(item) = hovered_item {
+        tooltip::draw(ui, item, ctx.input.mouse_pos);
+    }
+}
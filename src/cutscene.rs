@@ -0,0 +1,192 @@
+// scripted sequences, steps run one after the other while the player watches
+
+use ecs::{Component, Entity, Resource, World};
+use sdl2::pixels::Color;
+
+use crate::{
+    components::{Light, Pos},
+    game,
+    locale::tr,
+    signals::{Signal, SignalBroker},
+    transition::{self, Style},
+    ui::{Anchor, Ui},
+    Ctx,
+};
+
+const LETTERBOX_HEIGHT: u32 = 32;
+
+pub enum Step {
+    // pans from wherever the camera currently is
+    MoveCamera {
+        to: Pos,
+        ticks: u32,
+    },
+    // hands the camera back to the player
+    ReleaseCamera,
+    MoveEntity {
+        entity: Entity,
+        path: Vec<Pos>,
+        speed: f32,
+    },
+    Say {
+        // string table key
+        line: &'static str,
+        ticks: u32,
+    },
+    Wait(u32),
+    SetLight {
+        entity: Entity,
+        is_on: bool,
+    },
+    Signal {
+        channel: u16,
+        is_on: bool,
+    },
+}
+
+// plays the built cutscene when signalled or walked into
+#[derive(Component)]
+pub struct CutsceneTrigger {
+    pub build: fn(&World) -> Vec<Step>,
+}
+
+#[derive(Resource)]
+pub struct Director {
+    steps: Vec<Step>,
+    current: usize,
+    // ticks spent in the current step
+    ticks: u32,
+    waypoint: usize,
+    camera_from: Pos,
+    pub camera: Option<Pos>,
+    pub line: Option<&'static str>,
+}
+
+impl Director {
+    pub fn new() -> Self {
+        Director {
+            steps: Vec::new(),
+            current: 0,
+            ticks: 0,
+            waypoint: 0,
+            camera_from: Pos::zero(),
+            camera: None,
+            line: None,
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.current < self.steps.len()
+    }
+}
+
+pub fn play(world: &World, steps: Vec<Step>) {
+    let director = world.resource_mut::<Director>().unwrap();
+    director.steps = steps;
+    director.current = 0;
+    director.ticks = 0;
+    director.waypoint = 0;
+    transition::reveal(world, Style::Fade(Color::BLACK), transition::CUTSCENE_TICKS);
+}
+
+pub fn update(world: &World) {
+    let director = world.resource_mut::<Director>().unwrap();
+
+    // instant steps run back to back in the same tick
+    while director.is_playing() {
+        let is_done = match &director.steps[director.current] {
+            Step::MoveCamera { to, ticks } => {
+                if director.ticks == 0 {
+                    let player_pos = world.resource::<Ctx>().unwrap().player_pos;
+                    director.camera_from = director.camera.unwrap_or(player_pos);
+                }
+                director.ticks += 1;
+                let t = director.ticks as f32 / (*ticks).max(1) as f32;
+                director.camera = Some(director.camera_from.lerp(to, t.min(1.)));
+                director.ticks >= *ticks
+            }
+            Step::ReleaseCamera => {
+                director.camera = None;
+                true
+            }
+            Step::MoveEntity {
+                entity,
+                path,
+                speed,
+            } => match (
+                world.component_mut::<Pos>(*entity),
+                path.get(director.waypoint),
+            ) {
+                (Some(pos), Some(target)) => {
+                    let distance = pos.distance(target);
+                    if distance <= *speed {
+                        *pos = *target;
+                        director.waypoint += 1;
+                    } else {
+                        pos.x += (target.x - pos.x) / distance * speed;
+                        pos.y += (target.y - pos.y) / distance * speed;
+                    }
+                    director.waypoint >= path.len()
+                }
+                // the entity is gone or the path is walked
+                _ => true,
+            },
+            Step::Say { line, ticks } => {
+                director.line = Some(line);
+                director.ticks += 1;
+                if director.ticks >= *ticks {
+                    director.line = None;
+                }
+                director.ticks >= *ticks
+            }
+            Step::Wait(ticks) => {
+                director.ticks += 1;
+                director.ticks >= *ticks
+            }
+            Step::SetLight { entity, is_on } => {
+                if world.has_component::<Light>(*entity) {
+                    game::light_base_mut(world, *entity).intensity = if *is_on { 1. } else { 0. };
+                }
+                true
+            }
+            Step::Signal { channel, is_on } => {
+                world.resource_mut::<SignalBroker>().unwrap().send(Signal {
+                    channel: *channel,
+                    is_on: *is_on,
+                });
+                true
+            }
+        };
+
+        if !is_done {
+            break;
+        }
+        director.current += 1;
+        director.ticks = 0;
+        director.waypoint = 0;
+    }
+
+    // just finished, cut back to the game the way it cut in
+    if !director.steps.is_empty() && !director.is_playing() {
+        transition::reveal(world, Style::Fade(Color::BLACK), transition::CUTSCENE_TICKS);
+    }
+    if !director.is_playing() {
+        director.steps.clear();
+        director.current = 0;
+    }
+}
+
+pub fn draw(director: &Director, ui: &mut Ui) {
+    if !director.is_playing() {
+        return;
+    }
+
+    let viewport = ui.viewport();
+    let bar = (viewport.width(), LETTERBOX_HEIGHT);
+    ui.fill(Anchor::Top, (0, 0), bar, Color::BLACK, |_| {});
+    ui.fill(Anchor::Bottom, (0, 0), bar, Color::BLACK, |ui| {
+        if let Some(line) = director.line {
+            ui.label(Anchor::Center, (0, 0), tr(line), Color::WHITE, None);
+        }
+    });
+}
@@ -0,0 +1,713 @@
+// in-game room editor. F7 toggles it, the game pauses and the camera flies free
+//
+//   WASD - move the camera, shift for faster
+//   left click - place the selected prefab, or pick one from the palette
+//   right click - erase the newest placement on the tile
+//   shift+drag - move the newest placement on the tile
+//   ctrl+drag - select a rectangle of tiles
+//   ctrl+C/ctrl+X - copy/cut the selection
+//   ctrl+V - stamp the copied pattern, click or drag to paint it, right click to stop
+//   Q/E - cycle the palette
+//   ctrl+Z/ctrl+Y - undo/redo
+//   arrows - left/right pick a property of the hovered placement, up/down change it
+//
+// only what was placed in the editor or loaded from a room file is tracked, saving
+// writes that to a room file
+
+use std::{
+    collections::{BTreeSet, HashSet, VecDeque},
+    path::Path,
+    sync::Mutex,
+};
+
+use ecs::{Resource, World};
+use sdl2::{pixels::Color, rect::Rect};
+
+use crate::{
+    components::Pos,
+    game::{level_entities, pos_to_tile, tile_to_pos, TILE_SIZE},
+    locale::tr,
+    math::Transform,
+    prefabs::PrefabRegistry,
+    rooms::{self, Placement, Props},
+    tiled,
+    transition::{self, Style},
+    ui::{Anchor, Ui},
+    world_to_screen, Ctx,
+};
+
+const CAMERA_SPEED: f32 = 6.;
+const CAMERA_SPEED_FAST: f32 = 16.;
+const PALETTE_POS: (i32, i32) = (8, 8);
+const PALETTE_WIDTH: u32 = 160;
+const ROW_HEIGHT: i32 = 18;
+const PROPS: [&str; 3] = ["radius", "intensity", "channel"];
+// the player isn't part of a room
+const HIDDEN_PREFABS: [&str; 1] = ["player"];
+const MAX_HISTORY: usize = 200;
+
+// every level name loaded so far, leaked the first time like the hook names in scripts.rs
+static LEVEL_NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+#[derive(Resource)]
+pub struct Editor {
+    pub is_active: bool,
+    pub camera: Pos,
+    // filled when the editor first opens, prefabs are all registered by then
+    palette: Vec<&'static str>,
+    selected: usize,
+    // index into PROPS
+    selected_prop: usize,
+    hovered: (i32, i32),
+    placements: Vec<Placement>,
+    // prefab and tile being moved with shift+drag
+    dragging: Option<(&'static str, (i32, i32))>,
+    // corner the ctrl+drag started at
+    selecting: Option<(i32, i32)>,
+    // top left and bottom right tile, inclusive
+    selection: Option<((i32, i32), (i32, i32))>,
+    // prefab, offset from the selection's top left and properties
+    clipboard: Vec<(&'static str, (i32, i32), Props)>,
+    // painting the clipboard, and the last tile it was stamped at
+    stamping: bool,
+    last_stamp: Option<(i32, i32)>,
+    // each entry is undone in one go
+    undo: VecDeque<Vec<Op>>,
+    redo: Vec<Vec<Op>>,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Editor {
+            is_active: false,
+            camera: Pos::zero(),
+            palette: Vec::new(),
+            selected: 0,
+            selected_prop: 0,
+            hovered: (0, 0),
+            placements: Vec::new(),
+            dragging: None,
+            selecting: None,
+            selection: None,
+            clipboard: Vec::new(),
+            stamping: false,
+            last_stamp: None,
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    // newest first
+    fn placement_at(&self, tile: (i32, i32)) -> Option<&Placement> {
+        self.placements.iter().rev().find(|p| p.tile == tile)
+    }
+
+    fn selected_placements(&self) -> impl Iterator<Item = &Placement> {
+        // an empty range without a selection
+        let ((x0, y0), (x1, y1)) = self.selection.unwrap_or(((1, 1), (0, 0)));
+        self.placements
+            .iter()
+            .filter(move |p| (x0..=x1).contains(&p.tile.0) && (y0..=y1).contains(&p.tile.1))
+    }
+}
+
+pub fn toggle(world: &World) {
+    let editor = world.resource_mut::<Editor>().unwrap();
+    let ctx = world.resource::<Ctx>().unwrap();
+    editor.is_active = !editor.is_active;
+    if editor.is_active {
+        editor.camera = ctx.player_pos;
+        if editor.palette.is_empty() {
+            editor.palette = world
+                .resource::<PrefabRegistry>()
+                .unwrap()
+                .names()
+                .into_iter()
+                .filter(|name| !HIDDEN_PREFABS.contains(name))
+                .collect();
+        }
+    }
+}
+
+fn palette_row(idx: usize) -> Rect {
+    Rect::new(
+        PALETTE_POS.0,
+        PALETTE_POS.1 + ROW_HEIGHT * (idx as i32 + 1),
+        PALETTE_WIDTH,
+        ROW_HEIGHT as u32,
+    )
+}
+
+// reversible edits, placements are found by prefab and tile since respawning them
+// hands out new entities
+#[derive(Clone, Copy)]
+enum Op {
+    Place {
+        prefab: &'static str,
+        tile: (i32, i32),
+        props: Props,
+    },
+    Erase {
+        prefab: &'static str,
+        tile: (i32, i32),
+        props: Props,
+    },
+    Move {
+        prefab: &'static str,
+        from: (i32, i32),
+        to: (i32, i32),
+    },
+    SetProps {
+        prefab: &'static str,
+        tile: (i32, i32),
+        before: Props,
+        after: Props,
+    },
+}
+
+impl Op {
+    fn inverse(self) -> Op {
+        match self {
+            Op::Place {
+                prefab,
+                tile,
+                props,
+            } => Op::Erase {
+                prefab,
+                tile,
+                props,
+            },
+            Op::Erase {
+                prefab,
+                tile,
+                props,
+            } => Op::Place {
+                prefab,
+                tile,
+                props,
+            },
+            Op::Move { prefab, from, to } => Op::Move {
+                prefab,
+                from: to,
+                to: from,
+            },
+            Op::SetProps {
+                prefab,
+                tile,
+                before,
+                after,
+            } => Op::SetProps {
+                prefab,
+                tile,
+                before: after,
+                after: before,
+            },
+        }
+    }
+}
+
+fn find(placements: &[Placement], prefab: &str, tile: (i32, i32)) -> Option<usize> {
+    placements
+        .iter()
+        .position(|p| p.prefab == prefab && p.tile == tile)
+}
+
+// respawned rather than changed in place, so properties that were unset go back to
+// the prefab's defaults
+fn respawn(world: &World, placement: &mut Placement, tile: (i32, i32), props: Props) {
+    world.despawn(placement.entity);
+    *placement = rooms::place(world, placement.prefab, tile, props).unwrap();
+}
+
+fn apply(world: &World, placements: &mut Vec<Placement>, op: Op) {
+    match op {
+        Op::Place {
+            prefab,
+            tile,
+            props,
+        } => {
+            if let Some(placement) = rooms::place(world, prefab, tile, props) {
+                placements.push(placement);
+            }
+        }
+        Op::Erase { prefab, tile, .. } => {
+            if let Some(idx) = find(placements, prefab, tile) {
+                world.despawn(placements.remove(idx).entity);
+            }
+        }
+        Op::Move { prefab, from, to } => {
+            if let Some(idx) = find(placements, prefab, from) {
+                let props = placements[idx].props;
+                respawn(world, &mut placements[idx], to, props);
+            }
+        }
+        Op::SetProps {
+            prefab,
+            tile,
+            after,
+            ..
+        } => {
+            if let Some(idx) = find(placements, prefab, tile) {
+                respawn(world, &mut placements[idx], tile, after);
+            }
+        }
+    }
+}
+
+// applies them and makes them undoable as one edit
+fn push_all(world: &World, editor: &mut Editor, ops: Vec<Op>) {
+    if ops.is_empty() {
+        return;
+    }
+    for op in &ops {
+        apply(world, &mut editor.placements, *op);
+    }
+    editor.redo.clear();
+    if editor.undo.len() == MAX_HISTORY {
+        editor.undo.pop_front();
+    }
+    editor.undo.push_back(ops);
+}
+
+// applies it and makes it undoable
+fn push(world: &World, editor: &mut Editor, op: Op) {
+    // holding a property key keeps changing the same edit
+    if let (
+        Op::SetProps {
+            prefab,
+            tile,
+            after,
+            ..
+        },
+        Some(
+            [Op::SetProps {
+                prefab: last_prefab,
+                tile: last_tile,
+                after: last_after,
+                ..
+            }],
+        ),
+    ) = (op, editor.undo.back_mut().map(|ops| ops.as_mut_slice()))
+    {
+        if prefab == *last_prefab && tile == *last_tile {
+            apply(world, &mut editor.placements, op);
+            editor.redo.clear();
+            *last_after = after;
+            return;
+        }
+    }
+
+    push_all(world, editor, vec![op]);
+}
+
+pub fn undo(world: &World) {
+    let editor = world.resource_mut::<Editor>().unwrap();
+    if let Some(ops) = editor.undo.pop_back() {
+        for op in ops.iter().rev() {
+            apply(world, &mut editor.placements, op.inverse());
+        }
+        editor.redo.push(ops);
+    }
+}
+
+pub fn redo(world: &World) {
+    let editor = world.resource_mut::<Editor>().unwrap();
+    if let Some(ops) = editor.redo.pop() {
+        for op in &ops {
+            apply(world, &mut editor.placements, *op);
+        }
+        editor.undo.push_back(ops);
+    }
+}
+
+pub fn copy(world: &World) {
+    let editor = world.resource_mut::<Editor>().unwrap();
+    let Some((origin, _)) = editor.selection else {
+        return;
+    };
+    // channels come along as they are, so a copied lever and door stay wired
+    // together (and to whatever else is on that channel)
+    editor.clipboard = editor
+        .selected_placements()
+        .map(|p| {
+            let offset = (p.tile.0 - origin.0, p.tile.1 - origin.1);
+            (p.prefab, offset, p.props)
+        })
+        .collect();
+}
+
+pub fn cut(world: &World) {
+    copy(world);
+    let editor = world.resource_mut::<Editor>().unwrap();
+    let ops = editor
+        .selected_placements()
+        .map(|p| Op::Erase {
+            prefab: p.prefab,
+            tile: p.tile,
+            props: p.props,
+        })
+        .collect();
+    push_all(world, editor, ops);
+}
+
+pub fn paste(world: &World) {
+    let editor = world.resource_mut::<Editor>().unwrap();
+    if !editor.clipboard.is_empty() {
+        editor.stamping = true;
+        editor.last_stamp = None;
+        editor.selection = None;
+    }
+}
+
+// the clipboard with its top left on the tile, skipping anything already there
+fn stamp(world: &World, editor: &mut Editor, tile: (i32, i32)) {
+    let ops = editor
+        .clipboard
+        .iter()
+        .map(|(prefab, offset, props)| (*prefab, (tile.0 + offset.0, tile.1 + offset.1), *props))
+        .filter(|(prefab, tile, _)| find(&editor.placements, prefab, *tile).is_none())
+        .map(|(prefab, tile, props)| Op::Place {
+            prefab,
+            tile,
+            props,
+        })
+        .collect();
+    push_all(world, editor, ops);
+    editor.last_stamp = Some(tile);
+}
+
+pub fn update(world: &World) {
+    let editor = world.resource_mut::<Editor>().unwrap();
+    let ctx = world.resource::<Ctx>().unwrap();
+    let pressed = &ctx.input.pressed;
+    let just_pressed = &ctx.input.just_pressed;
+
+    let speed = if pressed.shift {
+        CAMERA_SPEED_FAST
+    } else {
+        CAMERA_SPEED
+    };
+    if pressed.up {
+        editor.camera.y -= speed;
+    }
+    if pressed.down {
+        editor.camera.y += speed;
+    }
+    if pressed.left {
+        editor.camera.x -= speed;
+    }
+    if pressed.right {
+        editor.camera.x += speed;
+    }
+
+    let count = editor.palette.len();
+    if just_pressed.q {
+        editor.selected = (editor.selected + count - 1) % count;
+    }
+    if just_pressed.e {
+        editor.selected = (editor.selected + 1) % count;
+    }
+
+    editor.hovered = pos_to_tile(ctx.cursor_world_pos());
+    let tile = editor.hovered;
+    let mouse = ctx.input.mouse_pos;
+
+    // painted every time the mouse moves onto another tile while held
+    if editor.stamping {
+        if just_pressed.mouse_right {
+            editor.stamping = false;
+        } else if !pressed.mouse_left {
+            editor.last_stamp = None;
+        } else if editor.last_stamp != Some(tile) {
+            stamp(world, editor, tile);
+        }
+        return;
+    }
+
+    // left ctrl is the dash key
+    if let Some(start) = editor.selecting {
+        editor.selection = Some((
+            (start.0.min(tile.0), start.1.min(tile.1)),
+            (start.0.max(tile.0), start.1.max(tile.1)),
+        ));
+        if !pressed.mouse_left {
+            editor.selecting = None;
+        }
+        return;
+    }
+
+    // dropped where the mouse is let go
+    if let Some((prefab, from)) = editor.dragging {
+        if !pressed.mouse_left {
+            editor.dragging = None;
+            if from != tile && find(&editor.placements, prefab, tile).is_none() {
+                push(
+                    world,
+                    editor,
+                    Op::Move {
+                        prefab,
+                        from,
+                        to: tile,
+                    },
+                );
+            }
+        }
+        return;
+    }
+
+    if just_pressed.mouse_left {
+        let clicked_row = (0..count).find(|idx| palette_row(*idx).contains_point(mouse));
+        if let Some(idx) = clicked_row {
+            editor.selected = idx;
+        } else if pressed.dash {
+            editor.selecting = Some(tile);
+            editor.selection = Some((tile, tile));
+        } else if pressed.shift {
+            if let Some(placement) = editor.placement_at(tile) {
+                editor.dragging = Some((placement.prefab, tile));
+            }
+        } else {
+            let prefab = editor.palette[editor.selected];
+            if find(&editor.placements, prefab, tile).is_none() {
+                let props = Props::default();
+                push(
+                    world,
+                    editor,
+                    Op::Place {
+                        prefab,
+                        tile,
+                        props,
+                    },
+                );
+            }
+        }
+    }
+    if just_pressed.mouse_right {
+        if let Some(placement) = editor.placement_at(tile) {
+            let (prefab, props) = (placement.prefab, placement.props);
+            push(
+                world,
+                editor,
+                Op::Erase {
+                    prefab,
+                    tile,
+                    props,
+                },
+            );
+        }
+    }
+
+    if just_pressed.fire_left {
+        editor.selected_prop = (editor.selected_prop + PROPS.len() - 1) % PROPS.len();
+    }
+    if just_pressed.fire_right {
+        editor.selected_prop = (editor.selected_prop + 1) % PROPS.len();
+    }
+    let step = match (just_pressed.fire_up, just_pressed.fire_down) {
+        (true, false) => 1,
+        (false, true) => -1,
+        _ => return,
+    };
+    let prop = PROPS[editor.selected_prop];
+    let Some(placement) = editor.placement_at(tile) else {
+        return;
+    };
+    let current = rooms::props_of(world, placement.entity);
+    let (prefab, before) = (placement.prefab, placement.props);
+    let mut after = before;
+    match prop {
+        "radius" => {
+            after.radius = current
+                .radius
+                .map(|radius| (radius as i32 + step * 8).clamp(0, 1000) as u16);
+        }
+        "intensity" => {
+            after.intensity = current
+                .intensity
+                .map(|intensity| (intensity + step as f32 * 0.1).clamp(0., 2.));
+        }
+        "channel" => {
+            after.channel = current
+                .channel
+                .map(|channel| (channel as i32 + step).clamp(0, u16::MAX as i32) as u16);
+        }
+        _ => {}
+    }
+    if after != before {
+        push(
+            world,
+            editor,
+            Op::SetProps {
+                prefab,
+                tile,
+                before,
+                after,
+            },
+        );
+    }
+}
+
+// replaces the level with the room
+pub fn load_room(world: &World, name: &str) -> Result<usize, String> {
+    replace_level(world, name, || rooms::load(world, name))
+}
+
+// replaces the level with a Tiled map, named after the file so saving it makes a room
+pub fn import_tmx(world: &World, path: &str) -> Result<usize, String> {
+    let name = Path::new(path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("imported");
+    replace_level(world, name, || tiled::load(world, path))
+}
+
+// the load spawns as it goes, so the old level stays until it's done. a load that fails
+// partway has what it spawned despawned and leaves the old level as it was
+fn replace_level(
+    world: &World,
+    name: &str,
+    load: impl FnOnce() -> Result<Vec<Placement>, String>,
+) -> Result<usize, String> {
+    let old_level = level_entities(world);
+    let placements = match load() {
+        Ok(placements) => placements,
+        Err(e) => {
+            let old_level: HashSet<_> = old_level.into_iter().collect();
+            for entity in level_entities(world) {
+                if !old_level.contains(&entity) {
+                    world.despawn(entity);
+                }
+            }
+            return Err(e);
+        }
+    };
+    for entity in old_level {
+        world.despawn(entity);
+    }
+
+    let editor = world.resource_mut::<Editor>().unwrap();
+    editor.placements = placements;
+    // the old placements are gone, their edits can't be undone
+    editor.undo.clear();
+    editor.redo.clear();
+    world.resource_mut::<Ctx>().unwrap().level_name = intern(name);
+    transition::reveal(world, Style::Wipe(Color::BLACK), transition::ROOM_TICKS);
+    Ok(editor.placements.len())
+}
+
+fn intern(name: &str) -> &'static str {
+    let mut names = LEVEL_NAMES.lock().unwrap();
+    if let Some(name) = names.get(name) {
+        return name;
+    }
+    let name: &'static str = Box::leak(name.into());
+    names.insert(name);
+    name
+}
+
+pub fn save_room(world: &World, name: &str) -> Result<usize, String> {
+    let editor = world.resource::<Editor>().unwrap();
+    rooms::save(name, &editor.placements)?;
+    Ok(editor.placements.len())
+}
+
+pub fn draw(editor: &Editor, world: &World, ui: &mut Ui, camera: &Transform) {
+    let (x, y) = editor.hovered;
+    let half = TILE_SIZE / 2.;
+    let tile_rect = |(x, y): (i32, i32)| {
+        let center = tile_to_pos(x, y);
+        let (x, y) = world_to_screen(&Pos::new(center.x - half, center.y - half), camera);
+        Rect::new(x, y, TILE_SIZE as u32, TILE_SIZE as u32)
+    };
+    let canvas = ui.canvas();
+    if editor.stamping {
+        canvas.set_draw_color(Color::RGB(0, 255, 255));
+        for (_, offset, _) in &editor.clipboard {
+            canvas
+                .draw_rect(tile_rect((x + offset.0, y + offset.1)))
+                .unwrap();
+        }
+    } else {
+        canvas.set_draw_color(Color::RGB(255, 255, 0));
+        canvas.draw_rect(tile_rect((x, y))).unwrap();
+    }
+    if let Some((top_left, bottom_right)) = editor.selection {
+        canvas.set_draw_color(Color::RGB(0, 255, 255));
+        canvas
+            .draw_rect(tile_rect(top_left).union(tile_rect(bottom_right)))
+            .unwrap();
+    }
+
+    let height = ROW_HEIGHT * (editor.palette.len() as i32 + 1) + 8;
+    ui.fill(
+        Anchor::TopLeft,
+        (PALETTE_POS.0, PALETTE_POS.1),
+        (PALETTE_WIDTH, height as u32),
+        Color::RGBA(0, 0, 0, 200),
+        |ui| {
+            ui.label(
+                Anchor::TopLeft,
+                (6, 2),
+                tr("editor-palette"),
+                Color::WHITE,
+                None,
+            );
+            for (idx, name) in editor.palette.iter().enumerate() {
+                let color = if idx == editor.selected {
+                    Color::RGB(255, 255, 0)
+                } else {
+                    Color::RGB(200, 200, 200)
+                };
+                ui.label(
+                    Anchor::TopLeft,
+                    (6, 2 + ROW_HEIGHT * (idx as i32 + 1)),
+                    name,
+                    color,
+                    None,
+                );
+            }
+        },
+    );
+
+    let mut lines = vec![(format!("{} {}, {}", tr("editor-tile"), x, y), Color::WHITE)];
+    if let Some(placement) = editor.placements.iter().rev().find(|p| p.tile == (x, y)) {
+        lines.push((placement.prefab.to_string(), Color::WHITE));
+        let props = rooms::props_of(world, placement.entity);
+        let values = [
+            props.radius.map(|v| v.to_string()),
+            props.intensity.map(|v| format!("{:.1}", v)),
+            props.channel.map(|v| v.to_string()),
+        ];
+        for (idx, (name, value)) in PROPS.iter().zip(values).enumerate() {
+            let Some(value) = value else {
+                continue;
+            };
+            let color = if idx == editor.selected_prop {
+                Color::RGB(255, 255, 0)
+            } else {
+                Color::RGB(200, 200, 200)
+            };
+            lines.push((format!("{} {}", name, value), color));
+        }
+        if let Some(script) = props.script {
+            lines.push((format!("script {}", script), Color::RGB(200, 200, 200)));
+        }
+    }
+    for (idx, (text, color)) in lines.iter().enumerate() {
+        ui.label(
+            Anchor::TopRight,
+            (-8, 32 + ROW_HEIGHT * idx as i32),
+            text,
+            *color,
+            Some(Color::RGBA(0, 0, 0, 255)),
+        );
+    }
+
+    ui.label(
+        Anchor::Bottom,
+        (0, -8),
+        tr("editor-hint"),
+        Color::WHITE,
+        Some(Color::RGBA(0, 0, 0, 255)),
+    );
+}
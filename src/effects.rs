@@ -0,0 +1,97 @@
+// short lived effects, spawned from the events so the weapon, collision and animation
+// code doesn't have to know about them. shots flash a bright light at the muzzle, hits
+// throw sparks back off walls and blood on through whatever bled, footsteps sound and
+// kick up dust depending on what the player walks on
+
+use ecs::{Resource, World};
+use sdl2::pixels::Color;
+
+use crate::{
+    audio,
+    commands::CommandBuffer,
+    components::{Light, Player, Pos, Timer, Timers},
+    events::{AnimationEvent, EventReader, Events, ProjectileHit, ShotFired},
+    game::{surface_at, MUZZLE_OFFSET},
+    particles,
+};
+
+const MUZZLE_FLASH_TICKS: u32 = 3;
+const MUZZLE_FLASH_RADIUS: u16 = 70;
+const FOOTSTEP_VOLUME: f32 = 0.4;
+// from the sprite's center down to its feet
+const FEET_OFFSET: f32 = 12.;
+
+#[derive(Resource)]
+pub struct Effects {
+    shot_fired: EventReader<ShotFired>,
+    projectile_hit: EventReader<ProjectileHit>,
+    animation: EventReader<AnimationEvent>,
+}
+
+impl Effects {
+    pub fn new() -> Self {
+        Effects {
+            shot_fired: EventReader::new(),
+            projectile_hit: EventReader::new(),
+            animation: EventReader::new(),
+        }
+    }
+}
+
+pub fn update(world: &World) {
+    let effects = world.resource_mut::<Effects>().unwrap();
+
+    for shot in effects
+        .shot_fired
+        .read(world.resource::<Events<ShotFired>>().unwrap())
+    {
+        let muzzle = Pos::new(
+            shot.pos.x + shot.direction.x * MUZZLE_OFFSET,
+            shot.pos.y + shot.direction.y * MUZZLE_OFFSET,
+        );
+        // gone again before it could fade, the lifetime timer despawns it
+        world.resource_mut::<CommandBuffer>().unwrap().spawn(vec![
+            Box::new(muzzle),
+            Box::new(Light {
+                radius: MUZZLE_FLASH_RADIUS,
+                color: Color::RGB(255, 220, 150),
+                intensity: 2.,
+                cone: None,
+            }),
+            Box::new(Timers::new().with("lifetime", Timer::once(MUZZLE_FLASH_TICKS))),
+        ]);
+        particles::burst(world, "muzzle_flash", muzzle, Some(shot.direction));
+    }
+
+    for hit in effects
+        .projectile_hit
+        .read(world.resource::<Events<ProjectileHit>>().unwrap())
+    {
+        if hit.is_flesh {
+            particles::burst(world, "blood", hit.pos, Some(hit.velocity));
+        } else {
+            particles::burst(
+                world,
+                "impact_sparks",
+                hit.pos,
+                Some(hit.velocity.scaled(-1.)),
+            );
+        }
+    }
+
+    for event in effects
+        .animation
+        .read(world.resource::<Events<AnimationEvent>>().unwrap())
+    {
+        if event.name != "footstep" || !world.has_component::<Player>(event.entity) {
+            continue;
+        }
+        let Some(pos) = world.component::<Pos>(event.entity) else {
+            continue;
+        };
+        let feet = Pos::new(pos.x, pos.y + FEET_OFFSET);
+        let name = format!("footstep_{}", surface_at(world, feet).name());
+        audio::play_at(world, &name, FOOTSTEP_VOLUME, feet);
+        particles::burst(world, &name, feet, None);
+    }
+}
@@ -0,0 +1,149 @@
+// worn gear and what it does to the player. the equip slots sit to the right of the
+// inventory grid, dropping a held piece of gear on its slot wears it and clicking worn gear
+// puts it back into the inventory
+//
+// every player wears their own gear and has their own stats, the screen shows the first
+// player's
+
+use ecs::{Component, World};
+use sdl2::{pixels::Color, rect::Rect};
+
+use crate::{
+    components::{EquipSlot, Equipment, Health},
+    locale::{tr, tr_args},
+    ui::{Anchor, Ui},
+    DrawOptions, SpriteRegistry,
+};
+
+const SLOT_SIZE: i32 = 48;
+// from the right edge of the inventory grid
+const MARGIN: i32 = 32;
+const LINE_HEIGHT: i32 = 20;
+
+// multipliers except for max_hp
+#[derive(Component)]
+pub struct PlayerStats {
+    pub speed: f32,
+    pub max_hp: i32,
+    pub light_radius: f32,
+    pub damage: f32,
+    base_hp: i32,
+}
+
+impl PlayerStats {
+    pub fn new(base_hp: i32) -> Self {
+        PlayerStats {
+            speed: 1.,
+            max_hp: base_hp,
+            light_radius: 1.,
+            damage: 1.,
+            base_hp,
+        }
+    }
+
+    pub fn light(&self, radius: u16) -> u16 {
+        (radius as f32 * self.light_radius) as u16
+    }
+
+    pub fn scale_damage(&self, damage: u16) -> u16 {
+        (damage as f32 * self.damage).round() as u16
+    }
+}
+
+// recomputed every tick, gear can change from the inventory screen and scripts alike
+pub fn update(world: &World) {
+    world.run(
+        |stats: &mut PlayerStats, equipment: &Equipment, health: &mut Health| {
+            let bonus = equipment.bonus();
+            stats.speed = (1. + bonus.speed).max(0.1);
+            stats.light_radius = (1. + bonus.light_radius).max(0.);
+            stats.damage = (1. + bonus.damage).max(0.);
+
+            stats.max_hp = (stats.base_hp + bonus.max_hp).max(1);
+            let max_hp = stats.max_hp;
+            if health.max_hp != max_hp {
+                // putting armor on heals by what it adds, taking it off can't kill
+                let gained = max_hp - health.max_hp;
+                health.max_hp = max_hp;
+                health.hp = (health.hp + gained).clamp(health.hp.min(1), max_hp);
+            }
+        },
+    );
+}
+
+// in the same coordinates as the inventory grid, whose right edge is grid_right
+pub fn slot_rect(slot: EquipSlot, grid_right: i32, viewport: Rect) -> Rect {
+    let rows = EquipSlot::ALL.len() as i32;
+    Rect::new(
+        grid_right + MARGIN,
+        viewport.height() as i32 / 2 - rows * SLOT_SIZE / 2 + slot as i32 * SLOT_SIZE,
+        SLOT_SIZE as u32,
+        SLOT_SIZE as u32,
+    )
+}
+
+pub fn slot_at(point: (i32, i32), grid_right: i32, viewport: Rect) -> Option<EquipSlot> {
+    EquipSlot::ALL
+        .into_iter()
+        .find(|slot| slot_rect(*slot, grid_right, viewport).contains_point(point))
+}
+
+pub fn draw(
+    ui: &mut Ui,
+    equipment: &Equipment,
+    stats: &PlayerStats,
+    sprites: &SpriteRegistry,
+    mouse_pos: (i32, i32),
+    grid_right: i32,
+) {
+    let viewport = ui.viewport();
+    let spritesheet = ui.spritesheet();
+    let hovered = slot_at(mouse_pos, grid_right, viewport);
+
+    for slot in EquipSlot::ALL {
+        let rect = slot_rect(slot, grid_right, viewport);
+        ui.canvas().set_draw_color(if hovered == Some(slot) {
+            Color::RGB(200, 200, 200)
+        } else {
+            Color::RGB(100, 100, 100)
+        });
+        ui.canvas().draw_rect(rect).unwrap();
+
+        match equipment.get(slot) {
+            Some(item) => spritesheet.draw_at(
+                ui.canvas(),
+                sprites.get(item.sprite()),
+                (rect.x() + 8, rect.y() + 8),
+                DrawOptions::default(),
+            ),
+            None => {
+                ui.label(
+                    Anchor::TopLeft,
+                    (rect.right() + 8, rect.y() + 14),
+                    tr(slot.name()),
+                    Color::RGB(120, 120, 120),
+                    None,
+                );
+            }
+        }
+    }
+
+    let percent = |value: f32| format!("{:.0}%", value * 100.);
+    let lines = [
+        tr_args("stat-max-hp", &[("value", &stats.max_hp.to_string())]),
+        tr_args("stat-speed", &[("value", &percent(stats.speed))]),
+        tr_args("stat-light", &[("value", &percent(stats.light_radius))]),
+        tr_args("stat-damage", &[("value", &percent(stats.damage))]),
+    ];
+    let last = *EquipSlot::ALL.last().unwrap();
+    let below = slot_rect(last, grid_right, viewport);
+    for (idx, line) in lines.iter().enumerate() {
+        ui.label(
+            Anchor::TopLeft,
+            (below.x(), below.bottom() + 8 + idx as i32 * LINE_HEIGHT),
+            line,
+            Color::RGB(200, 200, 200),
+            None,
+        );
+    }
+}
@@ -0,0 +1,299 @@
+// typed events, double-buffered so a reader that runs once per tick never misses one
+// no matter where in the tick it was sent
+
+use std::marker::PhantomData;
+
+use ecs::{Entity, Resource, World};
+use sdl2::pixels::Color;
+
+use crate::{components::Pos, console::Console, math::Vec2};
+
+pub struct Events<T: 'static> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    // number of events sent before the ones in previous
+    start: usize,
+}
+
+impl<T: 'static> Resource for Events<T> {}
+
+impl<T: 'static> Events<T> {
+    pub fn new() -> Self {
+        Events {
+            previous: Vec::new(),
+            current: Vec::new(),
+            start: 0,
+        }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    // events live for two ticks
+    pub fn update(&mut self) {
+        self.start += self.previous.len();
+        self.previous = std::mem::take(&mut self.current);
+    }
+}
+
+pub struct EventReader<T: 'static> {
+    next: usize,
+    _event: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> EventReader<T> {
+    pub fn new() -> Self {
+        EventReader {
+            next: 0,
+            _event: PhantomData,
+        }
+    }
+
+    // everything sent since the last read
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> {
+        let skip = self.next.saturating_sub(events.start);
+        self.next = events.start + events.previous.len() + events.current.len();
+        events
+            .previous
+            .iter()
+            .chain(events.current.iter())
+            .skip(skip)
+    }
+}
+
+pub fn send<T: 'static>(world: &World, event: T) {
+    world.resource_mut::<Events<T>>().unwrap().send(event);
+}
+
+pub struct Interacted {
+    pub entity: Entity,
+}
+
+pub struct Collided {
+    pub entity: Entity,
+    pub other: Entity,
+}
+
+pub struct Damaged {
+    pub entity: Entity,
+    pub amount: u16,
+}
+
+pub struct ItemUsed {
+    pub item: &'static str,
+}
+
+pub struct ShotFired {
+    // the shooter, the muzzle is MUZZLE_OFFSET along direction
+    pub pos: Pos,
+    pub direction: Vec2<f32>,
+}
+
+// a projectile hit something, flesh is anything with health
+pub struct ProjectileHit {
+    pub pos: Pos,
+    pub velocity: Vec2<f32>,
+    pub is_flesh: bool,
+}
+
+pub struct EnemyDied {
+    pub pos: Pos,
+}
+
+pub struct BossDied {
+    pub pos: Pos,
+}
+
+pub struct PlayerDied {
+    pub pos: Pos,
+}
+
+// a sprite's animation reached a frame with an event on it
+pub struct AnimationEvent {
+    pub entity: Entity,
+    pub name: &'static str,
+}
+
+pub fn add_resources(world: &World) {
+    world.add_resource(Events::<Interacted>::new());
+    world.add_resource(Events::<Collided>::new());
+    world.add_resource(Events::<Damaged>::new());
+    world.add_resource(Events::<ItemUsed>::new());
+    world.add_resource(Events::<ShotFired>::new());
+    world.add_resource(Events::<ProjectileHit>::new());
+    world.add_resource(Events::<EnemyDied>::new());
+    world.add_resource(Events::<BossDied>::new());
+    world.add_resource(Events::<PlayerDied>::new());
+    world.add_resource(Events::<AnimationEvent>::new());
+    world.add_resource(EventLog::new());
+}
+
+// runs once at the end of every tick
+pub fn update(world: &World) {
+    world.resource_mut::<Events<Interacted>>().unwrap().update();
+    world.resource_mut::<Events<Collided>>().unwrap().update();
+    world.resource_mut::<Events<Damaged>>().unwrap().update();
+    world.resource_mut::<Events<ItemUsed>>().unwrap().update();
+    world.resource_mut::<Events<ShotFired>>().unwrap().update();
+    world
+        .resource_mut::<Events<ProjectileHit>>()
+        .unwrap()
+        .update();
+    world.resource_mut::<Events<EnemyDied>>().unwrap().update();
+    world.resource_mut::<Events<BossDied>>().unwrap().update();
+    world.resource_mut::<Events<PlayerDied>>().unwrap().update();
+    world
+        .resource_mut::<Events<AnimationEvent>>()
+        .unwrap()
+        .update();
+}
+
+pub const LOG_FILTERS: [&str; 10] = [
+    "interacted",
+    "collided",
+    "damaged",
+    "item_used",
+    "shot_fired",
+    "projectile_hit",
+    "enemy_died",
+    "boss_died",
+    "player_died",
+    "animation",
+];
+
+// prints events to the console, for debugging
+#[derive(Resource)]
+pub struct EventLog {
+    pub filter: Option<&'static str>,
+    interacted: EventReader<Interacted>,
+    collided: EventReader<Collided>,
+    damaged: EventReader<Damaged>,
+    item_used: EventReader<ItemUsed>,
+    shot_fired: EventReader<ShotFired>,
+    projectile_hit: EventReader<ProjectileHit>,
+    enemy_died: EventReader<EnemyDied>,
+    boss_died: EventReader<BossDied>,
+    player_died: EventReader<PlayerDied>,
+    animation: EventReader<AnimationEvent>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        EventLog {
+            filter: None,
+            interacted: EventReader::new(),
+            collided: EventReader::new(),
+            damaged: EventReader::new(),
+            item_used: EventReader::new(),
+            shot_fired: EventReader::new(),
+            projectile_hit: EventReader::new(),
+            enemy_died: EventReader::new(),
+            boss_died: EventReader::new(),
+            player_died: EventReader::new(),
+            animation: EventReader::new(),
+        }
+    }
+}
+
+pub fn log(world: &World) {
+    let log = world.resource_mut::<EventLog>().unwrap();
+    let pos_of = |entity: Entity| {
+        world
+            .component::<Pos>(entity)
+            .map_or(Pos::zero(), |pos| *pos)
+    };
+
+    let mut lines = Vec::new();
+    for e in log
+        .interacted
+        .read(world.resource::<Events<Interacted>>().unwrap())
+    {
+        let pos = pos_of(e.entity);
+        lines.push(("interacted", format!("at {:.0}, {:.0}", pos.x, pos.y)));
+    }
+    for e in log
+        .collided
+        .read(world.resource::<Events<Collided>>().unwrap())
+    {
+        let (pos, other) = (pos_of(e.entity), pos_of(e.other));
+        lines.push((
+            "collided",
+            format!(
+                "{:.0}, {:.0} with {:.0}, {:.0}",
+                pos.x, pos.y, other.x, other.y
+            ),
+        ));
+    }
+    for e in log
+        .damaged
+        .read(world.resource::<Events<Damaged>>().unwrap())
+    {
+        let pos = pos_of(e.entity);
+        lines.push((
+            "damaged",
+            format!("{} at {:.0}, {:.0}", e.amount, pos.x, pos.y),
+        ));
+    }
+    for e in log
+        .item_used
+        .read(world.resource::<Events<ItemUsed>>().unwrap())
+    {
+        lines.push(("item_used", e.item.to_string()));
+    }
+    for e in log
+        .shot_fired
+        .read(world.resource::<Events<ShotFired>>().unwrap())
+    {
+        lines.push(("shot_fired", format!("at {:.0}, {:.0}", e.pos.x, e.pos.y)));
+    }
+    for e in log
+        .projectile_hit
+        .read(world.resource::<Events<ProjectileHit>>().unwrap())
+    {
+        let what = if e.is_flesh { "flesh" } else { "wall" };
+        lines.push((
+            "projectile_hit",
+            format!("{} at {:.0}, {:.0}", what, e.pos.x, e.pos.y),
+        ));
+    }
+    for e in log
+        .enemy_died
+        .read(world.resource::<Events<EnemyDied>>().unwrap())
+    {
+        lines.push(("enemy_died", format!("at {:.0}, {:.0}", e.pos.x, e.pos.y)));
+    }
+    for e in log
+        .boss_died
+        .read(world.resource::<Events<BossDied>>().unwrap())
+    {
+        lines.push(("boss_died", format!("at {:.0}, {:.0}", e.pos.x, e.pos.y)));
+    }
+    for e in log
+        .player_died
+        .read(world.resource::<Events<PlayerDied>>().unwrap())
+    {
+        lines.push(("player_died", format!("at {:.0}, {:.0}", e.pos.x, e.pos.y)));
+    }
+    for e in log
+        .animation
+        .read(world.resource::<Events<AnimationEvent>>().unwrap())
+    {
+        let pos = pos_of(e.entity);
+        lines.push((
+            "animation",
+            format!("{} at {:.0}, {:.0}", e.name, pos.x, pos.y),
+        ));
+    }
+
+    // the readers keep up even while the log is off
+    let Some(filter) = log.filter else {
+        return;
+    };
+    let console = world.resource_mut::<Console>().unwrap();
+    for (name, line) in lines {
+        if filter == "all" || filter == name {
+            console.print(format!("{}: {}", name, line), Color::RGB(150, 150, 220));
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// short-lived text above world positions, damage numbers and pickup labels
+
+use ecs::{Component, Entity, World};
+use sdl2::{pixels::Color, render::Canvas, ttf::Font, video::Window};
+
+use crate::{
+    commands::CommandBuffer,
+    components::{Pos, Timer, Timers},
+    game::render_pos,
+    math::{Transform, Vec2},
+    tween::{Alpha, Ease, Offset, Tween},
+    ui::{draw_glyphs, TextCache, TextStyle},
+    world_to_screen,
+};
+
+const MAX_LEN: usize = 24;
+const LIFETIME_TICKS: u32 = 45;
+const RISE: f32 = 24.;
+
+// components can't own a String, so the text lives in a fixed buffer
+#[derive(Component)]
+pub struct FloatingText {
+    text: [u8; MAX_LEN],
+    len: u8,
+    pub color: Color,
+}
+
+impl FloatingText {
+    // longer text is cut off
+    pub fn new(text: &str, color: Color) -> Self {
+        let mut buffer = [0; MAX_LEN];
+        let mut len = 0;
+        for c in text.chars() {
+            if len + c.len_utf8() > MAX_LEN {
+                break;
+            }
+            c.encode_utf8(&mut buffer[len..]);
+            len += c.len_utf8();
+        }
+        FloatingText {
+            text: buffer,
+            len: len as u8,
+            color,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        std::str::from_utf8(&self.text[..self.len as usize]).unwrap()
+    }
+}
+
+// rises and fades out, then despawns with its lifetime
+pub fn spawn(world: &World, pos: Pos, text: &str, color: Color) {
+    world.resource_mut::<CommandBuffer>().unwrap().spawn(vec![
+        Box::new(pos),
+        Box::new(FloatingText::new(text, color)),
+        Box::new(Tween::new(
+            Offset(Vec2::zero()),
+            Offset(Vec2::new(0., -RISE)),
+            LIFETIME_TICKS,
+            Ease::Out,
+        )),
+        // readable for most of its life, then gone quickly
+        Box::new(Tween::new(Alpha(1.), Alpha(0.), LIFETIME_TICKS, Ease::In)),
+        Box::new(Timers::new().with("lifetime", Timer::once(LIFETIME_TICKS))),
+    ]);
+}
+
+pub fn draw(world: &World, canvas: &mut Canvas<Window>, font: &Font, camera: &Transform) {
+    // damage numbers are all different, so they go a glyph at a time
+    let cache = world.resource_mut::<TextCache>().unwrap();
+    world.run(
+        |entity: &Entity, text: &FloatingText, pos: &Pos, fade: &Tween<Alpha>| {
+            let pos = render_pos(world, *entity, pos);
+            let alpha = (fade.value().0.clamp(0., 1.) * 255.).round() as u8;
+            let color = Color::RGBA(text.color.r, text.color.g, text.color.b, alpha);
+            // centered on pos
+            let (w, h) = cache.glyphs_size(canvas, font, text.text());
+            let (x, y) = world_to_screen(&pos, camera);
+            draw_glyphs(
+                canvas,
+                cache,
+                text.text(),
+                (x - w as i32 / 2, y - h as i32 / 2),
+                &TextStyle::new(font, color),
+            );
+        },
+    );
+}
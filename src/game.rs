@@ -1,40 +1,283 @@
 // TODO serializable entity definitions
 // TODO serializable room definitions
-// TODO how do we wanna scale sprites around entity centerpoint?
 // FIXME fix shadows
 // FIXME colliders are still fucky
 // TODO don't update colliders for static entities
 // FIXME we're leaking memory and the shadow maps are prime sus
 
-use ecs::{Entity, Res, ResMut, With, Without, World};
-use rand::{thread_rng, Rng};
-use sdl2::{pixels::Color, rect::Point};
+use ecs::{Component, Entity, Res, ResMut, With, Without, World};
+use rand::Rng;
+use sdl2::{
+    pixels::Color,
+    rect::{Point, Rect},
+    render::Canvas,
+    video::Window,
+};
 
 use crate::{
+    ambient::{self, AmbientLight},
+    anim_graph::{self, AnimGraph, AnimGraphDef, AnimState, Cond, Directions, Transition},
+    animation, audio,
+    checkpoints::{self, Checkpoints},
+    commands::{self, CommandBuffer},
     components::{
-        AnimatedSprite, Chemlight, Collider, ColliderGroup, Enemy, Floor, Interactable, Light,
-        LightOccluder, LightOccluderGroup, ParticleEmitter, PerfectlyGenericItem, Player, Pos,
-        Projectile, Prop, ProximityIndicator, Static, TestItem, Torch, Wall, CH_HITBOX, CH_NAV,
-        CH_NONE,
+        AmbienceZone, Ambush, AnimatedSprite, Attachment, Boss, BossAttack, BossState, Checkpoint,
+        Chemlight, Collider, ColliderGroup, Container, Conveyor, Dash, Door, Enemy, Equipment,
+        Explosive, Flashlight, Flocking, Floor, Gear, Hazard, HazardKind, Health, Heavy,
+        Interactable, Item, Light, LightAnimator, LightFlash, LightOccluder, LightOccluderGroup,
+        LootDrop, LootEntry, LootTable, MeleeSwing, Momentum, Mover, MoverMode, Occluder,
+        OccluderShape, ParticleEmitter, Partner, PerfectlyGenericItem, Pickup, Player, Pos,
+        PressurePlate, PrevPos, Projectile, Prop, ProximityIndicator, Pushable, Side,
+        SignalEmitter, SignalReceiver, SoundEmitter, Static, StatusEffect, StatusEffects,
+        StatusKind, Surface, SurfaceKind, Sword, Throwable, Timer, Timers, Torch, TriggerZone,
+        Wall, Weapon, INVENTORY_SIZE, MAX_TRIGGER_OCCUPANTS,
+    },
+    console::Console,
+    containers::{self, Containers},
+    cutscene::{self, CutsceneTrigger, Director, Step},
+    editor::{self, Editor},
+    effects,
+    equipment::{self, PlayerStats},
+    events::{
+        self, AnimationEvent, BossDied, Collided, Damaged, EnemyDied, EventLog, Events, Interacted,
+        PlayerDied, ProjectileHit, ShotFired,
     },
-    math::{Vec2, Vec3},
-    Ctx, DepthBuffer, DrawCmd,
+    floating_text, haptics,
+    layers::CollisionLayers,
+    lobby::{self, Lobby},
+    locale::{tr, tr_args},
+    math::{self, Transform, Vec2, Vec3},
+    music,
+    net::{self, Net},
+    particles,
+    physics::{self, Physics},
+    prefabs::{spawn_prefab, PrefabRegistry},
+    procgen::{self, Dungeon, Tile},
+    rng::GameRng,
+    rooms,
+    saves::{self, SaveSlots},
+    scheduler::{Scheduler, Stage},
+    scripts,
+    settings::{self, Settings},
+    shop::{self, Shop},
+    signals::{self, Signal, SignalBroker},
+    solver::{self, Contact, SKIN},
+    stats::{self, Stats, ACHIEVEMENTS},
+    toasts::{self, Toasts},
+    tooltip,
+    transition::{self, Style},
+    tuning::Tuning,
+    tween::{self, Alpha, Ease, LightIntensity, LightRadius, Offset, Scale, Tween},
+    ui::{Anchor, TextCache, Ui},
+    walls, world_to_screen, AnimationRepository, Ctx, DepthBuffer, DrawCmd, DrawOptions,
+    InputState, Inventory, Layer, Sprite, SpriteRegistry, Spritesheet, SPRITESHEET_TEXTURE,
+    TICKS_PER_SECOND,
+};
+
+pub const TILE_SIZE: f32 = 32.0;
+// bullets start this far out from the shooter
+pub const MUZZLE_OFFSET: f32 = 30.0;
+const CRATE_PUSH_SPEED: f32 = 2.0;
+const CRATE_FRICTION: f32 = 0.6;
+const BARREL_PUSH_SPEED: f32 = 3.0;
+const BARREL_DAMAGE: u16 = 3;
+// how far above the player's feet a carried prop is drawn
+const CARRY_HEIGHT: i16 = 40;
+const THROW_SPEED: f32 = 7.0;
+// how close to the screen edge local players get before they hold each other back
+const LEASH_MARGIN: f32 = 48.0;
+const THROW_TICKS: u32 = 30;
+// share of a thrown prop's velocity passed on to whatever pushable it hits
+const THROW_IMPULSE: f32 = 0.5;
+const CONVEYOR_SPEED: f32 = 1.5;
+const SPIKE_CYCLE_TICKS: u32 = 90;
+const HAZARD_DAMAGE_INTERVAL: u32 = 30;
+const ICE_FRICTION: f32 = 0.05;
+// fraction of a wading sprite's height that stays above the water
+const WATERLINE: f32 = 0.75;
+const LOOT_SCATTER_SPEED: f32 = 3.0;
+const PICKUP_FRICTION: f32 = 0.85;
+const PICKUP_BOB_HEIGHT: f32 = 3.0;
+const PICKUP_BOB_TICKS: u32 = 40;
+const PICKUP_POP_TICKS: u32 = 30;
+// how far above an entity's feet its floating text starts
+const FLOATING_TEXT_HEIGHT: f32 = 40.0;
+const PICKUP_RANGE: f32 = 16.0;
+const COIN_MAGNET_RANGE: f32 = 96.0;
+const COIN_MAGNET_SPEED: f32 = 0.8;
+const INVENTORY_COLUMNS: usize = 4;
+const INVENTORY_SLOT_SIZE: i32 = 48;
+const DOOR_TICKS_PER_FRAME: u32 = 10;
+// doors lift a little as they open
+const DOOR_LIFT: f32 = 6.0;
+const LIGHT_FADE_TICKS: u32 = 20;
+const DAMAGE_FLASH_TICKS: u32 = 8;
+const BOSS_HP: i32 = 40;
+const BOSS_CHARGE_SPEED: f32 = 5.0;
+const BOSS_CHARGE_TICKS: u32 = 40;
+const BOSS_RECOVER_TICKS: u32 = 45;
+const BOSS_BULLET_SPEED: f32 = 2.0;
+const BOSS_CONTACT_RANGE: f32 = 40.0;
+const PLAYER_KNOCKBACK: f32 = 24.0;
+const PLAYER_HIT_IFRAME_TICKS: u32 = 45;
+const BOSS_BULLET_DAMAGE: u16 = 1;
+const BOSS_CHARGE_DAMAGE: u16 = 2;
+const EXPLOSION_FLASH_TICKS: u32 = 20;
+const SLASH_TICKS: u32 = 10;
+const DASH_SPEED: f32 = 10.0;
+const DASH_TICKS: u32 = 10;
+pub const PLAYER_HP: i32 = 10;
+const DASH_COOLDOWN_TICKS: u32 = 60;
+// a little longer than the dash itself, so landing in a hit is forgiven
+const DASH_IFRAME_TICKS: u32 = 16;
+const PLAYER_ANIMS: AnimGraphDef = AnimGraphDef {
+    states: &[
+        AnimState {
+            name: "idle",
+            anim: "player_idle",
+            ticks_per_frame: 30,
+            one_shot: false,
+            transitions: &[Transition {
+                to: "walk",
+                when: Cond::Moving,
+            }],
+        },
+        AnimState {
+            name: "walk",
+            anim: "player_walk",
+            ticks_per_frame: 5,
+            one_shot: false,
+            transitions: &[
+                Transition {
+                    to: "idle",
+                    when: Cond::Still,
+                },
+                Transition {
+                    to: "run",
+                    when: Cond::SpeedAbove(6.),
+                },
+            ],
+        },
+        // holding shift
+        AnimState {
+            name: "run",
+            anim: "player_walk",
+            ticks_per_frame: 3,
+            one_shot: false,
+            transitions: &[
+                Transition {
+                    to: "idle",
+                    when: Cond::Still,
+                },
+                Transition {
+                    to: "walk",
+                    when: Cond::SpeedBelow(6.),
+                },
+            ],
+        },
+        AnimState {
+            name: "dash",
+            anim: "player_dash",
+            ticks_per_frame: DASH_TICKS,
+            one_shot: true,
+            transitions: &[],
+        },
+    ],
+    directions: Directions::Eight,
+};
+// these only turn to face where they're going
+const ENEMY_ANIMS: AnimGraphDef = AnimGraphDef {
+    states: &[AnimState {
+        name: "walk",
+        anim: "enemy_walk",
+        ticks_per_frame: 30,
+        one_shot: false,
+        transitions: &[],
+    }],
+    directions: Directions::Two,
+};
+const BOSS_ANIMS: AnimGraphDef = AnimGraphDef {
+    states: &[AnimState {
+        name: "walk",
+        anim: "boss_walk",
+        ticks_per_frame: 20,
+        one_shot: false,
+        transitions: &[],
+    }],
+    directions: Directions::Two,
 };
+// pixels at full trauma
+const CAMERA_SHAKE_MAX: f32 = 12.0;
+const CAMERA_TRAUMA_DECAY: f32 = 0.03;
+// noise features per tick
+const CAMERA_SHAKE_FREQUENCY: f32 = 0.7;
+const CAMERA_SHAKE_SEED: u32 = 0x5a4e;
 
-const TILE_SIZE: f32 = 32.0;
+struct BossPhase {
+    // the phase starts once health drops to this fraction of max_hp
+    health_fraction: f32,
+    // attacks are used in order, round and round
+    pattern: &'static [BossAttack],
+    idle_ticks: u32,
+    telegraph_ticks: u32,
+    ring_bullets: u32,
+    speed: f32,
+}
+
+const BOSS_PHASES: [BossPhase; 3] = [
+    BossPhase {
+        health_fraction: 1.,
+        pattern: &[BossAttack::Ring],
+        idle_ticks: 120,
+        telegraph_ticks: 50,
+        ring_bullets: 10,
+        speed: 0.5,
+    },
+    BossPhase {
+        health_fraction: 0.66,
+        pattern: &[BossAttack::Ring, BossAttack::Charge],
+        idle_ticks: 90,
+        telegraph_ticks: 40,
+        ring_bullets: 14,
+        speed: 0.7,
+    },
+    BossPhase {
+        health_fraction: 0.33,
+        pattern: &[BossAttack::Charge, BossAttack::Ring, BossAttack::Charge],
+        idle_ticks: 60,
+        telegraph_ticks: 30,
+        ring_bullets: 20,
+        speed: 0.9,
+    },
+];
 
 #[inline(always)]
-fn tile_to_pos(x: i32, y: i32) -> Pos {
+pub fn tile_to_pos(x: i32, y: i32) -> Pos {
     Pos::new(
         x as f32 * TILE_SIZE + (TILE_SIZE / 2.),
         y as f32 * TILE_SIZE + (TILE_SIZE / 2.),
     )
 }
 
+pub fn pos_to_tile(pos: Pos) -> (i32, i32) {
+    (
+        (pos.x / TILE_SIZE).floor() as i32,
+        (pos.y / TILE_SIZE).floor() as i32,
+    )
+}
+
 pub fn init(world: &World) {
+    register_prefabs(world.resource_mut::<PrefabRegistry>().unwrap());
+
     for x in 0..64 {
         for y in 0..64 {
-            spawn_floor(world, tile_to_pos(x, y));
+            // a boarded stretch east of the start
+            let prefab = if (16..24).contains(&x) && (10..16).contains(&y) {
+                "wood_floor"
+            } else {
+                "floor"
+            };
+            spawn_prefab(world, prefab, tile_to_pos(x, y));
         }
     }
 
@@ -56,534 +299,3727 @@ pub fn init(world: &World) {
     spawn_wall(world, tile_to_pos(16, 16), true, true);
     spawn_wall(world, tile_to_pos(16, 17), true, true);
 
-    spawn_torch(world, (350.0, 570.0).into());
-    spawn_torch(world, (600.0, 200.0).into());
+    spawn_prefab(world, "torch", (350.0, 570.0).into());
+    spawn_torch(world, (600.0, 200.0).into(), Some(2));
+
+    spawn_particle_emitter(world, (540.0, 640.0).into(), 1);
 
-    world.resource_mut::<Ctx>().unwrap().particle_emitter_entity =
-        Some(spawn_particle_emitter(world, (540.0, 640.0).into()));
+    spawn_lever(world, (200.0, 200.0).into(), 1);
+    spawn_lever(world, (264.0, 200.0).into(), 2);
+    spawn_lever(world, (336.0, 520.0).into(), 3);
 
-    spawn_lever(
+    spawn_door(world, tile_to_pos(12, 18), Some(3));
+    spawn_prefab(world, "vendor", (136.0, 420.0).into());
+
+    // the lever by the entrance shows off the cellar
+    spawn_lever(world, (400.0, 200.0).into(), 5);
+    spawn_cutscene_trigger(world, (400.0, 200.0).into(), Some(5), cellar_cutscene);
+
+    // push the crate onto the plate to light the torch
+    spawn_pressure_plate(world, (500.0, 300.0).into(), 4);
+    spawn_prefab(world, "crate", (440.0, 300.0).into());
+    spawn_prefab(world, "barrel", (380.0, 300.0).into());
+    spawn_prefab(world, "barrel", (380.0, 340.0).into());
+    spawn_torch(world, (560.0, 280.0).into(), Some(4));
+
+    // a sliding block and a belt feeding into the cellar door
+    spawn_moving_wall(
         world,
-        (200.0, 200.0).into(),
-        move |world: &World, me: Entity| {
-            let sprite = world.component_mut::<AnimatedSprite>(me).unwrap();
-            sprite.flip_horizontal = !sprite.flip_horizontal;
-            let particle_emitter_entity = world
-                .resource_mut::<Ctx>()
-                .unwrap()
-                .particle_emitter_entity
-                .unwrap();
-            let particle_emitter = world
-                .component_mut::<ParticleEmitter>(particle_emitter_entity)
-                .unwrap();
-            particle_emitter.is_active = !particle_emitter.is_active;
-            world
-                .component_mut::<Light>(particle_emitter_entity)
-                .unwrap()
-                .radius = if particle_emitter.is_active { 60 } else { 0 };
-        },
-    );
-
-    spawn_player(world, Vec2::new(400.0, 400.0));
-}
-
-pub fn update(world: &World) {
-    update_spawners(world);
-    update_player(world);
-    update_enemies(world);
-    update_projectiles(world);
-    fix_colliders(world);
-    detect_collisions(world);
-
-    let mut despawn_queue = world
-        .resource_mut::<Ctx>()
-        .unwrap()
-        .despawn_queue
-        .write()
-        .unwrap();
+        &[tile_to_pos(20, 12), tile_to_pos(26, 12)],
+        1.0,
+        MoverMode::PingPong,
+    );
+    spawn_moving_wall(
+        world,
+        &[
+            tile_to_pos(30, 11),
+            tile_to_pos(34, 11),
+            tile_to_pos(34, 15),
+            tile_to_pos(30, 15),
+        ],
+        1.5,
+        MoverMode::Loop,
+    );
+    for y in 13..18 {
+        spawn_conveyor(world, tile_to_pos(12, y), Vec2::new(0., CONVEYOR_SPEED));
+    }
+    spawn_ambush(
+        world,
+        tile_to_pos(7, 8),
+        &[tile_to_pos(4, 5), tile_to_pos(11, 5)],
+    );
+
+    // an ice rink and a spike trap in the cellar
+    for y in 22..25 {
+        for x in 20..26 {
+            spawn_hazard(world, tile_to_pos(x, y), HazardKind::Ice);
+        }
+    }
+    for x in 6..9 {
+        spawn_hazard(world, tile_to_pos(x, 24), HazardKind::Spikes);
+    }
+    // and a pool to wade through
+    for y in 26..30 {
+        for x in 40..47 {
+            spawn_hazard(world, tile_to_pos(x, y), HazardKind::Water);
+        }
+    }
+
+    // stormy corridor up top, cold cellar past the door
+    // the boss guards a vault that opens when it dies
+    for x in 0..64 {
+        if x != 32 {
+            spawn_wall(world, tile_to_pos(x, 40), x == 33, x == 31);
+        }
+    }
+    spawn_door(world, tile_to_pos(32, 40), Some(6));
+    spawn_boss(world, tile_to_pos(32, 32), Some(6));
+    for x in 30..35 {
+        spawn_coin(world, tile_to_pos(x, 43), 5);
+    }
+
+    spawn_ambience_zone(world, (0, 0), (64, 8), Color::RGB(60, 60, 80), true);
+    spawn_ambience_zone(world, (0, 19), (64, 45), Color::RGB(35, 40, 60), false);
+
+    spawn_prefab(world, "checkpoint", (300.0, 660.0).into());
+    spawn_prefab(world, "checkpoint", tile_to_pos(32, 38));
 
-    for e in despawn_queue.iter() {
-        world.despawn(*e);
+    spawn_prefab(world, "player", (400.0, 400.0).into());
+    world.resource_mut::<Checkpoints>().unwrap().respawn_pos = (400.0, 400.0).into();
+
+    register_console_commands(world.resource_mut::<Console>().unwrap());
+}
+
+// everything but the player
+pub fn clear_level(world: &World) {
+    for entity in level_entities(world) {
+        world.despawn(entity);
     }
+}
 
-    despawn_queue.clear();
+// everything placed in the world except the players
+pub fn level_entities(world: &World) -> Vec<Entity> {
+    let mut level = Vec::new();
+    world.run(|entity: &Entity, _: &Pos, _: Without<Player>| {
+        level.push(*entity);
+    });
+    level
 }
 
-fn spawn_player(world: &World, pos: Vec2<f32>) {
+// swaps the current level for a freshly generated dungeon, the player carries over
+pub fn regenerate_level(world: &World) {
+    clear_level(world);
+
     let ctx = world.resource_mut::<Ctx>().unwrap();
-    world.spawn(&[
-        &Player {
-            fire_cooldown: ctx.player_fire_cooldown,
-            can_fire_in: 0,
+    let dungeon = procgen::generate(
+        ctx.room_size.0 as i32 / TILE_SIZE as i32,
+        ctx.room_size.1 as i32 / TILE_SIZE as i32,
+        world.resource_mut::<GameRng>().unwrap(),
+    );
+    ctx.level_name = "dungeon";
+    spawn_dungeon(world, &dungeon);
+}
+
+fn spawn_dungeon(world: &World, dungeon: &Dungeon) {
+    for y in 0..dungeon.height {
+        for x in 0..dungeon.width {
+            match dungeon.get(x, y) {
+                Tile::Floor => {
+                    spawn_prefab(world, "floor", tile_to_pos(x, y));
+                }
+                Tile::Hazard(kind) => {
+                    spawn_hazard(world, tile_to_pos(x, y), kind);
+                }
+                Tile::Wall => {
+                    spawn_wall(
+                        world,
+                        tile_to_pos(x, y),
+                        dungeon.get(x - 1, y).is_walkable(),
+                        dungeon.get(x + 1, y).is_walkable(),
+                    );
+                }
+                Tile::Empty => {}
+            }
+        }
+    }
+
+    // channels of levers placed by hand stay free
+    let mut next_channel = 100;
+    for (idx, room) in dungeon.rooms.iter().enumerate() {
+        spawn_prefab(world, "torch", tile_to_pos(room.x + 1, room.y));
+
+        if idx == 0 {
+            continue;
+        }
+        // every few rooms, in the corner across from the torch
+        if idx % 3 == 0 {
+            spawn_prefab(
+                world,
+                "checkpoint",
+                tile_to_pos(room.x + room.w - 2, room.y + room.h - 2),
+            );
+        }
+
+        if idx % 2 == 1 {
+            let channel = next_channel;
+            next_channel += 1;
+            spawn_lever(world, tile_to_pos(room.x + room.w - 2, room.y + 1), channel);
+            let (cx, cy) = room.center();
+            spawn_particle_emitter(world, tile_to_pos(cx, cy), channel);
+        } else {
+            let (cx, cy) = room.center();
+            spawn_prefab(world, "enemy", tile_to_pos(cx, cy));
+        }
+    }
+
+    let (x, y) = dungeon.rooms[0].center();
+    let start = tile_to_pos(x, y);
+    world.resource_mut::<Checkpoints>().unwrap().respawn_pos = start;
+    world.run(|pos: &mut Pos, prev_pos: &mut PrevPos, _: With<Player>| {
+        *pos = start;
+        prev_pos.0 = start;
+    });
+}
+
+fn register_prefabs(prefabs: &mut PrefabRegistry) {
+    prefabs.register("player", spawn_player);
+    prefabs.register("enemy", spawn_enemy);
+    prefabs.register("boss", |world, pos| spawn_boss(world, pos, None));
+    prefabs.register("floor", spawn_floor);
+    prefabs.register("wood_floor", spawn_wood_floor);
+    prefabs.register("wall", |world, pos| spawn_wall(world, pos, false, false));
+    prefabs.register("ledge", spawn_ledge);
+    prefabs.register("door", |world, pos| spawn_door(world, pos, None));
+    prefabs.register("torch", |world, pos| spawn_torch(world, pos, None));
+    prefabs.register("crate", spawn_crate);
+    prefabs.register("barrel", spawn_barrel);
+    prefabs.register("checkpoint", spawn_checkpoint);
+    prefabs.register("lever", |world, pos| spawn_lever(world, pos, 0));
+    prefabs.register("script_zone", spawn_script_zone);
+    prefabs.register("vendor", spawn_vendor);
+    prefabs.register("chest", spawn_chest);
+    prefabs.register("coin", |world, pos| spawn_coin(world, pos, 1));
+    prefabs.register("chemlight_pickup", |world, pos| {
+        spawn_item_pickup(world, pos, "chemlight", |world, player| {
+            containers::inventory_mut(world, player)
+                .is_some_and(|inventory| inventory.insert(Chemlight::new(), world))
+        })
+    });
+    prefabs.register("spikes", |world, pos| {
+        spawn_hazard(world, pos, HazardKind::Spikes)
+    });
+    prefabs.register("lava", |world, pos| {
+        spawn_hazard(world, pos, HazardKind::Lava)
+    });
+    prefabs.register("ice", |world, pos| {
+        spawn_hazard(world, pos, HazardKind::Ice)
+    });
+    prefabs.register("water", |world, pos| {
+        spawn_hazard(world, pos, HazardKind::Water)
+    });
+    prefabs.register("pressure_plate", |world, pos| {
+        spawn_pressure_plate(world, pos, 0)
+    });
+}
+
+// None when there's no such item, Some(false) when the inventory is full or there's nothing
+// to carry it
+pub fn give_item(world: &World, entity: Entity, name: &str) -> Option<bool> {
+    let item = create_item(name)?;
+    let Some(inventory) = containers::inventory_mut(world, entity) else {
+        return Some(false);
+    };
+    Some(inventory.insert_boxed(item, world).is_ok())
+}
+
+pub fn player_entity(world: &World, slot: u8) -> Option<Entity> {
+    let mut found = None;
+    world.run(|entity: &Entity, player: &Player| {
+        if player.slot == slot {
+            found = Some(*entity);
+        }
+    });
+    found
+}
+
+// by the names the give command and the shop use
+pub fn create_item(name: &str) -> Option<Box<dyn Item>> {
+    Some(match name {
+        "pistol" => Box::new(Weapon::pistol()),
+        "shotgun" => Box::new(Weapon::shotgun()),
+        "automatic" => Box::new(Weapon::automatic()),
+        "bouncer" => Box::new(Weapon::bouncer()),
+        "flamer" => Box::new(Weapon::flamer()),
+        "piercer" => Box::new(Weapon::piercer()),
+        "launcher" => Box::new(Weapon::launcher()),
+        "sword" => Box::new(Sword::new()),
+        "torch" => Box::new(Torch::new()),
+        "chemlight" => Box::new(Chemlight::new()),
+        "flashlight" => Box::new(Flashlight::new()),
+        "armor" => Box::new(Gear::armor()),
+        "boots" => Box::new(Gear::boots()),
+        "lantern_charm" => Box::new(Gear::lantern_charm()),
+        "war_charm" => Box::new(Gear::war_charm()),
+        _ => return None,
+    })
+}
+
+fn register_console_commands(console: &mut Console) {
+    console.register("spawn", "spawn <prefab> - at the cursor", |world, args| {
+        let pos = world.resource::<Ctx>().unwrap().cursor_world_pos();
+        let Some(name) = args.first() else {
+            let names = world.resource::<PrefabRegistry>().unwrap().names();
+            return Err(format!("usage: spawn <{}>", names.join("|")));
+        };
+        match spawn_prefab(world, name, pos) {
+            Some(_) => Ok(format!("spawned {} at {:.0}, {:.0}", name, pos.x, pos.y)),
+            None => Err(format!("unknown prefab '{}'", name)),
+        }
+    });
+
+    console.register(
+        "seed",
+        "seed [n] - reseeds and regenerates the level",
+        |world, args| {
+            let rng = world.resource_mut::<GameRng>().unwrap();
+            let Some(seed) = args.first() else {
+                return Ok(format!("seed {}", rng.seed()));
+            };
+            let seed = seed
+                .parse()
+                .map_err(|_| format!("'{}' is not a seed", seed))?;
+            rng.reseed(seed);
+            transition::cover_then(
+                world,
+                Style::Fade(Color::BLACK),
+                transition::ROOM_TICKS,
+                regenerate_level,
+            );
+            Ok(format!("reseeded with {}", seed))
+        },
+    );
+
+    console.register(
+        "systems",
+        "systems - last run time of every system",
+        |world, _| {
+            let scheduler = world.resource::<Scheduler>().unwrap();
+            let lines: Vec<String> = scheduler
+                .systems()
+                .iter()
+                .map(|s| format!("{:?}/{}: {}us", s.stage, s.name, s.time.as_micros()))
+                .collect();
+            Ok(lines.join(" | "))
+        },
+    );
+
+    console.register(
+        "status",
+        "status <burning|slow|poison|invulnerable> [ticks] - on the player",
+        |world, args| {
+            let ticks = match args.get(1) {
+                Some(ticks) => ticks.parse().map_err(|_| "bad tick count".to_string())?,
+                None => 300,
+            };
+            let effect = match args.first() {
+                Some(&"burning") => StatusEffect::burning(ticks),
+                Some(&"slow") => StatusEffect::slow(ticks),
+                Some(&"poison") => StatusEffect::poison(ticks),
+                Some(&"invulnerable") => StatusEffect::invulnerable(ticks),
+                _ => return Err("unknown effect".into()),
+            };
+
+            let mut applied = false;
+            world.run(|status: &mut StatusEffects, _: With<Player>| {
+                status.apply(effect);
+                applied = true;
+            });
+            if applied {
+                Ok(format!("applied {}", args[0]))
+            } else {
+                Err("no player".into())
+            }
+        },
+    );
+
+    console.register(
+        "give",
+        "give <pistol|shotgun|automatic|bouncer|flamer|piercer|launcher|sword|torch|chemlight|flashlight>",
+        |world, args| match args
+            .first()
+            .zip(player_entity(world, 0))
+            .map(|(name, player)| give_item(world, player, name))
+        {
+            Some(Some(true)) => Ok(format!("gave {}", args[0])),
+            Some(Some(false)) => Err("inventory is full".into()),
+            _ => Err("unknown item".into()),
+        },
+    );
+
+    console.register(
+        "set",
+        "set <player_speed|enemy_speed|bullet_speed|bullet_lifetime|player_fire_cooldown> <value>",
+        |world, args| {
+            let [name, value] = args else {
+                return Err("usage: set <name> <value>".into());
+            };
+            world.resource_mut::<Tuning>().unwrap().set(name, value)?;
+            Ok(format!("{} = {}", name, value))
+        },
+    );
+
+    console.register(
+        "room",
+        "room save|load <name>, room import <file.tmx>",
+        |world, args| match args {
+            ["save", name] => editor::save_room(world, name)
+                .map(|count| format!("saved {} placements to {}", count, rooms::room_path(name))),
+            ["load", name] => {
+                editor::load_room(world, name).map(|count| format!("loaded {} placements", count))
+            }
+            ["import", path] => editor::import_tmx(world, path)
+                .map(|count| format!("imported {} placements", count)),
+            _ => Err("usage: room save|load <name>, room import <file.tmx>".into()),
+        },
+    );
+
+    console.register("save", "save [slot]", |world, args| {
+        let slot = match args {
+            [] => None,
+            [slot] => match slot.parse::<usize>() {
+                Ok(slot) if (1..=saves::NUM_SLOTS).contains(&slot) => Some(slot - 1),
+                _ => return Err(format!("'{}' is not a slot", slot)),
+            },
+            _ => return Err("usage: save [slot]".into()),
+        };
+        world
+            .resource_mut::<SaveSlots>()
+            .unwrap()
+            .request_save(slot);
+        Ok("saving".into())
+    });
+
+    console.register("stats", "stats", |world, _| {
+        let stats = world.resource::<Stats>().unwrap();
+        let unlocked = ACHIEVEMENTS
+            .iter()
+            .filter(|a| stats.is_unlocked(a.id))
+            .count();
+        Ok(format!(
+            "{} kills, {} shots, {:.0} tiles walked, {} items used, {} deaths, {}/{} achievements",
+            stats.enemies_killed,
+            stats.shots_fired,
+            stats.distance_walked / TILE_SIZE,
+            stats.items_used,
+            stats.deaths,
+            unlocked,
+            ACHIEVEMENTS.len()
+        ))
+    });
+
+    console.register(
+        "events",
+        "events [interacted|collided|damaged|item_used|shot_fired|enemy_died|boss_died|all|off]",
+        |world, args| {
+            let log = world.resource_mut::<EventLog>().unwrap();
+            log.filter = match args.first() {
+                None if log.filter.is_some() => None,
+                None | Some(&"all") => Some("all"),
+                Some(&"off") => None,
+                Some(name) => match events::LOG_FILTERS.iter().find(|f| *f == name) {
+                    Some(filter) => Some(filter),
+                    None => return Err(format!("unknown event '{}'", name)),
+                },
+            };
+            Ok(format!("logging {} events", log.filter.unwrap_or("no")))
+        },
+    );
+
+    console.register(
+        "resolution",
+        "resolution [native|<w> <h>] [integer]",
+        |world, args| {
+            let usage = || "usage: resolution [native|<w> <h>] [integer]".to_string();
+            let ctx = world.resource_mut::<Ctx>().unwrap();
+            let integer_scale = args.last() == Some(&"integer");
+            let size = match &args[..args.len() - integer_scale as usize] {
+                [] | ["native"] => None,
+                [w, h] => match (w.parse(), h.parse()) {
+                    (Ok(w), Ok(h)) if w > 0 && h > 0 => Some((w, h)),
+                    _ => return Err(usage()),
+                },
+                _ => return Err(usage()),
+            };
+            ctx.set_logical_size(size, integer_scale);
+            let (w, h) = ctx.render_size();
+            Ok(format!(
+                "rendering at {}x{}{}",
+                w,
+                h,
+                if integer_scale {
+                    ", integer scaled"
+                } else {
+                    ""
+                }
+            ))
         },
+    );
+
+    console.register("shadows", "shadows [on|off]", |world, args| {
+        let ctx = world.resource_mut::<Ctx>().unwrap();
+        ctx.shadows_enabled = match args.first() {
+            Some(&"on") => true,
+            Some(&"off") => false,
+            None => !ctx.shadows_enabled,
+            _ => return Err("usage: shadows [on|off]".into()),
+        };
+        Ok(format!(
+            "shadows {}",
+            if ctx.shadows_enabled { "on" } else { "off" }
+        ))
+    });
+
+    console.register(
+        "tp",
+        "tp [x y] - to the cursor without arguments",
+        |world, args| {
+            let ctx = world.resource::<Ctx>().unwrap();
+            let target = match args {
+                [] => ctx.cursor_world_pos(),
+                [x, y] => match (x.parse(), y.parse()) {
+                    (Ok(x), Ok(y)) => Pos::new(x, y),
+                    _ => return Err("usage: tp [x y]".into()),
+                },
+                _ => return Err("usage: tp [x y]".into()),
+            };
+            world.run(|pos: &mut Pos, prev_pos: &mut PrevPos, _: With<Player>| {
+                *pos = target;
+                // don't interpolate across the teleport
+                prev_pos.0 = target;
+            });
+            Ok(format!("teleported to {:.0}, {:.0}", target.x, target.y))
+        },
+    );
+}
+
+pub fn register_systems(scheduler: &mut Scheduler) {
+    scheduler.add(Stage::Input, "prev_positions", store_prev_positions);
+    // the client mirrors the host even though its own simulation is paused
+    scheduler
+        .add(Stage::Input, "net_receive", net::receive)
+        .after("prev_positions");
+    scheduler
+        .add(Stage::Input, "lobby", lobby::update)
+        .after("net_receive");
+    scheduler
+        .add(Stage::Input, "cutscene", update_cutscene)
+        .after("prev_positions");
+    scheduler
+        .add(Stage::Input, "menus", update_menus)
+        .after("cutscene");
+    scheduler
+        .add(Stage::Input, "player_stats", equipment::update)
+        .after("menus");
+    scheduler
+        .add(Stage::Input, "player", update_player)
+        .after("player_stats")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Input, "leash", leash_players)
+        .after("player")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Input, "partner", update_partner)
+        .after("player")
+        .run_if(is_unpaused);
+
+    scheduler
+        .add(Stage::Ai, "timers", update_timers)
+        .reads::<Projectile>()
+        .writes::<Timers>()
+        .writes::<CommandBuffer>()
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "spawners", particles::update_emitters)
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "lights", update_lights)
+        .after("timers")
+        .writes::<LightAnimator>()
+        .writes::<LightFlash>()
+        .writes::<Light>()
+        .writes::<GameRng>()
+        .writes::<CommandBuffer>()
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "camera_shake", update_camera_shake)
+        .writes::<Ctx>()
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "ambient", ambient::update)
+        .after("lights")
+        .reads::<AmbienceZone>()
+        .reads::<Pos>()
+        .reads::<Player>()
+        .writes::<AmbientLight>()
+        .writes::<GameRng>()
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "anim_graphs", anim_graph::update)
+        .after("boss")
+        .after("enemies")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "animations", update_animations)
+        .after("anim_graphs")
+        .reads::<AnimationRepository>()
+        .writes::<AnimatedSprite>()
+        .writes::<ProximityIndicator>()
+        .writes::<Events<AnimationEvent>>()
+        .run_if(is_unpaused);
+    scheduler
+        .add_job(Stage::Ai, "ambient_fade", AmbientLight::tick)
+        .after("ambient")
+        .run_if(is_unpaused);
+    // keeps going while paused, menus can push toasts too
+    scheduler.add_job(Stage::Ai, "toasts", toasts::update);
+    scheduler.add(Stage::Ai, "transitions", update_transitions);
+    scheduler
+        .add(Stage::Ai, "tweens", tween::update)
+        .after("animations")
+        .after("lights")
+        .writes::<Tween<Offset>>()
+        .writes::<Tween<LightRadius>>()
+        .writes::<Tween<LightIntensity>>()
+        .writes::<Tween<Alpha>>()
+        .writes::<Tween<Scale>>()
+        .writes::<AnimatedSprite>()
+        .writes::<LightAnimator>()
+        .writes::<Light>()
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "signals", signals::dispatch)
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "doors", update_doors)
+        .after("signals")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "status_effects", update_status_effects)
+        .run_if(is_unpaused);
+    scheduler.add(Stage::Ai, "containers", containers::prune);
+    scheduler
+        .add(Stage::Ai, "walls", walls::update)
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "boss", update_boss)
+        .after("status_effects")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Ai, "enemies", update_enemies)
+        .after("status_effects")
+        .run_if(is_unpaused);
+
+    scheduler
+        .add(Stage::Physics, "hazards", update_hazards)
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Physics, "projectiles", update_projectiles)
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Physics, "particles", particles::update)
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Physics, "movers", update_movers)
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Physics, "conveyors", update_conveyors)
+        .after("movers")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Physics, "pushables", update_pushables)
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Physics, "attachments", update_attachments)
+        .after("pushables")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Physics, "pickups", update_pickups)
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Physics, "fix_colliders", fix_colliders)
+        .after("projectiles")
+        .after("particles")
+        .after("conveyors")
+        .after("attachments")
+        .after("pickups")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::Physics, "collisions", detect_collisions)
+        .after("fix_colliders")
+        .run_if(is_unpaused);
+    // queries until the next rebuild see where everything ended up this tick
+    scheduler
+        .add(Stage::Physics, "broadphase", physics::rebuild)
+        .after("collisions")
+        .reads::<ColliderGroup>()
+        .writes::<Physics>()
+        .run_if(is_unpaused);
+
+    scheduler
+        .add(Stage::PostPhysics, "triggers", update_triggers)
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::PostPhysics, "event_log", events::log)
+        .after("triggers");
+    scheduler
+        .add(Stage::PostPhysics, "effects", effects::update)
+        .after("event_log")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::PostPhysics, "sound_emitters", audio::update)
+        .after("event_log");
+    scheduler
+        .add(Stage::PostPhysics, "music", music::update)
+        .after("sound_emitters");
+    scheduler
+        .add(Stage::PostPhysics, "stats", stats::update)
+        .after("event_log")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::PostPhysics, "checkpoints", checkpoints::update)
+        .after("event_log")
+        .run_if(is_unpaused);
+    scheduler
+        .add(Stage::PostPhysics, "commands", commands::apply)
+        .after("event_log")
+        .after("effects");
+    scheduler
+        .add(Stage::PostPhysics, "events", events::update)
+        .after("commands");
+    scheduler
+        .add(Stage::PostPhysics, "haptics", haptics::update)
+        .after("commands");
+    scheduler
+        .add(Stage::PostPhysics, "net_send", net::send)
+        .after("events");
+
+    scheduler.add(Stage::Render, "sprites", render);
+}
+
+// fnv-1a over what a desync shows up in first: every position, hit points and the rng.
+// two runs fed the same input must agree on it every tick
+pub fn state_hash(world: &World) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut add = |value: u64| {
+        for byte in value.to_le_bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+    };
+    add(world.resource::<GameRng>().unwrap().state());
+    world.run(|pos: &Pos| {
+        add(pos.x.to_bits() as u64);
+        add(pos.y.to_bits() as u64);
+    });
+    world.run(|health: &Health| add(health.hp as u64));
+    hash
+}
+
+// gameplay is paused while a menu is open, and the client leaves it to the host
+fn is_unpaused(world: &World) -> bool {
+    !world.resource::<Net>().unwrap().is_client() && !is_menu_open(world)
+}
+
+fn is_menu_open(world: &World) -> bool {
+    world.resource::<Lobby>().unwrap().is_open
+        || world.resource::<Shop>().unwrap().open.is_some()
+        || world.resource::<Containers>().unwrap().open.is_some()
+        || world.resource::<Ctx>().unwrap().inventory_open
+        || world.resource::<Settings>().unwrap().is_open
+        || world.resource::<SaveSlots>().unwrap().is_open
+        || world.resource::<Editor>().unwrap().is_active
+}
+
+fn update_transitions(world: &World) {
+    // the editor pauses too but wants to see the level
+    let is_menu_open = is_menu_open(world) && !world.resource::<Editor>().unwrap().is_active;
+    transition::set_dimmed(world, is_menu_open);
+    transition::update(world);
+}
+
+fn update_cutscene(world: &World) {
+    // the player just watches while a cutscene plays
+    cutscene::update(world);
+    if world.resource::<Director>().unwrap().is_playing() {
+        let ctx = world.resource_mut::<Ctx>().unwrap();
+        ctx.input.pressed = InputState::default();
+        ctx.input.just_pressed = InputState::default();
+    }
+}
+
+fn update_menus(world: &World) {
+    // it takes its keys from the events
+    if world.resource::<Lobby>().unwrap().is_open {
+        return;
+    }
+    if world.resource::<Editor>().unwrap().is_active {
+        editor::update(world);
+        return;
+    }
+    if world.resource::<Settings>().unwrap().is_open {
+        settings::update(world);
+        return;
+    }
+    if world.resource::<Shop>().unwrap().open.is_some() {
+        update_shop(world);
+        return;
+    }
+    if world.resource::<Containers>().unwrap().open.is_some() {
+        containers::update(world);
+        return;
+    }
+    if world.resource::<SaveSlots>().unwrap().is_open {
+        saves::update_menu(world);
+        return;
+    }
+
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    if ctx.input.just_pressed.tab {
+        ctx.inventory_open = !ctx.inventory_open;
+        ctx.inventory_held_slot = None;
+    }
+
+    if ctx.inventory_open {
+        update_inventory_screen(world);
+    }
+}
+
+fn spawn_player(world: &World, pos: Pos) -> Entity {
+    let player = spawn_player_body(world, pos, 0);
+
+    let inventory = containers::inventory_mut(world, player).unwrap();
+    assert!(inventory.insert(Weapon::pistol(), world));
+    assert!(inventory.insert(Weapon::shotgun(), world));
+    assert!(inventory.insert(Weapon::automatic(), world));
+    assert!(inventory.insert(Weapon::bouncer(), world));
+    assert!(inventory.insert(Torch::new(), world));
+    assert!(inventory.insert(Chemlight::new(), world));
+    assert!(inventory.insert(Flashlight::new(), world));
+    assert!(inventory.insert(PerfectlyGenericItem {}, world));
+
+    player
+}
+
+// next to the first player, with a lighter loadout
+pub fn spawn_second_player(world: &World) {
+    let mut pos = Pos::zero();
+    world.run(|player: &Player, player_pos: &Pos| {
+        if player.slot == 0 {
+            pos = Pos::new(player_pos.x + TILE_SIZE, player_pos.y);
+        }
+    });
+    let player = spawn_player_body(world, pos, 1);
+
+    let inventory = containers::inventory_mut(world, player).unwrap();
+    assert!(inventory.insert(Weapon::pistol(), world));
+    assert!(inventory.insert(Weapon::shotgun(), world));
+    assert!(inventory.insert(Torch::new(), world));
+}
+
+fn spawn_player_body(world: &World, pos: Pos, slot: u8) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let player = world.spawn(&[
+        &Player { slot },
+        &Health::new(PLAYER_HP),
+        &Dash::new(DASH_TICKS, DASH_COOLDOWN_TICKS),
+        &StatusEffects::new(),
+        &Momentum::new(),
         &Pos::new(pos.x, pos.y),
+        &PrevPos(Pos::new(pos.x, pos.y)),
         &AnimatedSprite::new(
             (-16, -48, 32, 64),
             15,
-            ctx.animations.get("player_idle").unwrap(),
+            animation(world, "player_idle"),
             None,
         ),
+        &AnimGraph::new(&PLAYER_ANIMS),
+        &Occluder::blob(10),
         &ColliderGroup {
-            nav: Some(Collider::new((-13, 0, 26, 16), CH_NAV, CH_NAV, None)),
+            nav: Some(Collider::on_layer(layers, "nav", (-13, 0, 26, 16))),
             hitbox: None,
         },
         &Light {
             radius: 0,
             color: Color::RGB(255, 255, 255),
             intensity: 0.,
+            cone: None,
+        },
+        &PlayerStats::new(PLAYER_HP),
+        &Equipment::new(),
+        &Container {
+            title: "container-inventory",
+            inventory: Inventory::container(0),
         },
     ]);
-
-    assert!(ctx.player_inventory.insert(TestItem {}, world));
-    assert!(ctx.player_inventory.insert(PerfectlyGenericItem {}, world));
-    assert!(ctx.player_inventory.insert(Torch::new(), world));
-    assert!(ctx.player_inventory.insert(Chemlight::new(), world));
+    // the items act on whoever carries them
+    *containers::inventory_mut(world, player).unwrap() = Inventory::carried_by(player);
+    player
 }
 
-fn spawn_lever(world: &World, pos: Pos, on_interact: fn(&World, Entity)) {
-    let ctx = world.resource::<Ctx>().unwrap();
+// the player's look and movement, nothing else
+fn spawn_partner(world: &World, pos: Pos) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let tuning = world.resource::<Tuning>().unwrap();
     world.spawn(&[
-        &pos,
+        &Partner {
+            fire_cooldown: Timer::ready(tuning.player_fire_cooldown),
+        },
+        &Momentum::new(),
+        &Pos::new(pos.x, pos.y),
+        &PrevPos(Pos::new(pos.x, pos.y)),
         &AnimatedSprite::new(
-            (-16, -16, 32, 32),
-            0,
-            ctx.animations.get("lever").unwrap(),
+            (-16, -48, 32, 64),
+            15,
+            animation(world, "player_idle"),
             None,
         ),
-        &Interactable { on_interact },
-        &ProximityIndicator {
-            range: 64.,
-            sprite: AnimatedSprite::new(
-                (-16, -16, 16, 16),
-                15,
-                ctx.animations.get("bang").unwrap(),
-                Some(255),
-            ),
+        &AnimGraph::new(&PLAYER_ANIMS),
+        &Occluder::blob(10),
+        &ColliderGroup {
+            nav: Some(Collider::on_layer(layers, "nav", (-13, 0, 26, 16))),
+            hitbox: None,
         },
-    ]);
+    ])
 }
 
-fn spawn_particle_emitter(world: &World, pos: Pos) -> Entity {
-    let ctx = world.resource::<Ctx>().unwrap();
+fn spawn_lever(world: &World, pos: Pos, channel: u16) -> Entity {
     world.spawn(&[
-        &Prop {},
         &pos,
-        &AnimatedSprite::new(
-            (-16, -16, 32, 32),
-            0,
-            ctx.animations.get("particle_emitter").unwrap(),
-            None,
-        ),
-        &ParticleEmitter {
-            is_active: false,
-            particle_cooldown: 1,
-            particle_ticks_left: 0,
+        &AnimatedSprite::new((-16, -16, 32, 32), 0, animation(world, "lever"), None),
+        &Interactable {
+            on_interact: on_lever_interact,
+            script: None,
         },
-        &Light {
-            radius: 0,
-            color: Color::RGB(150, 150, 150),
-            intensity: 1.,
+        &SignalEmitter {
+            channel,
+            is_on: false,
+        },
+        &ProximityIndicator {
+            range: 64.,
+            sprite: AnimatedSprite::new((-16, -16, 16, 16), 15, animation(world, "bang"), None),
         },
     ])
 }
 
-fn spawn_floor(world: &World, pos: Pos) -> Entity {
-    let ctx = world.resource::<Ctx>().unwrap();
+// does nothing until a room gives it a script, which runs for whoever walks in
+fn spawn_script_zone(world: &World, pos: Pos) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
     world.spawn(&[
-        &Floor {},
         &pos,
-        &AnimatedSprite::new(
-            (-16, -16, TILE_SIZE as u32, TILE_SIZE as u32),
-            0,
-            ctx.animations.get("floor").unwrap(),
-            None,
-        ),
+        &TriggerZone::new((-32, -32, 64, 64), layers.bit("nav"), None, None),
     ])
 }
 
-fn spawn_wall(world: &World, pos: Pos, occlude_left: bool, occlude_right: bool) -> Entity {
-    let ctx = world.resource::<Ctx>().unwrap();
-    world.spawn(&[
-        &Static {},
-        &Wall {},
-        &pos,
-        &AnimatedSprite::new(
-            (-16, -48, TILE_SIZE as u32, (TILE_SIZE * 2.) as u32),
-            0,
-            ctx.animations.get("wall").unwrap(),
-            None,
-        ),
-        &ColliderGroup {
-            nav: Some(Collider::new(
-                (-16, -14, 32, 30),
-                CH_NAV,
-                CH_NAV | CH_HITBOX,
-                None,
-            )),
-            hitbox: None,
+// wired to a channel, or a one-shot zone the player walks into
+fn spawn_cutscene_trigger(
+    world: &World,
+    pos: Pos,
+    channel: Option<u16>,
+    build: fn(&World) -> Vec<Step>,
+) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let trigger = CutsceneTrigger { build };
+    let receiver = channel.map(|channel| SignalReceiver {
+        channel,
+        on_signal: |world: &World, me: Entity, is_on: bool| {
+            if is_on {
+                play_cutscene_trigger(world, me);
+            }
         },
-        &LightOccluderGroup {
-            occluders: [
-                Some(LightOccluder {
-                    line: (Point::new(-16, -16), Point::new(16, -16)),
-                }),
-                if occlude_left {
-                    Some(LightOccluder {
-                        line: (Point::new(-16, -16), Point::new(-16, 15)),
-                    })
-                } else {
-                    None
-                },
-                if occlude_right {
-                    Some(LightOccluder {
-                        line: (Point::new(16, -16), Point::new(16, 15)),
-                    })
-                } else {
-                    None
-                },
-                None,
-            ],
+    });
+    let zone = TriggerZone::new(
+        (-32, -32, 64, 64),
+        layers.bit("nav"),
+        Some(|world: &World, me: Entity, other: Entity| {
+            if world.has_component::<Player>(other) {
+                play_cutscene_trigger(world, me);
+                world.component_mut::<TriggerZone>(me).unwrap().is_enabled = false;
+            }
+        }),
+        None,
+    );
+
+    let mut components: Vec<&dyn Component> = vec![&pos, &trigger];
+    if let Some(receiver) = receiver.as_ref() {
+        components.push(receiver);
+    } else {
+        components.push(&zone);
+    }
+
+    world.spawn(&components)
+}
+
+fn play_cutscene_trigger(world: &World, me: Entity) {
+    if !world.resource::<Director>().unwrap().is_playing() {
+        let build = world.component::<CutsceneTrigger>(me).unwrap().build;
+        cutscene::play(world, build(world));
+    }
+}
+
+fn cellar_cutscene(world: &World) -> Vec<Step> {
+    let door_pos = tile_to_pos(12, 18);
+
+    let mut player = None;
+    world.run(|entity: &Entity, _: &Player| {
+        player = Some(*entity);
+    });
+
+    // the torch closest to the door gutters out while it opens
+    let mut torch = None;
+    let mut torch_distance = f32::MAX;
+    world.run(
+        |entity: &Entity, pos: &Pos, _: &Light, _: Without<Player>| {
+            let distance = pos.distance(&door_pos);
+            if distance < torch_distance {
+                torch = Some(*entity);
+                torch_distance = distance;
+            }
+        },
+    );
+
+    let mut steps = vec![
+        Step::MoveCamera {
+            to: door_pos,
+            ticks: 60,
+        },
+        Step::Say {
+            line: "cutscene-cellar-stirs",
+            ticks: 120,
+        },
+    ];
+    if let Some(torch) = torch {
+        steps.push(Step::SetLight {
+            entity: torch,
+            is_on: false,
+        });
+    }
+    steps.push(Step::Wait(30));
+    steps.push(Step::Signal {
+        channel: 3,
+        is_on: true,
+    });
+    steps.push(Step::Wait(60));
+    if let Some(torch) = torch {
+        steps.push(Step::SetLight {
+            entity: torch,
+            is_on: true,
+        });
+    }
+    if let Some(player) = player {
+        steps.push(Step::MoveEntity {
+            entity: player,
+            path: vec![tile_to_pos(12, 12), tile_to_pos(12, 15)],
+            speed: 2.,
+        });
+    }
+    steps.push(Step::ReleaseCamera);
+    steps
+}
+
+// stocked from the price list
+fn spawn_vendor(world: &World, pos: Pos) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let shop = world.resource::<Shop>().unwrap();
+    let vendor = world.spawn(&[
+        &Static {},
+        &pos,
+        &AnimatedSprite::new((-16, -48, 32, 64), 30, animation(world, "vendor"), None),
+        &ColliderGroup {
+            nav: Some(Collider::on_layer(layers, "nav", (-13, 0, 26, 16))),
+            hitbox: None,
+        },
+        &Interactable {
+            on_interact: |world: &World, me: Entity, other: Entity| {
+                world.resource_mut::<Shop>().unwrap().open(me, other);
+            },
+            script: None,
+        },
+        &Container {
+            title: "shop-title",
+            inventory: Inventory::container(shop.entries.len()),
+        },
+    ]);
+    let inventory = containers::inventory_mut(world, vendor).unwrap();
+    for entry in &shop.entries {
+        match create_item(&entry.item) {
+            Some(item) => {
+                let _ = inventory.insert_boxed(item, world);
+            }
+            None => println!("shop: no item {}", entry.item),
+        }
+    }
+    vendor
+}
+
+// starts out with a light and something to light it with
+fn spawn_chest(world: &World, pos: Pos) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let chest = world.spawn(&[
+        &Static {},
+        &pos,
+        &AnimatedSprite::new((-16, -24, 32, 32), 0, animation(world, "chest"), None),
+        &ColliderGroup {
+            nav: Some(Collider::on_layer(layers, "nav", (-14, -14, 28, 28))),
+            hitbox: None,
+        },
+        &Container {
+            title: "container-chest",
+            inventory: Inventory::container(INVENTORY_SIZE),
+        },
+        &Interactable {
+            on_interact: containers::open,
+            script: None,
+        },
+    ]);
+    let inventory = containers::inventory_mut(world, chest).unwrap();
+    for name in ["torch", "chemlight"] {
+        let _ = inventory.insert_boxed(create_item(name).unwrap(), world);
+    }
+    chest
+}
+
+// left behind by an enemy that dropped items, looted like a chest
+fn spawn_corpse(world: &World, pos: Pos, items: Vec<Box<dyn Item>>) -> Entity {
+    let mut sprite = AnimatedSprite::new((-32, -40, 64, 64), 0, animation(world, "corpse"), None);
+    sprite.rotation = 90.;
+    sprite.color_mod = Color::RGB(90, 90, 90);
+    let corpse = world.spawn(&[
+        &pos,
+        &sprite,
+        &Container {
+            title: "container-corpse",
+            inventory: Inventory::container(INVENTORY_SIZE),
+        },
+        &Interactable {
+            on_interact: containers::open,
+            script: None,
+        },
+    ]);
+    let inventory = containers::inventory_mut(world, corpse).unwrap();
+    for item in items {
+        let _ = inventory.insert_boxed(item, world);
+    }
+    corpse
+}
+
+// pickups bob up and down while they wait
+fn pickup_bob() -> Tween<Offset> {
+    Tween::new(
+        Offset(Vec2::zero()),
+        Offset(Vec2::new(0., -PICKUP_BOB_HEIGHT)),
+        PICKUP_BOB_TICKS,
+        Ease::InOut,
+    )
+    .ping_pong()
+}
+
+fn spawn_coin(world: &World, pos: Pos, amount: u32) -> Entity {
+    let mut sprite = AnimatedSprite::new((-8, -8, 16, 16), 12, animation(world, "coin"), None);
+    sprite.pop_in(8);
+
+    world.spawn(&[
+        &pos,
+        &PrevPos(pos),
+        &sprite,
+        &pickup_bob(),
+        &Pickup {
+            velocity: Vec2::new(0., 0.),
+            coins: amount,
+            label: None,
+            on_pickup: None,
         },
     ])
 }
 
-fn spawn_torch(world: &World, pos: Pos) {
-    let ctx = world.resource::<Ctx>().unwrap();
+// name is also the animation
+fn spawn_item_pickup(
+    world: &World,
+    pos: Pos,
+    name: &'static str,
+    on_pickup: fn(&World, Entity) -> bool,
+) -> Entity {
+    let sprite = AnimatedSprite::new((-8, -8, 16, 16), 0, animation(world, name), None);
+    // items spring in rather than pop, they're worth more than a coin
+    let pop = Tween::new(
+        Scale(Vec2::zero()),
+        Scale(Vec2::new(1., 1.)),
+        PICKUP_POP_TICKS,
+        Ease::Elastic,
+    );
+
+    world.spawn(&[
+        &pos,
+        &PrevPos(pos),
+        &sprite,
+        &pickup_bob(),
+        &pop,
+        &Pickup {
+            velocity: Vec2::new(0., 0.),
+            coins: 0,
+            label: Some(name),
+            on_pickup: Some(on_pickup),
+        },
+    ])
+}
+
+fn spawn_particle_emitter(world: &World, pos: Pos, channel: u16) -> Entity {
+    // lit while active, see on_particle_emitter_signal
+    let light = Light {
+        radius: 0,
+        color: Color::RGB(150, 150, 150),
+        intensity: 1.,
+        cone: None,
+    };
     world.spawn(&[
+        &Prop {},
         &pos,
         &AnimatedSprite::new(
             (-16, -16, 32, 32),
-            5,
-            ctx.animations.get("torch").unwrap(),
+            0,
+            animation(world, "particle_emitter"),
             None,
         ),
-        &Light {
-            radius: 120,
-            color: Color::RGB(255, 255, 0),
-            intensity: 1.,
+        &particles::emitter(world, "sparks", false),
+        &SoundEmitter {
+            sound: "emitter_hum",
+            volume: 0.3,
+            radius: 250.,
         },
-    ]);
+        &SignalReceiver {
+            channel,
+            on_signal: on_particle_emitter_signal,
+        },
+        &Tween::idle(LightRadius(0.)),
+        &light,
+        &LightAnimator {
+            flicker_amplitude: 0.15,
+            flicker_frequency: 20.,
+            cycle_color: Some(Color::RGB(120, 160, 255)),
+            cycle_period: 60,
+            ..LightAnimator::new(light)
+        },
+    ])
 }
 
-fn spawn_enemy(world: &World, pos: Pos) {
-    let ctx = world.resource::<Ctx>().unwrap();
+// tile coordinates
+fn spawn_ambience_zone(
+    world: &World,
+    origin: (i32, i32),
+    size: (i32, i32),
+    color: Color,
+    is_stormy: bool,
+) -> Entity {
+    let width = size.0 as f32 * TILE_SIZE;
+    let height = size.1 as f32 * TILE_SIZE;
+    world.spawn(&[
+        &Pos::new(
+            origin.0 as f32 * TILE_SIZE + width / 2.,
+            origin.1 as f32 * TILE_SIZE + height / 2.,
+        ),
+        &AmbienceZone {
+            width: width as u32,
+            height: height as u32,
+            color,
+            is_stormy,
+        },
+    ])
+}
+
+// enemies drop in at the spawn points the first time the player walks through
+fn spawn_ambush(world: &World, pos: Pos, spawn_points: &[Pos]) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let mut points = [None; 4];
+    for (slot, point) in points.iter_mut().zip(spawn_points) {
+        *slot = Some(*point);
+    }
 
     world.spawn(&[
-        &Enemy {},
-        &Pos::new(pos.x, pos.y),
+        &pos,
+        &Ambush { points },
+        &TriggerZone::new(
+            (-32, -16, 64, 32),
+            layers.bit("nav"),
+            Some(|world: &World, me: Entity, other: Entity| {
+                if !world.has_component::<Player>(other) {
+                    return;
+                }
+
+                let points = world.component::<Ambush>(me).unwrap().points;
+                for point in points.into_iter().flatten() {
+                    spawn_prefab(world, "enemy", point);
+                }
+                world.component_mut::<TriggerZone>(me).unwrap().is_enabled = false;
+            }),
+            None,
+        ),
+    ])
+}
+
+// sends its signal while anything heavy rests on it
+fn spawn_pressure_plate(world: &World, pos: Pos, channel: u16) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    world.spawn(&[
+        &Floor {},
+        &PressurePlate {},
+        &pos,
         &AnimatedSprite::new(
-            (-32, -40, 64, 64),
-            30,
-            ctx.animations.get("enemy_walk").unwrap(),
+            (-16, -16, 32, 32),
+            0,
+            animation(world, "pressure_plate_up"),
             None,
         ),
-        &ColliderGroup {
-            nav: Some(Collider::new((-10, 6, 22, 10), CH_NAV, CH_NAV, None)),
-            hitbox: Some(Collider::new(
-                (-16, -16, 32, 32),
-                CH_HITBOX,
-                CH_HITBOX,
-                Some(|world: &World, me: Entity, other: Entity| {
-                    if world.component::<Projectile>(other).is_some() {
-                        let mut despawn_queue = world
-                            .resource::<Ctx>()
-                            .unwrap()
-                            .despawn_queue
-                            .write()
-                            .unwrap();
-                        despawn_queue.push(me);
-                    }
-                }),
-            )),
-        },
-        &Light {
-            radius: 30,
-            color: Color::RGB(200, 200, 200),
-            intensity: 1.,
+        &SignalEmitter {
+            channel,
+            is_on: false,
         },
-    ]);
+        &TriggerZone::new(
+            (-12, -12, 24, 24),
+            layers.bit("nav"),
+            Some(on_pressure_plate_changed),
+            Some(on_pressure_plate_changed),
+        ),
+    ])
 }
 
-fn spawn_bullet(world: &World, pos: Vec2<f32>, velocity_normal: Vec2<f32>) {
-    let ctx = world.resource::<Ctx>().unwrap();
-
+fn spawn_moving_wall(world: &World, waypoints: &[Pos], speed: f32, mode: MoverMode) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let pos = waypoints[0];
     world.spawn(&[
-        &Projectile {
-            velocity: velocity_normal.scaled(ctx.bullet_speed),
-            ticks_left: ctx.bullet_lifetime,
-        },
-        &Pos::new(pos.x, pos.y),
+        &Static {},
+        &pos,
+        &PrevPos(pos),
+        &Mover::new(waypoints, speed, mode),
         &AnimatedSprite::new(
-            (-8, -8, 16, 16),
-            10,
-            ctx.animations.get("bullet").unwrap(),
+            (-16, -48, TILE_SIZE as u32, (TILE_SIZE * 2.) as u32),
+            0,
+            animation(world, "wall"),
             None,
         ),
         &ColliderGroup {
-            nav: Some(Collider::new(
-                (-6, -6, 12, 12),
-                CH_NONE,
-                CH_HITBOX | CH_NAV,
-                Some(|world: &World, me: Entity, _: Entity| {
-                    let mut despawn_queue = world
-                        .resource::<Ctx>()
-                        .unwrap()
-                        .despawn_queue
-                        .write()
-                        .unwrap();
-                    despawn_queue.push(me);
-                }),
-            )),
+            nav: Some(Collider::on_layer(layers, "wall", (-16, -14, 32, 30))),
             hitbox: None,
         },
-        &Light {
-            radius: 20,
-            color: Color::RGB(160, 150, 10),
-            intensity: 1.,
-        },
-    ]);
+    ])
 }
 
-// ███████╗██╗   ██╗███████╗████████╗███████╗███╗   ███╗███████╗
-// ██╔════╝╚██╗ ██╔╝██╔════╝╚══██╔══╝██╔════╝████╗ ████║██╔════╝
-// ███████╗ ╚████╔╝ ███████╗   ██║   █████╗  ██╔████╔██║███████╗
-// ╚════██║  ╚██╔╝  ╚════██║   ██║   ██╔══╝  ██║╚██╔╝██║╚════██║
-// ███████║   ██║   ███████║   ██║   ███████╗██║ ╚═╝ ██║███████║
-// ╚══════╝   ╚═╝   ╚══════╝   ╚═╝   ╚══════╝╚═╝     ╚═╝╚══════╝
+fn spawn_conveyor(world: &World, pos: Pos, velocity: Vec2<f32>) -> Entity {
+    let mut sprite = AnimatedSprite::new(
+        (-16, -16, TILE_SIZE as u32, TILE_SIZE as u32),
+        8,
+        animation(world, "conveyor"),
+        None,
+    );
+    sprite.rotation = velocity.angle().to_degrees();
 
-fn update_player(world: &World) {
-    world.run(
-        |player: &mut Player,
-         pos: &mut Pos,
-         colliders: &ColliderGroup,
-         sprite: &mut AnimatedSprite,
-         mut ctx: ResMut<Ctx>| {
-            if ctx.input.pressed.up
-                | ctx.input.pressed.down
-                | ctx.input.pressed.left
-                | ctx.input.pressed.right
-            {
-                sprite.switch_anim(ctx.animations.get("player_walk").unwrap(), 5);
-            } else {
-                sprite.switch_anim(ctx.animations.get("player_idle").unwrap(), 30);
-            }
+    world.spawn(&[&Floor {}, &pos, &sprite, &Conveyor { velocity }])
+}
 
-            let speed = if ctx.input.pressed.shift {
-                8.
-            } else {
-                ctx.player_speed
-            };
+fn spawn_hazard(world: &World, pos: Pos, kind: HazardKind) -> Entity {
+    let (anim, ticks_per_frame) = match kind {
+        HazardKind::Spikes => ("spikes_up", 0),
+        HazardKind::Lava => ("lava", 20),
+        HazardKind::Ice => ("ice", 0),
+        HazardKind::Water => ("water", 40),
+    };
+    let mut sprite = AnimatedSprite::new(
+        (-16, -16, TILE_SIZE as u32, TILE_SIZE as u32),
+        ticks_per_frame,
+        animation(world, anim),
+        None,
+    );
+    let hazard = Hazard {
+        kind,
+        is_active: true,
+        ticks: 0,
+    };
+    let glow = Light {
+        radius: 24,
+        color: Color::RGB(255, 90, 20),
+        intensity: 0.6,
+        cone: None,
+    };
+    if kind == HazardKind::Water {
+        sprite.color_mod = Color::RGB(70, 120, 255);
+    }
+    sprite.is_emissive = kind == HazardKind::Lava;
 
-            let collider = colliders.nav.as_ref().unwrap();
-            if ctx.input.pressed.up && !collider.top {
-                pos.y -= speed;
-            }
-            if ctx.input.pressed.down && !collider.bottom {
-                pos.y += speed;
-            }
-            if ctx.input.pressed.left {
-                sprite.flip_horizontal = false;
-                if !collider.left {
-                    pos.x -= speed;
-                }
-            }
-            if ctx.input.pressed.right {
-                sprite.flip_horizontal = true;
-                if !collider.right {
-                    pos.x += speed;
-                }
-            }
+    let water = Surface {
+        kind: SurfaceKind::Water,
+    };
 
-            ctx.player_pos = *pos;
+    let mut components: Vec<&dyn Component> = vec![&Floor {}, &pos, &sprite, &hazard];
+    if kind == HazardKind::Lava {
+        components.push(&glow);
+    }
+    if kind == HazardKind::Water {
+        components.push(&water);
+    }
 
-            if player.can_fire_in > 0 {
-                player.can_fire_in -= 1;
-            }
+    world.spawn(&components)
+}
+
+fn spawn_crate(world: &World, pos: Pos) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    world.spawn(&[
+        &Heavy {},
+        &Pushable {
+            velocity: Vec2::new(0., 0.),
+        },
+        &pos,
+        &PrevPos(pos),
+        &AnimatedSprite::new((-16, -24, 32, 32), 0, animation(world, "crate"), None),
+        &Occluder::solid(OccluderShape::Rect(-14, -14, 28, 28)),
+        &ColliderGroup {
+            nav: Some(
+                Collider::on_layer(layers, "nav", (-14, -14, 28, 28))
+                    .with_on_collide(on_crate_collide),
+            ),
+            hitbox: None,
+        },
+    ])
+}
 
-            if player.can_fire_in == 0 {
-                let mut trajectory = Vec2::zero();
+fn spawn_barrel(world: &World, pos: Pos) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    world.spawn(&[
+        &Pushable {
+            velocity: Vec2::new(0., 0.),
+        },
+        &Throwable {
+            damage: BARREL_DAMAGE,
+            is_carried: false,
+        },
+        &Attachment {
+            parent: None,
+            local: Transform::IDENTITY,
+        },
+        &Interactable {
+            on_interact: on_throwable_interact,
+            script: None,
+        },
+        &pos,
+        &PrevPos(pos),
+        &AnimatedSprite::new((-16, -24, 32, 32), 0, animation(world, "barrel"), None),
+        &Occluder::solid(OccluderShape::Circle(12)),
+        &ColliderGroup {
+            nav: Some(
+                Collider::on_layer(layers, "nav", (-12, -12, 24, 24))
+                    .with_on_collide(on_barrel_collide),
+            ),
+            hitbox: None,
+        },
+    ])
+}
 
-                if ctx.input.pressed.fire_right {
-                    trajectory.x += 1.0;
-                }
-                if ctx.input.pressed.fire_left {
-                    trajectory.x -= 1.0;
-                }
-                if ctx.input.pressed.fire_up {
-                    trajectory.y -= 1.0;
-                }
-                if ctx.input.pressed.fire_down {
-                    trajectory.y += 1.0;
-                }
+fn spawn_checkpoint(world: &World, pos: Pos) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    world.spawn(&[
+        &Floor {},
+        &Checkpoint { is_active: false },
+        &pos,
+        &AnimatedSprite::new(
+            (-16, -16, 32, 32),
+            0,
+            animation(world, "checkpoint_off"),
+            None,
+        ),
+        &Light {
+            radius: 80,
+            color: Color::RGB(120, 200, 255),
+            intensity: 0.,
+            cone: None,
+        },
+        &Tween::idle(LightIntensity(0.)),
+        &TriggerZone::new(
+            (-12, -12, 24, 24),
+            layers.bit("nav"),
+            Some(checkpoints::on_checkpoint_enter),
+            None,
+        ),
+    ])
+}
 
-                if trajectory.magnitude() > 0.0 {
-                    spawn_bullet(
-                        world,
-                        Vec2::new(
-                            pos.x + trajectory.normalized().x * 30.,
-                            pos.y + trajectory.normalized().y * 30.,
-                        ),
-                        trajectory,
-                    );
-                    player.can_fire_in = player.fire_cooldown;
-                }
+fn spawn_floor(world: &World, pos: Pos) -> Entity {
+    world.spawn(&[
+        &Floor {},
+        &pos,
+        &AnimatedSprite::new(
+            (-16, -16, TILE_SIZE as u32, TILE_SIZE as u32),
+            0,
+            animation(world, "floor"),
+            None,
+        ),
+    ])
+}
+
+// a drop to the floor below, walked off going south but there's no climbing back up. the
+// stone floor darkened until there's art for it
+fn spawn_ledge(world: &World, pos: Pos) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let mut sprite = AnimatedSprite::new(
+        (-16, -16, TILE_SIZE as u32, TILE_SIZE as u32),
+        0,
+        animation(world, "floor"),
+        None,
+    );
+    sprite.color_mod = Color::RGB(110, 110, 120);
+    world.spawn(&[
+        &Floor {},
+        &Static {},
+        &pos,
+        &sprite,
+        &ColliderGroup {
+            nav: Some(Collider::on_layer(layers, "ledge", (-16, -4, 32, 8)).one_way(Side::Bottom)),
+            hitbox: None,
+        },
+    ])
+}
+
+// planks, the stone floor tinted until there's art for them
+fn spawn_wood_floor(world: &World, pos: Pos) -> Entity {
+    let mut sprite = AnimatedSprite::new(
+        (-16, -16, TILE_SIZE as u32, TILE_SIZE as u32),
+        0,
+        animation(world, "floor"),
+        None,
+    );
+    sprite.color_mod = Color::RGB(190, 130, 80);
+    world.spawn(&[
+        &Floor {},
+        &Surface {
+            kind: SurfaceKind::Wood,
+        },
+        &pos,
+        &sprite,
+    ])
+}
+
+// the most specific surface on the tile under pos
+pub fn surface_at(world: &World, pos: Pos) -> SurfaceKind {
+    let tile = pos_to_tile(pos);
+    let mut kind = SurfaceKind::Stone;
+    world.run(|entity: &Entity, floor_pos: &Pos, _: With<Floor>| {
+        if pos_to_tile(*floor_pos) == tile {
+            if let Some(surface) = world.component::<Surface>(*entity) {
+                kind = surface.kind;
+            }
+        }
+    });
+    kind
+}
+
+// collides as part of a merged run of walls, see walls.rs
+fn spawn_wall(world: &World, pos: Pos, occlude_left: bool, occlude_right: bool) -> Entity {
+    world.spawn(&[
+        &Static {},
+        &Wall {},
+        &pos,
+        &AnimatedSprite::new(
+            (-16, -48, TILE_SIZE as u32, (TILE_SIZE * 2.) as u32),
+            0,
+            animation(world, "wall"),
+            None,
+        ),
+        &LightOccluderGroup {
+            is_enabled: true,
+            occluders: [
+                Some(LightOccluder {
+                    line: (Point::new(-16, -16), Point::new(16, -16)),
+                }),
+                if occlude_left {
+                    Some(LightOccluder {
+                        line: (Point::new(-16, -16), Point::new(-16, 15)),
+                    })
+                } else {
+                    None
+                },
+                if occlude_right {
+                    Some(LightOccluder {
+                        line: (Point::new(16, -16), Point::new(16, 15)),
+                    })
+                } else {
+                    None
+                },
+                None,
+            ],
+        },
+    ])
+}
+
+fn spawn_door(world: &World, pos: Pos, channel: Option<u16>) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let sprite = AnimatedSprite::new(
+        (-16, -48, TILE_SIZE as u32, (TILE_SIZE * 2.) as u32),
+        0,
+        animation(world, "door_closed"),
+        None,
+    );
+    let colliders = ColliderGroup {
+        nav: Some(Collider::on_layer(layers, "wall", (-16, -14, 32, 30))),
+        hitbox: None,
+    };
+    let occluders = LightOccluderGroup {
+        is_enabled: true,
+        occluders: [
+            Some(LightOccluder {
+                line: (Point::new(-16, -16), Point::new(16, -16)),
+            }),
+            None,
+            None,
+            None,
+        ],
+    };
+    let door = Door {
+        is_open: false,
+        ticks_until_open: 0,
+    };
+    let lift = Tween::idle(Offset(Vec2::zero()));
+
+    // doors are either wired to a switch or opened by hand
+    let receiver = channel.map(|channel| SignalReceiver {
+        channel,
+        on_signal: set_door_open,
+    });
+    let interactable = Interactable {
+        on_interact: |world: &World, me: Entity, _: Entity| {
+            let is_open = world.component::<Door>(me).unwrap().is_open;
+            set_door_open(world, me, !is_open);
+        },
+        script: None,
+    };
+
+    let mut components: Vec<&dyn Component> = vec![
+        &Static {},
+        &pos,
+        &sprite,
+        &colliders,
+        &occluders,
+        &door,
+        &lift,
+    ];
+    if let Some(receiver) = receiver.as_ref() {
+        components.push(receiver);
+    } else {
+        components.push(&interactable);
+    }
+
+    world.spawn(&components)
+}
+
+fn spawn_torch(world: &World, pos: Pos, channel: Option<u16>) -> Entity {
+    let mut sprite = AnimatedSprite::new((-16, -16, 32, 32), 5, animation(world, "torch"), None);
+    sprite.is_emissive = true;
+    // wired torches start out unlit
+    let light = Light {
+        radius: 120,
+        color: Color::RGB(255, 255, 0),
+        intensity: if channel.is_some() { 0. } else { 1. },
+        cone: None,
+    };
+    let receiver = channel.map(|channel| SignalReceiver {
+        channel,
+        on_signal: set_light,
+    });
+
+    let animator = LightAnimator::fire(light);
+    let fade = Tween::idle(LightIntensity(light.intensity));
+    // only smokes while lit
+    let smoke = particles::emitter(world, "torch_smoke", true);
+    let crackle = SoundEmitter {
+        sound: "torch_crackle",
+        volume: 0.5,
+        radius: 200.,
+    };
+
+    let mut components: Vec<&dyn Component> =
+        vec![&pos, &sprite, &light, &animator, &fade, &smoke, &crackle];
+    if let Some(receiver) = receiver.as_ref() {
+        components.push(receiver);
+    }
+
+    world.spawn(&components)
+}
+
+fn spawn_enemy(world: &World, pos: Pos) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let mut sprite =
+        AnimatedSprite::new((-32, -40, 64, 64), 30, animation(world, "enemy_walk"), None);
+    sprite.pop_in(12);
+
+    world.spawn(&[
+        &Enemy {},
+        &Health::new(3),
+        &StatusEffects::new(),
+        &Momentum::new(),
+        &Flocking::separation(28., 1.5).with_flocking(0.2, 0.1),
+        &LootTable::new(
+            2,
+            &[
+                LootEntry {
+                    weight: 4,
+                    drop: LootDrop::Nothing,
+                },
+                LootEntry {
+                    weight: 6,
+                    drop: LootDrop::Coins(1),
+                },
+                LootEntry {
+                    weight: 2,
+                    drop: LootDrop::Coins(5),
+                },
+                LootEntry {
+                    weight: 1,
+                    drop: LootDrop::Item("chemlight"),
+                },
+            ],
+        ),
+        &Pos::new(pos.x, pos.y),
+        &PrevPos(pos),
+        &sprite,
+        &AnimGraph::new(&ENEMY_ANIMS),
+        &Occluder::blob(14),
+        &ColliderGroup {
+            nav: Some(Collider::on_layer(layers, "nav", (-10, 6, 22, 10))),
+            hitbox: Some(Collider::on_layer(layers, "hitbox", (-16, -16, 32, 32))),
+        },
+        &Light {
+            radius: 30,
+            color: Color::RGB(200, 200, 200),
+            intensity: 1.,
+            cone: None,
+        },
+    ])
+}
+
+fn spawn_boss(world: &World, pos: Pos, door_channel: Option<u16>) -> Entity {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let mut sprite = AnimatedSprite::new(
+        (-64, -80, 128, 128),
+        20,
+        animation(world, "boss_walk"),
+        None,
+    );
+    sprite.pop_in(30);
+
+    world.spawn(&[
+        &Boss {
+            phase: 0,
+            state: BossState::Idle,
+            state_timer: Timer::once(BOSS_PHASES[0].idle_ticks),
+            attacks_done: 0,
+            door_channel,
+        },
+        &Health::new(BOSS_HP),
+        &StatusEffects::new(),
+        &Momentum::new(),
+        &LootTable::new(
+            6,
+            &[
+                LootEntry {
+                    weight: 1,
+                    drop: LootDrop::Coins(10),
+                },
+                LootEntry {
+                    weight: 1,
+                    drop: LootDrop::Prefab("chemlight_pickup"),
+                },
+            ],
+        ),
+        &Pos::new(pos.x, pos.y),
+        &PrevPos(pos),
+        &sprite,
+        &AnimGraph::new(&BOSS_ANIMS),
+        &Occluder::blob(28),
+        &ColliderGroup {
+            nav: Some(Collider::on_layer(layers, "nav", (-24, 12, 48, 20))),
+            hitbox: Some(Collider::on_layer(layers, "hitbox", (-36, -36, 72, 72))),
+        },
+        &Light {
+            radius: 60,
+            color: Color::RGB(220, 120, 120),
+            intensity: 1.,
+            cone: None,
+        },
+    ])
+}
+
+// hostile bullets only hurt the player and pass through other enemies
+fn spawn_boss_bullet(world: &World, pos: Pos, velocity: Vec2<f32>) {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let mut sprite = AnimatedSprite::new((-8, -8, 16, 16), 10, animation(world, "bullet"), None);
+    sprite.color_mod = Color::RGB(255, 80, 80);
+    sprite.is_emissive = true;
+    sprite.animate_scale_from(Vec2::new(0.5, 0.5), 4);
+
+    world.resource_mut::<CommandBuffer>().unwrap().spawn(vec![
+        Box::new(Projectile {
+            velocity,
+            damage: 0,
+            bounces_left: 0,
+            pierces_left: 0,
+            last_hit: None,
+            effect: None,
+        }),
+        Box::new(Timers::new().with("lifetime", Timer::once(240))),
+        Box::new(pos),
+        Box::new(PrevPos(pos)),
+        Box::new(sprite),
+        Box::new(ColliderGroup {
+            nav: Some(
+                Collider::on_layer(layers, "enemy_projectile", (-6, -6, 12, 12))
+                    .with_on_collide(on_boss_bullet_collide)
+                    .as_sensor(),
+            ),
+            hitbox: None,
+        }),
+        Box::new(Light {
+            radius: 20,
+            color: Color::RGB(200, 40, 40),
+            intensity: 1.,
+            cone: None,
+        }),
+    ]);
+}
+
+fn on_boss_bullet_collide(world: &World, me: Entity, other: Entity) {
+    if world.has_component::<Boss>(other) || world.has_component::<Enemy>(other) {
+        return;
+    }
+    if world.has_component::<Player>(other) {
+        let pos = *world.component::<Pos>(me).unwrap();
+        hit_player(world, other, pos, BOSS_BULLET_DAMAGE);
+    }
+    world.resource_mut::<CommandBuffer>().unwrap().despawn(me);
+}
+
+// damage plus a knockback, a slow and a moment of i-frames so a charge only lands once
+fn hit_player(world: &World, player: Entity, from: Pos, amount: u16) {
+    if is_invulnerable(world, player) {
+        return;
+    }
+    damage(world, player, amount);
+    if let Some(pos) = world.component_mut::<Pos>(player) {
+        let mut away = Vec2::new(pos.x - from.x, pos.y - from.y);
+        if away.magnitude() > 0. {
+            away.normalize();
+            pos.x += away.x * PLAYER_KNOCKBACK;
+            pos.y += away.y * PLAYER_KNOCKBACK;
+        }
+    }
+    apply_status(world, player, StatusEffect::slow(60));
+    apply_status(
+        world,
+        player,
+        StatusEffect::invulnerable(PLAYER_HIT_IFRAME_TICKS),
+    );
+}
+
+fn spawn_bullet(
+    world: &World,
+    pos: Vec2<f32>,
+    velocity_normal: Vec2<f32>,
+    weapon: &Weapon,
+    damage: u16,
+) {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let tuning = world.resource::<Tuning>().unwrap();
+
+    let mut sprite = AnimatedSprite::new((-8, -8, 16, 16), 10, animation(world, "bullet"), None);
+    sprite.animate_scale_from(Vec2::new(0.5, 0.5), 4);
+    sprite.is_emissive = true;
+
+    // bullets are fired from inside the player system
+    let mut components: Vec<Box<dyn Component>> = vec![
+        Box::new(Projectile {
+            velocity: velocity_normal.scaled(tuning.bullet_speed),
+            damage,
+            bounces_left: weapon.bounces,
+            pierces_left: weapon.pierces,
+            last_hit: None,
+            effect: weapon.effect,
+        }),
+        Box::new(Timers::new().with("lifetime", Timer::once(tuning.bullet_lifetime))),
+        Box::new(Pos::new(pos.x, pos.y)),
+        Box::new(PrevPos(Pos::new(pos.x, pos.y))),
+        Box::new(sprite),
+        Box::new(ColliderGroup {
+            nav: Some(
+                Collider::on_layer(layers, "projectile", (-6, -6, 12, 12))
+                    .with_on_collide(on_bullet_collide)
+                    .as_sensor(),
+            ),
+            hitbox: None,
+        }),
+        Box::new(Light {
+            radius: 20,
+            color: Color::RGB(160, 150, 10),
+            intensity: 1.,
+            cone: None,
+        }),
+    ];
+    if let Some(explosive) = weapon.explosive {
+        components.push(Box::new(explosive));
+    }
+    world
+        .resource_mut::<CommandBuffer>()
+        .unwrap()
+        .spawn(components);
+}
+
+fn on_bullet_collide(world: &World, me: Entity, other: Entity) {
+    let projectile = world.component_mut::<Projectile>(me).unwrap();
+
+    // explosives go off on anything, and only once
+    if let Some(explosive) = world.component::<Explosive>(me) {
+        if projectile.last_hit.is_none() {
+            projectile.last_hit = Some(other);
+            let pos = *world.component::<Pos>(me).unwrap();
+            explode(world, pos, explosive);
+            world.resource_mut::<CommandBuffer>().unwrap().despawn(me);
+        }
+        return;
+    }
+
+    if world.has_component::<Health>(other) {
+        // the bullet touches both colliders of its target, and a piercing one keeps
+        // touching it for a few ticks
+        if projectile.last_hit == Some(other) {
+            return;
+        }
+        projectile.last_hit = Some(other);
+        send_hit(world, me, projectile, true);
+        if projectile.damage > 0 {
+            damage(world, other, projectile.damage);
+        }
+        if let Some(effect) = projectile.effect {
+            apply_status(world, other, effect);
+        }
+        if projectile.pierces_left > 0 {
+            projectile.pierces_left -= 1;
+            return;
+        }
+    } else if projectile.bounces_left > 0 {
+        let (Some(mine), Some(theirs)) = (
+            world.component::<ColliderGroup>(me),
+            world.component::<ColliderGroup>(other),
+        ) else {
+            return;
+        };
+        let bounds = mine.nav.as_ref().unwrap().bounds;
+        let Some(other_bounds) = theirs.nav.as_ref().or(theirs.hitbox.as_ref()) else {
+            return;
+        };
+        let normal = collision_normal(bounds, other_bounds.bounds);
+
+        // still overlapping after the last bounce, it's already on its way out
+        let dot = projectile.velocity.x * normal.x + projectile.velocity.y * normal.y;
+        if dot < 0. {
+            send_hit(world, me, projectile, false);
+            projectile.bounces_left -= 1;
+            projectile.velocity.x -= 2. * dot * normal.x;
+            projectile.velocity.y -= 2. * dot * normal.y;
+        }
+        return;
+    } else {
+        send_hit(world, me, projectile, false);
+    }
+
+    world.resource_mut::<CommandBuffer>().unwrap().despawn(me);
+}
+
+fn send_hit(world: &World, me: Entity, projectile: &Projectile, is_flesh: bool) {
+    events::send(
+        world,
+        ProjectileHit {
+            pos: *world.component::<Pos>(me).unwrap(),
+            velocity: projectile.velocity,
+            is_flesh,
+        },
+    );
+}
+
+// hits everything with a hitbox inside the arc in front of the player swinging. facing is
+// theirs while the player systems run, see Ctx::swap_players
+pub fn swing(world: &World, player: Entity, melee: &MeleeSwing) {
+    let ctx = world.resource::<Ctx>().unwrap();
+    let (Some(origin), Some(stats)) = (
+        world.component::<Pos>(player).copied(),
+        world.component::<PlayerStats>(player).copied(),
+    ) else {
+        return;
+    };
+    let facing = ctx.player_facing.angle();
+
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let physics = world.resource::<Physics>().unwrap();
+    for entity in physics.entities_in_circle(origin, melee.range, layers.bit("hitbox")) {
+        if world.has_component::<Player>(entity) {
+            continue;
+        }
+        let Some(hitbox) = world
+            .component::<ColliderGroup>(entity)
+            .and_then(|cg| cg.hitbox.as_ref())
+            .filter(|hitbox| hitbox.is_enabled)
+        else {
+            continue;
+        };
+
+        // the closest point of the hitbox decides whether it's in reach
+        let bounds = hitbox.bounds;
+        let closest = Vec2::new(
+            origin.x.clamp(bounds.left() as f32, bounds.right() as f32),
+            origin.y.clamp(bounds.top() as f32, bounds.bottom() as f32),
+        );
+        let mut to_target = Vec2::new(closest.x - origin.x, closest.y - origin.y);
+        let distance = to_target.magnitude();
+        if distance > melee.range {
+            continue;
+        }
+        if distance > 0. {
+            let angle = to_target.angle();
+            let diff = (angle - facing + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+                - std::f32::consts::PI;
+            if diff.abs() > melee.arc / 2. {
+                continue;
+            }
+        }
+
+        damage(world, entity, stats.scale_damage(melee.damage));
+        if let Some(pos) = world.component_mut::<Pos>(entity) {
+            let push = if distance > 0. {
+                to_target.normalized()
+            } else {
+                ctx.player_facing
+            };
+            pos.x += push.x * melee.knockback;
+            pos.y += push.y * melee.knockback;
+        }
+    }
+
+    let mut sprite = AnimatedSprite::new((-24, -24, 48, 48), 5, animation(world, "slash"), None);
+    sprite.rotation = facing.to_degrees();
+    sprite.animate_scale_from(Vec2::new(0.6, 0.6), 5);
+    let pos = Pos::new(
+        origin.x + ctx.player_facing.x * melee.range / 2.,
+        origin.y + ctx.player_facing.y * melee.range / 2.,
+    );
+    world.resource_mut::<CommandBuffer>().unwrap().spawn(vec![
+        Box::new(pos),
+        Box::new(PrevPos(pos)),
+        Box::new(sprite),
+        Box::new(Tween::new(Alpha(1.), Alpha(0.), SLASH_TICKS, Ease::Linear)),
+        Box::new(Timers::new().with("lifetime", Timer::once(SLASH_TICKS))),
+    ]);
+}
+
+fn explode(world: &World, pos: Pos, explosive: &Explosive) {
+    let physics = world.resource::<Physics>().unwrap();
+    for entity in physics.entities_in_circle(pos, explosive.radius, usize::MAX) {
+        if world.has_component::<Static>(entity) || is_invulnerable(world, entity) {
+            continue;
+        }
+        let Some(target) = world.component_mut::<Pos>(entity) else {
+            continue;
+        };
+        // colliders are as of the last rebuild, so measure from where the target is now
+        let mut away = Vec2::new(target.x - pos.x, target.y - pos.y);
+        let distance = away.magnitude();
+        if distance > explosive.radius {
+            continue;
+        }
+        let falloff = 1. - distance / explosive.radius;
+
+        if distance > 0. {
+            away.scale(explosive.knockback * falloff / distance);
+            target.x += away.x;
+            target.y += away.y;
+        }
+        if world.has_component::<Health>(entity) {
+            let amount = (explosive.damage as f32 * falloff).ceil() as u16;
+            damage(world, entity, amount.max(1));
+        } else if world.has_component::<Player>(entity) {
+            if let Some(sprite) = world.component_mut::<AnimatedSprite>(entity) {
+                sprite.flash(DAMAGE_FLASH_TICKS);
+            }
+        }
+    }
+
+    let commands = world.resource_mut::<CommandBuffer>().unwrap();
+    commands.spawn(vec![
+        Box::new(pos),
+        Box::new(Light {
+            radius: 0,
+            color: Color::RGB(255, 200, 120),
+            intensity: 2.,
+            cone: None,
+        }),
+        Box::new(LightFlash::new(
+            (explosive.radius * 2.) as u16,
+            EXPLOSION_FLASH_TICKS,
+        )),
+    ]);
+    particles::burst(world, "explosion", pos, None);
+
+    shake_camera(world, explosive.shake);
+    haptics::rumble(world, explosive.shake, 250);
+}
+
+// adds trauma, the shake falls off with its square so small hits stay subtle
+fn shake_camera(world: &World, trauma: f32) {
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    ctx.camera_trauma = (ctx.camera_trauma + trauma).min(1.);
+}
+
+fn update_camera_shake(world: &World) {
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    let strength = ctx.camera_trauma * ctx.camera_trauma * CAMERA_SHAKE_MAX;
+    // noise rather than a fresh random offset every tick, it rattles instead of teleporting
+    let t = ctx.ticks as f32 * CAMERA_SHAKE_FREQUENCY;
+    ctx.camera_shake = if strength > 0. {
+        Vec2::new(
+            math::value_noise(t, CAMERA_SHAKE_SEED) * strength,
+            math::value_noise(t, CAMERA_SHAKE_SEED + 1) * strength,
+        )
+    } else {
+        Vec2::zero()
+    };
+    ctx.camera_trauma = (ctx.camera_trauma - CAMERA_TRAUMA_DECAY).max(0.);
+}
+
+// axis of least penetration, pointing from b towards a
+fn collision_normal(a: Rect, b: Rect) -> Vec2<f32> {
+    let d_bottom = b.bottom() - a.top();
+    let d_top = a.bottom() - b.top();
+    let d_left = a.right() - b.left();
+    let d_right = b.right() - a.left();
+
+    let min = d_bottom.min(d_top).min(d_left).min(d_right);
+    if min == d_top {
+        Vec2::new(0., -1.)
+    } else if min == d_bottom {
+        Vec2::new(0., 1.)
+    } else if min == d_left {
+        Vec2::new(-1., 0.)
+    } else {
+        Vec2::new(1., 0.)
+    }
+}
+
+fn on_lever_interact(world: &World, me: Entity, _: Entity) {
+    let emitter = world.component_mut::<SignalEmitter>(me).unwrap();
+    emitter.is_on = !emitter.is_on;
+    world
+        .component_mut::<AnimatedSprite>(me)
+        .unwrap()
+        .flip_horizontal = emitter.is_on;
+    signals::emit(world, me);
+}
+
+fn on_pressure_plate_changed(world: &World, me: Entity, _other: Entity) {
+    let is_pressed = world
+        .component::<TriggerZone>(me)
+        .unwrap()
+        .occupants
+        .into_iter()
+        .flatten()
+        .any(|entity| world.has_component::<Heavy>(entity));
+
+    let emitter = world.component_mut::<SignalEmitter>(me).unwrap();
+    if emitter.is_on == is_pressed {
+        return;
+    }
+    emitter.is_on = is_pressed;
+    signals::emit(world, me);
+
+    let anim = if is_pressed {
+        "pressure_plate_down"
+    } else {
+        "pressure_plate_up"
+    };
+    world
+        .component_mut::<AnimatedSprite>(me)
+        .unwrap()
+        .switch_anim(animation(world, anim), 0);
+}
+
+// the player shoves crates along whichever axis they hit them on
+fn on_crate_collide(world: &World, me: Entity, other: Entity) {
+    if !world.has_component::<Player>(other) {
+        return;
+    }
+
+    let pos = world.component::<Pos>(me).unwrap();
+    let player_pos = world.component::<Pos>(other).unwrap();
+    let away = Vec2::new(pos.x - player_pos.x, pos.y - player_pos.y);
+
+    let pushable = world.component_mut::<Pushable>(me).unwrap();
+    pushable.velocity = away.to_direction4().to_vec2() * CRATE_PUSH_SPEED;
+}
+
+// barrels roll off in whatever direction they're bumped
+fn on_barrel_collide(world: &World, me: Entity, other: Entity) {
+    if !world.has_component::<Player>(other) {
+        return;
+    }
+
+    let pos = world.component::<Pos>(me).unwrap();
+    let player_pos = world.component::<Pos>(other).unwrap();
+    let mut away = Vec2::new(pos.x - player_pos.x, pos.y - player_pos.y);
+    if away.magnitude() == 0. {
+        return;
+    }
+
+    let pushable = world.component_mut::<Pushable>(me).unwrap();
+    pushable.velocity = away.normalized().scaled(BARREL_PUSH_SPEED);
+}
+
+// picks the prop up, or throws it if it's already held
+fn on_throwable_interact(world: &World, me: Entity, player: Entity) {
+    let throwable = world.component_mut::<Throwable>(me).unwrap();
+    if throwable.is_carried {
+        throw_prop(world, me);
+        return;
+    }
+
+    throwable.is_carried = true;
+    world.component_mut::<Attachment>(me).unwrap().parent = Some(player);
+    world.component_mut::<Pushable>(me).unwrap().velocity = Vec2::zero();
+    let sprite = world.component_mut::<AnimatedSprite>(me).unwrap();
+    sprite.y_offset -= CARRY_HEIGHT;
+    // drawn in front of the player
+    sprite.z_offset = Some(1);
+    if let Some(nav) = world
+        .component_mut::<ColliderGroup>(me)
+        .unwrap()
+        .nav
+        .as_mut()
+    {
+        nav.is_enabled = false;
+    }
+}
+
+// swaps the held prop for a projectile that looks just like it
+fn throw_prop(world: &World, me: Entity) {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let ctx = world.resource::<Ctx>().unwrap();
+    let throwable = world.component::<Throwable>(me).unwrap();
+    let sprite = world.component::<AnimatedSprite>(me).unwrap();
+    // thrown from whoever's holding it
+    let holder = world
+        .component::<Attachment>(me)
+        .unwrap()
+        .parent
+        .and_then(|parent| world.component::<Pos>(parent))
+        .copied()
+        .unwrap_or(ctx.player_pos);
+    let pos = Pos::new(
+        holder.x + ctx.player_facing.x * 24.,
+        holder.y + ctx.player_facing.y * 24.,
+    );
+
+    let mut thrown = AnimatedSprite::new(
+        (
+            sprite.x_offset,
+            sprite.y_offset,
+            sprite.width,
+            sprite.height,
+        ),
+        sprite.ticks_per_frame,
+        sprite.anim(),
+        None,
+    );
+    thrown.flip_horizontal = sprite.flip_horizontal;
+
+    let commands = world.resource_mut::<CommandBuffer>().unwrap();
+    commands.spawn(vec![
+        Box::new(Projectile {
+            velocity: ctx.player_facing.scaled(THROW_SPEED),
+            damage: throwable.damage,
+            bounces_left: 0,
+            pierces_left: 0,
+            last_hit: None,
+            effect: None,
+        }),
+        Box::new(Throwable {
+            damage: throwable.damage,
+            is_carried: false,
+        }),
+        Box::new(Timers::new().with("lifetime", Timer::once(THROW_TICKS))),
+        Box::new(pos),
+        Box::new(PrevPos(pos)),
+        Box::new(thrown),
+        Box::new(ColliderGroup {
+            nav: Some(
+                Collider::on_layer(layers, "projectile", (-10, -10, 20, 20))
+                    .with_on_collide(on_thrown_collide),
+            ),
+            hitbox: None,
+        }),
+    ]);
+    commands.despawn(me);
+}
+
+fn on_thrown_collide(world: &World, me: Entity, other: Entity) {
+    if world.has_component::<Player>(other) {
+        return;
+    }
+    let projectile = world.component_mut::<Projectile>(me).unwrap();
+    // can touch several things in the tick it lands
+    if projectile.last_hit.is_some() {
+        return;
+    }
+    projectile.last_hit = Some(other);
+
+    if world.has_component::<Health>(other) {
+        damage(world, other, projectile.damage);
+    }
+    if let Some(pushable) = world.component_mut::<Pushable>(other) {
+        pushable.velocity = projectile.velocity.scaled(THROW_IMPULSE);
+    }
+    break_prop(world, me);
+}
+
+fn break_prop(world: &World, me: Entity) {
+    let pos = *world.component::<Pos>(me).unwrap();
+    particles::burst(world, "debris", pos, None);
+    world.resource_mut::<CommandBuffer>().unwrap().despawn(me);
+}
+
+fn on_particle_emitter_signal(world: &World, me: Entity, is_on: bool) {
+    world
+        .component_mut::<ParticleEmitter>(me)
+        .unwrap()
+        .is_active = is_on;
+    let radius = if is_on { 60. } else { 0. };
+    world.component_mut::<Tween<LightRadius>>(me).unwrap().to(
+        LightRadius(radius),
+        LIGHT_FADE_TICKS,
+        Ease::InOut,
+    );
+}
+
+// torches fade, other lights just switch
+pub fn set_light(world: &World, me: Entity, is_on: bool) {
+    let intensity = if is_on { 1. } else { 0. };
+    match world.component_mut::<Tween<LightIntensity>>(me) {
+        Some(fade) => fade.to(LightIntensity(intensity), LIGHT_FADE_TICKS, Ease::InOut),
+        None => light_base_mut(world, me).intensity = intensity,
+    }
+}
+
+fn set_door_open(world: &World, me: Entity, is_open: bool) {
+    let door = world.component_mut::<Door>(me).unwrap();
+    if door.is_open == is_open {
+        return;
+    }
+    door.is_open = is_open;
+
+    let sprite = world.component_mut::<AnimatedSprite>(me).unwrap();
+
+    if is_open {
+        // the way stays blocked until the animation finishes, see update_doors
+        let anim = animation(world, "door_opening");
+        sprite.switch_anim(anim, DOOR_TICKS_PER_FRAME);
+        let num_frames = world
+            .resource::<AnimationRepository>()
+            .unwrap()
+            .get_frames(anim)
+            .len();
+        door.ticks_until_open = num_frames as u32 * DOOR_TICKS_PER_FRAME;
+        world.component_mut::<Tween<Offset>>(me).unwrap().to(
+            Offset(Vec2::new(0., -DOOR_LIFT)),
+            door.ticks_until_open,
+            Ease::InOut,
+        );
+    } else {
+        // drops shut and bounces on the floor
+        world.component_mut::<Tween<Offset>>(me).unwrap().to(
+            Offset(Vec2::zero()),
+            DOOR_TICKS_PER_FRAME,
+            Ease::Bounce,
+        );
+        sprite.switch_anim(animation(world, "door_closed"), 0);
+        door.ticks_until_open = 0;
+        if let Some(collider) = world
+            .component_mut::<ColliderGroup>(me)
+            .unwrap()
+            .nav
+            .as_mut()
+        {
+            collider.is_enabled = true;
+        }
+        world
+            .component_mut::<LightOccluderGroup>(me)
+            .unwrap()
+            .is_enabled = true;
+    }
+}
+
+fn is_invulnerable(world: &World, entity: Entity) -> bool {
+    world
+        .component::<StatusEffects>(entity)
+        .is_some_and(|status| status.is_invulnerable())
+}
+
+fn damage(world: &World, entity: Entity, amount: u16) {
+    if is_invulnerable(world, entity) {
+        return;
+    }
+    if let Some(sprite) = world.component_mut::<AnimatedSprite>(entity) {
+        sprite.flash(DAMAGE_FLASH_TICKS);
+        sprite.squash(0.3, DAMAGE_FLASH_TICKS as u16);
+    }
+    if let Some(pos) = world.component::<Pos>(entity) {
+        let above = Pos::new(pos.x, pos.y - FLOATING_TEXT_HEIGHT);
+        floating_text::spawn(world, above, &amount.to_string(), Color::RGB(255, 80, 60));
+    }
+
+    events::send(world, Damaged { entity, amount });
+    if world.has_component::<Player>(entity) {
+        haptics::rumble(world, 0.5, 150);
+    }
+
+    if let Some(health) = world.component_mut::<Health>(entity) {
+        // only drop loot on the hit that kills
+        let was_alive = health.hp > 0;
+        health.hp -= amount as i32;
+        // the player respawns at the last checkpoint instead
+        if health.hp <= 0 && world.has_component::<Player>(entity) {
+            if was_alive {
+                let pos = *world.component::<Pos>(entity).unwrap();
+                events::send(world, PlayerDied { pos });
+            }
+            return;
+        }
+        if health.hp <= 0 {
+            let commands = world.resource_mut::<CommandBuffer>().unwrap();
+            // deferred commands run before despawns, so the loot table is still there
+            if was_alive {
+                commands.defer(move |world| drop_loot(world, entity));
+                if world.has_component::<Enemy>(entity) {
+                    let pos = *world.component::<Pos>(entity).unwrap();
+                    events::send(world, EnemyDied { pos });
+                }
+                if let Some(boss) = world.component::<Boss>(entity) {
+                    let pos = *world.component::<Pos>(entity).unwrap();
+                    events::send(world, BossDied { pos });
+                    if let Some(channel) = boss.door_channel {
+                        world.resource_mut::<SignalBroker>().unwrap().send(Signal {
+                            channel,
+                            is_on: true,
+                        });
+                    }
+                }
+            }
+            commands.despawn(entity);
+        }
+    }
+}
+
+fn drop_loot(world: &World, entity: Entity) {
+    let (Some(table), Some(pos)) = (
+        world.component::<LootTable>(entity),
+        world.component::<Pos>(entity),
+    ) else {
+        return;
+    };
+
+    let rng = world.resource_mut::<GameRng>().unwrap();
+    let mut items = Vec::new();
+    for _ in 0..table.rolls {
+        let dropped = match table.roll(rng) {
+            LootDrop::Nothing => None,
+            LootDrop::Coins(amount) => Some(spawn_coin(world, *pos, amount)),
+            LootDrop::Prefab(name) => spawn_prefab(world, name, *pos),
+            LootDrop::Item(name) => {
+                items.extend(create_item(name));
+                None
+            }
+        };
+
+        // scatter the drops a little
+        if let Some(pickup) = dropped.and_then(|e| world.component_mut::<Pickup>(e)) {
+            let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+            pickup.velocity = Vec2::from_angle(angle) * LOOT_SCATTER_SPEED;
+        }
+    }
+    if !items.is_empty() {
+        spawn_corpse(world, *pos, items);
+    }
+}
+
+fn apply_status(world: &World, entity: Entity, effect: StatusEffect) {
+    if let Some(status) = world.component_mut::<StatusEffects>(entity) {
+        status.apply(effect);
+    }
+}
+
+fn speed_multiplier(world: &World, entity: Entity) -> f32 {
+    world
+        .component::<StatusEffects>(entity)
+        .map_or(1., |status| status.speed_multiplier())
+}
+
+// damage_scale multiplies the weapon's damage
+fn fire_weapon(world: &World, weapon: &Weapon, damage_scale: f32, pos: Pos, trajectory: Vec2<f32>) {
+    let base_angle = trajectory.angle();
+    events::send(
+        world,
+        ShotFired {
+            pos,
+            direction: Vec2::from_angle(base_angle),
+        },
+    );
+    haptics::rumble(world, 0.2, 60);
+
+    for i in 0..weapon.projectile_count {
+        let offset = if weapon.projectile_count > 1 {
+            // spread evenly across the fan
+            weapon.spread * (i as f32 / (weapon.projectile_count - 1) as f32 - 0.5)
+        } else if weapon.spread > 0. {
+            let rng = world.resource_mut::<GameRng>().unwrap();
+            rng.gen_range(-weapon.spread / 2.0..weapon.spread / 2.0)
+        } else {
+            0.
+        };
+
+        let angle = base_angle + offset;
+        let direction = Vec2::from_angle(angle);
+        spawn_bullet(
+            world,
+            Vec2::new(
+                pos.x + direction.x * MUZZLE_OFFSET,
+                pos.y + direction.y * MUZZLE_OFFSET,
+            ),
+            direction,
+            weapon,
+            (weapon.damage as f32 * damage_scale).round() as u16,
+        );
+    }
+}
+
+// ███████╗██╗   ██╗███████╗████████╗███████╗███╗   ███╗███████╗
+// ██╔════╝╚██╗ ██╔╝██╔════╝╚══██╔══╝██╔════╝████╗ ████║██╔════╝
+// ███████╗ ╚████╔╝ ███████╗   ██║   █████╗  ██╔████╔██║███████╗
+// ╚════██║  ╚██╔╝  ╚════██║   ██║   ██╔══╝  ██║╚██╔╝██║╚════██║
+// ███████║   ██║   ███████║   ██║   ███████╗██║ ╚═╝ ██║███████║
+// ╚══════╝   ╚═╝   ╚══════╝   ╚═╝   ╚══════╝╚═╝     ╚═╝╚══════╝
+
+fn update_player(world: &World) {
+    world.run(
+        |entity: &Entity,
+         player: &Player,
+         pos: &mut Pos,
+         momentum: &mut Momentum,
+         colliders: &ColliderGroup,
+         sprite: &mut AnimatedSprite,
+         graph: &mut AnimGraph,
+         dash: &mut Dash,
+         mut ctx: ResMut<Ctx>| {
+            if world.resource::<Checkpoints>().unwrap().is_player_dead() {
+                momentum.velocity = Vec2::zero();
+                graph.params.is_moving = false;
+                return;
+            }
+            if player.slot == 1 {
+                ctx.swap_players();
+            }
+            let inventory = containers::inventory_mut(world, *entity).unwrap();
+            let stats = *world.component::<PlayerStats>(*entity).unwrap();
+
+            let speed = if ctx.input.pressed.shift {
+                8.
+            } else {
+                world.resource::<Tuning>().unwrap().player_speed
+            } * speed_multiplier(world, *entity)
+                * stats.speed;
+
+            let mut movement = Vec2::<f32>::zero();
+            if ctx.input.pressed.up {
+                movement.y -= 1.;
+            }
+            if ctx.input.pressed.down {
+                movement.y += 1.;
+            }
+            if ctx.input.pressed.left {
+                movement.x -= 1.;
+            }
+            if ctx.input.pressed.right {
+                movement.x += 1.;
+            }
+            let mut desired = walk_velocity(movement, speed);
+            graph.params.is_moving = desired.magnitude() > 0.;
+            graph.params.speed = speed;
+            graph.params.move_dir = desired;
+
+            dash.cooldown.tick();
+            if ctx.input.just_pressed.dash && dash.cooldown.is_finished() {
+                // where the player is heading, or facing when standing still
+                dash.direction = if desired.magnitude() > 0. {
+                    desired.normalized()
+                } else {
+                    ctx.player_facing
+                };
+                dash.active.reset();
+                dash.cooldown.reset();
+                apply_status(
+                    world,
+                    *entity,
+                    StatusEffect::invulnerable(DASH_IFRAME_TICKS),
+                );
+                sprite.squash(0.3, DASH_TICKS as u16);
+                graph.play("dash");
+            }
+            if dash.is_dashing() {
+                dash.active.tick();
+                desired = dash.direction.scaled(DASH_SPEED);
+            }
+
+            let collider = colliders.nav.as_ref().unwrap();
+            move_and_slide(world, *entity, pos, momentum, desired, collider);
+
+            ctx.player_pos = *pos;
+
+            if movement.magnitude() > 0. {
+                ctx.player_facing = movement.normalized();
+            }
+
+            let mut trajectory = Vec2::zero();
+
+            if ctx.input.pressed.fire_right {
+                trajectory.x += 1.0;
+            }
+            if ctx.input.pressed.fire_left {
+                trajectory.x -= 1.0;
+            }
+            if ctx.input.pressed.fire_up {
+                trajectory.y -= 1.0;
+            }
+            if ctx.input.pressed.fire_down {
+                trajectory.y += 1.0;
+            }
+
+            // arrow keys take precedence over the mouse
+            if trajectory.magnitude() == 0.0 && ctx.input.pressed.mouse_left {
+                let cursor = ctx.cursor_world_pos();
+                trajectory = Vec2::new(cursor.x - pos.x, cursor.y - pos.y);
+            }
+
+            graph.params.is_firing = trajectory.magnitude() > 0.0;
+            graph.params.aim_dir = trajectory;
+            if trajectory.magnitude() > 0.0 {
+                ctx.player_facing = trajectory.normalized();
+                if let Some(weapon) = inventory.active_weapon_mut() {
+                    if weapon.try_fire(world.resource::<Tuning>().unwrap()) {
+                        fire_weapon(world, weapon, stats.damage, *pos, trajectory);
+                    }
+                }
+            }
+
+            if ctx.input.just_pressed.q {
+                inventory.set_active_offset(-1, world)
+            }
+
+            if ctx.input.just_pressed.e {
+                inventory.set_active_offset(1, world)
+            }
+
+            if ctx.input.just_pressed.use_item {
+                inventory.do_use(world)
+            }
+
+            inventory.tick(world);
+            if player.slot == 1 {
+                ctx.swap_players();
+            }
+        },
+    );
+
+    // while holding something the interact key only throws it
+    let mut carried = None;
+    world.run(|entity: &Entity, throwable: &Throwable| {
+        if throwable.is_carried {
+            carried = Some(*entity);
+        }
+    });
+
+    let mut players = Vec::new();
+    world.run(|entity: &Entity, player: &Player, pos: &Pos| {
+        players.push((*entity, player.slot, *pos))
+    });
+    let ctx = world.resource::<Ctx>().unwrap();
+    for (player, slot, player_pos) in players {
+        let input = if slot == 1 {
+            &ctx.player2.input
+        } else {
+            &ctx.input
+        };
+        if !input.just_pressed.interact {
+            continue;
+        }
+        world.run(
+            |entity: &Entity, interactable: &mut Interactable, pos: &Pos| {
+                if carried.is_some_and(|held| held != *entity) {
+                    return;
+                }
+                if player_pos.distance(pos) < 32.0 {
+                    (interactable.on_interact)(world, *entity, player);
+                    if let Some(script) = interactable.script {
+                        scripts::call(world, script, *entity, player);
+                    }
+                    events::send(world, Interacted { entity: *entity });
+                }
+            },
+        );
+    }
+}
+
+// the camera sits between the local players and doesn't zoom, so they have to stay on
+// screen together. a step that takes one too far from the other is undone on that axis
+fn leash_players(world: &World) {
+    let (w, h) = world.resource::<Ctx>().unwrap().render_size();
+    let max = (w as f32 - LEASH_MARGIN * 2., h as f32 - LEASH_MARGIN * 2.);
+    let mut players = Vec::new();
+    world.run(|entity: &Entity, _: &Player, pos: &Pos| players.push((*entity, *pos)));
+    let [(first, first_pos), (second, second_pos)] = players[..] else {
+        return;
+    };
+    for (entity, other) in [(first, second_pos), (second, first_pos)] {
+        let prev = world.component::<PrevPos>(entity).unwrap().0;
+        let pos = world.component_mut::<Pos>(entity).unwrap();
+        let dx = (pos.x - other.x).abs();
+        if dx > max.0 && dx > (prev.x - other.x).abs() {
+            pos.x = prev.x;
+        }
+        let dy = (pos.y - other.y).abs();
+        if dy > max.1 && dy > (prev.y - other.y).abs() {
+            pos.y = prev.y;
+        }
+    }
+}
+
+// comes and goes with the client, and joins next to the player
+fn update_partner(world: &World) {
+    let Net::Host(host) = world.resource_mut::<Net>().unwrap() else {
+        return;
+    };
+    match host.partner {
+        Some(partner) if !host.has_peer() => {
+            world.despawn(partner);
+            host.partner = None;
+        }
+        None if host.has_peer() => {
+            let pos = world.resource::<Ctx>().unwrap().player_pos;
+            host.partner = Some(spawn_partner(world, pos));
+        }
+        _ => {}
+    }
+
+    let input = &host.input;
+    world.run(
+        |entity: &Entity,
+         partner: &mut Partner,
+         pos: &mut Pos,
+         momentum: &mut Momentum,
+         colliders: &ColliderGroup,
+         graph: &mut AnimGraph,
+         tuning: Res<Tuning>| {
+            let speed = tuning.player_speed * speed_multiplier(world, *entity);
+            let mut movement = Vec2::<f32>::zero();
+            if input.pressed.up {
+                movement.y -= 1.;
+            }
+            if input.pressed.down {
+                movement.y += 1.;
+            }
+            if input.pressed.left {
+                movement.x -= 1.;
+            }
+            if input.pressed.right {
+                movement.x += 1.;
+            }
+            let desired = walk_velocity(movement, speed);
+            graph.params.is_moving = desired.magnitude() > 0.;
+            graph.params.speed = speed;
+            graph.params.move_dir = desired;
+
+            let collider = colliders.nav.as_ref().unwrap();
+            move_and_slide(world, *entity, pos, momentum, desired, collider);
+
+            let mut trajectory = Vec2::zero();
+            if input.pressed.fire_right {
+                trajectory.x += 1.0;
+            }
+            if input.pressed.fire_left {
+                trajectory.x -= 1.0;
+            }
+            if input.pressed.fire_up {
+                trajectory.y -= 1.0;
+            }
+            if input.pressed.fire_down {
+                trajectory.y += 1.0;
+            }
+            if trajectory.magnitude() == 0.0 && input.pressed.mouse_left {
+                trajectory = Vec2::new(input.aim.x - pos.x, input.aim.y - pos.y);
+            }
+
+            graph.params.is_firing = trajectory.magnitude() > 0.0;
+            graph.params.aim_dir = trajectory;
+            partner.fire_cooldown.tick();
+            if trajectory.magnitude() > 0.0 && partner.fire_cooldown.is_finished() {
+                // like the pistol, see Weapon::try_fire
+                partner.fire_cooldown.duration = tuning.player_fire_cooldown;
+                partner.fire_cooldown.reset();
+                fire_weapon(world, &Weapon::pistol(), 1., *pos, trajectory);
+            }
+        },
+    );
+}
+
+// TODO pathfinding debug overlay (walkable tiles, each enemy's last path, open/closed
+// sets of the last search, smoothed paths) once enemies path with A*, for now they
+// steer straight at the player and there's no nav grid to show
+fn update_enemies(world: &World) {
+    let mut players = Vec::new();
+    world.run(|_: &Player, pos: &Pos| players.push(*pos));
+
+    world.run(
+        |entity: &Entity,
+         _: &Enemy,
+         pos: &mut Pos,
+         momentum: &mut Momentum,
+         colliders: &mut ColliderGroup,
+         graph: &mut AnimGraph,
+         tuning: Res<Tuning>| {
+            let collider = colliders.nav.as_ref().unwrap();
+            // the nearest player in sight, or just the nearest when there's a wall in the way
+            // of everyone
+            let nearest = |a: &&Pos, b: &&Pos| a.distance(pos).total_cmp(&b.distance(pos));
+            let Some(player_pos) = players
+                .iter()
+                .filter(|player_pos| can_see(world, *pos, **player_pos))
+                .min_by(nearest)
+                .or_else(|| players.iter().min_by(nearest))
+            else {
+                return;
+            };
+            let mut v = Vec2::<f32>::new(player_pos.x - pos.x, player_pos.y - pos.y);
+
+            v.normalize();
+            if let Some(flocking) = world.component::<Flocking>(*entity) {
+                v += flock(world, *entity, pos, flocking);
+                // never faster than chasing alone
+                if v.magnitude() > 1. {
+                    v.normalize();
+                }
+            }
+            v.scale(tuning.enemy_speed * speed_multiplier(world, *entity));
+            graph.params.is_moving = v.magnitude() > 0.;
+            graph.params.move_dir = v;
+
+            let v = move_with_momentum(momentum, v, collider);
+            pos.x += v.x;
+            pos.y += v.y;
+        },
+    );
+}
+
+fn update_boss(world: &World) {
+    let ctx = world.resource::<Ctx>().unwrap();
+    let mut player = None;
+    world.run(|entity: &Entity, _: &Player| player = Some(*entity));
+
+    world.run(
+        |entity: &Entity,
+         boss: &mut Boss,
+         pos: &mut Pos,
+         health: &Health,
+         momentum: &mut Momentum,
+         colliders: &ColliderGroup,
+         sprite: &mut AnimatedSprite,
+         graph: &mut AnimGraph| {
+            let fraction = health.hp as f32 / health.max_hp as f32;
+            let phase = BOSS_PHASES
+                .iter()
+                .rposition(|phase| fraction <= phase.health_fraction)
+                .unwrap_or(0);
+            if phase > boss.phase {
+                // a breather and a shake to mark the new phase
+                boss.phase = phase;
+                boss.state = BossState::Recover;
+                boss.state_timer = Timer::once(BOSS_RECOVER_TICKS);
+                sprite.squash(0.4, 20);
+            }
+
+            let def = &BOSS_PHASES[boss.phase];
+            let finished = boss.state_timer.tick();
+            let mut to_player = Vec2::new(ctx.player_pos.x - pos.x, ctx.player_pos.y - pos.y);
+            let to_player = to_player.normalized();
+            let collider = colliders.nav.as_ref().unwrap();
+
+            sprite.color_mod = Color::WHITE;
+            let desired = match boss.state {
+                BossState::Idle => {
+                    if finished {
+                        let attack = def.pattern[boss.attacks_done % def.pattern.len()];
+                        boss.state = BossState::Telegraph(attack);
+                        boss.state_timer = Timer::once(def.telegraph_ticks);
+                    }
+                    to_player.scaled(def.speed * speed_multiplier(world, *entity))
+                }
+                BossState::Telegraph(attack) => {
+                    // blinks red while winding up
+                    if boss.state_timer.elapsed % 12 < 6 {
+                        sprite.color_mod = Color::RGB(255, 80, 80);
+                    }
+                    if finished {
+                        boss.attacks_done += 1;
+                        match attack {
+                            BossAttack::Ring => {
+                                // every other ring is rotated so the gaps move
+                                let offset = boss.attacks_done % 2;
+                                for i in 0..def.ring_bullets {
+                                    let angle = (i as f32 + offset as f32 * 0.5)
+                                        / def.ring_bullets as f32
+                                        * std::f32::consts::TAU;
+                                    let direction = Vec2::from_angle(angle);
+                                    spawn_boss_bullet(
+                                        world,
+                                        Pos::new(
+                                            pos.x + direction.x * 48.,
+                                            pos.y + direction.y * 48.,
+                                        ),
+                                        direction.scaled(BOSS_BULLET_SPEED),
+                                    );
+                                }
+                                boss.state = BossState::Recover;
+                                boss.state_timer = Timer::once(BOSS_RECOVER_TICKS);
+                            }
+                            BossAttack::Charge => {
+                                boss.state = BossState::Charging(to_player);
+                                boss.state_timer = Timer::once(BOSS_CHARGE_TICKS);
+                            }
+                        }
+                    }
+                    Vec2::zero()
+                }
+                BossState::Charging(direction) => {
+                    let hit_wall =
+                        collider.left || collider.right || collider.top || collider.bottom;
+                    if let Some(player) = player {
+                        if ctx.player_pos.distance(pos) < BOSS_CONTACT_RANGE {
+                            hit_player(world, player, *pos, BOSS_CHARGE_DAMAGE);
+                        }
+                    }
+                    if hit_wall {
+                        shake_camera(world, 0.4);
+                    }
+                    if finished || hit_wall {
+                        boss.state = BossState::Recover;
+                        boss.state_timer = Timer::once(BOSS_RECOVER_TICKS);
+                        sprite.squash(0.3, 12);
+                    }
+                    direction.scaled(BOSS_CHARGE_SPEED)
+                }
+                BossState::Recover => {
+                    if finished {
+                        boss.state = BossState::Idle;
+                        boss.state_timer = Timer::once(def.idle_ticks);
+                    }
+                    Vec2::zero()
+                }
+            };
+
+            graph.params.is_moving = desired.magnitude() > 0.;
+            graph.params.move_dir = desired;
+
+            let v = move_with_momentum(momentum, desired, collider);
+            pos.x += v.x;
+            pos.y += v.y;
+        },
+    );
+}
+
+// no wall between the two points
+fn can_see(world: &World, from: Pos, to: Pos) -> bool {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let physics = world.resource::<Physics>().unwrap();
+    let distance = from.distance(&to);
+    let direction = Vec2::new(to.x - from.x, to.y - from.y);
+    physics
+        .raycast(from, direction, distance, layers.bit("wall"))
+        .is_none()
+}
+
+// unit-less steering from the neighbours that also flock, added on top of the chase direction
+fn flock(world: &World, entity: Entity, pos: &Pos, flocking: &Flocking) -> Vec2<f32> {
+    let mut separation = Vec2::<f32>::zero();
+    let mut velocity = Vec2::<f32>::zero();
+    let mut center = Vec2::<f32>::zero();
+    let mut neighbours = 0;
+
+    let physics = world.resource::<Physics>().unwrap();
+    for other in physics.entities_in_circle(*pos, flocking.radius, usize::MAX) {
+        if other == entity || !world.has_component::<Flocking>(other) {
+            continue;
+        }
+        let Some(other_pos) = world.component::<Pos>(other) else {
+            continue;
+        };
+        let mut away = Vec2::new(pos.x - other_pos.x, pos.y - other_pos.y);
+        let distance = away.magnitude();
+        // a big collider can reach in from further away than its center
+        if distance > flocking.radius {
+            continue;
+        }
+        // exactly on top of each other has no direction to push in
+        if distance > 0. {
+            // the closer the harder the push
+            away.scale((1. - distance / flocking.radius) / distance);
+        }
+        separation += away;
+
+        if let Some(momentum) = world.component::<Momentum>(other) {
+            velocity += momentum.velocity;
+        }
+        center += Vec2::new(other_pos.x, other_pos.y);
+        neighbours += 1;
+    }
+
+    if neighbours == 0 {
+        return Vec2::zero();
+    }
+
+    let mut steering = separation * flocking.separation;
+    if flocking.alignment > 0. && velocity.magnitude() > 0. {
+        steering += velocity.normalized() * flocking.alignment;
+    }
+    if flocking.cohesion > 0. {
+        let mut to_center = center * (1. / neighbours as f32) - Vec2::new(pos.x, pos.y);
+        if to_center.magnitude() > 0. {
+            steering += to_center.normalized() * flocking.cohesion;
+        }
+    }
+    steering
+}
+
+// steers towards the desired velocity and stops dead against walls
+fn move_with_momentum(
+    momentum: &mut Momentum,
+    desired: Vec2<f32>,
+    collider: &Collider,
+) -> Vec2<f32> {
+    let mut v = momentum.steer(desired);
+
+    if v.x > 0.0 && collider.right {
+        v.x = 0.0;
+    }
+
+    if v.x < 0.0 && collider.left {
+        v.x = 0.0;
+    }
+
+    if v.y > 0.0 && collider.bottom {
+        v.y = 0.0;
+    }
+
+    if v.y < 0.0 && collider.top {
+        v.y = 0.0;
+    }
+
+    momentum.velocity = v;
+    v
+}
+
+// input directions add up to a diagonal, it's as fast as walking straight
+fn walk_velocity(mut movement: Vec2<f32>, speed: f32) -> Vec2<f32> {
+    if movement.magnitude() > 0. {
+        movement.normalized().scaled(speed)
+    } else {
+        Vec2::zero()
+    }
+}
+
+// the players' movement. walls are swept one axis at a time instead of stopping dead on the
+// contact flags, the blocked axis ends flush against the wall and the other keeps going, so
+// pushing diagonally into a wall slides along it. anything that isn't static is still pushed
+// apart by detect_collisions, crates have to be touched to be shoved
+fn move_and_slide(
+    world: &World,
+    me: Entity,
+    pos: &mut Pos,
+    momentum: &mut Momentum,
+    desired: Vec2<f32>,
+    collider: &Collider,
+) {
+    let mut v = momentum.steer(desired);
+    // where fix_colliders will put it, resolution may have moved pos since
+    let mut bounds = collider.bounds;
+    bounds.set_x(pos.pixel().0 + collider.x_offset);
+    bounds.set_y(pos.pixel().1 + collider.y_offset);
+
+    v.x = sweep(world, me, collider, bounds, v.x, true);
+    pos.x += v.x;
+    bounds.set_x(pos.pixel().0 + collider.x_offset);
+    v.y = sweep(world, me, collider, bounds, v.y, false);
+    pos.y += v.y;
+
+    momentum.velocity = v;
+}
+
+// how much of delta bounds can move along one axis before running into a static collider
+fn sweep(
+    world: &World,
+    me: Entity,
+    collider: &Collider,
+    bounds: Rect,
+    mut delta: f32,
+    is_x: bool,
+) -> f32 {
+    if delta == 0. {
+        return delta;
+    }
+    // (start, end) along the moving axis and the other one
+    let spans = |rect: Rect| {
+        if is_x {
+            ((rect.left(), rect.right()), (rect.top(), rect.bottom()))
+        } else {
+            ((rect.top(), rect.bottom()), (rect.left(), rect.right()))
+        }
+    };
+    let ((start, end), (side_start, side_end)) = spans(bounds);
+
+    // everything between where it is and where it wants to be
+    let reach = delta.abs().ceil() as i32 + SKIN;
+    let area = if is_x {
+        Rect::new(
+            bounds.x() - reach,
+            bounds.y(),
+            bounds.width() + 2 * reach as u32,
+            bounds.height(),
+        )
+    } else {
+        Rect::new(
+            bounds.x(),
+            bounds.y() - reach,
+            bounds.width(),
+            bounds.height() + 2 * reach as u32,
+        )
+    };
+    let physics = world.resource::<Physics>().unwrap();
+    for other in physics.entities_in_rect(area, collider.collides_with) {
+        if other == me || !world.has_component::<Static>(other) {
+            continue;
+        }
+        let Some(wall) = world
+            .component::<ColliderGroup>(other)
+            .and_then(|cg| cg.nav.as_ref())
+        else {
+            continue;
+        };
+        if !wall.is_enabled || wall.is_sensor || collider.collides_with & wall.channels == 0 {
+            continue;
+        }
+        // a one-way wall only stops what comes at its blocking side
+        let facing = match (is_x, delta > 0.) {
+            (true, true) => Side::Left,
+            (true, false) => Side::Right,
+            (false, true) => Side::Top,
+            (false, false) => Side::Bottom,
+        };
+        if wall.one_way.is_some_and(|blocks| blocks != facing) {
+            continue;
+        }
+        let ((wall_start, wall_end), (wall_side_start, wall_side_end)) = spans(wall.bounds);
+        // resting against a wall on the other axis doesn't block, that's the slide
+        if side_end.min(wall_side_end) - side_start.max(wall_side_start) <= SKIN {
+            continue;
+        }
+        // already overlapping ones are left to detect_collisions
+        if delta > 0. && wall_start >= end - SKIN {
+            delta = delta.min((wall_start - end + SKIN) as f32);
+        } else if delta < 0. && wall_end <= start + SKIN {
+            delta = delta.max((wall_end - start - SKIN) as f32);
+        }
+    }
+    delta
+}
+
+fn store_prev_positions(world: &World) {
+    world.run(|prev_pos: &mut PrevPos, pos: &Pos| {
+        prev_pos.0 = *pos;
+    });
+}
+
+fn update_animations(world: &World) {
+    #[inline(always)]
+    // true when it moved on to another frame
+    fn update_anim(sprite: &mut AnimatedSprite, animations: &AnimationRepository) -> bool {
+        let anim = sprite.anim();
+        sprite.ticks += 1;
+        // timed animations say how long each frame is shown
+        let ticks = animations
+            .frame_ticks(anim, sprite.frame)
+            .unwrap_or(sprite.ticks_per_frame);
+        if sprite.ticks >= ticks {
+            sprite.frame = (sprite.frame + 1) % animations.get_frames(anim).len() as u32;
+            sprite.ticks = 0;
+            return true;
+        }
+        false
+    }
+
+    // switching to an animation starts it on frame 0 without sending that frame's events
+    world.run(
+        |entity: &Entity,
+         sprite: &mut AnimatedSprite,
+         animations: Res<AnimationRepository>,
+         mut events: ResMut<Events<AnimationEvent>>| {
+            if update_anim(sprite, &animations) {
+                for name in animations.frame_events(sprite.anim(), sprite.frame) {
+                    events.send(AnimationEvent {
+                        entity: *entity,
+                        name,
+                    });
+                }
+            }
+            sprite.flash.tick();
+            sprite.tick_scale_anim();
+        },
+    );
+
+    world.run(
+        |indicator: &mut ProximityIndicator, animations: Res<AnimationRepository>| {
+            update_anim(&mut indicator.sprite, &animations);
+        },
+    );
+}
+
+fn update_doors(world: &World) {
+    world.run(
+        |door: &mut Door,
+         sprite: &mut AnimatedSprite,
+         colliders: &mut ColliderGroup,
+         occluders: &mut LightOccluderGroup| {
+            if door.ticks_until_open == 0 {
+                return;
+            }
+
+            door.ticks_until_open -= 1;
+            if door.ticks_until_open == 0 {
+                sprite.switch_anim(animation(world, "door_open"), 0);
+                if let Some(collider) = colliders.nav.as_mut() {
+                    collider.is_enabled = false;
+                }
+                occluders.is_enabled = false;
+            }
+        },
+    );
+}
+
+fn update_projectiles(world: &World) {
+    world.run(
+        |entity: &Entity, projectile: &mut Projectile, pos: &mut Pos, timers: &Timers| {
+            if timers.is_finished("lifetime") {
+                if world.has_component::<Throwable>(*entity) {
+                    // thrown props break where they land
+                    break_prop(world, *entity);
+                    return;
+                }
+                world
+                    .resource_mut::<CommandBuffer>()
+                    .unwrap()
+                    .despawn(*entity);
+            } else {
+                pos.x += projectile.velocity.x;
+                pos.y += projectile.velocity.y;
+            }
+        },
+    );
+
+    // orient projectile sprites along their velocity
+    world.run(
+        |projectile: &Projectile, anim: &mut AnimatedSprite, _: Without<Throwable>| {
+            let velocity = projectile.velocity;
+            anim.rotation = velocity.angle().to_degrees();
+        },
+    );
+}
+
+fn update_lights(world: &World) {
+    let rng = world.resource_mut::<GameRng>().unwrap();
+    world.run(|animator: &mut LightAnimator, light: &mut Light| {
+        animator.ticks = animator.ticks.wrapping_add(1);
+
+        // noise wanders smoothly, so the flicker doesn't strobe. every light gets its own
+        // stretch of it or the torches in a room would flicker in step
+        let seed = *animator.seed.get_or_insert_with(|| rng.gen());
+        let t = animator.ticks as f32 * animator.flicker_frequency / TICKS_PER_SECOND as f32;
+        animator.flicker = math::value_noise(t, seed);
+
+        let phase = |period: u32| {
+            (animator.ticks % period.max(1)) as f32 / period.max(1) as f32 * std::f32::consts::TAU
+        };
+
+        let base = animator.base;
+        let pulse = math::sin(phase(animator.pulse_period)) * animator.pulse_amplitude;
+        light.radius = (base.radius as f32 * (1. + pulse)).max(0.) as u16;
+        light.intensity =
+            (base.intensity * (1. + animator.flicker * animator.flicker_amplitude)).max(0.);
+        light.color = match animator.cycle_color {
+            Some(to) => {
+                let t = (1. - math::cos(phase(animator.cycle_period))) / 2.;
+                let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t) as u8;
+                Color::RGB(
+                    lerp(base.color.r, to.r),
+                    lerp(base.color.g, to.g),
+                    lerp(base.color.b, to.b),
+                )
+            }
+            None => base.color,
+        };
+    });
+
+    let commands = world.resource_mut::<CommandBuffer>().unwrap();
+    world.run(
+        |entity: &Entity, flash: &mut LightFlash, light: &mut Light| {
+            if flash.timer.tick() {
+                commands.despawn(*entity);
+            }
+            // grows fast and fades out
+            let t = flash.timer.elapsed as f32 / flash.timer.duration as f32;
+            let left = 1. - t;
+            light.radius = (flash.radius as f32 * (1. - left * left * left)) as u16;
+            light.intensity = flash.intensity * (1. - t);
+        },
+    );
+}
+
+// animated lights are driven from the animator base, so changes have to go there
+pub fn light_base_mut(world: &World, entity: Entity) -> &mut Light {
+    match world.component_mut::<LightAnimator>(entity) {
+        Some(animator) => &mut animator.base,
+        None => world.component_mut::<Light>(entity).unwrap(),
+    }
+}
+
+fn update_timers(world: &World) {
+    world.run(|timers: &mut Timers| timers.tick());
+
+    // projectiles handle their own lifetime
+    let commands = world.resource_mut::<CommandBuffer>().unwrap();
+    world.run(|entity: &Entity, timers: &Timers, _: Without<Projectile>| {
+        if timers.is_finished("lifetime") {
+            commands.despawn(*entity);
+        }
+    });
+}
+
+fn inventory_slot_rect(idx: usize, viewport: Rect) -> Rect {
+    let rows = INVENTORY_SIZE.div_ceil(INVENTORY_COLUMNS);
+    let w = INVENTORY_COLUMNS as i32 * INVENTORY_SLOT_SIZE;
+    let h = rows as i32 * INVENTORY_SLOT_SIZE;
+
+    Rect::new(
+        viewport.width() as i32 / 2 - w / 2
+            + (idx % INVENTORY_COLUMNS) as i32 * INVENTORY_SLOT_SIZE,
+        viewport.height() as i32 / 2 - h / 2
+            + (idx / INVENTORY_COLUMNS) as i32 * INVENTORY_SLOT_SIZE,
+        INVENTORY_SLOT_SIZE as u32,
+        INVENTORY_SLOT_SIZE as u32,
+    )
+}
+
+fn inventory_slot_at(point: (i32, i32), viewport: Rect) -> Option<usize> {
+    (0..INVENTORY_SIZE).find(|idx| inventory_slot_rect(*idx, viewport).contains_point(point))
+}
+
+fn update_shop(world: &World) {
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    let shop = world.resource_mut::<Shop>().unwrap();
+    shop.tick();
+
+    let Some((vendor, customer)) = shop.open else {
+        return;
+    };
+    let (Some(stock), Some(own)) = (
+        containers::inventory_mut(world, vendor),
+        containers::inventory_mut(world, customer),
+    ) else {
+        shop.open = None;
+        return;
+    };
+    if ctx.input.just_pressed.tab {
+        shop.open = None;
+        return;
+    }
+    if ctx.input.just_pressed.up {
+        shop.select_offset(-1, stock.items().count());
+    }
+    if ctx.input.just_pressed.down {
+        shop.select_offset(1, stock.items().count());
+    }
+
+    if !ctx.input.just_pressed.interact {
+        return;
+    }
+    let Some((idx, item)) = stock.items().nth(shop.selected) else {
+        return;
+    };
+    let name = item.name();
+    let Some(price) = shop.price(name) else {
+        let text = tr_args("shop-not-for-sale", &[("item", name)]);
+        shop.set_feedback(text, Color::RGB(255, 80, 80));
+        return;
+    };
+
+    if price > ctx.coins {
+        shop.set_feedback(tr("shop-cant-afford"), Color::RGB(255, 80, 80));
+        return;
+    }
+    if own.is_full() {
+        shop.set_feedback(tr("shop-inventory-full"), Color::RGB(255, 80, 80));
+        return;
+    }
+    let item = stock.take(idx, world).unwrap();
+    // there was room
+    let _ = own.insert_boxed(item, world);
+    ctx.coins -= price;
+    let text = tr_args("shop-bought", &[("item", name)]);
+    shop.set_feedback(text, Color::RGB(120, 255, 120));
+    // the list got shorter, stay on the row below or the new last one
+    let left = stock.items().count();
+    if shop.selected >= left {
+        shop.select_offset(-1, left);
+    }
+}
+
+// the equip slots line up against it
+fn inventory_grid_right(viewport: Rect) -> i32 {
+    inventory_slot_rect(INVENTORY_COLUMNS - 1, viewport).right()
+}
+
+fn update_inventory_screen(world: &World) {
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    // the first player's, the mouse is theirs
+    let Some(player) = player_entity(world, 0) else {
+        return;
+    };
+    let (Some(inventory), Some(equipment)) = (
+        containers::inventory_mut(world, player),
+        world.component_mut::<Equipment>(player),
+    ) else {
+        return;
+    };
+    let viewport = ctx.canvas.viewport();
+    let hovered = inventory_slot_at(ctx.input.mouse_pos, viewport);
+    let equip_slot = equipment::slot_at(
+        ctx.input.mouse_pos,
+        inventory_grid_right(viewport),
+        viewport,
+    );
+
+    if ctx.input.just_pressed.mouse_left {
+        match (ctx.inventory_held_slot, hovered, equip_slot) {
+            (Some(held), Some(idx), _) => {
+                inventory.swap(held, idx);
+                ctx.inventory_held_slot = None;
+            }
+            (Some(held), None, Some(slot)) => {
+                let fits = inventory
+                    .get(held)
+                    .is_some_and(|item| item.equip_slot() == Some(slot));
+                if fits {
+                    let item = inventory.take(held, world).unwrap();
+                    // the slot it came from is free for whatever was worn
+                    if let Some(worn) = equipment.equip(item) {
+                        let _ = inventory.insert_boxed(worn, world);
+                    }
+                }
+                ctx.inventory_held_slot = None;
+            }
+            (None, Some(idx), _) if inventory.get(idx).is_some() => {
+                ctx.inventory_held_slot = Some(idx);
+            }
+            (None, None, Some(slot)) if !inventory.is_full() => {
+                if let Some(worn) = equipment.take(slot) {
+                    let _ = inventory.insert_boxed(worn, world);
+                }
+            }
+            _ => ctx.inventory_held_slot = None,
+        }
+    }
+
+    if ctx.input.just_pressed.mouse_right {
+        if let Some(idx) = hovered {
+            inventory.set_active(idx, world);
+        }
+    }
+}
+
+fn update_status_effects(world: &World) {
+    world.run(|entity: &Entity, status: &mut StatusEffects| {
+        let had_tint = status.has_tint();
+        let was_invulnerable = status.is_invulnerable();
+        for slot in status.effects.iter_mut() {
+            let Some(effect) = slot.as_mut() else {
+                continue;
+            };
+
+            if effect.damage > 0 && effect.ticks_left % effect.damage_interval.max(1) == 0 {
+                damage(world, *entity, effect.damage);
+            }
+
+            effect.ticks_left = effect.ticks_left.saturating_sub(1);
+            if effect.ticks_left == 0 {
+                *slot = None;
+            }
+        }
+
+        // only while an effect shows and on the tick it ends, the rest of the time the sprite
+        // belongs to tweens, telegraphs and whatever else tints it
+        // a dead player stays hidden until the respawn shows it again
+        let is_dead_player = world.has_component::<Player>(*entity)
+            && world.resource::<Checkpoints>().unwrap().is_player_dead();
+        if let Some(sprite) = world.component_mut::<AnimatedSprite>(*entity) {
+            if had_tint || status.has_tint() {
+                sprite.color_mod = status.tint();
+            }
+            // i-frames show as see-through
+            if !is_dead_player && (was_invulnerable || status.is_invulnerable()) {
+                sprite.alpha = if status.is_invulnerable() { 140 } else { 255 };
             }
+        }
+    });
+}
 
-            if ctx.input.just_pressed.q {
-                ctx.player_inventory.set_active_offset(-1, world)
-            }
+fn update_hazards(world: &World) {
+    world.run(|momentum: &mut Momentum| {
+        momentum.friction = 1.;
+    });
 
-            if ctx.input.just_pressed.e {
-                ctx.player_inventory.set_active_offset(1, world)
+    world.run(
+        |hazard: &mut Hazard, hazard_pos: &Pos, sprite: &mut AnimatedSprite| {
+            hazard.ticks += 1;
+            if hazard.kind == HazardKind::Spikes && hazard.ticks.is_multiple_of(SPIKE_CYCLE_TICKS) {
+                hazard.is_active = !hazard.is_active;
+                let anim = if hazard.is_active {
+                    "spikes_up"
+                } else {
+                    "spikes_down"
+                };
+                sprite.switch_anim(animation(world, anim), 0);
             }
 
-            if ctx.input.just_pressed.use_item {
-                ctx.player_inventory.do_use(world)
+            if !hazard.is_active {
+                return;
             }
 
-            ctx.player_inventory.tick(world);
-        },
-    );
+            let half = TILE_SIZE as i32 / 2;
+            let (x, y) = hazard_pos.pixel();
+            let tile = Rect::new(x - half, y - half, TILE_SIZE as u32, TILE_SIZE as u32);
+            let deals_damage = hazard.ticks.is_multiple_of(HAZARD_DAMAGE_INTERVAL);
 
-    world.run(
-        |entity: &Entity, interactable: &mut Interactable, pos: &Pos, ctx: Res<Ctx>| {
-            if ctx.input.just_pressed.interact && ctx.player_pos.distance(pos) < 32.0 {
-                (interactable.on_interact)(world, *entity);
+            for entity in standing_on(world, tile) {
+                match hazard.kind {
+                    HazardKind::Spikes => {
+                        if deals_damage {
+                            damage(world, entity, 1);
+                        }
+                    }
+                    HazardKind::Lava => {
+                        apply_status(world, entity, StatusEffect::burning(120));
+                        if deals_damage {
+                            damage(world, entity, 2);
+                        }
+                    }
+                    HazardKind::Ice => {
+                        if let Some(momentum) = world.component_mut::<Momentum>(entity) {
+                            momentum.friction = ICE_FRICTION;
+                        }
+                    }
+                    HazardKind::Water => {
+                        // fire goes out, whether it's flying or burning on someone
+                        if let Some(projectile) = world.component::<Projectile>(entity) {
+                            let is_fire = projectile
+                                .effect
+                                .is_some_and(|e| e.kind == StatusKind::Burning);
+                            if is_fire {
+                                world
+                                    .resource_mut::<CommandBuffer>()
+                                    .unwrap()
+                                    .despawn(entity);
+                            }
+                        }
+                        if let Some(status) = world.component_mut::<StatusEffects>(entity) {
+                            status.remove(StatusKind::Burning);
+                            // runs out a tick after leaving the water
+                            status.apply(StatusEffect::wet(2));
+                        }
+                    }
+                }
             }
         },
     );
 }
 
-fn update_enemies(world: &World) {
-    let mut player_pos = Pos::zero();
-
-    world.run(|_: &Player, pos: &Pos| {
-        player_pos = *pos;
+// everything whose nav collider is on the tile, walls and other static things don't count
+fn standing_on(world: &World, tile: Rect) -> Vec<Entity> {
+    let physics = world.resource::<Physics>().unwrap();
+    let mut found = physics.entities_in_rect(tile, usize::MAX);
+    found.retain(|entity| {
+        !world.has_component::<Static>(*entity)
+            && world
+                .component::<ColliderGroup>(*entity)
+                .and_then(|cg| cg.nav.as_ref())
+                .is_some_and(|nav| nav.is_enabled && nav.bounds.has_intersection(tile))
     });
+    found
+}
 
+fn update_pickups(world: &World) {
+    // the first player's, like ctx.player_pos
+    let Some(player) = player_entity(world, 0) else {
+        return;
+    };
     world.run(
-        |_: &Enemy,
-         pos: &mut Pos,
-         colliders: &mut ColliderGroup,
-         sprite: &mut AnimatedSprite,
-         ctx: Res<Ctx>| {
-            let collider = colliders.nav.as_ref().unwrap();
-            let mut v = Vec2::<f32>::new(player_pos.x - pos.x, player_pos.y - pos.y);
-
-            v.normalize();
-            v.scale(ctx.enemy_speed);
-
-            if v.x > 0.0 {
-                sprite.flip_horizontal = true;
-            } else if v.x < 0.0 {
-                sprite.flip_horizontal = false;
-            }
-
-            if v.x > 0.0 && collider.right {
-                v.x = 0.0;
-            }
+        |entity: &Entity, pickup: &mut Pickup, pos: &mut Pos, mut ctx: ResMut<Ctx>| {
+            let distance = ctx.player_pos.distance(pos);
 
-            if v.x < 0.0 && collider.left {
-                v.x = 0.0;
+            // coins get pulled in once the player is close enough
+            if pickup.coins > 0 && distance < COIN_MAGNET_RANGE && distance > 0. {
+                let pull = COIN_MAGNET_SPEED * (1. - distance / COIN_MAGNET_RANGE);
+                pickup.velocity.x += (ctx.player_pos.x - pos.x) / distance * pull;
+                pickup.velocity.y += (ctx.player_pos.y - pos.y) / distance * pull;
             }
 
-            if v.y > 0.0 && collider.bottom {
-                v.y = 0.0;
-            }
+            pos.x += pickup.velocity.x;
+            pos.y += pickup.velocity.y;
+            pickup.velocity.x *= PICKUP_FRICTION;
+            pickup.velocity.y *= PICKUP_FRICTION;
 
-            if v.y < 0.0 && collider.top {
-                v.y = 0.0;
+            if distance > PICKUP_RANGE {
+                return;
             }
 
-            pos.x += v.x;
-            pos.y += v.y;
-        },
-    );
-}
-
-fn update_projectiles(world: &World) {
-    world.run(
-        |entity: &Entity, projectile: &mut Projectile, pos: &mut Pos| {
-            if projectile.ticks_left == 0 {
+            let taken = match pickup.on_pickup {
+                Some(on_pickup) => on_pickup(world, player),
+                None => true,
+            };
+            if taken {
+                ctx.coins += pickup.coins;
+                let above = Pos::new(ctx.player_pos.x, ctx.player_pos.y - FLOATING_TEXT_HEIGHT);
+                if pickup.coins > 0 {
+                    let plural = if pickup.coins == 1 { "" } else { "s" };
+                    floating_text::spawn(
+                        world,
+                        above,
+                        &format!("+{} coin{}", pickup.coins, plural),
+                        Color::RGB(255, 220, 80),
+                    );
+                }
+                if let Some(label) = pickup.label {
+                    floating_text::spawn(world, above, label, Color::WHITE);
+                    toasts::push(world, &tr_args("toast-item-acquired", &[("name", label)]));
+                }
                 world
-                    .resource::<Ctx>()
-                    .unwrap()
-                    .despawn_queue
-                    .write()
+                    .resource_mut::<CommandBuffer>()
                     .unwrap()
-                    .push(*entity);
-            } else {
-                pos.x += projectile.velocity.x;
-                pos.y += projectile.velocity.y;
-                projectile.ticks_left -= 1;
+                    .despawn(*entity);
             }
         },
     );
 }
 
-fn update_spawners(world: &World) {
-    world.run(|spawner: &mut ParticleEmitter, pos: &Pos| {
-        if spawner.is_active {
-            if spawner.particle_ticks_left == 0 {
-                for _ in 0..2 {
-                    let mut v = Vec2::new(
-                        thread_rng().gen_range(-1.0..1.0),
-                        thread_rng().gen_range(-1.0..1.0),
-                    );
-                    v.scale(2.0);
-
-                    world.spawn(&[
-                        pos,
-                        &Projectile {
-                            velocity: v,
-                            ticks_left: 60,
-                        },
-                        &Light {
-                            radius: 2,
-                            color: Color::RGB(255, 255, 255),
-                            intensity: 1.,
-                        },
-                        &ColliderGroup {
-                            nav: Some(Collider::new(
-                                (-2, -2, 4, 4),
-                                CH_NONE,
-                                CH_NAV | CH_HITBOX,
-                                Some(|world: &World, me: Entity, _: Entity| {
-                                    world.component_mut::<Projectile>(me).unwrap().velocity =
-                                        Vec2::zero();
-                                }),
-                            )),
-                            hitbox: None,
-                        },
-                    ]);
-                }
+fn update_movers(world: &World) {
+    world.run(|mover: &mut Mover, pos: &mut Pos| {
+        mover.delta = Vec2::new(0., 0.);
+        if mover.num_waypoints() < 2 {
+            return;
+        }
 
-                spawner.particle_ticks_left = spawner.particle_cooldown;
-            } else {
-                spawner.particle_ticks_left -= 1;
+        let target = mover.waypoints[mover.target].unwrap();
+        let distance = pos.distance(&target);
+        if distance <= mover.speed {
+            mover.delta = Vec2::new(target.x - pos.x, target.y - pos.y);
+            mover.advance();
+        } else {
+            mover.delta = Vec2::new(
+                (target.x - pos.x) / distance * mover.speed,
+                (target.y - pos.y) / distance * mover.speed,
+            );
+        }
+        pos.x += mover.delta.x;
+        pos.y += mover.delta.y;
+    });
+}
+
+fn update_conveyors(world: &World) {
+    world.run(|conveyor: &Conveyor, conveyor_pos: &Pos| {
+        let half = TILE_SIZE as i32 / 2;
+        let (x, y) = conveyor_pos.pixel();
+        let tile = Rect::new(x - half, y - half, TILE_SIZE as u32, TILE_SIZE as u32);
+
+        for entity in standing_on(world, tile) {
+            if let Some(pos) = world.component_mut::<Pos>(entity) {
+                pos.x += conveyor.velocity.x;
+                pos.y += conveyor.velocity.y;
             }
         }
     });
 }
 
+fn update_attachments(world: &World) {
+    world.run(|attachment: &mut Attachment, pos: &mut Pos| {
+        let Some(parent) = attachment.parent else {
+            return;
+        };
+        let Some(parent_pos) = world.component::<Pos>(parent) else {
+            attachment.parent = None;
+            return;
+        };
+        let world_transform =
+            Transform::translation(parent_pos.x, parent_pos.y).compose(&attachment.local);
+        let at = world_transform.apply(Vec2::zero());
+        *pos = Pos::new(at.x, at.y);
+    });
+}
+
+fn update_pushables(world: &World) {
+    world.run(|pushable: &mut Pushable, pos: &mut Pos| {
+        pos.x += pushable.velocity.x;
+        pos.y += pushable.velocity.y;
+        pushable.velocity.x *= CRATE_FRICTION;
+        pushable.velocity.y *= CRATE_FRICTION;
+    });
+}
+
 fn fix_colliders(world: &World) {
     world.run(|colliders: &mut ColliderGroup, pos: &Pos| {
+        let (x, y) = pos.pixel();
         if let Some(collider) = colliders.nav.as_mut() {
-            collider.set_pos(
-                pos.x.round() as i32 + collider.x_offset,
-                pos.y.round() as i32 + collider.y_offset,
-            );
+            collider.set_pos(x + collider.x_offset, y + collider.y_offset);
         }
         if let Some(collider) = colliders.hitbox.as_mut() {
-            collider.set_pos(
-                pos.x.round() as i32 + collider.x_offset,
-                pos.y.round() as i32 + collider.y_offset,
-            );
+            collider.set_pos(x + collider.x_offset, y + collider.y_offset);
         }
     });
 }
 
+// reports and flags every overlap, then solver.rs pushes each body out of everything solid
+// it touched at once
 fn detect_collisions(world: &World) {
+    #[allow(clippy::too_many_arguments)]
     fn test(
         world: &World,
         e1: &Entity,
@@ -591,9 +4027,12 @@ fn detect_collisions(world: &World) {
         pos1: &mut Pos,
         e2: &Entity,
         c2: &Collider,
+        contacts: &mut Vec<Contact>,
         should_move: bool,
     ) {
         if *e1 != *e2
+            && c1.is_enabled
+            && c2.is_enabled
             && c1.collides_with & c2.channels != 0
             && c1.bounds.has_intersection(c2.bounds)
         {
@@ -602,33 +4041,51 @@ fn detect_collisions(world: &World) {
             if let Some(on_collide) = c1.on_collide {
                 on_collide(world, *e1, *e2);
             }
+            events::send(
+                world,
+                Collided {
+                    entity: *e1,
+                    other: *e2,
+                },
+            );
 
-            let d_bottom = c2.bounds.bottom() - c1.bounds.top();
-            let d_top = c1.bounds.bottom() - c2.bounds.top();
-            let d_left = c1.bounds.right() - c2.bounds.left();
-            let d_right = c2.bounds.right() - c1.bounds.left();
+            // sensors only report
+            if c1.is_sensor || c2.is_sensor {
+                return;
+            }
+            let should_move = should_move && !world.has_component::<Static>(*e1);
 
-            if d_top < d_bottom && d_top < d_left && d_top < d_right {
-                c1.bottom = true;
-                if should_move && !world.has_component::<Static>(*e1) {
-                    pos1.y -= c1.bounds.bottom() as f32 - c2.bounds.top() as f32 - 1.;
-                }
-            } else if d_bottom < d_top && d_bottom < d_left && d_bottom < d_right {
-                c1.top = true;
-                if should_move && !world.has_component::<Static>(*e1) {
-                    pos1.y += c2.bounds.bottom() as f32 - c1.bounds.top() as f32 - 1.;
-                }
-            } else if d_left < d_right && d_left < d_top && d_left < d_bottom {
-                c1.right = true;
-                if should_move && !world.has_component::<Static>(*e1) {
-                    pos1.x -= c1.bounds.right() as f32 - c2.bounds.left() as f32 - 1.;
-                }
-            } else if d_right < d_left && d_right < d_top && d_right < d_bottom {
-                c1.left = true;
-                if should_move && !world.has_component::<Static>(*e1) {
-                    pos1.x += c2.bounds.right() as f32 - c1.bounds.left() as f32 - 1.;
+            // ride along with moving walls, only once per entity
+            if should_move {
+                if let Some(mover) = world.component::<Mover>(*e2) {
+                    let nav = world.resource::<CollisionLayers>().unwrap().bit("nav");
+                    if c1.channels & nav != 0 {
+                        pos1.x += mover.delta.x;
+                        pos1.y += mover.delta.y;
+                    }
                 }
             }
+
+            let Some(side) = solver::push_side(c1.bounds, c2.bounds) else {
+                return;
+            };
+            // coming through a one-way collider from the open side, or halfway through it
+            if c2.one_way.is_some_and(|blocks| blocks != side) {
+                return;
+            }
+            match side {
+                Side::Top => c1.bottom = true,
+                Side::Bottom => c1.top = true,
+                Side::Left => c1.right = true,
+                Side::Right => c1.left = true,
+            }
+            if should_move {
+                contacts.push(Contact {
+                    bounds: c2.bounds,
+                    one_way: c2.one_way,
+                    is_static: world.has_component::<Static>(*e2),
+                });
+            }
         }
     }
 
@@ -639,15 +4096,20 @@ fn detect_collisions(world: &World) {
         c1.top = false;
         c1.bottom = false;
 
+        let mut contacts = Vec::new();
         world.run(|e2: &Entity, cg: &mut ColliderGroup| {
             if let Some(c2) = cg.nav.as_ref() {
-                test(world, e1, c1, pos1, e2, c2, true);
+                test(world, e1, c1, pos1, e2, c2, &mut contacts, true);
             }
 
             if let Some(c2) = cg.hitbox.as_ref() {
-                test(world, e1, c1, pos1, e2, c2, false);
+                test(world, e1, c1, pos1, e2, c2, &mut contacts, false);
             }
         });
+
+        let (dx, dy) = solver::solve(c1.bounds, &contacts);
+        pos1.x += dx as f32;
+        pos1.y += dy as f32;
     }
 
     world.run(|e1: &Entity, pos1: &mut Pos, cg: &mut ColliderGroup| {
@@ -661,6 +4123,49 @@ fn detect_collisions(world: &World) {
     });
 }
 
+fn update_triggers(world: &World) {
+    world.run(|me: &Entity, trigger: &mut TriggerZone, pos: &Pos| {
+        let (x, y) = pos.pixel();
+        trigger.bounds.set_x(x + trigger.x_offset);
+        trigger.bounds.set_y(y + trigger.y_offset);
+
+        let mut inside = [None; MAX_TRIGGER_OCCUPANTS];
+        let mut num_inside = 0;
+        if trigger.is_enabled {
+            let physics = world.resource::<Physics>().unwrap();
+            for other in physics.entities_in_rect(trigger.bounds, trigger.channels) {
+                if other != *me && num_inside < MAX_TRIGGER_OCCUPANTS {
+                    inside[num_inside] = Some(other);
+                    num_inside += 1;
+                }
+            }
+        }
+
+        // despawned occupants leave silently
+        let previous = trigger.occupants;
+        trigger.occupants = inside;
+
+        for entity in previous.into_iter().flatten() {
+            if !inside.contains(&Some(entity)) && world.has_component::<ColliderGroup>(entity) {
+                if let Some(on_exit) = trigger.on_exit {
+                    on_exit(world, *me, entity);
+                }
+            }
+        }
+
+        for entity in inside.into_iter().flatten() {
+            if !previous.contains(&Some(entity)) {
+                if let Some(on_enter) = trigger.on_enter {
+                    on_enter(world, *me, entity);
+                }
+                if let Some(script) = trigger.script {
+                    scripts::call(world, script, *me, entity);
+                }
+            }
+        }
+    });
+}
+
 // ██████╗ ███████╗███╗   ██╗██████╗ ███████╗██████╗
 // ██╔══██╗██╔════╝████╗  ██║██╔══██╗██╔════╝██╔══██╗
 // ██████╔╝█████╗  ██╔██╗ ██║██║  ██║█████╗  ██████╔╝
@@ -668,219 +4173,662 @@ fn detect_collisions(world: &World) {
 // ██║  ██║███████╗██║ ╚████║██████╔╝███████╗██║  ██║
 // ╚═╝  ╚═╝╚══════╝╚═╝  ╚═══╝╚═════╝ ╚══════╝╚═╝  ╚═╝
 
-pub fn render(world: &World) {
-    let ctx = world.resource_mut::<Ctx>().unwrap();
-    let camera_pos = ctx.camera_pos();
-
-    #[inline(always)]
-    fn update_anim(sprite: &mut AnimatedSprite, num_frames: usize) {
-        sprite.ticks += 1;
-        if sprite.ticks >= sprite.ticks_per_frame {
-            sprite.frame = if sprite.frame as usize == num_frames {
-                0
-            } else {
-                sprite.frame + 1
-            };
-            sprite.ticks = 0;
+pub fn render_pos(world: &World, entity: Entity, pos: &Pos) -> Pos {
+    let mut pos = match world.component::<PrevPos>(entity) {
+        Some(prev_pos) => {
+            let alpha = world.resource::<Ctx>().unwrap().frame_alpha;
+            prev_pos.0.lerp(pos, alpha)
         }
+        None => *pos,
+    };
+    if let Some(offset) = world.component::<Tween<Offset>>(entity) {
+        let offset = offset.value().0;
+        pos.x += offset.x;
+        pos.y += offset.y;
     }
+    pos
+}
 
-    #[inline(always)]
-    fn draw(ctx: &mut Ctx, anim: &mut AnimatedSprite, pos: &Pos, camera_pos: (i32, i32)) {
-        let frames = ctx.animations.get_frames(anim.anim());
-        let sprite = frames[anim.frame as usize];
+// scales the sprite rect around pos, offsets included
+fn sprite_rect(
+    spritesheet: &Spritesheet,
+    anim: &AnimatedSprite,
+    sprite: Sprite,
+    pos: &Pos,
+    camera: &Transform,
+) -> Rect {
+    let scale = anim.current_scale();
+    let (width, height) = spritesheet.sprite_size(sprite);
+    // snapped before the offset, like colliders, so a sprite can't land a pixel off its own
+    let (x, y) = world_to_screen(pos, camera);
+    Rect::new(
+        x + (anim.x_offset as f32 * scale.x).round() as i32,
+        y + (anim.y_offset as f32 * scale.y).round() as i32,
+        (width as f32 * scale.x).round() as u32,
+        (height as f32 * scale.y).round() as u32,
+    )
+}
 
-        ctx.spritesheet.draw_to_canvas(
-            &mut ctx.canvas,
-            sprite,
+fn sprite_options(anim: &AnimatedSprite) -> DrawOptions {
+    let scale = anim.current_scale();
+    DrawOptions {
+        angle: anim.rotation as f64,
+        origin: anim.rotation_origin.map(|o| {
             (
-                pos.x as i32 + anim.x_offset as i32 - camera_pos.0,
-                pos.y as i32 + anim.y_offset as i32 - camera_pos.1,
-            ),
-            0.,
-            anim.flip_horizontal,
-            false,
+                (o.0 as f32 * scale.x).round() as i32,
+                (o.1 as f32 * scale.y).round() as i32,
+            )
+        }),
+        flip_horizontal: anim.flip_horizontal,
+        tint: anim.tint(),
+        ..Default::default()
+    }
+}
+
+fn render(world: &World) {
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    // between the local players
+    let mut players = Vec::new();
+    world.run(|entity: &Entity, pos: &Pos, _: With<Player>| {
+        players.push(render_pos(world, *entity, pos));
+    });
+    if !players.is_empty() {
+        let count = players.len() as f32;
+        ctx.camera_target = Pos::new(
+            players.iter().map(|pos| pos.x).sum::<f32>() / count,
+            players.iter().map(|pos| pos.y).sum::<f32>() / count,
         );
+    }
+    if let Some(you) = net::you(world) {
+        ctx.camera_target = render_pos(world, you, world.component::<Pos>(you).unwrap());
+    }
+    if let Some(camera) = world.resource::<Director>().unwrap().camera {
+        ctx.camera_target = camera;
+    }
+    let editor = world.resource::<Editor>().unwrap();
+    if editor.is_active {
+        ctx.camera_target = editor.camera;
+    }
+    ctx.camera_target.x += ctx.camera_shake.x;
+    ctx.camera_target.y += ctx.camera_shake.y;
+    let camera = ctx.camera();
+    ctx.render_stats.reset();
+    let animations = world.resource::<AnimationRepository>().unwrap();
+
+    #[inline(always)]
+    fn draw(
+        ctx: &mut Ctx,
+        animations: &AnimationRepository,
+        anim: &mut AnimatedSprite,
+        pos: &Pos,
+        camera: &Transform,
+    ) {
+        let frames = animations.get_frames(anim.anim());
+        let sprite = frames[anim.frame as usize];
+        let sheet = &ctx.spritesheets[*animations.get_sheet(anim.anim())];
 
-        update_anim(anim, frames.len() - 1);
+        ctx.render_stats.record(sheet.id);
+        let dst = sprite_rect(sheet, anim, sprite, pos, camera);
+        sheet.draw_to_canvas(&mut ctx.canvas, sprite, dst, sprite_options(anim));
     }
 
     #[inline(always)]
     fn push(
         ctx: &Ctx,
+        animations: &AnimationRepository,
         depth_buffer: &mut DepthBuffer,
         anim: &mut AnimatedSprite,
         pos: &Pos,
-        camera_pos: (i32, i32),
+        camera: &Transform,
+        layer: Layer,
     ) {
-        let frames = ctx.animations.get_frames(anim.anim());
+        let frames = animations.get_frames(anim.anim());
         let sprite = frames[anim.frame as usize];
+        let sheet = &ctx.spritesheets[*animations.get_sheet(anim.anim())];
+        let dst = sprite_rect(sheet, anim, sprite, pos, camera);
         depth_buffer.push(DrawCmd {
+            texture: sheet.id,
             sprite,
             pos: Vec3::<i32> {
-                x: pos.x.round() as i32 + anim.x_offset as i32 - camera_pos.0,
-                y: pos.y.round() as i32 + anim.y_offset as i32 - camera_pos.1,
-                z: pos.y.round() as i32 + anim.z_offset.map_or(0, |o| o) as i32,
+                x: dst.x(),
+                y: dst.y(),
+                z: pos.pixel().1 + anim.z_offset.map_or(0, |o| o) as i32,
             },
-            flip_horizontal: anim.flip_horizontal,
+            size: (dst.width(), dst.height()),
+            layer,
+            order: 0,
+            options: sprite_options(anim),
         });
+    }
+
+    // split at the waterline, the part below is tinted and wobbles
+    fn push_wading(
+        ctx: &Ctx,
+        animations: &AnimationRepository,
+        depth_buffer: &mut DepthBuffer,
+        anim: &mut AnimatedSprite,
+        pos: &Pos,
+        camera: &Transform,
+    ) {
+        let frames = animations.get_frames(anim.anim());
+        let sprite = frames[anim.frame as usize];
+        let sheet = &ctx.spritesheets[*animations.get_sheet(anim.anim())];
+        let dst = sprite_rect(sheet, anim, sprite, pos, camera);
+        let z = pos.pixel().1 + anim.z_offset.map_or(0, |o| o) as i32;
+        let above = (dst.height() as f32 * WATERLINE).round() as u32;
+        let wave = ((ctx.ticks as f32 * 0.15 + pos.x * 0.1).sin() * 2.).round() as i32;
 
-        update_anim(anim, frames.len() - 1);
+        let options = sprite_options(anim);
+        depth_buffer.push(DrawCmd {
+            texture: sheet.id,
+            sprite,
+            pos: Vec3::new(dst.x(), dst.y(), z),
+            size: (dst.width(), above),
+            layer: Layer::World,
+            order: 0,
+            options: DrawOptions {
+                rows: Some((0., WATERLINE)),
+                ..options
+            },
+        });
+        let tint = options.tint;
+        depth_buffer.push(DrawCmd {
+            texture: sheet.id,
+            sprite,
+            pos: Vec3::new(dst.x() + wave, dst.y() + above as i32, z),
+            size: (dst.width(), dst.height() - above),
+            layer: Layer::World,
+            order: 0,
+            options: DrawOptions {
+                rows: Some((WATERLINE, 1.)),
+                tint: Color::RGBA(
+                    (tint.r as u16 * 90 / 255) as u8,
+                    (tint.g as u16 * 140 / 255) as u8,
+                    tint.b,
+                    tint.a / 2,
+                ),
+                ..options
+            },
+        });
     }
 
     // draw floors
     world.run(
         |pos: &mut Pos, sprite: &mut AnimatedSprite, mut ctx: ResMut<Ctx>, _: With<Floor>| {
-            draw(&mut ctx, sprite, pos, camera_pos);
+            draw(&mut ctx, animations, sprite, pos, &camera);
         },
     );
 
     // draw props
     world.run(
         |pos: &mut Pos, sprite: &mut AnimatedSprite, mut ctx: ResMut<Ctx>, _: With<Prop>| {
-            draw(&mut ctx, sprite, pos, camera_pos);
+            draw(&mut ctx, animations, sprite, pos, &camera);
         },
     );
 
     // draw sprites
     world.run(
-        |pos: &mut Pos,
+        |entity: &Entity,
+         pos: &mut Pos,
          sprite: &mut AnimatedSprite,
          mut depth_buffer: ResMut<DepthBuffer>,
          ctx: Res<Ctx>,
          _: Without<Floor>,
          _: Without<Prop>| {
-            push(&ctx, &mut depth_buffer, sprite, pos, camera_pos);
+            let pos = render_pos(world, *entity, pos);
+            let is_wet = world
+                .component::<StatusEffects>(*entity)
+                .is_some_and(|status| status.has(StatusKind::Wet));
+            if is_wet && sprite.rotation == 0. {
+                push_wading(&ctx, animations, &mut depth_buffer, sprite, &pos, &camera);
+                return;
+            }
+            push(
+                &ctx,
+                animations,
+                &mut depth_buffer,
+                sprite,
+                &pos,
+                &camera,
+                Layer::World,
+            );
+        },
+    );
+
+    world.run(
+        |indicator: &mut ProximityIndicator,
+         pos: &Pos,
+         mut depth_buffer: ResMut<DepthBuffer>,
+         ctx: Res<Ctx>| {
+            if ctx.player_pos.distance(pos) < indicator.range {
+                let mut draw_pos = *pos;
+                draw_pos.y -= 32.;
+                // FIXME don't recreate the AnimatedSprite
+                // FIXME needs to be drawn to the ui layer
+                push(
+                    &ctx,
+                    animations,
+                    &mut depth_buffer,
+                    &mut indicator.sprite,
+                    &draw_pos,
+                    &camera,
+                    Layer::Overlay,
+                );
+            }
         },
     );
 
     let ctx = world.resource_mut::<Ctx>().unwrap();
     let depth_buffer = world.resource_mut::<DepthBuffer>().unwrap();
-    depth_buffer.draw_to_canvas(&mut ctx.canvas, &ctx.spritesheet);
+    depth_buffer.draw_to_canvas(&mut ctx.canvas, &ctx.spritesheets, &mut ctx.render_stats);
 
     if ctx.debug_draw_centerpoints {
         world.run(|pos: &Pos, _: Without<Floor>| {
-            let x = pos.x - ctx.camera_pos().0 as f32;
-            let y = pos.y - ctx.camera_pos().1 as f32;
+            let (x, y) = ctx.world_to_screen(pos);
 
             ctx.canvas.set_draw_color(Color::RGBA(0, 255, 0, 255));
-            ctx.canvas
-                .draw_line(((x - 2.) as i32, y as i32), ((x + 2.) as i32, y as i32))
-                .unwrap();
-            ctx.canvas
-                .draw_line((x as i32, (y - 2.) as i32), (x as i32, (y + 2.) as i32))
-                .unwrap();
+            ctx.canvas.draw_line((x - 2, y), (x + 2, y)).unwrap();
+            ctx.canvas.draw_line((x, y - 2), (x, y + 2)).unwrap();
         });
     }
 
+    let shop = world.resource::<Shop>().unwrap();
+    let camera = ctx.camera();
     ctx.canvas
         .with_texture_canvas(&mut ctx.ui_tex, |canvas| {
             canvas.set_draw_color(Color::RGB(0, 0, 0));
             canvas.clear();
 
-            if let Some(item) = ctx.player_inventory.get_left() {
-                ctx.spritesheet.draw_to_canvas(
-                    canvas,
-                    item.sprite(),
-                    (
-                        canvas.viewport().width() as i32 / 2 - 58,
-                        canvas.viewport().height() as i32 - 40,
-                    ),
-                    0.,
-                    false,
-                    false,
-                )
+            // under the hud
+            floating_text::draw(world, canvas, &ctx.font, &camera);
+
+            let mut ui = Ui::new(
+                canvas,
+                &ctx.spritesheets[*SPRITESHEET_TEXTURE],
+                &ctx.font,
+                world.resource_mut::<TextCache>().unwrap(),
+            );
+            // with a second player each gets a corner
+            let mut is_coop = false;
+            world.run(|player: &Player| is_coop |= player.slot == 1);
+            world.run(|player: &Player, container: &Container| {
+                let (anchor, offset) = match (is_coop, player.slot) {
+                    (false, _) => (Anchor::Bottom, (0, -4)),
+                    (true, 0) => (Anchor::BottomLeft, (4, -4)),
+                    _ => (Anchor::BottomRight, (-4, -4)),
+                };
+                draw_active_items(&mut ui, anchor, offset, &container.inventory, &ctx.sprites);
+            });
+
+            ui.icon(
+                Anchor::TopRight,
+                (-48, 8),
+                ctx.sprites.get("ui_coin"),
+                DrawOptions::default(),
+            );
+            world.run(|dash: &Dash, player: &Player| {
+                let readiness = dash.readiness();
+                let color = if readiness < 1. {
+                    Color::RGB(120, 120, 120)
+                } else {
+                    Color::RGB(120, 200, 255)
+                };
+                ui.fill(
+                    Anchor::TopRight,
+                    (-8, 32 + player.slot as i32 * 10),
+                    (56, 6),
+                    Color::RGBA(0, 0, 0, 180),
+                    |ui| {
+                        ui.fill(
+                            Anchor::Left,
+                            (1, 0),
+                            ((54. * readiness) as u32, 4),
+                            color,
+                            |_| {},
+                        );
+                    },
+                );
+            });
+            ui.label(
+                Anchor::TopRight,
+                (-8, 8),
+                &ctx.coins.to_string(),
+                Color::RGB(255, 220, 80),
+                None,
+            );
+
+            cutscene::draw(world.resource::<Director>().unwrap(), &mut ui);
+            toasts::draw(world.resource::<Toasts>().unwrap(), &mut ui);
+
+            let stock = shop
+                .open
+                .and_then(|(vendor, _)| containers::inventory(world, vendor));
+            if let Some(stock) = stock {
+                shop::draw(shop, stock, ctx.coins, &mut ui);
             }
+            containers::draw(world, &mut ui, &ctx.sprites);
 
-            if let Some(item) = ctx.player_inventory.active_item() {
-                ctx.spritesheet.draw_to_canvas(
-                    canvas,
-                    item.sprite(),
-                    (
-                        canvas.viewport().width() as i32 / 2 - 16,
-                        canvas.viewport().height() as i32 - 40,
-                    ),
-                    0.,
-                    false,
-                    false,
-                )
+            let editor = world.resource::<Editor>().unwrap();
+            if editor.is_active {
+                editor::draw(editor, world, &mut ui, &camera);
             }
 
-            if let Some(item) = ctx.player_inventory.get_right() {
-                ctx.spritesheet.draw_to_canvas(
-                    canvas,
-                    item.sprite(),
-                    (
-                        canvas.viewport().width() as i32 / 2 + 28,
-                        canvas.viewport().height() as i32 - 40,
-                    ),
-                    0.,
-                    false,
-                    false,
-                )
+            let slots = world.resource::<SaveSlots>().unwrap();
+            if slots.is_open {
+                saves::draw(slots, &mut ui);
+            }
+
+            let settings = world.resource::<Settings>().unwrap();
+            if settings.is_open {
+                settings::draw(settings, &mut ui);
+            }
+
+            let lobby = world.resource::<Lobby>().unwrap();
+            if lobby.is_open {
+                lobby::draw(lobby, &mut ui);
+            }
+
+            // the first player's, like update_inventory_screen
+            let player = player_entity(world, 0).filter(|_| ctx.inventory_open);
+            let screen = player.and_then(|player| {
+                Some((
+                    containers::inventory(world, player)?,
+                    world.component::<Equipment>(player)?,
+                    world.component::<PlayerStats>(player)?,
+                ))
+            });
+            if let Some((inventory, equipment, stats)) = screen {
+                draw_inventory_screen(
+                    &mut ui,
+                    inventory,
+                    &ctx.sprites,
+                    ctx.input.mouse_pos,
+                    ctx.inventory_held_slot,
+                );
+                let viewport = ui.viewport();
+                equipment::draw(
+                    &mut ui,
+                    equipment,
+                    stats,
+                    &ctx.sprites,
+                    ctx.input.mouse_pos,
+                    inventory_grid_right(viewport),
+                );
+
+                // not while dragging, the held item is under the cursor
+                let mouse_pos = ctx.input.mouse_pos;
+                let hovered = inventory_slot_at(mouse_pos, viewport)
+                    .and_then(|idx| inventory.get(idx))
+                    .or_else(|| {
+                        equipment::slot_at(mouse_pos, inventory_grid_right(viewport), viewport)
+                            .and_then(|slot| equipment.get(slot))
+                    });
+                if let Some(item) = hovered.filter(|_| ctx.inventory_held_slot.is_none()) {
+                    tooltip::draw(&mut ui, item, mouse_pos);
+                }
             }
 
-            canvas.set_draw_color(Color::RGB(255, 255, 255));
-            ctx.spritesheet.draw_to_canvas(
+            let spritesheet = &ctx.spritesheets[*SPRITESHEET_TEXTURE];
+            let crosshair_size = spritesheet.sprite_size(ctx.sprites.get("ui_crosshair"));
+            spritesheet.draw_at(
                 canvas,
-                ctx.ui_active_item_bg,
+                ctx.sprites.get("ui_crosshair"),
                 (
-                    canvas.viewport().width() as i32 / 2 - 16,
-                    canvas.viewport().height() as i32 - 40,
+                    ctx.input.mouse_pos.0 - crosshair_size.0 as i32 / 2,
+                    ctx.input.mouse_pos.1 - crosshair_size.1 as i32 / 2,
                 ),
-                0.,
-                false,
-                false,
+                DrawOptions::default(),
             );
         })
         .unwrap();
 
-    world.run(
-        |indicator: &mut ProximityIndicator, pos: &Pos, mut ctx: ResMut<Ctx>| {
-            if ctx.player_pos.distance(pos) < indicator.range {
-                let mut draw_pos = *pos;
-                draw_pos.y -= 32.;
-                // FIXME don't recreate the AnimatedSprite
-                // FIXME needs to be drawn to the ui layer
-                draw(&mut ctx, &mut indicator.sprite, &draw_pos, camera_pos);
+    // DEBUG
+    if ctx.debug_draw_nav_colliders || ctx.debug_draw_hitboxes {
+        // in their layer's color, doubled up while they touch something. the ones under the
+        // cursor are white, to tell apart what's stacked up
+        let layers = world.resource::<CollisionLayers>().unwrap();
+        let physics = world.resource::<Physics>().unwrap();
+        let picked = physics.entities_at_point(ctx.cursor_world_pos(), usize::MAX);
+        world.run(|entity: &Entity, cg: &ColliderGroup| {
+            let colliders = [
+                cg.nav.as_ref().filter(|_| ctx.debug_draw_nav_colliders),
+                cg.hitbox.as_ref().filter(|_| ctx.debug_draw_hitboxes),
+            ];
+            for collider in colliders.into_iter().flatten().filter(|c| c.is_enabled) {
+                let mut rect = collider.bounds;
+                let (x, y) = ctx.world_to_screen(&Pos::new(rect.x as f32, rect.y as f32));
+                rect.set_x(x);
+                rect.set_y(y);
+
+                ctx.canvas.set_draw_color(if picked.contains(entity) {
+                    Color::WHITE
+                } else {
+                    layers.color(collider.channels)
+                });
+                ctx.canvas.draw_rect(rect).unwrap();
+                if collider.is_colliding && rect.width() > 2 && rect.height() > 2 {
+                    let inner =
+                        Rect::new(rect.x + 1, rect.y + 1, rect.width() - 2, rect.height() - 2);
+                    ctx.canvas.draw_rect(inner).unwrap();
+                }
             }
+        });
+    }
+}
+
+// redraws the world sprites from the specular texture, in the same order as render
+// emissive sprites again on top of the lit scene, added so they glow. there's no depth
+// test, something emissive behind a wall shows through it
+pub fn render_emissive(world: &World) {
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    draw_emissive(world, &mut ctx.canvas);
+}
+
+// also drawn into the bloom texture
+pub fn draw_emissive(world: &World, canvas: &mut Canvas<Window>) {
+    let ctx = world.resource::<Ctx>().unwrap();
+    let animations = world.resource::<AnimationRepository>().unwrap();
+    let camera = ctx.camera();
+
+    let mut sprites = Vec::new();
+    world.run(|entity: &Entity, pos: &Pos, sprite: &AnimatedSprite| {
+        if !sprite.is_emissive || sprite.alpha == 0 {
+            return;
+        }
+        // an unlit torch doesn't glow
+        if world
+            .component::<Light>(*entity)
+            .is_some_and(|light| light.intensity <= 0.)
+        {
+            return;
+        }
+        let pos = render_pos(world, *entity, pos);
+        let z = pos.pixel().1 + sprite.z_offset.map_or(0, |o| o) as i32;
+        sprites.push((z, *sprite, pos));
+    });
+    sprites.sort_by_key(|(z, _, _)| *z);
+
+    for (_, anim, pos) in &sprites {
+        let sprite = animations.get_frames(anim.anim())[anim.frame as usize];
+        let sheet = &ctx.spritesheets[*animations.get_sheet(anim.anim())];
+        let dst = sprite_rect(sheet, anim, sprite, pos, &camera);
+        let options = DrawOptions {
+            additive: true,
+            ..sprite_options(anim)
+        };
+        sheet.draw_to_canvas(canvas, sprite, dst, options);
+    }
+}
+
+pub fn render_specular(world: &World, canvas: &mut Canvas<Window>) {
+    let ctx = world.resource::<Ctx>().unwrap();
+    let animations = world.resource::<AnimationRepository>().unwrap();
+    let camera = ctx.camera();
+
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    canvas.clear();
+
+    let draw = |canvas: &mut Canvas<Window>, anim: &AnimatedSprite, pos: &Pos| {
+        let sprite = animations.get_frames(anim.anim())[anim.frame as usize];
+        let sheet = &ctx.spritesheets[*animations.get_sheet(anim.anim())];
+        let dst = sprite_rect(sheet, anim, sprite, pos, &camera);
+        sheet.draw_specular_to_canvas(canvas, sprite, dst, sprite_options(anim));
+    };
+
+    world.run(|pos: &Pos, sprite: &AnimatedSprite, _: With<Floor>| {
+        draw(canvas, sprite, pos);
+    });
+
+    world.run(|pos: &Pos, sprite: &AnimatedSprite, _: With<Prop>| {
+        draw(canvas, sprite, pos);
+    });
+
+    let mut sprites = Vec::new();
+    world.run(
+        |entity: &Entity,
+         pos: &Pos,
+         sprite: &AnimatedSprite,
+         _: Without<Floor>,
+         _: Without<Prop>| {
+            let pos = render_pos(world, *entity, pos);
+            let z = pos.pixel().1 + sprite.z_offset.map_or(0, |o| o) as i32;
+            sprites.push((z, *sprite, pos));
         },
     );
+    sprites.sort_by_key(|(z, _, _)| *z);
 
-    // DEBUG
-    if ctx.debug_draw_nav_colliders || ctx.debug_draw_hitboxes {
-        world.run(|cg: &ColliderGroup| {
-            if ctx.debug_draw_nav_colliders {
-                if let Some(collider) = cg.nav.as_ref() {
-                    let mut rect = collider.bounds;
-                    rect.x -= ctx.camera_pos().0;
-                    rect.y -= ctx.camera_pos().1;
-
-                    if collider.is_colliding {
-                        ctx.canvas.set_draw_color(Color::RGB(255, 0, 0));
-                        ctx.canvas.draw_rect(rect).unwrap();
-                    } else {
-                        ctx.canvas.set_draw_color(Color::RGB(0, 255, 0));
-                        ctx.canvas.draw_rect(rect).unwrap();
-                    }
-                }
+    for (_, sprite, pos) in &sprites {
+        draw(canvas, sprite, pos);
+    }
+}
+
+// the active item between its neighbours
+fn draw_active_items(
+    ui: &mut Ui,
+    anchor: Anchor,
+    offset: (i32, i32),
+    inventory: &Inventory,
+    sprites: &SpriteRegistry,
+) {
+    ui.panel(
+        anchor,
+        offset,
+        (134, 40),
+        sprites.get("ui_active_bg"),
+        4,
+        |ui| {
+            if let Some(item) = inventory.get_left() {
+                ui.icon(
+                    Anchor::Left,
+                    (9, 0),
+                    sprites.get(item.sprite()),
+                    DrawOptions::default(),
+                );
             }
 
-            if ctx.debug_draw_hitboxes {
-                if let Some(collider) = cg.hitbox.as_ref() {
-                    let mut rect = collider.bounds;
-                    rect.x += ctx.camera_pos().0;
-                    rect.y += ctx.camera_pos().1;
+            if let Some(item) = inventory.active_item() {
+                ui.icon(
+                    Anchor::Center,
+                    (0, 0),
+                    sprites.get(item.sprite()),
+                    DrawOptions::default(),
+                );
+            }
 
-                    if collider.is_colliding {
-                        ctx.canvas.set_draw_color(Color::RGB(255, 0, 0));
-                        ctx.canvas.draw_rect(rect).unwrap();
-                    } else {
-                        ctx.canvas.set_draw_color(Color::RGB(255, 255, 0));
-                        ctx.canvas.draw_rect(rect).unwrap();
-                    }
-                }
+            if let Some(item) = inventory.get_right() {
+                ui.icon(
+                    Anchor::Right,
+                    (-7, 0),
+                    sprites.get(item.sprite()),
+                    DrawOptions::default(),
+                );
             }
+
+            ui.icon(
+                Anchor::Center,
+                (0, 0),
+                sprites.get("ui_active_bg"),
+                DrawOptions::default(),
+            );
+        },
+    );
+}
+
+fn draw_inventory_screen(
+    ui: &mut Ui,
+    inventory: &Inventory,
+    sprites: &SpriteRegistry,
+    mouse_pos: (i32, i32),
+    held_slot: Option<usize>,
+) {
+    let viewport = ui.viewport();
+    let spritesheet = ui.spritesheet();
+    let hovered = inventory_slot_at(mouse_pos, viewport);
+
+    for idx in 0..INVENTORY_SIZE {
+        let slot = inventory_slot_rect(idx, viewport);
+
+        ui.canvas().set_draw_color(if held_slot == Some(idx) {
+            Color::RGB(255, 255, 0)
+        } else if hovered == Some(idx) {
+            Color::RGB(200, 200, 200)
+        } else {
+            Color::RGB(100, 100, 100)
         });
+        ui.canvas().draw_rect(slot).unwrap();
+
+        let sprite_pos = (slot.x() + 8, slot.y() + 8);
+
+        if let Some(item) = inventory.get(idx) {
+            // the held item stays behind as a faded placeholder
+            let tint = if held_slot == Some(idx) {
+                Color::RGBA(255, 255, 255, 80)
+            } else {
+                Color::WHITE
+            };
+            spritesheet.draw_at(
+                ui.canvas(),
+                sprites.get(item.sprite()),
+                sprite_pos,
+                DrawOptions {
+                    tint,
+                    ..Default::default()
+                },
+            );
+        }
+
+        if idx == inventory.active_idx() {
+            spritesheet.draw_at(
+                ui.canvas(),
+                sprites.get("ui_active_bg"),
+                sprite_pos,
+                DrawOptions::default(),
+            );
+        }
+    }
+
+    // translucent preview of the held item under the cursor
+    if let Some(item) = held_slot.and_then(|idx| inventory.get(idx)) {
+        spritesheet.draw_at(
+            ui.canvas(),
+            sprites.get(item.sprite()),
+            (mouse_pos.0 - 16, mouse_pos.1 - 16),
+            DrawOptions {
+                tint: Color::RGBA(255, 255, 255, 160),
+                ..Default::default()
+            },
+        );
+    }
+
+    // the hovered one gets a tooltip instead
+    if let Some(item) = inventory.active_item().filter(|_| hovered.is_none()) {
+        let bottom = inventory_slot_rect(INVENTORY_SIZE - 1, viewport).bottom();
+        ui.label(
+            Anchor::TopLeft,
+            (inventory_slot_rect(0, viewport).x(), bottom + 8),
+            &item.name().replace('_', " "),
+            Color::RGB(255, 255, 255),
+            None,
+        );
     }
 }
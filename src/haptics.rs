@@ -0,0 +1,103 @@
+// controller rumble. systems ask for it with rumble(), the strongest request of a
+// tick wins and it's only sent while the player is actually playing on a controller
+
+use ecs::{Resource, World};
+use sdl2::{controller::GameController, event::Event, GameControllerSubsystem};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum InputSource {
+    KeyboardMouse,
+    Controller,
+}
+
+#[derive(Resource)]
+pub struct Haptics {
+    // None when running headless
+    subsystem: Option<GameControllerSubsystem>,
+    controller: Option<GameController>,
+    // whichever was touched last
+    pub source: InputSource,
+    pub enabled: bool,
+    // intensity 0 to 1 and duration in ms
+    pending: Option<(f32, u32)>,
+}
+
+impl Haptics {
+    pub fn new(subsystem: Option<GameControllerSubsystem>) -> Self {
+        Haptics {
+            subsystem,
+            controller: None,
+            source: InputSource::KeyboardMouse,
+            enabled: true,
+            pending: None,
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            // only the first controller rumbles
+            Event::ControllerDeviceAdded { which, .. } if self.controller.is_none() => {
+                let Some(subsystem) = &self.subsystem else {
+                    return;
+                };
+                match subsystem.open(*which) {
+                    Ok(controller) => self.controller = Some(controller),
+                    Err(e) => println!("failed to open controller {}: {}", which, e),
+                }
+            }
+            Event::ControllerDeviceRemoved { which, .. }
+                if self
+                    .controller
+                    .as_ref()
+                    .is_some_and(|c| c.instance_id() == *which) =>
+            {
+                self.controller = None;
+                self.source = InputSource::KeyboardMouse;
+            }
+            Event::ControllerButtonDown { .. } => self.source = InputSource::Controller,
+            // small stick drift shouldn't steal it back from the keyboard
+            Event::ControllerAxisMotion { value, .. } if value.unsigned_abs() > 8000 => {
+                self.source = InputSource::Controller
+            }
+            Event::KeyDown { .. } | Event::MouseButtonDown { .. } | Event::MouseMotion { .. } => {
+                self.source = InputSource::KeyboardMouse
+            }
+            _ => {}
+        }
+    }
+
+    // also what the second local player plays on
+    pub fn controller(&self) -> Option<&GameController> {
+        self.controller.as_ref()
+    }
+
+    pub fn request(&mut self, intensity: f32, duration_ms: u32) {
+        let intensity = intensity.clamp(0., 1.);
+        if self.pending.is_none_or(|(pending, _)| intensity > pending) {
+            self.pending = Some((intensity, duration_ms));
+        }
+    }
+}
+
+pub fn rumble(world: &World, intensity: f32, duration_ms: u32) {
+    world
+        .resource_mut::<Haptics>()
+        .unwrap()
+        .request(intensity, duration_ms);
+}
+
+pub fn update(world: &World) {
+    let haptics = world.resource_mut::<Haptics>().unwrap();
+    let Some((intensity, duration_ms)) = haptics.pending.take() else {
+        return;
+    };
+    if !haptics.enabled || haptics.source != InputSource::Controller {
+        return;
+    }
+    let Some(controller) = haptics.controller.as_mut() else {
+        return;
+    };
+    let strength = (intensity * u16::MAX as f32) as u16;
+    // not every controller has motors, that's fine
+    let _ = controller.set_rumble(strength, strength, duration_ms);
+}
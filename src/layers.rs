@@ -0,0 +1,127 @@
+// collision layers from LAYERS_PATH. every layer is one channel bit, the order they're
+// declared in, and says which layers it collides with:
+//
+//   layer wall 120,120,255
+//   projectile hits nav wall hitbox
+//
+// a collider only gets pushed around, flagged and told about the layers its own layer hits,
+// the other side has to hit it back to notice. colliders that are already spawned keep the
+// bits they were built with
+//
+// the debug overlay draws colliders in their layer's color
+
+use ecs::Resource;
+use sdl2::pixels::Color;
+
+pub const LAYERS_PATH: &str = "data/layers.txt";
+
+struct Layer {
+    name: String,
+    color: Color,
+    collides_with: usize,
+}
+
+#[derive(Resource)]
+pub struct CollisionLayers {
+    layers: Vec<Layer>,
+}
+
+impl CollisionLayers {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+        let mut layers: Vec<Layer> = Vec::new();
+        // hits lines can come before the layers they name
+        let mut hits = Vec::new();
+        for (idx, line) in data.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |what: &str| format!("{}:{}: {}", path, idx + 1, what);
+            let words: Vec<&str> = line.split_whitespace().collect();
+
+            match words[..] {
+                ["layer", name, color] => {
+                    if layers.iter().any(|layer| layer.name == name) {
+                        return Err(error(&format!("layer {} defined twice", name)));
+                    }
+                    if layers.len() == usize::BITS as usize {
+                        return Err(error("too many layers"));
+                    }
+                    let Some(color) = parse_color(color) else {
+                        return Err(error(&format!("bad color '{}'", color)));
+                    };
+                    layers.push(Layer {
+                        name: name.to_string(),
+                        color,
+                        collides_with: 0,
+                    });
+                }
+                [name, "hits", ref others @ ..] if !others.is_empty() => {
+                    hits.push((idx, name, others.to_vec()));
+                }
+                _ => {
+                    return Err(error(&format!(
+                        "expected a layer or hits line, got '{}'",
+                        line
+                    )))
+                }
+            }
+        }
+
+        for (idx, name, others) in hits {
+            let error = |what: &str| format!("{}:{}: {}", path, idx + 1, what);
+            let find = |name: &str| {
+                layers
+                    .iter()
+                    .position(|layer| layer.name == name)
+                    .ok_or_else(|| error(&format!("no layer {}", name)))
+            };
+            let layer = find(name)?;
+            let mut collides_with = 0;
+            for other in others {
+                collides_with |= 1 << find(other)?;
+            }
+            layers[layer].collides_with |= collides_with;
+        }
+
+        Ok(CollisionLayers { layers })
+    }
+
+    fn find(&self, name: &str) -> usize {
+        self.layers
+            .iter()
+            .position(|layer| layer.name == name)
+            .unwrap_or_else(|| panic!("no collision layer {}", name))
+    }
+
+    // the channel bit
+    pub fn bit(&self, name: &str) -> usize {
+        1 << self.find(name)
+    }
+
+    // the channel bits of every layer it hits
+    pub fn collides_with(&self, name: &str) -> usize {
+        self.layers[self.find(name)].collides_with
+    }
+
+    // of the lowest layer in channels, grey for none
+    pub fn color(&self, channels: usize) -> Color {
+        match channels.trailing_zeros() as usize {
+            idx if idx < self.layers.len() => self.layers[idx].color,
+            _ => Color::RGB(160, 160, 160),
+        }
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let channels: Vec<u8> = value
+        .split(',')
+        .map(|c| c.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match channels[..] {
+        [r, g, b] => Some(Color::RGB(r, g, b)),
+        _ => None,
+    }
+}
@@ -0,0 +1,239 @@
+// the pre-game screen: play alone, host, or join a host by address. it comes back when a
+// connection fails or drops and says why, gameplay stays paused behind it
+//
+// it reads keys straight from the events so the address can be typed
+
+use ecs::{Resource, World};
+use sdl2::{event::Event, keyboard::Keycode, pixels::Color};
+
+use crate::{
+    locale::{tr, tr_args},
+    net::{self, Net, Notice},
+    toasts,
+    ui::{Anchor, Ui},
+};
+
+const ROW_HEIGHT: i32 = 24;
+const MAX_ADDRESS_LEN: usize = 64;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Row {
+    Play,
+    Host,
+    Join,
+    Quit,
+}
+
+#[derive(Resource)]
+pub struct Lobby {
+    pub is_open: bool,
+    selected: usize,
+    address: String,
+    // what happened last, shown under the rows
+    status: Option<(String, Color)>,
+    // the host's level replaced ours, there's nothing left to play alone
+    is_level_gone: bool,
+    pub quit_requested: bool,
+}
+
+impl Lobby {
+    pub fn new(is_open: bool) -> Self {
+        Lobby {
+            is_open,
+            selected: 0,
+            address: "127.0.0.1".to_string(),
+            status: None,
+            is_level_gone: false,
+            quit_requested: false,
+        }
+    }
+
+    fn rows(&self) -> &'static [Row] {
+        if self.is_level_gone {
+            &[Row::Join, Row::Quit]
+        } else {
+            &[Row::Play, Row::Host, Row::Join, Row::Quit]
+        }
+    }
+
+    fn error(&mut self, text: String) {
+        self.status = Some((text, Color::RGB(255, 80, 80)));
+    }
+
+    // back with the reason
+    fn reopen(&mut self, text: String) {
+        self.is_open = true;
+        self.selected = 0;
+        self.error(text);
+    }
+}
+
+// returns true if the lobby consumed the event
+pub fn handle_event(world: &World, event: &Event) -> bool {
+    let lobby = world.resource_mut::<Lobby>().unwrap();
+    if !lobby.is_open {
+        return false;
+    }
+    let rows = lobby.rows();
+    lobby.selected = lobby.selected.min(rows.len() - 1);
+
+    match event {
+        Event::KeyDown {
+            keycode: Some(Keycode::Up),
+            ..
+        } => lobby.selected = (lobby.selected + rows.len() - 1) % rows.len(),
+        Event::KeyDown {
+            keycode: Some(Keycode::Down),
+            ..
+        } => lobby.selected = (lobby.selected + 1) % rows.len(),
+        Event::TextInput { text, .. } if rows[lobby.selected] == Row::Join => {
+            // addresses are ascii, so a char is a byte and the cap can't split one
+            let room = MAX_ADDRESS_LEN.saturating_sub(lobby.address.len());
+            let typed = text.chars().filter(|c| c.is_ascii_graphic()).take(room);
+            lobby.address.extend(typed);
+        }
+        Event::KeyDown {
+            keycode: Some(Keycode::Backspace),
+            ..
+        } if rows[lobby.selected] == Row::Join => {
+            lobby.address.pop();
+        }
+        Event::KeyDown {
+            keycode: Some(Keycode::Return),
+            ..
+        } => activate(world, rows[lobby.selected]),
+        Event::KeyDown { .. } | Event::KeyUp { .. } | Event::TextInput { .. } => {}
+        _ => return false,
+    }
+    true
+}
+
+fn activate(world: &World, row: Row) {
+    let lobby = world.resource_mut::<Lobby>().unwrap();
+    let net = world.resource_mut::<Net>().unwrap();
+    match row {
+        Row::Play => lobby.is_open = false,
+        Row::Host => {
+            net.leave();
+            match Net::host(net::DEFAULT_PORT) {
+                Ok(host) => {
+                    *net = host;
+                    lobby.is_open = false;
+                    lobby.status = None;
+                    let port = net::DEFAULT_PORT.to_string();
+                    toasts::push(world, &tr_args("lobby-hosting", &[("port", &port)]));
+                }
+                Err(e) => lobby.error(tr_args("lobby-error", &[("error", &e)])),
+            }
+        }
+        Row::Join => {
+            net.leave();
+            match Net::join(&lobby.address) {
+                Ok(client) => {
+                    *net = client;
+                    let status = tr_args("lobby-connecting", &[("address", &lobby.address)]);
+                    lobby.status = Some((status, Color::RGB(200, 200, 200)));
+                }
+                Err(e) => lobby.error(tr_args("lobby-error", &[("error", &e)])),
+            }
+        }
+        Row::Quit => lobby.quit_requested = true,
+    }
+}
+
+// after the net has received, turns what happened into toasts and lobby messages
+pub fn update(world: &World) {
+    let lobby = world.resource_mut::<Lobby>().unwrap();
+    let net = world.resource_mut::<Net>().unwrap();
+    let is_client = net.is_client();
+    for notice in net.take_notices() {
+        let message = match notice {
+            Notice::Joined if is_client => {
+                lobby.is_open = false;
+                lobby.is_level_gone = true;
+                lobby.status = None;
+                toasts::push(world, tr("lobby-connected"));
+                continue;
+            }
+            Notice::Joined => {
+                toasts::push(world, tr("lobby-partner-joined"));
+                continue;
+            }
+            Notice::Left if !is_client => {
+                toasts::push(world, tr("lobby-partner-left"));
+                continue;
+            }
+            Notice::Left => tr("lobby-host-left").to_string(),
+            Notice::Lost => tr("lobby-connection-lost").to_string(),
+            Notice::WrongVersion(version) => {
+                tr_args("lobby-wrong-version", &[("host", &version.to_string())])
+            }
+            Notice::WrongContent => tr("lobby-wrong-content").to_string(),
+            Notice::Full => tr("lobby-full").to_string(),
+            Notice::NoAnswer => tr("lobby-no-answer").to_string(),
+        };
+        lobby.reopen(message);
+        // the host keeps waiting for a partner, the client is done
+        if is_client {
+            net.leave();
+        }
+    }
+}
+
+pub fn draw(lobby: &Lobby, ui: &mut Ui) {
+    let rows = lobby.rows();
+    let height = ROW_HEIGHT * (rows.len() as i32 + 2) + 24;
+
+    ui.fill(
+        Anchor::Center,
+        (0, 0),
+        (360, height as u32),
+        Color::RGBA(0, 0, 0, 220),
+        |ui| {
+            ui.label(
+                Anchor::TopLeft,
+                (12, 8),
+                tr("lobby-title"),
+                Color::WHITE,
+                None,
+            );
+
+            for (idx, row) in rows.iter().enumerate() {
+                let y = 8 + ROW_HEIGHT * (idx as i32 + 1);
+                let (color, marker) = if idx == lobby.selected {
+                    (Color::RGB(255, 255, 0), ">")
+                } else {
+                    (Color::RGB(200, 200, 200), " ")
+                };
+                let name = match row {
+                    Row::Play => tr("lobby-play"),
+                    Row::Host => tr("lobby-host"),
+                    Row::Join => tr("lobby-join"),
+                    Row::Quit => tr("settings-quit"),
+                };
+                ui.label(
+                    Anchor::TopLeft,
+                    (12, y),
+                    &format!("{} {}", marker, name),
+                    color,
+                    None,
+                );
+                if *row == Row::Join {
+                    let cursor = if idx == lobby.selected { "_" } else { "" };
+                    ui.label(
+                        Anchor::TopRight,
+                        (-12, y),
+                        &format!("{}{}", lobby.address, cursor),
+                        color,
+                        None,
+                    );
+                }
+            }
+
+            if let Some((status, color)) = &lobby.status {
+                let y = 8 + ROW_HEIGHT * (rows.len() as i32 + 1);
+                ui.label(Anchor::TopLeft, (12, y), status, *color, None);
+            }
+        },
+    );
+}
@@ -0,0 +1,85 @@
+// string tables per language, tr("key") looks up the current language and falls
+// back to english, then to the key itself
+//
+// the files are a small subset of fluent: "key = value" lines, # comments and
+// { $name } placeholders filled in by tr_args
+
+use std::{collections::HashMap, sync::RwLock};
+
+use crate::assets;
+
+const LOCALE_DIR: &str = "locale";
+// the first one is the fallback, every key should be in it
+pub const LANGUAGES: [&str; 2] = ["en", "de"];
+
+struct Tables {
+    current: &'static str,
+    // leaked on load, each language is only ever loaded once
+    loaded: HashMap<&'static str, HashMap<&'static str, &'static str>>,
+}
+
+static TABLES: RwLock<Option<Tables>> = RwLock::new(None);
+
+fn load(language: &str) -> HashMap<&'static str, &'static str> {
+    let path = assets::path(&format!("{}/{}.ftl", LOCALE_DIR, language));
+    let Ok(data) = std::fs::read_to_string(&path) else {
+        println!("no string table at {}", path);
+        return HashMap::new();
+    };
+
+    let mut table = HashMap::new();
+    for line in data
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        let Some((key, value)) = line.split_once('=') else {
+            println!("{}: ignoring '{}'", path, line);
+            continue;
+        };
+        let key: &'static str = Box::leak(key.trim().to_string().into_boxed_str());
+        let value: &'static str = Box::leak(value.trim().to_string().into_boxed_str());
+        table.insert(key, value);
+    }
+    table
+}
+
+// unknown languages fall back to english
+pub fn set_language(language: &str) {
+    let language = LANGUAGES
+        .into_iter()
+        .find(|l| *l == language)
+        .unwrap_or(LANGUAGES[0]);
+
+    let mut tables = TABLES.write().unwrap();
+    let tables = tables.get_or_insert_with(|| Tables {
+        current: LANGUAGES[0],
+        loaded: HashMap::new(),
+    });
+    for needed in [LANGUAGES[0], language] {
+        if !tables.loaded.contains_key(needed) {
+            tables.loaded.insert(needed, load(needed));
+        }
+    }
+    tables.current = language;
+}
+
+pub fn tr(key: &'static str) -> &'static str {
+    let tables = TABLES.read().unwrap();
+    let Some(tables) = tables.as_ref() else {
+        return key;
+    };
+    [tables.current, LANGUAGES[0]]
+        .iter()
+        .find_map(|language| tables.loaded.get(language)?.get(key).copied())
+        .unwrap_or(key)
+}
+
+// replaces { $name } placeholders
+pub fn tr_args(key: &'static str, args: &[(&str, &str)]) -> String {
+    let mut text = tr(key).to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{ ${} }}", name), value);
+    }
+    text
+}
@@ -1,36 +1,166 @@
 extern crate sdl2;
 
+mod ambient;
+mod anim_graph;
+mod aseprite;
+mod assets;
+mod audio;
+mod broadphase;
+mod capture;
+mod checkpoints;
+mod commands;
 mod components;
+mod console;
+mod containers;
+mod cutscene;
+mod editor;
+mod effects;
+mod equipment;
+mod events;
+mod floating_text;
 mod game;
+mod haptics;
+mod layers;
+mod lobby;
+mod locale;
 mod math;
+mod music;
+mod net;
+mod particles;
+mod physics;
+mod prefabs;
+mod procgen;
+mod profiler;
+mod replay;
+mod rng;
+mod rooms;
+mod saves;
+mod scheduler;
+mod scripts;
+mod settings;
+mod shop;
+mod signals;
+mod solver;
+mod stats;
+mod streaming;
+mod tiled;
+mod toasts;
+mod tooltip;
+mod transition;
+mod tuning;
+mod tween;
+mod ui;
+mod walls;
 
 use std::{
-    collections::{BinaryHeap, HashMap},
+    collections::{hash_map::DefaultHasher, BinaryHeap, HashMap},
+    hash::{Hash, Hasher},
     mem::MaybeUninit,
     ops::Deref,
-    sync::RwLock,
     time::{Duration, Instant},
 };
 
-use components::{ColliderGroup, Inventory, LightOccluder, LightOccluderGroup, Wall};
+use ambient::AmbientLight;
+use audio::Audio;
+use capture::Capture;
+use checkpoints::Checkpoints;
+use commands::CommandBuffer;
+use components::{
+    ColliderGroup, Enemy, Health, Inventory, LightOccluder, LightOccluderGroup, Occluder,
+    OccluderShape, Player, PrevPos, Wall,
+};
+use console::Console;
+use containers::Containers;
+use cutscene::Director;
 use ecs::{Entity, Resource, With, World};
-use math::Vec3;
+use editor::Editor;
+use effects::Effects;
+use haptics::Haptics;
+use layers::CollisionLayers;
+use lobby::Lobby;
+use math::{Transform, Vec2, Vec3};
+use music::Music;
+use net::Net;
+use particles::ParticlePresets;
+use physics::Physics;
+use prefabs::PrefabRegistry;
+use profiler::Profiler;
+use replay::Replay;
+use rng::GameRng;
+use saves::SaveSlots;
+use scheduler::{Scheduler, Stage};
+use scripts::Scripts;
 use sdl2::{
-    event::Event,
+    controller::{Axis, Button, GameController},
+    event::{Event, WindowEvent},
     gfx::primitives::DrawRenderer,
     image::{InitFlag, LoadTexture},
-    keyboard::{Keycode, Scancode},
+    keyboard::{Keycode, Mod, Scancode},
     pixels::Color,
-    rect::Rect,
-    render::{BlendMode, Canvas, Texture, TextureCreator},
-    video::{Window, WindowContext},
+    rect::{Point, Rect},
+    render::{BlendMode, Canvas, Texture},
+    ttf::Font,
+    video::Window,
+    EventPump,
 };
+use settings::Settings;
+use shop::Shop;
+use signals::SignalBroker;
+use stats::Stats;
+use streaming::Streamer;
+use toasts::Toasts;
+use transition::{ScreenTransition, Style};
+use tuning::Tuning;
+use ui::{Anchor, TextCache, Ui};
+use walls::WallColliders;
 
 use crate::components::{Light, Pos};
 
-#[derive(Clone, Copy)]
+pub const TICKS_PER_SECOND: u32 = 60;
+const MAX_FRAME_TIME: Duration = Duration::from_millis(250);
+const SPECULAR_INTENSITY: f32 = 0.6;
+// fractions of the light radius
+const SPECULAR_RADIUS: f32 = 0.5;
+const SPECULAR_OFFSET: f32 = 0.25;
+// width and shade of blob shadows, each step paints over the wider fainter one before it
+// so the edge fades out
+const BLOB_SHADOW_STEPS: [(f32, u8); 3] = [(1.6, 200), (1.3, 150), (1., 110)];
+const DEPTH_BAND_SIZE: i32 = 4;
+// bloom is built at a fraction of the render size, the blur is cheap there and the
+// upscale softens it further
+const BLOOM_DOWNSCALE: u32 = 4;
+// blur passes for each bloom quality, 0 is off
+const BLOOM_PASSES: [u32; 3] = [0, 1, 3];
+// taps either side of each pixel in a blur pass
+const BLOOM_TAPS: i32 = 2;
+// brightness of the glow when it's added back
+const BLOOM_STRENGTH: u8 = 200;
+// fraction of a light's radius that glows
+const BLOOM_LIGHT_CORE: f32 = 0.25;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TextureId(usize);
 
+// world pixels per spritesheet pixel
+pub const SPRITE_SCALE: u32 = 2;
+pub const SPRITESHEET_TEXTURE: TextureId = TextureId(0);
+
+struct SheetDef {
+    texture: &'static str,
+    specular: Option<&'static str>,
+    tile_size: u16,
+    // sprite names, see SpriteRegistry
+    manifest: &'static str,
+}
+
+// indexed by TextureId
+const SPRITESHEETS: [SheetDef; 1] = [SheetDef {
+    texture: "textures/spritesheet.png",
+    specular: Some("textures/specular.png"),
+    tile_size: 16,
+    manifest: "textures/spritesheet.txt",
+}];
+
 impl Deref for TextureId {
     type Target = usize;
 
@@ -42,100 +172,419 @@ impl Deref for TextureId {
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct AnimationId(usize);
 
+struct Animation {
+    // every frame of an animation comes from the same sheet
+    sheet: TextureId,
+    frames: Vec<Sprite>,
+    // ticks each frame is shown, None leaves it to the sprite's ticks_per_frame
+    durations: Option<Vec<u32>>,
+    // frame and event name, sent as an AnimationEvent when the frame comes up
+    events: Vec<(u32, &'static str)>,
+}
+
+#[derive(Resource)]
 struct AnimationRepository {
-    animations: Vec<Vec<Sprite>>,
+    animations: Vec<Animation>,
     lookup: HashMap<&'static str, AnimationId>,
 }
 
 impl AnimationRepository {
     pub fn new() -> Self {
         AnimationRepository {
-            animations: vec![vec![]],
+            animations: vec![Animation {
+                sheet: SPRITESHEET_TEXTURE,
+                frames: vec![],
+                durations: None,
+                events: vec![],
+            }],
             lookup: HashMap::new(),
         }
     }
 
-    pub fn push(&mut self, name: &'static str, frames: &[Sprite]) {
+    pub fn push(&mut self, name: &'static str, sheet: TextureId, frames: &[Sprite]) {
+        self.insert(name, sheet, frames, None);
+    }
+
+    pub fn push_timed(
+        &mut self,
+        name: &'static str,
+        sheet: TextureId,
+        frames: &[Sprite],
+        durations: &[u32],
+    ) {
+        assert_eq!(frames.len(), durations.len(), "animation {}", name);
+        self.insert(name, sheet, frames, Some(Vec::from(durations)));
+    }
+
+    // a later animation with the same name replaces the earlier one
+    fn insert(
+        &mut self,
+        name: &'static str,
+        sheet: TextureId,
+        frames: &[Sprite],
+        durations: Option<Vec<u32>>,
+    ) {
         let id = AnimationId(self.animations.len());
-        self.animations.push(Vec::from(frames));
+        self.animations.push(Animation {
+            sheet,
+            frames: Vec::from(frames),
+            durations,
+            events: Vec::new(),
+        });
         self.lookup.insert(name, id);
     }
 
+    pub fn add_event(&mut self, name: &'static str, frame: u32, event: &'static str) {
+        let Some(id) = self.get(name) else {
+            panic!("event {} on unknown animation {}", event, name);
+        };
+        let anim = &mut self.animations[id.0];
+        assert!(
+            (frame as usize) < anim.frames.len(),
+            "event {} past the end of {}",
+            event,
+            name
+        );
+        anim.events.push((frame, event));
+    }
+
+    // for ids that come from outside, like the network
+    pub fn has_frame(&self, anim_id: AnimationId, frame: u32) -> bool {
+        self.animations
+            .get(anim_id.0)
+            .is_some_and(|anim| (frame as usize) < anim.frames.len())
+    }
+
+    // (name, frame count) of every named animation, in id order
+    pub fn summary(&self) -> Vec<(&'static str, usize)> {
+        let mut named: Vec<_> = self.lookup.iter().map(|(name, id)| (id.0, *name)).collect();
+        named.sort();
+        named
+            .into_iter()
+            .map(|(id, name)| (name, self.animations[id].frames.len()))
+            .collect()
+    }
+
     pub fn get_frames(&self, anim_id: AnimationId) -> &[Sprite] {
         // TODO unwrap_unchecked is probably safe unless AnimationId's are constructed elsewhere
-        self.animations.get(anim_id.0).unwrap()
+        &self.animations.get(anim_id.0).unwrap().frames
+    }
+
+    pub fn get_sheet(&self, anim_id: AnimationId) -> TextureId {
+        self.animations.get(anim_id.0).unwrap().sheet
+    }
+
+    pub fn frame_ticks(&self, anim_id: AnimationId, frame: u32) -> Option<u32> {
+        let durations = self.animations.get(anim_id.0).unwrap().durations.as_ref()?;
+        durations.get(frame as usize).copied()
+    }
+
+    pub fn frame_events(
+        &self,
+        anim_id: AnimationId,
+        frame: u32,
+    ) -> impl Iterator<Item = &'static str> + '_ {
+        self.animations[anim_id.0]
+            .events
+            .iter()
+            .filter(move |(f, _)| *f == frame)
+            .map(|(_, event)| *event)
     }
 
-    pub fn get(&self, name: &'static str) -> Option<AnimationId> {
+    pub fn get(&self, name: &str) -> Option<AnimationId> {
         self.lookup.get(name).copied()
     }
 }
 
+pub fn animation(world: &World, name: &'static str) -> AnimationId {
+    world
+        .resource::<AnimationRepository>()
+        .unwrap()
+        .get(name)
+        .unwrap()
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Sprite(u16, u16, u16, u16);
 
+// named sprite rects, so the sheet layout lives in its manifest instead of the code
+struct SpriteRegistry {
+    sprites: HashMap<String, (TextureId, Sprite)>,
+}
+
+impl SpriteRegistry {
+    // names are shared between all sheets. bad and duplicate lines are reported and skipped,
+    // a manifest that can't be read leaves its sheet without sprites
+    pub fn load(sheets: &[SheetDef]) -> Self {
+        let mut sprites = HashMap::new();
+        for (idx, sheet) in sheets.iter().enumerate() {
+            let data = match std::fs::read_to_string(assets::path(sheet.manifest)) {
+                Ok(data) => data,
+                Err(e) => {
+                    println!("can't read {}: {}", sheet.manifest, e);
+                    continue;
+                }
+            };
+            for line in data
+                .lines()
+                .map(|line| line.split('#').next().unwrap().trim())
+                .filter(|line| !line.is_empty())
+            {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                let rect: Option<Vec<u16>> = parts[1..].iter().map(|n| n.parse().ok()).collect();
+                let sprite = match rect.as_deref() {
+                    Some(&[x, y, w, h]) => Sprite(x, y, w, h),
+                    _ => {
+                        println!("{}: ignoring '{}'", sheet.manifest, line);
+                        continue;
+                    }
+                };
+                let name = parts[0].to_string();
+                if sprites.contains_key(&name) {
+                    println!("{}: ignoring duplicate '{}'", sheet.manifest, line);
+                    continue;
+                }
+                sprites.insert(name, (TextureId(idx), sprite));
+            }
+        }
+
+        SpriteRegistry { sprites }
+    }
+
+    pub fn get(&self, name: &str) -> Sprite {
+        self.lookup(name).1
+    }
+
+    pub fn sheet(&self, name: &str) -> TextureId {
+        self.lookup(name).0
+    }
+
+    fn lookup(&self, name: &str) -> (TextureId, Sprite) {
+        match self.sprites.get(name) {
+            Some(sprite) => *sprite,
+            None => panic!("unknown sprite {}", name),
+        }
+    }
+}
+
 impl From<(u16, u16, u16, u16)> for Sprite {
     fn from(value: (u16, u16, u16, u16)) -> Self {
         Sprite(value.0, value.1, value.2, value.3)
     }
 }
 
+#[derive(Clone, Copy)]
+pub struct DrawOptions {
+    pub angle: f64,
+    pub origin: Option<(i32, i32)>,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    pub tint: Color,
+    // top and bottom of the source to draw, as fractions of its height
+    pub rows: Option<(f32, f32)>,
+    pub additive: bool,
+}
+
+impl Default for DrawOptions {
+    fn default() -> Self {
+        DrawOptions {
+            angle: 0.,
+            origin: None,
+            flip_horizontal: false,
+            flip_vertical: false,
+            tint: Color::WHITE,
+            rows: None,
+            additive: false,
+        }
+    }
+}
+
+// the textures come in through streaming, a sheet draws nothing until they have
 struct Spritesheet {
-    texture: MaybeUninit<Texture>,
-    specular: MaybeUninit<Texture>,
+    id: TextureId,
+    path: String,
+    texture: Option<Texture>,
+    // sheets without one draw nothing into the specular pass
+    specular_path: Option<String>,
+    specular: Option<Texture>,
+    is_requested: bool,
     tile_size: u16,
 }
 
 impl Spritesheet {
-    pub fn new_from_file(
-        texture_creator: &TextureCreator<WindowContext>,
-        spritesheet_path: &'static str,
-        specular_path: &'static str,
-        tile_size: u16,
-    ) -> Self {
-        if let Ok(spritesheet) = texture_creator.load_texture(spritesheet_path) {
-            if let Ok(specular) = texture_creator.load_texture(specular_path) {
-                Spritesheet {
-                    texture: MaybeUninit::new(spritesheet),
-                    specular: MaybeUninit::new(specular),
-                    tile_size,
-                }
-            } else {
-                panic!("Failed to load texture {}", specular_path)
-            }
+    pub fn new(id: TextureId, path: String, specular_path: Option<String>, tile_size: u16) -> Self {
+        Spritesheet {
+            id,
+            path,
+            texture: None,
+            specular_path,
+            specular: None,
+            is_requested: false,
+            tile_size,
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.texture.is_some() && (self.specular_path.is_none() || self.specular.is_some())
+    }
+
+    fn wants(&self, path: &str) -> bool {
+        (self.path == path && self.texture.is_none())
+            || (self.specular_path.as_deref() == Some(path) && self.specular.is_none())
+    }
+
+    fn receive(&mut self, path: &str, texture: Texture) {
+        if self.path == path && self.texture.is_none() {
+            self.texture = Some(texture);
         } else {
-            panic!("Failed to load texture {}", spritesheet_path)
+            self.specular = Some(texture);
         }
     }
 
-    pub fn draw_to_canvas(
+    pub fn sprite_size(&self, src: Sprite) -> (u32, u32) {
+        (
+            (self.tile_size * src.2) as u32 * SPRITE_SCALE,
+            (self.tile_size * src.3) as u32 * SPRITE_SCALE,
+        )
+    }
+
+    // draws at the unscaled sprite size
+    pub fn draw_at(
         &self,
         canvas: &mut Canvas<Window>,
         src: Sprite,
         dst: (i32, i32),
-        angle: f64,
-        flip_horizontal: bool,
-        flip_vertical: bool,
+        options: DrawOptions,
+    ) {
+        let (width, height) = self.sprite_size(src);
+        self.draw_to_canvas(canvas, src, Rect::new(dst.0, dst.1, width, height), options);
+    }
+
+    pub fn draw_to_canvas(
+        &self,
+        canvas: &mut Canvas<Window>,
+        src: Sprite,
+        dst: Rect,
+        options: DrawOptions,
+    ) {
+        if let Some(texture) = &self.texture {
+            self.copy_sprite(texture, canvas, src, dst, options);
+        }
+    }
+
+    // same sprite from the specular texture
+    pub fn draw_specular_to_canvas(
+        &self,
+        canvas: &mut Canvas<Window>,
+        src: Sprite,
+        dst: Rect,
+        options: DrawOptions,
+    ) {
+        if let Some(texture) = &self.specular {
+            self.copy_sprite(texture, canvas, src, dst, options);
+        }
+    }
+
+    // stretches the middle of the sprite and keeps the corners at their scaled size
+    pub fn draw_nine_slice(
+        &self,
+        canvas: &mut Canvas<Window>,
+        src: Sprite,
+        border: u32,
+        dst: Rect,
+        tint: Color,
+    ) {
+        let Some(texture) = &self.texture else {
+            return;
+        };
+        unsafe {
+            sdl2::sys::SDL_SetTextureColorMod(texture.raw(), tint.r, tint.g, tint.b);
+            sdl2::sys::SDL_SetTextureAlphaMod(texture.raw(), tint.a);
+            // the emissive pass may have left it additive
+            sdl2::sys::SDL_SetTextureBlendMode(
+                texture.raw(),
+                sdl2::sys::SDL_BlendMode::SDL_BLENDMODE_BLEND,
+            );
+        }
+
+        let src = Rect::new(
+            (src.0 * self.tile_size) as i32,
+            (src.1 * self.tile_size) as i32,
+            (self.tile_size * src.2) as u32,
+            (self.tile_size * src.3) as u32,
+        );
+        let dst_border = border * SPRITE_SCALE;
+
+        // (offset, size) of the three slices along one axis
+        let slices = |start: i32, len: u32, border: u32| {
+            let middle = len.saturating_sub(border * 2);
+            [
+                (start, border),
+                (start + border as i32, middle),
+                (start + (border + middle) as i32, border),
+            ]
+        };
+
+        for (src_y, dst_y) in slices(src.y(), src.height(), border)
+            .into_iter()
+            .zip(slices(dst.y(), dst.height(), dst_border))
+        {
+            for (src_x, dst_x) in slices(src.x(), src.width(), border).into_iter().zip(slices(
+                dst.x(),
+                dst.width(),
+                dst_border,
+            )) {
+                if src_x.1 == 0 || src_y.1 == 0 || dst_x.1 == 0 || dst_y.1 == 0 {
+                    continue;
+                }
+
+                canvas
+                    .copy(
+                        texture,
+                        Rect::new(src_x.0, src_y.0, src_x.1, src_y.1),
+                        Rect::new(dst_x.0, dst_y.0, dst_x.1, dst_y.1),
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
+    fn copy_sprite(
+        &self,
+        texture: &Texture,
+        canvas: &mut Canvas<Window>,
+        src: Sprite,
+        dst: Rect,
+        options: DrawOptions,
     ) {
+        let tint = options.tint;
+        let blend_mode = if options.additive {
+            sdl2::sys::SDL_BlendMode::SDL_BLENDMODE_ADD
+        } else {
+            sdl2::sys::SDL_BlendMode::SDL_BLENDMODE_BLEND
+        };
+        unsafe {
+            sdl2::sys::SDL_SetTextureColorMod(texture.raw(), tint.r, tint.g, tint.b);
+            sdl2::sys::SDL_SetTextureAlphaMod(texture.raw(), tint.a);
+            sdl2::sys::SDL_SetTextureBlendMode(texture.raw(), blend_mode);
+        }
+
+        let height = (self.tile_size * src.3) as f32;
+        let (top, bottom) = options.rows.unwrap_or((0., 1.));
         canvas
             .copy_ex(
-                unsafe { self.texture.assume_init_ref() },
+                texture,
                 Some(Rect::new(
                     (src.0 * self.tile_size) as i32,
-                    (src.1 * self.tile_size) as i32,
+                    (src.1 * self.tile_size) as i32 + (height * top).round() as i32,
                     (self.tile_size * src.2) as u32,
-                    (self.tile_size * src.3) as u32,
+                    ((height * (bottom - top)).round() as u32).max(1),
                 )),
-                Some(Rect::new(
-                    dst.0,
-                    dst.1,
-                    (self.tile_size * src.2 * 2) as u32,
-                    (self.tile_size * src.3 * 2) as u32,
-                )),
-                angle,
-                None,
-                flip_horizontal,
-                flip_vertical,
+                Some(dst),
+                options.angle,
+                options.origin.map(|o| Point::new(o.0, o.1)),
+                options.flip_horizontal,
+                options.flip_vertical,
             )
             .unwrap();
     }
@@ -143,20 +592,47 @@ impl Spritesheet {
 
 impl Drop for Spritesheet {
     fn drop(&mut self) {
-        unsafe { self.texture.assume_init_read().destroy() }
+        if let Some(texture) = self.texture.take() {
+            unsafe { texture.destroy() }
+        }
+        if let Some(specular) = self.specular.take() {
+            unsafe { specular.destroy() }
+        }
     }
 }
 
+// drawn in order, z only sorts within a layer
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Layer {
+    World,
+    Overlay,
+}
+
 // TODO dunno what to call this
 struct DrawCmd {
+    texture: TextureId,
     sprite: Sprite,
     pos: Vec3<i32>,
-    flip_horizontal: bool,
+    size: (u32, u32),
+    layer: Layer,
+    // submission index, keeps sprites at the same depth from swapping between frames
+    order: u32,
+    options: DrawOptions,
+}
+
+impl DrawCmd {
+    fn sort_key(&self) -> (Layer, i32, u32) {
+        (self.layer, self.pos.z, self.order)
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::new(self.pos.x, self.pos.y, self.size.0, self.size.1)
+    }
 }
 
 impl PartialEq for DrawCmd {
     fn eq(&self, other: &Self) -> bool {
-        self.pos.z == other.pos.z
+        self.sort_key() == other.sort_key()
     }
 }
 
@@ -164,56 +640,109 @@ impl Eq for DrawCmd {}
 
 impl PartialOrd for DrawCmd {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        use std::cmp::Ordering::*;
-        match self.pos.z.cmp(&other.pos.z) {
-            Less => Some(Greater),
-            Equal => Some(Equal),
-            Greater => Some(Less),
-        }
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for DrawCmd {
+    // reversed, BinaryHeap pops the greatest first
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        use std::cmp::Ordering::*;
-        match self.pos.z.cmp(&other.pos.z) {
-            Less => Greater,
-            Equal => Equal,
-            Greater => Less,
+        other.sort_key().cmp(&self.sort_key())
+    }
+}
+
+#[derive(Default)]
+pub struct RenderStats {
+    draw_calls: usize,
+    sprites: usize,
+    last_texture: Option<TextureId>,
+}
+
+impl RenderStats {
+    pub fn reset(&mut self) {
+        *self = RenderStats::default();
+    }
+
+    // consecutive sprites from the same texture get batched by the renderer
+    pub fn record(&mut self, texture: TextureId) {
+        if self.last_texture != Some(texture) {
+            self.draw_calls += 1;
+            self.last_texture = Some(texture);
         }
+        self.sprites += 1;
     }
 }
 
 #[derive(Resource)]
 struct DepthBuffer {
     buffer: BinaryHeap<DrawCmd>,
+    sorted: Vec<DrawCmd>,
+    next_order: u32,
 }
 
 impl DepthBuffer {
     pub fn new() -> Self {
         DepthBuffer {
             buffer: BinaryHeap::new(),
+            sorted: Vec::new(),
+            next_order: 0,
         }
     }
 
-    pub fn push(&mut self, texture: DrawCmd) {
-        self.buffer.push(texture);
+    pub fn push(&mut self, mut draw_cmd: DrawCmd) {
+        draw_cmd.order = self.next_order;
+        self.next_order += 1;
+        self.buffer.push(draw_cmd);
     }
 
-    pub fn draw_to_canvas(&mut self, canvas: &mut Canvas<Window>, spritesheet: &Spritesheet) {
+    pub fn draw_to_canvas(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        sheets: &[Spritesheet],
+        stats: &mut RenderStats,
+    ) {
         while let Some(draw_cmd) = self.buffer.pop() {
-            spritesheet.draw_to_canvas(
+            self.sorted.push(draw_cmd);
+        }
+        self.next_order = 0;
+
+        // within a depth band a sprite moves up to the last one from its texture to cut down
+        // on state changes, but only past sprites it doesn't overlap so nothing visibly
+        // changes order
+        for band in self.sorted.chunk_by_mut(|a, b| {
+            a.layer == b.layer
+                && a.pos.z.div_euclid(DEPTH_BAND_SIZE) == b.pos.z.div_euclid(DEPTH_BAND_SIZE)
+        }) {
+            for idx in 1..band.len() {
+                let Some(same) = band[..idx]
+                    .iter()
+                    .rposition(|draw_cmd| draw_cmd.texture == band[idx].texture)
+                else {
+                    continue;
+                };
+                let rect = band[idx].rect();
+                if band[same + 1..idx]
+                    .iter()
+                    .all(|draw_cmd| !draw_cmd.rect().has_intersection(rect))
+                {
+                    band[same + 1..=idx].rotate_right(1);
+                }
+            }
+        }
+
+        for draw_cmd in self.sorted.drain(..) {
+            stats.record(draw_cmd.texture);
+            sheets[*draw_cmd.texture].draw_to_canvas(
                 canvas,
                 draw_cmd.sprite,
-                (draw_cmd.pos.x, draw_cmd.pos.y),
-                0.,
-                draw_cmd.flip_horizontal,
-                false,
+                draw_cmd.rect(),
+                draw_cmd.options,
             )
         }
     }
 }
 
+#[derive(Default)]
 pub struct InputState {
     pub up: bool,
     pub down: bool,
@@ -228,11 +757,75 @@ pub struct InputState {
     pub q: bool,
     pub e: bool,
     pub use_item: bool,
+    pub tab: bool,
+    pub mouse_left: bool,
+    pub mouse_right: bool,
+    pub dash: bool,
+}
+
+impl InputState {
+    // one bit per field, for replays
+    pub fn to_bits(&self) -> u32 {
+        [
+            self.up,
+            self.down,
+            self.left,
+            self.right,
+            self.shift,
+            self.fire_up,
+            self.fire_down,
+            self.fire_left,
+            self.fire_right,
+            self.interact,
+            self.q,
+            self.e,
+            self.use_item,
+            self.tab,
+            self.mouse_left,
+            self.mouse_right,
+            self.dash,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (idx, is_set)| bits | (*is_set as u32) << idx)
+    }
+
+    pub fn from_bits(bits: u32) -> Self {
+        let bit = |idx: u32| bits & (1 << idx) != 0;
+        InputState {
+            up: bit(0),
+            down: bit(1),
+            left: bit(2),
+            right: bit(3),
+            shift: bit(4),
+            fire_up: bit(5),
+            fire_down: bit(6),
+            fire_left: bit(7),
+            fire_right: bit(8),
+            interact: bit(9),
+            q: bit(10),
+            e: bit(11),
+            use_item: bit(12),
+            tab: bit(13),
+            mouse_left: bit(14),
+            mouse_right: bit(15),
+            dash: bit(16),
+        }
+    }
 }
 
 pub struct Input {
     pressed: InputState,
     just_pressed: InputState,
+    mouse_pos: (i32, i32),
+}
+
+// a light's wall shadows, centered on the light and 2 radii across
+struct CachedShadow {
+    texture: Texture,
+    pos: (f32, f32),
+    radius: u16,
+    walls: u64,
 }
 
 pub struct Lightmap {
@@ -240,6 +833,12 @@ pub struct Lightmap {
     per_light_tex: MaybeUninit<Texture>,
     shadow_mask: MaybeUninit<Texture>,
     specular_map: MaybeUninit<Texture>,
+    specular_lights: MaybeUninit<Texture>,
+    // the bloom and the other half of the blur ping pong, both BLOOM_DOWNSCALE smaller
+    bloom: MaybeUninit<Texture>,
+    bloom_scratch: MaybeUninit<Texture>,
+    // lights that stood still with no moving occluders nearby last frame
+    shadow_cache: HashMap<Entity, CachedShadow>,
 }
 
 impl Lightmap {
@@ -268,11 +867,34 @@ impl Lightmap {
             .unwrap();
         specular_map.set_blend_mode(sdl2::render::BlendMode::Mul);
 
+        let mut specular_lights = canvas
+            .texture_creator()
+            .create_texture_target(canvas.default_pixel_format(), w, h)
+            .unwrap();
+        specular_lights.set_blend_mode(sdl2::render::BlendMode::Add);
+
+        let (bloom_w, bloom_h) = ((w / BLOOM_DOWNSCALE).max(1), (h / BLOOM_DOWNSCALE).max(1));
+        let mut bloom = canvas
+            .texture_creator()
+            .create_texture_target(canvas.default_pixel_format(), bloom_w, bloom_h)
+            .unwrap();
+        bloom.set_blend_mode(sdl2::render::BlendMode::Add);
+
+        let mut bloom_scratch = canvas
+            .texture_creator()
+            .create_texture_target(canvas.default_pixel_format(), bloom_w, bloom_h)
+            .unwrap();
+        bloom_scratch.set_blend_mode(sdl2::render::BlendMode::Add);
+
         Lightmap {
             lights: MaybeUninit::new(lights),
             per_light_tex: MaybeUninit::new(per_light_tex),
             shadow_mask: MaybeUninit::new(shadow_mask),
             specular_map: MaybeUninit::new(specular_map),
+            specular_lights: MaybeUninit::new(specular_lights),
+            bloom: MaybeUninit::new(bloom),
+            bloom_scratch: MaybeUninit::new(bloom_scratch),
+            shadow_cache: HashMap::new(),
         }
     }
 
@@ -291,6 +913,18 @@ impl Lightmap {
     pub fn specular_map(&self) -> Texture {
         unsafe { self.specular_map.assume_init_read() }
     }
+
+    pub fn specular_lights(&self) -> Texture {
+        unsafe { self.specular_lights.assume_init_read() }
+    }
+
+    pub fn bloom(&self) -> Texture {
+        unsafe { self.bloom.assume_init_read() }
+    }
+
+    pub fn bloom_scratch(&self) -> Texture {
+        unsafe { self.bloom_scratch.assume_init_read() }
+    }
 }
 
 impl Drop for Lightmap {
@@ -298,141 +932,365 @@ impl Drop for Lightmap {
         unsafe { self.lights.assume_init_read().destroy() }
         unsafe { self.shadow_mask.assume_init_read().destroy() }
         unsafe { self.specular_map.assume_init_read().destroy() }
+        unsafe { self.specular_lights.assume_init_read().destroy() }
+        unsafe { self.bloom.assume_init_read().destroy() }
+        unsafe { self.bloom_scratch.assume_init_read().destroy() }
+        for (_, cached) in self.shadow_cache.drain() {
+            unsafe { cached.texture.destroy() }
+        }
     }
 }
 
 #[derive(Resource)]
 pub struct Ctx {
     canvas: Canvas<Window>,
-    spritesheet: Spritesheet,
-    animations: AnimationRepository,
+    font: Font<'static, 'static>,
+    spritesheets: Vec<Spritesheet>,
+    // images of the sheets loaded from aseprite exports, for reloading them
+    aseprite_images: Vec<String>,
     light_tex: Texture,
     ui_tex: Texture,
-    ui_active_item_bg: Sprite,
+    sprites: SpriteRegistry,
     lightmap: Lightmap,
-    despawn_queue: RwLock<Vec<Entity>>,
     input: Input,
-    player_speed: f32,
-    enemy_speed: f32,
-    bullet_speed: f32,
-    bullet_lifetime: usize,
-    player_fire_cooldown: usize,
     debug_draw_nav_colliders: bool,
     debug_draw_hitboxes: bool,
     debug_draw_centerpoints: bool,
     shadows_enabled: bool,
+    specular_enabled: bool,
+    // index into BLOOM_PASSES
+    bloom_quality: usize,
     player_pos: Pos,
+    // last movement or fire direction
+    player_facing: Vec2<f32>,
+    camera_target: Pos,
+    // 0 to 1, decays every tick
+    camera_trauma: f32,
+    camera_shake: Vec2<f32>,
+    // fixed update ticks since startup, for things that animate with game time
+    ticks: u32,
     room_size: (u16, u16),
-    player_inventory: Inventory,
-    particle_emitter_entity: Option<Entity>,
+    // shown in save slots
+    level_name: &'static str,
+    frame_alpha: f32,
+    render_stats: RenderStats,
+    inventory_open: bool,
+    inventory_held_slot: Option<usize>,
+    coins: u32,
+    player2: SecondPlayer,
+}
+
+// the second local player's share of Ctx, see Ctx::swap_players
+struct SecondPlayer {
+    input: Input,
+    pos: Pos,
+    facing: Vec2<f32>,
 }
 
 impl Ctx {
-    pub fn camera_pos(&self) -> (i32, i32) {
-        let window_w = self.canvas.window().size().0 as i32;
-        let window_h = self.canvas.window().size().1 as i32;
+    // the player systems and items act on the first player's fields, the second player
+    // gets its turn by swapping its own in and back out again
+    pub fn swap_players(&mut self) {
+        std::mem::swap(&mut self.input, &mut self.player2.input);
+        std::mem::swap(&mut self.player_pos, &mut self.player2.pos);
+        std::mem::swap(&mut self.player_facing, &mut self.player2.facing);
+    }
+
+    pub fn screen_to_world(&self, screen_pos: (i32, i32)) -> Pos {
+        let screen = Vec2::new(screen_pos.0 as f32, screen_pos.1 as f32);
+        let world = self.camera().inverse().unwrap().apply(screen);
+        Pos::new(world.x, world.y)
+    }
+
+    pub fn cursor_world_pos(&self) -> Pos {
+        self.screen_to_world(self.input.mouse_pos)
+    }
+
+    // what the game renders at, the logical size when one is set
+    pub fn render_size(&self) -> (u32, u32) {
+        match self.canvas.logical_size() {
+            (0, 0) => self.canvas.output_size().unwrap(),
+            size => size,
+        }
+    }
+
+    // accounts for logical scaling and letterboxing
+    pub fn window_to_render(&self, pos: (i32, i32)) -> (i32, i32) {
+        let (mut x, mut y) = (0., 0.);
+        unsafe {
+            sdl2::sys::SDL_RenderWindowToLogical(self.canvas.raw(), pos.0, pos.1, &mut x, &mut y)
+        };
+        (x as i32, y as i32)
+    }
+
+    // None renders at the window resolution
+    pub fn set_logical_size(&mut self, size: Option<(u32, u32)>, integer_scale: bool) {
+        let (w, h) = size.unwrap_or((0, 0));
+        self.canvas.set_logical_size(w, h).unwrap();
+        self.canvas.set_integer_scale(integer_scale).unwrap();
+        self.resize_render_targets();
+    }
+
+    // the lightmap and ui are drawn at the render size and stretched over the window
+    pub fn resize_render_targets(&mut self) {
+        let (w, h) = self.render_size();
+        self.lightmap = Lightmap::new(&self.canvas, w, h);
+        let ui_tex = std::mem::replace(&mut self.ui_tex, create_ui_tex(&self.canvas, w, h));
+        unsafe { ui_tex.destroy() };
+    }
+
+    // the top left corner of the view in the world
+    fn camera_pos(&self) -> (i32, i32) {
+        let window_w = self.render_size().0 as i32;
+        let window_h = self.render_size().1 as i32;
+        let target = self.camera_target.pixel();
 
         (
-            ((self.player_pos.x as i32) - window_w / 2)
-                .clamp(0, self.room_size.0 as i32 - window_w / 2),
-            ((self.player_pos.y as i32) - window_h / 2)
-                .clamp(0, self.room_size.1 as i32 - window_h / 2),
+            (target.0 - window_w / 2).clamp(0, self.room_size.0 as i32 - window_w / 2),
+            (target.1 - window_h / 2).clamp(0, self.room_size.1 as i32 - window_h / 2),
         )
     }
+
+    // world to screen. only a translation for now, kept on whole pixels
+    pub fn camera(&self) -> Transform {
+        let (x, y) = self.camera_pos();
+        Transform::translation(-x as f32, -y as f32)
+    }
+
+    pub fn world_to_screen(&self, pos: &Pos) -> (i32, i32) {
+        world_to_screen(pos, &self.camera())
+    }
+}
+
+// the only way from world to screen coordinates, for anything drawn in the world. positions
+// snap before the camera applies, and the camera is on whole pixels, so where something lands
+// on screen only depends on its own snapping
+pub fn world_to_screen(pos: &Pos, camera: &Transform) -> (i32, i32) {
+    let (x, y) = pos.pixel();
+    let screen = camera.apply(Vec2::new(x as f32, y as f32));
+    (screen.x.round() as i32, screen.y.round() as i32)
+}
+
+const USAGE: &str = "usage: game [--headless <ticks>] [--seed <n>] [--replay <file>] \
+[--record <file>] [--coop] [--host [port]] [--join <addr>]";
+
+// a bad command line isn't worth a panic, say what's wrong and how it goes
+fn usage_error(message: &str) -> ! {
+    println!("{}", message);
+    println!("{}", USAGE);
+    std::process::exit(1);
+}
+
+// the argument after name
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let idx = args.iter().position(|arg| arg == name)?;
+    match args.get(idx + 1) {
+        Some(value) => Some(value),
+        None => usage_error(&format!("{} needs a value", name)),
+    }
+}
+
+fn create_ui_tex(canvas: &Canvas<Window>, w: u32, h: u32) -> Texture {
+    let mut ui_tex = canvas
+        .texture_creator()
+        .create_texture(None, sdl2::render::TextureAccess::Target, w, h)
+        .unwrap();
+    ui_tex.set_blend_mode(BlendMode::Add);
+    ui_tex
+}
+
+// the aseprite sheets come after SPRITESHEETS, in the same order every time. they start out
+// empty, see streaming
+fn spritesheets(aseprite_images: &[String]) -> Vec<Spritesheet> {
+    let mut sheets: Vec<_> = SPRITESHEETS
+        .iter()
+        .enumerate()
+        .map(|(idx, sheet)| {
+            Spritesheet::new(
+                TextureId(idx),
+                assets::path(sheet.texture),
+                sheet.specular.map(assets::path),
+                sheet.tile_size,
+            )
+        })
+        .collect();
+    for image in aseprite_images {
+        // aseprite frame rects are in pixels
+        let id = TextureId(sheets.len());
+        sheets.push(Spritesheet::new(id, image.clone(), None, 1));
+    }
+    sheets
 }
 
 pub fn main() {
-    let mut is_fullscreen = false;
     let world = World::new();
+    let args: Vec<String> = std::env::args().collect();
+    // before anything loads, the mods decide where assets come from
+    let settings = Settings::load(settings::CONFIG_PATH);
+    assets::set_mods(&settings.mods);
+    let headless_ticks = arg_value(&args, "--headless").map(|ticks| {
+        ticks
+            .parse::<u32>()
+            .unwrap_or_else(|_| usage_error("--headless takes a tick count"))
+    });
+    if headless_ticks.is_some() {
+        // SDL still needs a window for the renderer and textures, the dummy driver never shows it
+        sdl2::hint::set("SDL_VIDEODRIVER", "dummy");
+    }
     let sdl_context = sdl2::init().unwrap();
     let video_subsystem = sdl_context.video().unwrap();
     let _image_context = sdl2::image::init(InitFlag::PNG).unwrap();
-    let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string()).unwrap();
-    let window = video_subsystem
-        .window("gaem", 800, 800)
-        .position_centered()
-        .opengl()
-        .build()
-        .map_err(|e| e.to_string())
-        .unwrap();
+    // leaked so the font can live in Ctx for the lifetime of the program
+    let ttf_context = Box::leak(Box::new(
+        sdl2::ttf::init().map_err(|e| e.to_string()).unwrap(),
+    ));
+    // replaced by the crosshair
+    sdl_context.mouse().show_cursor(false);
+    let mut window = video_subsystem.window("gaem", 800, 800);
+    if headless_ticks.is_some() {
+        window.hidden();
+    } else {
+        window.position_centered().resizable().opengl();
+    }
+    let window = window.build().map_err(|e| e.to_string()).unwrap();
 
-    let canvas = window
-        .into_canvas()
-        .accelerated()
-        .build()
-        .map_err(|e| e.to_string())
-        .unwrap();
+    let mut canvas = window.into_canvas();
+    canvas = if headless_ticks.is_some() {
+        canvas.software()
+    } else {
+        canvas.accelerated()
+    };
+    let canvas = canvas.build().map_err(|e| e.to_string()).unwrap();
 
     let texture_creator = canvas.texture_creator();
 
     let mut font = ttf_context
-        .load_font("assets/fonts/vcr_osd_mono.ttf", 18)
+        .load_font(assets::path("fonts/vcr_osd_mono.ttf"), 18)
         .unwrap();
     font.set_style(sdl2::ttf::FontStyle::NORMAL);
 
+    let sprites = SpriteRegistry::load(&SPRITESHEETS);
+
     let mut animations = AnimationRepository::new();
+    let mut push = |name: &'static str, frames: &[&str]| {
+        let sheet = sprites.sheet(frames[0]);
+        assert!(
+            frames.iter().all(|frame| sprites.sheet(frame) == sheet),
+            "animation {} spans multiple sheets",
+            name
+        );
+        let frames: Vec<Sprite> = frames.iter().map(|frame| sprites.get(frame)).collect();
+        animations.push(name, sheet, &frames);
+    };
 
-    animations.push("player_idle", &[(0, 0, 1, 2).into(), (1, 0, 1, 2).into()]);
-    animations.push(
+    push("player_idle", &["player_0", "player_1"]);
+    // placeholder, a held stride until there's a proper roll
+    push("player_dash", &["player_2"]);
+    push(
         "player_walk",
-        &[
-            (0, 0, 1, 2).into(),
-            (2, 0, 1, 2).into(),
-            (0, 0, 1, 2).into(),
-            (3, 0, 1, 2).into(),
-        ],
+        &["player_0", "player_2", "player_0", "player_3"],
     );
 
-    animations.push("enemy_walk", &[(4, 0, 2, 2).into(), (6, 0, 2, 2).into()]);
+    push("enemy_walk", &["enemy_0", "enemy_1"]);
+    // placeholder, the enemy art lying down
+    push("corpse", &["enemy_0"]);
+    push("boss_walk", &["boss_0", "boss_1"]);
 
-    animations.push("bang", &[(10, 0, 1, 1).into(), (11, 0, 1, 1).into()]);
+    push("bang", &["bang_0", "bang_1"]);
+    push("slash", &["slash_0", "slash_1"]);
 
-    animations.push("floor", &[(8, 0, 1, 1).into()]);
+    push("floor", &["floor"]);
 
-    animations.push("wall", &[(0, 2, 1, 2).into()]);
+    push("wall", &["wall"]);
 
-    animations.push(
-        "torch",
-        &[
-            (9, 1, 1, 1).into(),
-            (10, 1, 1, 1).into(),
-            (11, 1, 1, 1).into(),
-        ],
-    );
+    push("lever", &["lever"]);
+
+    push("door_closed", &["door_closed"]);
+    push("door_opening", &["door_opening_0", "door_opening_1"]);
+    push("door_open", &["door_open"]);
+
+    push("particle_emitter", &["particle_emitter"]);
 
-    animations.push("lever", &[(8, 1, 1, 1).into()]);
+    push("chemlight", &["chemlight"]);
 
-    animations.push("particle_emitter", &[(9, 0, 1, 1).into()]);
+    push("bullet", &["bullet"]);
 
-    animations.push("chemlight", &[(12, 1, 1, 1).into()]);
+    push("pressure_plate_up", &["pressure_plate_up"]);
+    push("pressure_plate_down", &["pressure_plate_down"]);
+    push("crate", &["crate"]);
+    push("barrel", &["barrel"]);
+    push("checkpoint_off", &["checkpoint_off"]);
+    push("checkpoint_on", &["checkpoint_on"]);
+
+    push("conveyor", &["conveyor_0", "conveyor_1"]);
+
+    push("spikes_up", &["spikes_up"]);
+    push("spikes_down", &["spikes_down"]);
+    push("lava", &["lava_0", "lava_1"]);
+    push("ice", &["ice"]);
+    push("water", &["water_0", "water_1"]);
+
+    push("coin", &["coin_0", "coin_1"]);
+
+    push("vendor", &["vendor_0", "vendor_1"]);
+    // placeholder until there's chest art
+    push("chest", &["crate"]);
+
+    // ticks per frame, these ignore the sprite's ticks_per_frame
+    let mut push_timed = |name: &'static str, frames: &[(&str, u32)]| {
+        let sheet = sprites.sheet(frames[0].0);
+        let (frames, durations): (Vec<Sprite>, Vec<u32>) = frames
+            .iter()
+            .map(|(frame, ticks)| (sprites.get(frame), *ticks))
+            .unzip();
+        animations.push_timed(name, sheet, &frames, &durations);
+    };
+
+    // an uneven flicker
+    push_timed("torch", &[("torch_0", 6), ("torch_1", 3), ("torch_2", 8)]);
+
+    // the strides
+    animations.add_event("player_walk", 1, "footstep");
+    animations.add_event("player_walk", 3, "footstep");
+
+    // after the built in ones so an export can replace them. a broken export is reported and
+    // left out, the ids only count the sheets that loaded
+    let mut aseprite_images = Vec::new();
+    for path in aseprite::paths() {
+        let sheet = match aseprite::load(&path) {
+            Ok(sheet) => sheet,
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
+        let id = TextureId(SPRITESHEETS.len() + aseprite_images.len());
+        for (name, frames, durations) in &sheet.animations {
+            let name = Box::leak(name.clone().into_boxed_str());
+            animations.push_timed(name, id, frames, durations);
+        }
+        aseprite_images.push(sheet.image);
+    }
 
     let mut ctx = Ctx {
-        despawn_queue: RwLock::new(Vec::new()),
         light_tex: texture_creator
-            .load_texture("assets/textures/light.png")
+            .load_texture(assets::path("textures/light.png"))
             .unwrap(),
-        ui_tex: texture_creator
-            .create_texture(
-                None,
-                sdl2::render::TextureAccess::Target,
-                canvas.window().drawable_size().0,
-                canvas.window().drawable_size().1,
-            )
-            .unwrap(),
-        ui_active_item_bg: (13, 0, 1, 1).into(),
-        lightmap: Lightmap::new(
+        ui_tex: create_ui_tex(
             &canvas,
-            canvas.window().drawable_size().0,
-            canvas.window().drawable_size().1,
+            canvas.output_size().unwrap().0,
+            canvas.output_size().unwrap().1,
         ),
-        spritesheet: Spritesheet::new_from_file(
-            &texture_creator,
-            "assets/textures/spritesheet.png",
-            "assets/textures/specular.png",
-            16,
+        sprites,
+        lightmap: Lightmap::new(
+            &canvas,
+            canvas.output_size().unwrap().0,
+            canvas.output_size().unwrap().1,
         ),
-        animations,
+        spritesheets: spritesheets(&aseprite_images),
+        aseprite_images,
         canvas,
+        font,
         input: Input {
             pressed: InputState {
                 up: false,
@@ -448,6 +1306,10 @@ pub fn main() {
                 q: false,
                 e: false,
                 use_item: false,
+                tab: false,
+                mouse_left: false,
+                mouse_right: false,
+                dash: false,
             },
             just_pressed: InputState {
                 up: false,
@@ -463,40 +1325,177 @@ pub fn main() {
                 q: false,
                 e: false,
                 use_item: false,
+                tab: false,
+                mouse_left: false,
+                mouse_right: false,
+                dash: false,
             },
+            mouse_pos: (0, 0),
         },
-        player_speed: 3.0,
-        enemy_speed: 1.2,
-        bullet_speed: 4.0,
         debug_draw_nav_colliders: false,
         debug_draw_hitboxes: false,
         debug_draw_centerpoints: false,
-        bullet_lifetime: 60,
-        player_fire_cooldown: 20,
         shadows_enabled: true,
+        specular_enabled: true,
+        bloom_quality: 1,
         player_pos: Pos::zero(),
+        player_facing: Vec2::new(1., 0.),
+        camera_target: Pos::zero(),
+        camera_trauma: 0.,
+        camera_shake: Vec2::zero(),
+        ticks: 0,
         room_size: (2048, 2048),
-        player_inventory: Inventory::new(),
-        particle_emitter_entity: None,
+        level_name: "cellar",
+        frame_alpha: 0.,
+        render_stats: RenderStats::default(),
+        inventory_open: false,
+        inventory_held_slot: None,
+        coins: 0,
+        player2: SecondPlayer {
+            input: Input {
+                pressed: InputState::default(),
+                just_pressed: InputState::default(),
+                mouse_pos: (0, 0),
+            },
+            pos: Pos::zero(),
+            facing: Vec2::new(1., 0.),
+        },
     };
 
-    ctx.ui_tex.set_blend_mode(BlendMode::Add);
-
+    world.add_resource(ScreenTransition::new(&mut ctx.canvas));
     world.add_resource(ctx);
+    world.add_resource(animations);
+    world.add_resource(WallColliders::new());
+    world.add_resource(CollisionLayers::load(&assets::path(layers::LAYERS_PATH)).unwrap());
+    world.add_resource(ParticlePresets::load(&assets::path(particles::PARTICLES_PATH)).unwrap());
+    world.add_resource(Tuning::load(&assets::path(tuning::TUNING_PATH)));
+    // a broken script shouldn't keep the game from starting, it just runs without hooks
+    world.add_resource(Scripts::load(scripts::SCRIPT_DIR).unwrap_or_else(|e| {
+        println!("{}", e);
+        Scripts::new()
+    }));
     world.add_resource(DepthBuffer::new());
+    world.add_resource(SignalBroker::new());
+    world.add_resource(CommandBuffer::new());
+    world.add_resource(Physics::new());
+    events::add_resources(&world);
+    world.add_resource(AmbientLight::new(Color::RGB(70, 70, 70)));
+    world.add_resource(Console::new());
+    world.add_resource(PrefabRegistry::new());
+    world.add_resource(Director::new());
+    world.add_resource(Shop::load(&assets::path("data/shop.txt")));
+    world.add_resource(Scheduler::new());
+    world.add_resource(Profiler::new());
+    world.add_resource(Capture::new());
+    world.add_resource(TextCache::new());
+    world.add_resource(Streamer::new());
+    world.add_resource(Containers::new());
+    world.add_resource(Toasts::new());
+    world.add_resource(SaveSlots::new());
+    world.add_resource(Checkpoints::new());
+    world.add_resource(Effects::new());
+    world.add_resource(Editor::new());
+    world.add_resource(Audio::new(if headless_ticks.is_some() {
+        None
+    } else {
+        Some(sdl_context.audio().unwrap())
+    }));
+    world.add_resource(Music::load(&assets::path(music::MUSIC_PATH)).unwrap());
+    world.add_resource(Haptics::new(if headless_ticks.is_some() {
+        None
+    } else {
+        Some(sdl_context.game_controller().unwrap())
+    }));
+
+    // --seed <n> makes a run reproducible, a replay brings its own seed
+    let (replay, replay_seed) = match arg_value(&args, "--replay") {
+        Some(path) => match Replay::load(path) {
+            Ok((replay, seed)) => (replay, Some(seed)),
+            Err(e) => {
+                println!("can't play the replay: {}", e);
+                std::process::exit(1);
+            }
+        },
+        None => (Replay::Idle, None),
+    };
+    let rng = match replay_seed.or(arg_value(&args, "--seed").map(|seed| {
+        seed.parse()
+            .unwrap_or_else(|_| usage_error("--seed takes a number"))
+    })) {
+        Some(seed) => GameRng::new(seed),
+        None => GameRng::from_time(),
+    };
+    println!("seed {}", rng.seed());
+    let replay = match arg_value(&args, "--record") {
+        Some(path) => Replay::record(path, rng.seed(), args.iter().any(|arg| arg == "--coop")),
+        None => replay,
+    };
+    world.add_resource(rng);
+    world.add_resource(replay);
+
+    // --host [port] waits for a partner to --join <addr>, otherwise the lobby asks
+    let net = if let Some(addr) = arg_value(&args, "--join") {
+        Net::join(addr).unwrap()
+    } else if let Some(idx) = args.iter().position(|arg| arg == "--host") {
+        let port = args.get(idx + 1).and_then(|port| port.parse().ok());
+        Net::host(port.unwrap_or(net::DEFAULT_PORT)).unwrap()
+    } else {
+        Net::Offline
+    };
+    let skips_lobby = ["--host", "--join", "--coop", "--replay"]
+        .iter()
+        .any(|arg| args.iter().any(|a| a == arg));
+    world.add_resource(net);
+    world.add_resource(Lobby::new(headless_ticks.is_none() && !skips_lobby));
+
+    world.add_resource(settings);
+    world.add_resource(Stats::load(&stats::stats_path()));
+    // the string tables are needed even without a window
+    locale::set_language(world.resource::<Settings>().unwrap().language);
+    if headless_ticks.is_none() {
+        settings::apply(&world);
+    }
     let ctx = world.resource_mut::<Ctx>().unwrap();
+    let scheduler = world.resource_mut::<Scheduler>().unwrap();
+    game::register_systems(scheduler);
+    register_render_systems(scheduler);
 
     game::init(&world);
+    // a second player on the same machine, on the first controller or the second key set
+    if world.resource::<Replay>().unwrap().is_coop() || args.iter().any(|arg| arg == "--coop") {
+        game::spawn_second_player(&world);
+    }
+
+    if let Some(ticks) = headless_ticks {
+        run_headless(&world, ticks);
+        return;
+    }
+
+    let tick_duration = Duration::from_secs(1) / TICKS_PER_SECOND;
+    let mut accumulator = Duration::ZERO;
+    let mut last_frame = Instant::now();
+    let mut update_time = 0;
 
     let mut event_pump = sdl_context.event_pump().unwrap();
     'mainloop: loop {
         for event in event_pump.poll_iter() {
+            world
+                .resource_mut::<Haptics>()
+                .unwrap()
+                .handle_event(&event);
+            if console::handle_event(&world, &event) || lobby::handle_event(&world, &event) {
+                continue;
+            }
+
             match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
+                Event::Quit { .. } => break 'mainloop,
+                Event::KeyDown {
                     keycode: Some(Keycode::Escape),
                     ..
-                } => break 'mainloop,
+                } => {
+                    let settings = world.resource_mut::<Settings>().unwrap();
+                    settings.is_open = !settings.is_open;
+                }
                 Event::KeyDown {
                     keycode: Some(Keycode::F1),
                     ..
@@ -509,100 +1508,175 @@ pub fn main() {
                     keycode: Some(Keycode::F3),
                     ..
                 } => ctx.debug_draw_centerpoints = !ctx.debug_draw_centerpoints,
+                Event::KeyDown {
+                    keycode: Some(Keycode::F4),
+                    ..
+                } => {
+                    let profiler = world.resource_mut::<Profiler>().unwrap();
+                    profiler.is_visible = !profiler.is_visible;
+                }
                 Event::KeyDown {
                     keycode: Some(Keycode::F5),
                     ..
-                } => ctx.shadows_enabled = !ctx.shadows_enabled,
+                } => world
+                    .resource_mut::<SaveSlots>()
+                    .unwrap()
+                    .request_save(None),
                 Event::KeyDown {
                     keycode: Some(Keycode::F9),
                     ..
                 } => {
-                    is_fullscreen = !is_fullscreen;
-                    ctx.canvas
-                        .window_mut()
-                        .set_fullscreen(if is_fullscreen {
-                            sdl2::video::FullscreenType::Desktop
-                        } else {
-                            sdl2::video::FullscreenType::Off
-                        })
-                        .unwrap();
-                    ctx.lightmap = Lightmap::new(
-                        &ctx.canvas,
-                        ctx.canvas.window().drawable_size().0,
-                        ctx.canvas.window().drawable_size().1,
-                    );
-                    ctx.ui_tex = ctx
-                        .canvas
-                        .texture_creator()
-                        .create_texture(
-                            None,
-                            sdl2::render::TextureAccess::Target,
-                            ctx.canvas.window().drawable_size().0,
-                            ctx.canvas.window().drawable_size().1,
-                        )
-                        .unwrap();
-                    ctx.ui_tex.set_blend_mode(BlendMode::Add);
+                    if world.resource::<SaveSlots>().unwrap().is_open {
+                        saves::close(&world);
+                    } else {
+                        saves::open(&world);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F6),
+                    ..
+                } => ctx.specular_enabled = !ctx.specular_enabled,
+                Event::KeyDown {
+                    keycode: Some(Keycode::F7),
+                    ..
+                } => editor::toggle(&world),
+                Event::KeyDown {
+                    keycode:
+                        Some(
+                            keycode @ (Keycode::Z
+                            | Keycode::Y
+                            | Keycode::C
+                            | Keycode::X
+                            | Keycode::V),
+                        ),
+                    keymod,
+                    ..
+                } if keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD)
+                    && world.resource::<Editor>().unwrap().is_active =>
+                {
+                    match keycode {
+                        Keycode::Z => editor::undo(&world),
+                        Keycode::Y => editor::redo(&world),
+                        Keycode::C => editor::copy(&world),
+                        Keycode::X => editor::cut(&world),
+                        _ => editor::paste(&world),
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F8),
+                    ..
+                } => transition::cover_then(
+                    &world,
+                    Style::Fade(Color::BLACK),
+                    transition::ROOM_TICKS,
+                    game::regenerate_level,
+                ),
+                Event::Window {
+                    win_event: WindowEvent::Resized(..) | WindowEvent::SizeChanged(..),
+                    ..
+                } => ctx.resize_render_targets(),
+                Event::KeyDown {
+                    keycode: Some(Keycode::F10),
+                    ..
+                } => {
+                    world
+                        .resource_mut::<Capture>()
+                        .unwrap()
+                        .screenshot_requested = true
                 }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F11),
+                    ..
+                } => world.resource_mut::<Capture>().unwrap().clip_requested = true,
                 Event::KeyDown {
                     keycode: Some(Keycode::F12),
                     ..
                 } => {
-                    ctx.spritesheet = Spritesheet::new_from_file(
-                        &ctx.canvas.texture_creator(),
-                        "assets/textures/spritesheet.png",
-                        "assets/textures/specular.png",
-                        16,
-                    );
-                    println!("Assets reloaded");
+                    ctx.spritesheets = spritesheets(&ctx.aseprite_images);
+                    world
+                        .resource_mut::<Tuning>()
+                        .unwrap()
+                        .reload(&assets::path(tuning::TUNING_PATH));
+                    // a broken script keeps the old ones
+                    match Scripts::load(scripts::SCRIPT_DIR) {
+                        Ok(scripts) => *world.resource_mut::<Scripts>().unwrap() = scripts,
+                        Err(e) => println!("{}", e),
+                    }
+                    toasts::push(&world, locale::tr("toast-assets-reloaded"));
                 }
                 _ => {}
             }
         }
 
-        let kb = event_pump.keyboard_state();
-        let input = &mut ctx.input;
-        // TODO just_pressed for all
-        input.pressed.up = kb.is_scancode_pressed(Scancode::W);
-        input.pressed.down = kb.is_scancode_pressed(Scancode::S);
-        input.pressed.left = kb.is_scancode_pressed(Scancode::A);
-        input.pressed.right = kb.is_scancode_pressed(Scancode::D);
-        input.pressed.fire_right = kb.is_scancode_pressed(Scancode::Right);
-        input.pressed.fire_left = kb.is_scancode_pressed(Scancode::Left);
-        input.pressed.fire_up = kb.is_scancode_pressed(Scancode::Up);
-        input.pressed.fire_down = kb.is_scancode_pressed(Scancode::Down);
-        input.pressed.shift = kb.is_scancode_pressed(Scancode::LShift);
-        input.just_pressed.interact =
-            !input.pressed.interact && kb.is_scancode_pressed(Scancode::F);
-        input.pressed.interact = kb.is_scancode_pressed(Scancode::F);
-        input.just_pressed.q = !input.pressed.q && kb.is_scancode_pressed(Scancode::Q);
-        input.pressed.q = kb.is_scancode_pressed(Scancode::Q);
-        input.just_pressed.e = !input.pressed.e && kb.is_scancode_pressed(Scancode::E);
-        input.pressed.e = kb.is_scancode_pressed(Scancode::E);
-        input.just_pressed.use_item =
-            !input.pressed.use_item && kb.is_scancode_pressed(Scancode::Space);
-        input.pressed.use_item = kb.is_scancode_pressed(Scancode::Space);
-
-        let update_start = Instant::now();
-        game::update(&world);
-        let end = Instant::now().duration_since(update_start);
-        let update_time = end.as_micros();
+        // typing goes to the console or the lobby's address
+        let is_typing = world.resource::<Console>().unwrap().is_open
+            || world.resource::<Lobby>().unwrap().is_open;
+        if is_typing != video_subsystem.text_input().is_active() {
+            if is_typing {
+                video_subsystem.text_input().start();
+            } else {
+                video_subsystem.text_input().stop();
+            }
+        }
+
+        streaming::update(&world);
+        if streaming::is_loading(ctx) {
+            streaming::draw_loading_screen(&world);
+            continue;
+        }
+
+        let frame_start = Instant::now();
+        // clamped so a long stall doesn't snowball into ever more catch-up ticks
+        accumulator += frame_start.duration_since(last_frame).min(MAX_FRAME_TIME);
+        last_frame = frame_start;
+
+        let mut ticks = 0;
+        while accumulator >= tick_duration {
+            // typing into the console shouldn't move the player
+            if world.resource::<Console>().unwrap().is_open {
+                ctx.input.pressed = InputState::default();
+                ctx.input.just_pressed = InputState::default();
+                let mouse = event_pump.mouse_state();
+                ctx.input.mouse_pos = (mouse.x(), mouse.y());
+            } else {
+                read_input(&mut ctx.input, &event_pump);
+                let controller = world.resource::<Haptics>().unwrap().controller();
+                read_second_input(&mut ctx.player2.input, &event_pump, controller);
+            }
+            ctx.input.mouse_pos = ctx.window_to_render(ctx.input.mouse_pos);
+            world
+                .resource_mut::<Replay>()
+                .unwrap()
+                .tick(&mut ctx.input, &mut ctx.player2.input);
+
+            let update_start = Instant::now();
+            for stage in Stage::UPDATE {
+                scheduler.run(&world, stage);
+            }
+            ctx.ticks = ctx.ticks.wrapping_add(1);
+            let hash = game::state_hash(&world);
+            world.resource_mut::<Replay>().unwrap().check(hash);
+            update_time = Instant::now().duration_since(update_start).as_micros();
+
+            accumulator -= tick_duration;
+            ticks += 1;
+        }
+        if world.resource::<Settings>().unwrap().quit_requested
+            || world.resource::<Lobby>().unwrap().quit_requested
+        {
+            break 'mainloop;
+        }
+        ctx.frame_alpha = accumulator.as_secs_f32() / tick_duration.as_secs_f32();
 
         let render_start = Instant::now();
         ctx.canvas.set_draw_color(Color::RGB(0, 0, 0));
         ctx.canvas.clear();
 
-        game::render(&world);
-        build_lightmap(&world, ctx);
-        ctx.canvas.copy(&ctx.lightmap.lights(), None, None).unwrap();
-        ctx.canvas.copy(&ctx.ui_tex, None, None).unwrap();
+        scheduler.run(&world, Stage::Render);
 
         let end = Instant::now().duration_since(render_start);
         let render_time = end.as_micros();
-        let frame_time = update_time + render_time;
-
-        let sleep_duration = Duration::new(0, 1_000_000_000u32 / 60)
-            .saturating_sub(Instant::now().duration_since(update_start));
-        ::std::thread::sleep(sleep_duration);
+        let frame_time = Instant::now().duration_since(frame_start).as_micros();
 
         use memory_stats::memory_stats;
         let mut mem_usage = 0;
@@ -610,64 +1684,328 @@ pub fn main() {
             mem_usage = usage.physical_mem;
         }
 
-        let surface = font
-            .render(
-                format!(
-                    "MEM: {:.2} MB | FRAME: {:.2}ms | UPDATE: {:.2}ms | RENDER: {:.2}ms",
-                    mem_usage as f32 / (1024 * 1204) as f32,
-                    frame_time as f32 / 1000.,
-                    update_time as f32 / 1000.,
-                    render_time as f32 / 1000.
-                )
-                .as_str(),
-            )
-            .shaded(
-                if sleep_duration.is_zero() {
-                    Color::RGBA(255, 0, 0, 255)
-                } else {
-                    Color::RGBA(255, 255, 255, 255)
-                },
-                Color::RGBA(0, 0, 0, 255),
-            )
-            .map_err(|e| e.to_string())
-            .unwrap();
-        let texture = texture_creator
-            .create_texture_from_surface(&surface)
-            .map_err(|e| e.to_string())
-            .unwrap();
+        let mut ui = Ui::new(
+            &mut ctx.canvas,
+            &ctx.spritesheets[*SPRITESHEET_TEXTURE],
+            &ctx.font,
+            world.resource_mut::<TextCache>().unwrap(),
+        );
+        ui.changing_label(
+            Anchor::TopLeft,
+            (0, 0),
+            &format!(
+                "MEM: {:.2} MB | FRAME: {:.2}ms | UPDATE: {:.2}ms | RENDER: {:.2}ms | DRAWS: {} | SPRITES: {}",
+                mem_usage as f32 / (1024 * 1204) as f32,
+                frame_time as f32 / 1000.,
+                update_time as f32 / 1000.,
+                render_time as f32 / 1000.,
+                ctx.render_stats.draw_calls,
+                ctx.render_stats.sprites,
+            ),
+            // more than one tick per frame means the simulation is catching up
+            if ticks > 1 {
+                Color::RGBA(255, 0, 0, 255)
+            } else {
+                Color::RGBA(255, 255, 255, 255)
+            },
+            Some(Color::RGBA(0, 0, 0, 255)),
+        );
+        if world.resource::<Replay>().unwrap().is_playing() {
+            ui.label(
+                Anchor::TopLeft,
+                (0, 20),
+                "REPLAY",
+                Color::RGB(255, 200, 0),
+                Some(Color::RGBA(0, 0, 0, 255)),
+            );
+        }
 
-        let sdl2::render::TextureQuery { width, height, .. } = texture.query();
-        ctx.canvas
-            .copy(&texture, None, Rect::new(0, 0, width, height))
-            .unwrap();
-        unsafe { texture.destroy() };
+        scheduler.run(&world, Stage::Ui);
+        saves::write_pending(&world);
+        capture::update(&world);
 
         ctx.canvas.present();
+
+        let frame_time = Instant::now().duration_since(frame_start);
+        world
+            .resource_mut::<Profiler>()
+            .unwrap()
+            .record(frame_time, scheduler);
+        scheduler.end_frame();
+    }
+
+    world.resource_mut::<Net>().unwrap().leave();
+    world.resource::<Replay>().unwrap().save();
+    world
+        .resource::<Stats>()
+        .unwrap()
+        .save(&stats::stats_path());
+}
+
+// runs the update stages as fast as possible without drawing or reading the keyboard,
+// input comes from --replay if given and is otherwise left empty
+fn run_headless(world: &World, ticks: u32) {
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    let scheduler = world.resource_mut::<Scheduler>().unwrap();
+
+    let start = Instant::now();
+    for _ in 0..ticks {
+        world
+            .resource_mut::<Replay>()
+            .unwrap()
+            .tick(&mut ctx.input, &mut ctx.player2.input);
+        for stage in Stage::UPDATE {
+            scheduler.run(world, stage);
+        }
+        ctx.ticks = ctx.ticks.wrapping_add(1);
+        let hash = game::state_hash(world);
+        world.resource_mut::<Replay>().unwrap().check(hash);
+        scheduler.end_frame();
+    }
+    let elapsed = Instant::now().duration_since(start);
+
+    let mut entities = 0;
+    let mut enemies = 0;
+    world.run(|_: &Pos| entities += 1);
+    world.run(|_: &Enemy| enemies += 1);
+    println!(
+        "{} ticks in {:.2}s ({:.0} ticks/s), {} entities, {} enemies",
+        ticks,
+        elapsed.as_secs_f32(),
+        ticks as f32 / elapsed.as_secs_f32().max(f32::EPSILON),
+        entities,
+        enemies,
+    );
+    world.run(|_: &Player, pos: &Pos, health: &Health| {
+        println!("player at {:.1}, {:.1} with {} hp", pos.x, pos.y, health.hp);
+    });
+    // the same seed and input always ends on the same hash
+    println!("state hash {:016x}", game::state_hash(world));
+}
+
+fn register_render_systems(scheduler: &mut Scheduler) {
+    scheduler
+        .add(Stage::Render, "lighting", |world| {
+            let ctx = world.resource_mut::<Ctx>().unwrap();
+            build_lightmap(world, ctx);
+            ctx.canvas.copy(&ctx.lightmap.lights(), None, None).unwrap();
+        })
+        .after("sprites");
+    scheduler
+        .add(Stage::Render, "specular", |world| {
+            let ctx = world.resource_mut::<Ctx>().unwrap();
+            build_specular_map(world, ctx);
+            ctx.canvas
+                .copy(&ctx.lightmap.specular_lights(), None, None)
+                .unwrap();
+        })
+        .after("lighting")
+        .run_if(|world| world.resource::<Ctx>().unwrap().specular_enabled);
+    scheduler
+        .add(Stage::Render, "emissive", game::render_emissive)
+        .after("specular");
+    scheduler
+        .add(Stage::Render, "bloom", |world| {
+            let ctx = world.resource_mut::<Ctx>().unwrap();
+            build_bloom(world, ctx);
+            ctx.canvas.copy(&ctx.lightmap.bloom(), None, None).unwrap();
+        })
+        .after("emissive")
+        .run_if(|world| world.resource::<Ctx>().unwrap().bloom_quality > 0);
+    // over the scene but under the ui, so menus stay readable
+    scheduler
+        .add(Stage::Render, "transition", transition::draw)
+        .after("bloom");
+    scheduler
+        .add(Stage::Render, "ui", |world| {
+            let ctx = world.resource_mut::<Ctx>().unwrap();
+            ctx.canvas.copy(&ctx.ui_tex, None, None).unwrap();
+        })
+        .after("transition");
+
+    scheduler.add(Stage::Ui, "profiler", |world| {
+        let ctx = world.resource_mut::<Ctx>().unwrap();
+        let profiler = world.resource::<Profiler>().unwrap();
+        if profiler.is_visible {
+            let mut ui = Ui::new(
+                &mut ctx.canvas,
+                &ctx.spritesheets[*SPRITESHEET_TEXTURE],
+                &ctx.font,
+                world.resource_mut::<TextCache>().unwrap(),
+            );
+            profiler::draw(profiler, &mut ui);
+        }
+    });
+    scheduler
+        .add(Stage::Ui, "console", |world| {
+            let ctx = world.resource_mut::<Ctx>().unwrap();
+            let console = world.resource::<Console>().unwrap();
+            if console.is_open {
+                let mut ui = Ui::new(
+                    &mut ctx.canvas,
+                    &ctx.spritesheets[*SPRITESHEET_TEXTURE],
+                    &ctx.font,
+                    world.resource_mut::<TextCache>().unwrap(),
+                );
+                console::draw(console, &mut ui);
+            }
+        })
+        .after("profiler");
+}
+
+fn read_input(input: &mut Input, event_pump: &EventPump) {
+    let kb = event_pump.keyboard_state();
+    // TODO just_pressed for all
+    input.just_pressed.up = !input.pressed.up && kb.is_scancode_pressed(Scancode::W);
+    input.pressed.up = kb.is_scancode_pressed(Scancode::W);
+    input.just_pressed.down = !input.pressed.down && kb.is_scancode_pressed(Scancode::S);
+    input.pressed.down = kb.is_scancode_pressed(Scancode::S);
+    input.just_pressed.left = !input.pressed.left && kb.is_scancode_pressed(Scancode::A);
+    input.pressed.left = kb.is_scancode_pressed(Scancode::A);
+    input.just_pressed.right = !input.pressed.right && kb.is_scancode_pressed(Scancode::D);
+    input.pressed.right = kb.is_scancode_pressed(Scancode::D);
+    input.just_pressed.fire_right =
+        !input.pressed.fire_right && kb.is_scancode_pressed(Scancode::Right);
+    input.pressed.fire_right = kb.is_scancode_pressed(Scancode::Right);
+    input.just_pressed.fire_left =
+        !input.pressed.fire_left && kb.is_scancode_pressed(Scancode::Left);
+    input.pressed.fire_left = kb.is_scancode_pressed(Scancode::Left);
+    input.just_pressed.fire_up = !input.pressed.fire_up && kb.is_scancode_pressed(Scancode::Up);
+    input.pressed.fire_up = kb.is_scancode_pressed(Scancode::Up);
+    input.just_pressed.fire_down =
+        !input.pressed.fire_down && kb.is_scancode_pressed(Scancode::Down);
+    input.pressed.fire_down = kb.is_scancode_pressed(Scancode::Down);
+    input.pressed.shift = kb.is_scancode_pressed(Scancode::LShift);
+    input.just_pressed.interact = !input.pressed.interact && kb.is_scancode_pressed(Scancode::F);
+    input.pressed.interact = kb.is_scancode_pressed(Scancode::F);
+    input.just_pressed.q = !input.pressed.q && kb.is_scancode_pressed(Scancode::Q);
+    input.pressed.q = kb.is_scancode_pressed(Scancode::Q);
+    input.just_pressed.e = !input.pressed.e && kb.is_scancode_pressed(Scancode::E);
+    input.pressed.e = kb.is_scancode_pressed(Scancode::E);
+    input.just_pressed.use_item =
+        !input.pressed.use_item && kb.is_scancode_pressed(Scancode::Space);
+    input.pressed.use_item = kb.is_scancode_pressed(Scancode::Space);
+    input.just_pressed.tab = !input.pressed.tab && kb.is_scancode_pressed(Scancode::Tab);
+    input.pressed.tab = kb.is_scancode_pressed(Scancode::Tab);
+    input.just_pressed.dash = !input.pressed.dash && kb.is_scancode_pressed(Scancode::LCtrl);
+    input.pressed.dash = kb.is_scancode_pressed(Scancode::LCtrl);
+
+    let mouse = event_pump.mouse_state();
+    input.mouse_pos = (mouse.x(), mouse.y());
+    input.just_pressed.mouse_left = !input.pressed.mouse_left && mouse.left();
+    input.pressed.mouse_left = mouse.left();
+    input.just_pressed.mouse_right = !input.pressed.mouse_right && mouse.right();
+    input.pressed.mouse_right = mouse.right();
+}
+
+// the first controller when there is one, otherwise
+//   move IJKL, fire numpad 8456, interact H, use N, prev/next item U/O, dash/run RCtrl/RShift
+fn read_second_input(
+    input: &mut Input,
+    event_pump: &EventPump,
+    controller: Option<&GameController>,
+) {
+    let kb = event_pump.keyboard_state();
+    let key = |scancode| kb.is_scancode_pressed(scancode);
+    let button = |button| controller.is_some_and(|c| c.button(button));
+    // past a third of the way, either direction
+    let axis = |axis, sign: i32| controller.is_some_and(|c| c.axis(axis) as i32 * sign > 11000);
+
+    let mut state = InputState::default();
+    if controller.is_some() {
+        state.up = button(Button::DPadUp) || axis(Axis::LeftY, -1);
+        state.down = button(Button::DPadDown) || axis(Axis::LeftY, 1);
+        state.left = button(Button::DPadLeft) || axis(Axis::LeftX, -1);
+        state.right = button(Button::DPadRight) || axis(Axis::LeftX, 1);
+        state.fire_up = axis(Axis::RightY, -1);
+        state.fire_down = axis(Axis::RightY, 1);
+        state.fire_left = axis(Axis::RightX, -1);
+        state.fire_right = axis(Axis::RightX, 1);
+        state.interact = button(Button::A);
+        state.use_item = button(Button::X);
+        state.q = button(Button::LeftShoulder);
+        state.e = button(Button::RightShoulder);
+        state.dash = button(Button::B);
+        state.shift = button(Button::LeftStick);
+    } else {
+        state.up = key(Scancode::I);
+        state.down = key(Scancode::K);
+        state.left = key(Scancode::J);
+        state.right = key(Scancode::L);
+        state.fire_up = key(Scancode::Kp8);
+        state.fire_down = key(Scancode::Kp5);
+        state.fire_left = key(Scancode::Kp4);
+        state.fire_right = key(Scancode::Kp6);
+        state.interact = key(Scancode::H);
+        state.use_item = key(Scancode::N);
+        state.q = key(Scancode::U);
+        state.e = key(Scancode::O);
+        state.dash = key(Scancode::RCtrl);
+        state.shift = key(Scancode::RShift);
     }
+
+    let was = input.pressed.to_bits();
+    let now = state.to_bits();
+    input.just_pressed = InputState::from_bits(now & !was);
+    input.pressed = state;
 }
 
 fn build_lightmap(world: &World, ctx: &mut Ctx) {
+    // taken out so the light loop can fill it while the lightmap is borrowed
+    let mut shadow_cache = std::mem::take(&mut ctx.lightmap.shadow_cache);
+    let walls = wall_signature(world);
+    let mut seen = Vec::new();
+
     // TODO cull off-screen lights
     ctx.canvas
         .with_texture_canvas(&mut ctx.lightmap.lights(), |lightmap_canvas| {
             // clear lightmap to ambient
-            lightmap_canvas.set_draw_color(Color::RGB(70, 70, 70));
+            lightmap_canvas.set_draw_color(world.resource::<AmbientLight>().unwrap().current());
             lightmap_canvas.clear();
 
-            world.run(|light: &mut Light, lp: &Pos| {
-                let camera_pos = world.resource::<Ctx>().unwrap().camera_pos();
-                let x = lp.x - camera_pos.0 as f32;
-                let y = lp.y - camera_pos.1 as f32;
+            if ctx.specular_enabled {
+                lightmap_canvas
+                    .with_texture_canvas(&mut ctx.lightmap.specular_lights(), |specular_canvas| {
+                        specular_canvas.set_draw_color(Color::RGB(0, 0, 0));
+                        specular_canvas.clear();
+                    })
+                    .unwrap();
+            }
+
+            world.run(|entity: &Entity, light: &mut Light, lp: &Pos| {
+                // snapped like the sprites, a torch's light sits still on its torch
+                let lp = &Pos::from(game::render_pos(world, *entity, lp).pixel());
+                let camera = world.resource::<Ctx>().unwrap().camera();
+                let (x, y) = world_to_screen(lp, &camera);
 
                 if ctx.shadows_enabled {
-                    build_shadow_mask(
-                        light,
-                        *lp,
-                        camera_pos.into(),
-                        &ctx.lightmap,
-                        world,
-                        lightmap_canvas,
-                    );
+                    seen.push(*entity);
+                    let is_moving = world
+                        .component::<PrevPos>(*entity)
+                        .is_some_and(|prev| prev.0.x != lp.x || prev.0.y != lp.y);
+                    if is_moving || has_occluders_near(world, *entity, *lp, light.radius) {
+                        build_shadow_mask(
+                            *entity,
+                            light,
+                            *lp,
+                            &camera,
+                            &mut ctx.lightmap.mask(),
+                            world,
+                            lightmap_canvas,
+                        );
+                    } else {
+                        draw_cached_shadow(
+                            &mut shadow_cache,
+                            *entity,
+                            light,
+                            *lp,
+                            &camera,
+                            walls,
+                            &ctx.lightmap,
+                            world,
+                            lightmap_canvas,
+                        );
+                    }
                 }
 
                 lightmap_canvas
@@ -686,7 +2024,7 @@ fn build_lightmap(world: &World, ctx: &mut Ctx) {
                                     &ctx.light_tex,
                                     None,
                                     Rect::from_center(
-                                        (x as i32, y as i32),
+                                        (x, y),
                                         (light.radius as u32) * 2,
                                         (light.radius as u32) * 2,
                                     ),
@@ -703,24 +2041,442 @@ fn build_lightmap(world: &World, ctx: &mut Ctx) {
                 lightmap_canvas
                     .copy(&ctx.lightmap.per_light_tex(), None, None)
                     .unwrap();
+
+                if ctx.specular_enabled && light.radius > 0 && light.intensity > 0. {
+                    let viewport = lightmap_canvas.viewport();
+                    lightmap_canvas
+                        .with_texture_canvas(
+                            &mut ctx.lightmap.per_light_tex(),
+                            |per_light_canvas| {
+                                per_light_canvas.set_draw_color(Color::RGB(0, 0, 0));
+                                per_light_canvas.clear();
+                                draw_specular_highlight(
+                                    per_light_canvas,
+                                    &mut ctx.light_tex,
+                                    light,
+                                    (x as f32, y as f32),
+                                    viewport,
+                                );
+                                mask_light_cone(per_light_canvas, light, (x as f32, y as f32));
+                                per_light_canvas
+                                    .copy(&ctx.lightmap.mask(), None, None)
+                                    .unwrap();
+                            },
+                        )
+                        .unwrap();
+
+                    lightmap_canvas
+                        .with_texture_canvas(
+                            &mut ctx.lightmap.specular_lights(),
+                            |specular_canvas| {
+                                specular_canvas
+                                    .copy(&ctx.lightmap.per_light_tex(), None, None)
+                                    .unwrap();
+                            },
+                        )
+                        .unwrap();
+                }
+            });
+        })
+        .unwrap();
+
+    // despawned lights, or all of them with shadows off
+    let gone: Vec<Entity> = shadow_cache
+        .keys()
+        .filter(|entity| !seen.contains(entity))
+        .copied()
+        .collect();
+    for entity in gone {
+        unsafe { shadow_cache.remove(&entity).unwrap().texture.destroy() }
+    }
+    ctx.lightmap.shadow_cache = shadow_cache;
+}
+
+// changes whenever a wall shadow could, the same walls in any order give the same value
+fn wall_signature(world: &World) -> u64 {
+    let mut signature = 0u64;
+    world.run(|entity: &Entity, og: &LightOccluderGroup, pos: &Pos| {
+        let mut hasher = DefaultHasher::new();
+        entity.hash(&mut hasher);
+        og.is_enabled.hash(&mut hasher);
+        pos.x.to_bits().hash(&mut hasher);
+        pos.y.to_bits().hash(&mut hasher);
+        for occluder in og.occluders.iter().flatten() {
+            (occluder.line.0.x, occluder.line.0.y).hash(&mut hasher);
+            (occluder.line.1.x, occluder.line.1.y).hash(&mut hasher);
+        }
+        signature = signature.wrapping_add(hasher.finish());
+    });
+    signature
+}
+
+// moving occluders in reach of the light mean its mask can't come from the cache
+fn has_occluders_near(world: &World, light_entity: Entity, lp: Pos, radius: u16) -> bool {
+    let mut found = false;
+    world.run(|entity: &Entity, occluder: &Occluder, pos: &Pos| {
+        if found || !occluder.is_enabled || *entity == light_entity {
+            return;
+        }
+        let size = match occluder.shape {
+            OccluderShape::Circle(r) => r,
+            OccluderShape::Rect(_, _, w, h) => w.max(h),
+        };
+        let (dx, dy) = (pos.x - lp.x, pos.y - lp.y);
+        let reach = (radius + size) as f32;
+        found = dx * dx + dy * dy < reach * reach;
+    });
+    found
+}
+
+// the wall shadows are drawn once around the light and copied into the mask after that
+#[allow(clippy::too_many_arguments)]
+fn draw_cached_shadow(
+    cache: &mut HashMap<Entity, CachedShadow>,
+    entity: Entity,
+    light: &Light,
+    lp: Pos,
+    camera: &Transform,
+    walls: u64,
+    lightmap: &Lightmap,
+    world: &World,
+    canvas: &mut Canvas<Window>,
+) {
+    let size = (light.radius as u32 * 2).max(1);
+    let is_stale = cache.get(&entity).is_none_or(|cached| {
+        cached.pos != (lp.x, lp.y) || cached.radius != light.radius || cached.walls != walls
+    });
+    if is_stale {
+        if let Some(old) = cache.remove(&entity) {
+            unsafe { old.texture.destroy() }
+        }
+        let mut texture = canvas
+            .texture_creator()
+            .create_texture_target(canvas.default_pixel_format(), size, size)
+            .unwrap();
+        // a camera that puts the light in the middle of the texture
+        let local_camera =
+            Transform::translation(light.radius as f32 - lp.x, light.radius as f32 - lp.y);
+        build_shadow_mask(
+            entity,
+            light,
+            lp,
+            &local_camera,
+            &mut texture,
+            world,
+            canvas,
+        );
+        cache.insert(
+            entity,
+            CachedShadow {
+                texture,
+                pos: (lp.x, lp.y),
+                radius: light.radius,
+                walls,
+            },
+        );
+    }
+
+    let cached = &cache[&entity];
+    let (x, y) = world_to_screen(&lp, camera);
+    canvas
+        .with_texture_canvas(&mut lightmap.mask(), |mask_canvas| {
+            mask_canvas.set_draw_color(Color::RGB(255, 255, 255));
+            mask_canvas.clear();
+            mask_canvas
+                .copy(
+                    &cached.texture,
+                    None,
+                    Rect::new(x - light.radius as i32, y - light.radius as i32, size, size),
+                )
+                .unwrap();
+        })
+        .unwrap();
+}
+
+// blacks out everything outside of a cone light
+fn mask_light_cone(canvas: &mut Canvas<Window>, light: &Light, light_screen_pos: (f32, f32)) {
+    let Some(cone) = light.cone else {
+        return;
+    };
+
+    // fan around the back of the cone, far enough out to cover the light texture
+    let reach = light.radius as f32 * 2.;
+    let back_angle = std::f32::consts::TAU - cone.angle;
+    let mut xs = vec![light_screen_pos.0 as i16];
+    let mut ys = vec![light_screen_pos.1 as i16];
+    for i in 0..=16 {
+        let theta = cone.direction + cone.angle / 2. + back_angle * i as f32 / 16.;
+        xs.push((light_screen_pos.0 + theta.cos() * reach) as i16);
+        ys.push((light_screen_pos.1 + theta.sin() * reach) as i16);
+    }
+
+    canvas
+        .filled_polygon(&xs, &ys, Color::RGB(0, 0, 0))
+        .unwrap();
+}
+
+// highlights lean towards the viewer, who looks down at the screen center, and
+// fade as the light moves away from it
+fn draw_specular_highlight(
+    canvas: &mut Canvas<Window>,
+    light_tex: &mut Texture,
+    light: &Light,
+    light_screen_pos: (f32, f32),
+    viewport: Rect,
+) {
+    let center = viewport.center();
+    let mut to_viewer = Vec2::new(
+        center.x as f32 - light_screen_pos.0,
+        center.y as f32 - light_screen_pos.1,
+    );
+    let distance = to_viewer.magnitude();
+    let max_distance = Vec2::new(center.x as f32, center.y as f32).magnitude();
+    let facing = 1. - (distance / max_distance).min(1.) * 0.5;
+    if distance > 0. {
+        to_viewer.scale(light.radius as f32 * SPECULAR_OFFSET / distance);
+    }
+
+    let intensity = light.intensity * facing * SPECULAR_INTENSITY;
+    // highlights wash out towards white
+    light_tex.set_color_mod(
+        ((light.color.r as f32 + 255.) / 2. * intensity).min(255.) as u8,
+        ((light.color.g as f32 + 255.) / 2. * intensity).min(255.) as u8,
+        ((light.color.b as f32 + 255.) / 2. * intensity).min(255.) as u8,
+    );
+
+    let radius = (light.radius as f32 * SPECULAR_RADIUS) as u32;
+    canvas
+        .copy(
+            light_tex,
+            None,
+            Rect::from_center(
+                (
+                    (light_screen_pos.0 + to_viewer.x) as i32,
+                    (light_screen_pos.1 + to_viewer.y) as i32,
+                ),
+                radius * 2,
+                radius * 2,
+            ),
+        )
+        .unwrap();
+}
+
+// masks the accumulated highlights by the scene drawn with the specular texture
+fn build_specular_map(world: &World, ctx: &mut Ctx) {
+    ctx.canvas
+        .with_texture_canvas(&mut ctx.lightmap.specular_map(), |specular_map_canvas| {
+            game::render_specular(world, specular_map_canvas);
+        })
+        .unwrap();
+
+    ctx.canvas
+        .with_texture_canvas(&mut ctx.lightmap.specular_lights(), |specular_canvas| {
+            specular_canvas
+                .copy(&ctx.lightmap.specular_map(), None, None)
+                .unwrap();
+        })
+        .unwrap();
+}
+
+// glow around emissive sprites and light sources. they're drawn small into the bloom
+// texture, blurred back and forth with the scratch texture and added over the frame
+// by the caller, tinted down to BLOOM_STRENGTH
+fn build_bloom(world: &World, ctx: &mut Ctx) {
+    let mut bloom = ctx.lightmap.bloom();
+    let mut scratch = ctx.lightmap.bloom_scratch();
+    let camera = ctx.camera();
+    let scale = 1. / BLOOM_DOWNSCALE as f32;
+
+    ctx.canvas
+        .with_texture_canvas(&mut bloom, |bloom_canvas| {
+            bloom_canvas.set_draw_color(Color::RGB(0, 0, 0));
+            bloom_canvas.clear();
+            bloom_canvas.set_scale(scale, scale).unwrap();
+
+            world.run(|entity: &Entity, light: &Light, lp: &Pos| {
+                if light.radius == 0 || light.intensity <= 0. {
+                    return;
+                }
+                let lp = game::render_pos(world, *entity, lp);
+                let size = (light.radius as f32 * BLOOM_LIGHT_CORE * 2.) as u32;
+                ctx.light_tex.set_color_mod(
+                    (light.color.r as f32 * light.intensity.min(1.)) as u8,
+                    (light.color.g as f32 * light.intensity.min(1.)) as u8,
+                    (light.color.b as f32 * light.intensity.min(1.)) as u8,
+                );
+                bloom_canvas
+                    .copy(
+                        &ctx.light_tex,
+                        None,
+                        Rect::from_center(world_to_screen(&lp, &camera), size, size),
+                    )
+                    .unwrap();
             });
+            game::draw_emissive(world, bloom_canvas);
+            bloom_canvas.set_scale(1., 1.).unwrap();
         })
         .unwrap();
+
+    // each tap adds a share of the source, so a pass is a box blur along one axis
+    let share = (255 / (BLOOM_TAPS * 2 + 1)) as u8;
+    bloom.set_color_mod(share, share, share);
+    scratch.set_color_mod(share, share, share);
+    let blur = |canvas: &mut Canvas<Window>, src: &Texture, dst: &mut Texture, step: (i32, i32)| {
+        let query = src.query();
+        canvas
+            .with_texture_canvas(dst, |dst_canvas| {
+                dst_canvas.set_draw_color(Color::RGB(0, 0, 0));
+                dst_canvas.clear();
+                for tap in -BLOOM_TAPS..=BLOOM_TAPS {
+                    let dst = Rect::new(tap * step.0, tap * step.1, query.width, query.height);
+                    dst_canvas.copy(src, None, dst).unwrap();
+                }
+            })
+            .unwrap();
+    };
+    for _ in 0..BLOOM_PASSES[ctx.bloom_quality] {
+        blur(&mut ctx.canvas, &bloom, &mut scratch, (1, 0));
+        blur(&mut ctx.canvas, &scratch, &mut bloom, (0, 1));
+    }
+    bloom.set_color_mod(BLOOM_STRENGTH, BLOOM_STRENGTH, BLOOM_STRENGTH);
+}
+
+// the quad from an edge out to well past the light's reach, away from the light
+fn cast_shadow(
+    canvas: &mut Canvas<Window>,
+    lp: Pos,
+    reach: f32,
+    p0: Point,
+    p1: Point,
+    color: Color,
+) {
+    let far = |p: Point| {
+        let theta = f32::atan2(lp.y - p.y as f32, lp.x - p.x as f32);
+        (
+            lp.x as i32 - (theta.cos() * reach) as i32,
+            lp.y as i32 - (theta.sin() * reach) as i32,
+        )
+    };
+    let (p0_prime, p1_prime) = (far(p0), far(p1));
+    canvas
+        .filled_polygon(
+            &[
+                p0.x as i16,
+                p1.x as i16,
+                p1_prime.0 as i16,
+                p0_prime.0 as i16,
+            ],
+            &[
+                p0.y as i16,
+                p1.y as i16,
+                p1_prime.1 as i16,
+                p0_prime.1 as i16,
+            ],
+            color,
+        )
+        .unwrap();
+}
+
+// shadows from Occluders, in screen space. blobs go first so walls and solid
+// occluders paint over them
+fn cast_occluder_shadows(
+    world: &World,
+    canvas: &mut Canvas<Window>,
+    light_entity: Entity,
+    lp: Pos,
+    camera: &Transform,
+    radius: f32,
+    blobs: bool,
+) {
+    world.run(|entity: &Entity, occluder: &Occluder, pos: &Pos| {
+        // nothing shadows its own light
+        if !occluder.is_enabled || occluder.is_blob != blobs || *entity == light_entity {
+            return;
+        }
+        let pos = game::render_pos(world, *entity, pos);
+        let center = Pos::from(world_to_screen(&pos, camera));
+        let (dx, dy) = (center.x - lp.x, center.y - lp.y);
+        let distance = (dx * dx + dy * dy).sqrt();
+
+        match occluder.shape {
+            OccluderShape::Circle(r) => {
+                let r = r as f32;
+                if distance <= r || distance > radius + r {
+                    return;
+                }
+                let steps: &[(f32, u8)] = if blobs {
+                    &BLOB_SHADOW_STEPS
+                } else {
+                    &[(1., 0)]
+                };
+                for (scale, shade) in steps {
+                    let r = (r * scale).min(distance - 1.);
+                    // the edge between the two tangent points as seen from the light
+                    let to_light = f32::atan2(-dy, -dx);
+                    let spread = (r / distance).acos();
+                    let tangent = |angle: f32| {
+                        Point::new(
+                            (center.x + angle.cos() * r) as i32,
+                            (center.y + angle.sin() * r) as i32,
+                        )
+                    };
+                    cast_shadow(
+                        canvas,
+                        lp,
+                        radius * 10.,
+                        tangent(to_light + spread),
+                        tangent(to_light - spread),
+                        Color::RGB(*shade, *shade, *shade),
+                    );
+                }
+            }
+            OccluderShape::Rect(x, y, w, h) => {
+                let (x0, y0) = ((center.x + x as f32) as i32, (center.y + y as f32) as i32);
+                let (x1, y1) = (x0 + w as i32, y0 + h as i32);
+                let inside = (x0..x1).contains(&(lp.x as i32)) && (y0..y1).contains(&(lp.y as i32));
+                if inside || distance > radius + (w.max(h)) as f32 {
+                    return;
+                }
+                let shade = if blobs {
+                    BLOB_SHADOW_STEPS[BLOB_SHADOW_STEPS.len() - 1].1
+                } else {
+                    0
+                };
+                let corners = [
+                    Point::new(x0, y0),
+                    Point::new(x1, y0),
+                    Point::new(x1, y1),
+                    Point::new(x0, y1),
+                ];
+                for i in 0..4 {
+                    cast_shadow(
+                        canvas,
+                        lp,
+                        radius * 10.,
+                        corners[i],
+                        corners[(i + 1) % 4],
+                        Color::RGB(shade, shade, shade),
+                    );
+                }
+            }
+        }
+    });
 }
 
 fn build_shadow_mask(
+    light_entity: Entity,
     light: &Light,
     lp: Pos, // light pos
-    cp: Pos, // camera pos
-    lightmap: &Lightmap,
+    camera: &Transform,
+    target: &mut Texture,
     world: &World,
     canvas: &mut Canvas<Window>,
 ) {
     // world space to screen space
-    let lp = Pos::new(lp.x - cp.x, lp.y - cp.y);
+    let lp = Pos::from(world_to_screen(&lp, camera));
 
     canvas
-        .with_texture_canvas(&mut lightmap.mask(), |shadow_mask_canvas| {
+        .with_texture_canvas(target, |shadow_mask_canvas| {
             // clear occlusion mask
             shadow_mask_canvas.set_draw_color(Color::RGB(255, 255, 255));
             shadow_mask_canvas.clear();
@@ -731,51 +2487,55 @@ fn build_shadow_mask(
                 light.radius as u32 * 2,
                 light.radius as u32 * 2,
             );
+            let radius = light.radius as f32;
+
+            cast_occluder_shadows(
+                world,
+                shadow_mask_canvas,
+                light_entity,
+                lp,
+                camera,
+                radius,
+                true,
+            );
 
             world.run(|og: &LightOccluderGroup, pos: &Pos| {
+                if !og.is_enabled {
+                    return;
+                }
+
                 for occluder in og.occluders.into_iter().flatten() {
                     let mut p0 = occluder.line.0;
                     let mut p1 = occluder.line.1;
-                    p0.x -= cp.x as i32 - pos.x as i32;
-                    p0.y -= cp.y as i32 - pos.y as i32;
-                    p1.x -= cp.x as i32 - pos.x as i32;
-                    p1.y -= cp.y as i32 - pos.y as i32;
+                    let (x, y) = world_to_screen(pos, camera);
+                    p0.x += x;
+                    p0.y += y;
+                    p1.x += x;
+                    p1.y += y;
 
                     if light_bounds.intersect_line(p0, p1).is_some() {
-                        let theta_0 = f32::atan2(lp.y - p0.y as f32, lp.x - p0.x as f32);
-                        let theta_1 = f32::atan2(lp.y - p1.y as f32, lp.x - p1.x as f32);
-
                         // TODO extrapolate p0' and p1' to screen edge
-                        let p0_prime = (
-                            lp.x as i32 - (theta_0.cos() * light.radius as f32 * 10.) as i32,
-                            lp.y as i32 - (theta_0.sin() * light.radius as f32 * 10.) as i32,
+                        cast_shadow(
+                            shadow_mask_canvas,
+                            lp,
+                            radius * 10.,
+                            p0,
+                            p1,
+                            Color::RGB(0, 0, 0),
                         );
-
-                        let p1_prime = (
-                            lp.x as i32 - (theta_1.cos() * light.radius as f32 * 10.) as i32,
-                            lp.y as i32 - (theta_1.sin() * light.radius as f32 * 10.) as i32,
-                        );
-
-                        shadow_mask_canvas
-                            .filled_polygon(
-                                &[
-                                    p0.x as i16,
-                                    p1.x as i16,
-                                    p1_prime.0 as i16,
-                                    p0_prime.0 as i16,
-                                ],
-                                &[
-                                    p0.y as i16,
-                                    p1.y as i16,
-                                    p1_prime.1 as i16,
-                                    p0_prime.1 as i16,
-                                ],
-                                Color::RGB(0, 0, 0),
-                            )
-                            .unwrap();
                     }
                 }
             });
+
+            cast_occluder_shadows(
+                world,
+                shadow_mask_canvas,
+                light_entity,
+                lp,
+                camera,
+                radius,
+                false,
+            );
         })
         .unwrap();
 }
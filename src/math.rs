@@ -1,9 +1,16 @@
-// TODO tests
-
-use std::ops::{Add, Div, DivAssign, Mul, MulAssign};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
 pub trait Scalar<S>:
-    Add<Output = S> + Mul<S, Output = S> + Div<S, Output = S> + MulAssign + DivAssign + Copy + Sized
+    Add<Output = S>
+    + Sub<Output = S>
+    + Mul<S, Output = S>
+    + Div<S, Output = S>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + DivAssign
+    + Copy
+    + Sized
 {
     fn zero() -> S;
     fn sqrt(self) -> S;
@@ -85,7 +92,7 @@ impl Scalar<u16> for u16 {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Vec2<T> {
     pub x: T,
     pub y: T,
@@ -105,7 +112,7 @@ impl<T: Scalar<T>> Vec2<T> {
     }
 
     pub fn magnitude(&self) -> T {
-        T::sqrt(self.x.powi(2) + self.y.powi(2))
+        T::sqrt(self.x * self.x + self.y * self.y)
     }
 
     pub fn normalize(&mut self) {
@@ -128,6 +135,65 @@ impl<T: Scalar<T>> Vec2<T> {
     pub fn scaled(&self, scale: T) -> Vec2<T> {
         Vec2::<T>::new(self.x * scale, self.y * scale)
     }
+
+    pub fn dot(&self, other: Vec2<T>) -> T {
+        self.x * other.x + self.y * other.y
+    }
+
+    // the z of the 3d cross product, positive when other is clockwise from self on screen
+    pub fn cross(&self, other: Vec2<T>) -> T {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn distance(&self, other: Vec2<T>) -> T {
+        (other - *self).magnitude()
+    }
+}
+
+#[allow(dead_code)]
+impl Vec2<f32> {
+    // turned a quarter clockwise on screen, y points down
+    pub fn perp(&self) -> Vec2<f32> {
+        Vec2::new(-self.y, self.x)
+    }
+
+    pub fn lerp(&self, other: Vec2<f32>, t: f32) -> Vec2<f32> {
+        *self + (other - *self) * t
+    }
+
+    // radians from the +x axis, through the deterministic atan2
+    pub fn angle(&self) -> f32 {
+        atan2(self.y, self.x)
+    }
+
+    pub fn from_angle(angle: f32) -> Vec2<f32> {
+        Vec2::new(cos(angle), sin(angle))
+    }
+
+    // shortened to max_length if it's longer, the direction stays
+    pub fn clamp_length(&self, max_length: f32) -> Vec2<f32> {
+        let length = self.magnitude();
+        if length > max_length && length > 0. {
+            *self * (max_length / length)
+        } else {
+            *self
+        }
+    }
+
+    // bounced off a surface, normal has to be unit length
+    pub fn reflect(&self, normal: Vec2<f32>) -> Vec2<f32> {
+        *self - normal * (2. * self.dot(normal))
+    }
+
+    // the nearest of all eight, right for a zero vector
+    pub fn to_direction8(self) -> Direction {
+        Direction::from_angle(self.angle())
+    }
+
+    // the nearest of up, down, left and right
+    pub fn to_direction4(self) -> Direction {
+        Direction::straight_from_angle(self.angle())
+    }
 }
 
 impl<S: Scalar<S>> From<(S, S)> for Vec2<S> {
@@ -136,7 +202,53 @@ impl<S: Scalar<S>> From<(S, S)> for Vec2<S> {
     }
 }
 
-#[derive(Clone, Copy)]
+impl<T: Scalar<T>> Add for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn add(self, other: Vec2<T>) -> Vec2<T> {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl<T: Scalar<T>> Sub for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn sub(self, other: Vec2<T>) -> Vec2<T> {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl<T: Scalar<T>> Mul<T> for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn mul(self, scale: T) -> Vec2<T> {
+        self.scaled(scale)
+    }
+}
+
+impl<T: Scalar<T> + Neg<Output = T>> Neg for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn neg(self) -> Vec2<T> {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl<T: Scalar<T>> AddAssign for Vec2<T> {
+    fn add_assign(&mut self, other: Vec2<T>) {
+        self.x += other.x;
+        self.y += other.y;
+    }
+}
+
+impl<T: Scalar<T>> SubAssign for Vec2<T> {
+    fn sub_assign(&mut self, other: Vec2<T>) {
+        self.x -= other.x;
+        self.y -= other.y;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Vec3<T> {
     pub x: T,
     pub y: T,
@@ -156,4 +268,715 @@ impl<T: Scalar<T>> Vec3<T> {
             z: T::zero(),
         }
     }
+
+    pub fn magnitude(&self) -> T {
+        T::sqrt(self.dot(*self))
+    }
+
+    pub fn dot(&self, other: Vec3<T>) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(&self, other: Vec3<T>) -> Vec3<T> {
+        Vec3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn distance(&self, other: Vec3<T>) -> T {
+        (other - *self).magnitude()
+    }
+}
+
+#[allow(dead_code)]
+impl Vec3<f32> {
+    pub fn lerp(&self, other: Vec3<f32>, t: f32) -> Vec3<f32> {
+        *self + (other - *self) * t
+    }
+}
+
+impl<T: Scalar<T>> Add for Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn add(self, other: Vec3<T>) -> Vec3<T> {
+        Vec3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl<T: Scalar<T>> Sub for Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn sub(self, other: Vec3<T>) -> Vec3<T> {
+        Vec3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl<T: Scalar<T>> Mul<T> for Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn mul(self, scale: T) -> Vec3<T> {
+        Vec3::new(self.x * scale, self.y * scale, self.z * scale)
+    }
+}
+
+impl<T: Scalar<T> + Neg<Output = T>> Neg for Vec3<T> {
+    type Output = Vec3<T>;
+
+    fn neg(self) -> Vec3<T> {
+        Vec3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl<T: Scalar<T>> AddAssign for Vec3<T> {
+    fn add_assign(&mut self, other: Vec3<T>) {
+        self.x += other.x;
+        self.y += other.y;
+        self.z += other.z;
+    }
+}
+
+impl<T: Scalar<T>> SubAssign for Vec3<T> {
+    fn sub_assign(&mut self, other: Vec3<T>) {
+        self.x -= other.x;
+        self.y -= other.y;
+        self.z -= other.z;
+    }
+}
+
+// 2d affine transform, scale and rotation and then a translation. a point maps to
+//   (a * x + c * y + tx, b * x + d * y + ty)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Transform {
+    a: f32,
+    b: f32,
+    c: f32,
+    d: f32,
+    tx: f32,
+    ty: f32,
+}
+
+#[allow(dead_code)]
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        a: 1.,
+        b: 0.,
+        c: 0.,
+        d: 1.,
+        tx: 0.,
+        ty: 0.,
+    };
+
+    pub fn translation(x: f32, y: f32) -> Transform {
+        Transform {
+            tx: x,
+            ty: y,
+            ..Transform::IDENTITY
+        }
+    }
+
+    // clockwise on screen, through the deterministic trig
+    pub fn rotation(angle: f32) -> Transform {
+        let (sin, cos) = (sin(angle), cos(angle));
+        Transform {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            ..Transform::IDENTITY
+        }
+    }
+
+    pub fn scale(x: f32, y: f32) -> Transform {
+        Transform {
+            a: x,
+            d: y,
+            ..Transform::IDENTITY
+        }
+    }
+
+    // scaled, then rotated, then moved, the usual order for placing something
+    pub fn new(translation: Vec2<f32>, rotation: f32, scale: Vec2<f32>) -> Transform {
+        Transform::translation(translation.x, translation.y)
+            .compose(&Transform::rotation(rotation))
+            .compose(&Transform::scale(scale.x, scale.y))
+    }
+
+    // other first and then self, like a parent's transform composed with a child's local one
+    pub fn compose(&self, other: &Transform) -> Transform {
+        Transform {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            tx: self.a * other.tx + self.c * other.ty + self.tx,
+            ty: self.b * other.tx + self.d * other.ty + self.ty,
+        }
+    }
+
+    // None when it squashes everything onto a line or a point
+    pub fn inverse(&self) -> Option<Transform> {
+        let det = self.a * self.d - self.b * self.c;
+        if det == 0. {
+            return None;
+        }
+        let (a, b, c, d) = (self.d / det, -self.b / det, -self.c / det, self.a / det);
+        Some(Transform {
+            a,
+            b,
+            c,
+            d,
+            tx: -(a * self.tx + c * self.ty),
+            ty: -(b * self.tx + d * self.ty),
+        })
+    }
+
+    pub fn apply(&self, point: Vec2<f32>) -> Vec2<f32> {
+        self.apply_vector(point) + self.offset()
+    }
+
+    // directions and distances, which don't move with the translation
+    pub fn apply_vector(&self, vector: Vec2<f32>) -> Vec2<f32> {
+        Vec2::new(
+            self.a * vector.x + self.c * vector.y,
+            self.b * vector.x + self.d * vector.y,
+        )
+    }
+
+    // where it puts the origin
+    pub fn offset(&self) -> Vec2<f32> {
+        Vec2::new(self.tx, self.ty)
+    }
+
+    pub fn angle(&self) -> f32 {
+        atan2(self.b, self.a)
+    }
+}
+
+impl Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, other: Transform) -> Transform {
+        self.compose(&other)
+    }
+}
+
+// which way something faces, clockwise from right like angles go on screen (y points down)
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+    Up,
+    UpRight,
+}
+
+#[allow(dead_code)]
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::Right,
+        Direction::DownRight,
+        Direction::Down,
+        Direction::DownLeft,
+        Direction::Left,
+        Direction::UpLeft,
+        Direction::Up,
+        Direction::UpRight,
+    ];
+
+    // the nearest of all eight
+    pub fn from_angle(angle: f32) -> Direction {
+        let sector = (angle / std::f32::consts::TAU * 8.).round() as i32;
+        Direction::ALL[sector.rem_euclid(8) as usize]
+    }
+
+    // the nearest of up, down, left and right
+    pub fn straight_from_angle(angle: f32) -> Direction {
+        let sector = (angle / std::f32::consts::TAU * 4.).round() as i32;
+        Direction::ALL[sector.rem_euclid(4) as usize * 2]
+    }
+
+    pub fn angle(self) -> f32 {
+        self as usize as f32 * std::f32::consts::FRAC_PI_4
+    }
+
+    // unit length, diagonals too
+    pub fn to_vec2(self) -> Vec2<f32> {
+        use std::f32::consts::FRAC_1_SQRT_2 as D;
+
+        match self {
+            Direction::Right => Vec2::new(1., 0.),
+            Direction::DownRight => Vec2::new(D, D),
+            Direction::Down => Vec2::new(0., 1.),
+            Direction::DownLeft => Vec2::new(-D, D),
+            Direction::Left => Vec2::new(-1., 0.),
+            Direction::UpLeft => Vec2::new(-D, -D),
+            Direction::Up => Vec2::new(0., -1.),
+            Direction::UpRight => Vec2::new(D, -D),
+        }
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        self as usize % 2 == 1
+    }
+
+    pub fn opposite(self) -> Direction {
+        Direction::ALL[(self as usize + 4) % 8]
+    }
+}
+
+// trig for the simulation. std's sin and atan2 come from the platform's libm and can differ
+// in the last bit between machines, which is enough for a replay or a co-op partner to drift
+// apart. these only use + - * / and round, which ieee pins down exactly
+pub fn sin(x: f32) -> f32 {
+    use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+    // into -pi..pi, then folded into -pi/2..pi/2 where the series converges quickly
+    let mut x = x - TAU * (x / TAU).round();
+    if x > FRAC_PI_2 {
+        x = PI - x;
+    } else if x < -FRAC_PI_2 {
+        x = -PI - x;
+    }
+    let x2 = x * x;
+    x * (1. - x2 / 6. * (1. - x2 / 20. * (1. - x2 / 42. * (1. - x2 / 72. * (1. - x2 / 110.)))))
+}
+
+pub fn cos(x: f32) -> f32 {
+    sin(x + std::f32::consts::FRAC_PI_2)
+}
+
+pub fn atan2(y: f32, x: f32) -> f32 {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_6, PI};
+
+    if x == 0. && y == 0. {
+        return 0.;
+    }
+    let (ax, ay) = (x.abs(), y.abs());
+    let ratio = ax.min(ay) / ax.max(ay);
+
+    // atan of 0..1, shifted by pi/6 above tan(pi/12) so the series stays short
+    const TAN_PI_12: f32 = 0.267_949_2;
+    const SQRT_3: f32 = 1.732_050_8;
+    let (t, offset) = if ratio > TAN_PI_12 {
+        ((ratio * SQRT_3 - 1.) / (ratio + SQRT_3), FRAC_PI_6)
+    } else {
+        (ratio, 0.)
+    };
+    let t2 = t * t;
+    let mut angle =
+        offset + t * (1. - t2 * (1. / 3. - t2 * (1. / 5. - t2 * (1. / 7. - t2 * (1. / 9.)))));
+
+    if ay > ax {
+        angle = FRAC_PI_2 - angle;
+    }
+    if x < 0. {
+        angle = PI - angle;
+    }
+    if y < 0. {
+        -angle
+    } else {
+        angle
+    }
+}
+
+// 0 below edge0, 1 above edge1 and an s-curve in between
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
+// easing curves, t goes from 0 to 1 and so do they, apart from overshooting ones
+pub fn ease_in(t: f32) -> f32 {
+    t * t
+}
+
+pub fn ease_out(t: f32) -> f32 {
+    1. - (1. - t) * (1. - t)
+}
+
+pub fn ease_in_out(t: f32) -> f32 {
+    smoothstep(0., 1., t)
+}
+
+// overshoots and wobbles into place
+pub fn ease_out_elastic(t: f32) -> f32 {
+    if t <= 0. || t >= 1. {
+        return t.clamp(0., 1.);
+    }
+    let period = std::f32::consts::TAU / 3.;
+    (-10. * t).exp2() * sin((t * 10. - 0.75) * period) + 1.
+}
+
+// drops in and bounces a few times, each lower than the last
+pub fn ease_out_bounce(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1. / D {
+        N * t * t
+    } else if t < 2. / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+// noise for things that should wander smoothly rather than jump every tick, flames, camera
+// shake, how a level is laid out. like the trig it only uses + - * / and floor, so it's the
+// same on every machine. each seed is an unrelated pattern, features are about one unit
+// apart so scale the input to taste
+
+fn hash(x: i32, y: i32, seed: u32) -> u32 {
+    let mut h = seed ^ (x as u32).wrapping_mul(0x27d4_eb2d) ^ (y as u32).wrapping_mul(0x1656_67b1);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+// -1..1, fixed for each lattice point
+fn lattice(x: i32, y: i32, seed: u32) -> f32 {
+    (hash(x, y, seed) >> 8) as f32 / (1 << 23) as f32 - 1.
+}
+
+// smootherstep, flat at both ends so there are no creases at the lattice lines
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// -1..1 along a line
+pub fn value_noise(x: f32, seed: u32) -> f32 {
+    let x0 = x.floor();
+    let t = fade(x - x0);
+    let x0 = x0 as i32;
+    lerp(lattice(x0, 0, seed), lattice(x0 + 1, 0, seed), t)
+}
+
+// -1..1 over a plane, blockier than perlin
+#[allow(dead_code)]
+pub fn value_noise2(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (tx, ty) = (fade(x - x0), fade(y - y0));
+    let (x0, y0) = (x0 as i32, y0 as i32);
+    lerp(
+        lerp(lattice(x0, y0, seed), lattice(x0 + 1, y0, seed), tx),
+        lerp(lattice(x0, y0 + 1, seed), lattice(x0 + 1, y0 + 1, seed), tx),
+        ty,
+    )
+}
+
+// gradient noise over a plane, -1..1 though it rarely gets near either end
+pub fn perlin(x: f32, y: f32, seed: u32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (ix, iy) = (x0 as i32, y0 as i32);
+    let corner = |dx: i32, dy: i32| {
+        let gradient = Direction::ALL[(hash(ix + dx, iy + dy, seed) % 8) as usize].to_vec2();
+        gradient.dot(Vec2::new(fx - dx as f32, fy - dy as f32))
+    };
+    let (tx, ty) = (fade(fx), fade(fy));
+    let value = lerp(
+        lerp(corner(0, 0), corner(1, 0), tx),
+        lerp(corner(0, 1), corner(1, 1), tx),
+        ty,
+    );
+    // unit gradients only reach about +-0.7
+    (value * std::f32::consts::SQRT_2).clamp(-1., 1.)
+}
+
+// octaves of perlin, each twice as fine and half as strong, for rougher detail on top of the
+// broad shapes. still -1..1
+pub fn fbm(x: f32, y: f32, seed: u32, octaves: u32) -> f32 {
+    let (mut sum, mut total, mut amplitude, mut frequency) = (0., 0., 1., 1.);
+    for octave in 0..octaves {
+        sum += perlin(x * frequency, y * frequency, seed.wrapping_add(octave)) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.;
+    }
+    if total > 0. {
+        sum / total
+    } else {
+        0.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-5;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < EPSILON, "{} != {}", a, b);
+    }
+
+    fn assert_vec_close(a: Vec2<f32>, b: Vec2<f32>) {
+        assert!(
+            (a.x - b.x).abs() < EPSILON && (a.y - b.y).abs() < EPSILON,
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn operators() {
+        let a = Vec2::new(1., 2.);
+        let b = Vec2::new(3., -4.);
+        assert_eq!(a + b, Vec2::new(4., -2.));
+        assert_eq!(a - b, Vec2::new(-2., 6.));
+        assert_eq!(a * 3., Vec2::new(3., 6.));
+        assert_eq!(-a, Vec2::new(-1., -2.));
+
+        let mut c = a;
+        c += b;
+        assert_eq!(c, a + b);
+        c -= b;
+        assert_eq!(c, a);
+
+        // integer vectors too, tile coordinates and the like
+        assert_eq!(Vec2::new(2, 3) + Vec2::new(1, 1), Vec2::new(3, 4));
+        assert_eq!(Vec2::<u32>::new(4, 6) * 2, Vec2::new(8, 12));
+
+        let mut v = Vec3::new(1, 2, 3);
+        v += Vec3::new(1, 1, 1);
+        assert_eq!(v, Vec3::new(2, 3, 4));
+        assert_eq!(v - Vec3::new(2, 3, 4), Vec3::zero());
+        assert_eq!(-v * 2, Vec3::new(-4, -6, -8));
+    }
+
+    #[test]
+    fn products() {
+        let x = Vec2::new(1., 0.);
+        let y = Vec2::new(0., 1.);
+        assert_eq!(x.dot(y), 0.);
+        assert_eq!(x.dot(x), 1.);
+        assert_eq!(Vec2::new(2., 3.).dot(Vec2::new(4., -1.)), 5.);
+        assert_eq!(x.cross(y), 1.);
+        assert_eq!(y.cross(x), -1.);
+        assert_eq!(x.perp(), y);
+        assert_eq!(Vec2::new(3., 4.).perp().dot(Vec2::new(3., 4.)), 0.);
+
+        let x3 = Vec3::new(1, 0, 0);
+        let y3 = Vec3::new(0, 1, 0);
+        assert_eq!(x3.cross(y3), Vec3::new(0, 0, 1));
+        assert_eq!(y3.cross(x3), Vec3::new(0, 0, -1));
+        assert_eq!(Vec3::new(1, 2, 3).dot(Vec3::new(4, 5, 6)), 32);
+    }
+
+    #[test]
+    fn lengths() {
+        let v = Vec2::new(3., 4.);
+        assert_eq!(v.magnitude(), 5.);
+        assert_eq!(Vec2::new(1., 1.).distance(Vec2::new(4., 5.)), 5.);
+        assert_vec_close(v.clamp_length(2.5), Vec2::new(1.5, 2.));
+        assert_eq!(v.clamp_length(10.), v);
+        assert_eq!(Vec2::<f32>::zero().clamp_length(1.), Vec2::zero());
+        assert_vec_close(v.clone().normalized(), Vec2::new(0.6, 0.8));
+        assert_eq!(Vec3::new(2., 3., 6.).magnitude(), 7.);
+    }
+
+    #[test]
+    fn lerp() {
+        let a = Vec2::new(0., 10.);
+        let b = Vec2::new(10., 20.);
+        assert_eq!(a.lerp(b, 0.), a);
+        assert_eq!(a.lerp(b, 1.), b);
+        assert_eq!(a.lerp(b, 0.5), Vec2::new(5., 15.));
+        let c = Vec3::new(0., 0., 0.).lerp(Vec3::new(4., 8., 12.), 0.25);
+        assert_eq!(c, Vec3::new(1., 2., 3.));
+    }
+
+    #[test]
+    fn angles() {
+        assert_close(Vec2::new(1., 0.).angle(), 0.);
+        assert_close(Vec2::new(0., 1.).angle(), FRAC_PI_2);
+        assert_close(Vec2::new(-1., 0.).angle(), PI);
+        assert_close(Vec2::new(1., -1.).angle(), -FRAC_PI_4);
+        assert_vec_close(Vec2::from_angle(0.), Vec2::new(1., 0.));
+        assert_vec_close(Vec2::from_angle(FRAC_PI_2), Vec2::new(0., 1.));
+        // round trips within -pi..pi
+        for i in -7..=7 {
+            let angle = i as f32 * 0.4;
+            assert_close(Vec2::from_angle(angle).angle(), angle);
+        }
+    }
+
+    #[test]
+    fn reflect() {
+        let down_right = Vec2::new(1., 1.);
+        // off a floor and off a wall
+        assert_eq!(down_right.reflect(Vec2::new(0., -1.)), Vec2::new(1., -1.));
+        assert_eq!(down_right.reflect(Vec2::new(-1., 0.)), Vec2::new(-1., 1.));
+        // straight into it comes straight back
+        assert_eq!(
+            Vec2::new(0., 2.).reflect(Vec2::new(0., -1.)),
+            Vec2::new(0., -2.)
+        );
+    }
+
+    #[test]
+    fn directions() {
+        assert_eq!(Vec2::new(1., 0.).to_direction8(), Direction::Right);
+        assert_eq!(Vec2::new(0., -3.).to_direction8(), Direction::Up);
+        assert_eq!(Vec2::new(-1., 1.).to_direction8(), Direction::DownLeft);
+        // a bit off diagonal still counts as diagonal, further off it doesn't
+        assert_eq!(Vec2::new(1., 0.8).to_direction8(), Direction::DownRight);
+        assert_eq!(Vec2::new(1., 0.3).to_direction8(), Direction::Right);
+        assert_eq!(Vec2::new(1., 0.8).to_direction4(), Direction::Right);
+        assert_eq!(Vec2::new(-0.8, 1.).to_direction4(), Direction::Down);
+        assert_eq!(Vec2::<f32>::zero().to_direction8(), Direction::Right);
+
+        for direction in Direction::ALL {
+            assert_eq!(direction.to_vec2().to_direction8(), direction);
+            assert_eq!(Direction::from_angle(direction.angle()), direction);
+            assert_close(direction.to_vec2().magnitude(), 1.);
+            assert_vec_close(direction.opposite().to_vec2(), -direction.to_vec2());
+            assert_eq!(direction.opposite().opposite(), direction);
+        }
+        assert!(Direction::UpLeft.is_diagonal());
+        assert!(!Direction::Left.is_diagonal());
+        assert_eq!(Direction::from_angle(-0.1), Direction::Right);
+        assert_eq!(
+            Direction::from_angle(std::f32::consts::TAU * 3.),
+            Direction::Right
+        );
+    }
+
+    #[test]
+    fn smoothstep_and_easing() {
+        assert_eq!(smoothstep(2., 4., 1.), 0.);
+        assert_eq!(smoothstep(2., 4., 5.), 1.);
+        assert_eq!(smoothstep(2., 4., 3.), 0.5);
+        assert!(smoothstep(0., 1., 0.25) < 0.25);
+
+        let curves: [fn(f32) -> f32; 5] = [
+            ease_in,
+            ease_out,
+            ease_in_out,
+            ease_out_elastic,
+            ease_out_bounce,
+        ];
+        for curve in curves {
+            assert_close(curve(0.), 0.);
+            assert_close(curve(1.), 1.);
+        }
+        assert!(ease_in(0.5) < 0.5);
+        assert!(ease_out(0.5) > 0.5);
+        // the elastic one shoots past the end before settling
+        assert!((1..100).any(|i| ease_out_elastic(i as f32 / 100.) > 1.));
+        assert!((0..=100).all(|i| ease_out_bounce(i as f32 / 100.) <= 1. + EPSILON));
+    }
+
+    #[test]
+    fn noise() {
+        for i in 0..500 {
+            let (x, y) = (i as f32 * 0.37 - 90., i as f32 * 0.61 - 150.);
+            for value in [
+                value_noise(x, 7),
+                value_noise2(x, y, 7),
+                perlin(x, y, 7),
+                fbm(x, y, 7, 4),
+            ] {
+                assert!((-1. ..=1.).contains(&value), "{} out of range", value);
+            }
+            // the same input always gives the same value
+            assert_eq!(perlin(x, y, 3), perlin(x, y, 3));
+        }
+
+        // perlin is zero on the lattice, value noise isn't
+        assert_eq!(perlin(3., -2., 1), 0.);
+        assert!((0..20).any(|i| value_noise(i as f32, 1) != 0.));
+
+        // continuous, nearby inputs give nearby values
+        for i in 0..200 {
+            let x = i as f32 * 0.05;
+            assert!((value_noise(x, 2) - value_noise(x + 0.001, 2)).abs() < 0.01);
+            assert!((perlin(x, 1.3, 2) - perlin(x + 0.001, 1.3, 2)).abs() < 0.01);
+        }
+
+        // a different seed is a different pattern
+        let differs = (0..20).any(|i| {
+            let x = i as f32 * 0.5 + 0.25;
+            perlin(x, x, 1) != perlin(x, x, 2)
+        });
+        assert!(differs);
+    }
+
+    #[test]
+    fn transforms() {
+        let p = Vec2::new(3., -2.);
+        assert_eq!(Transform::IDENTITY.apply(p), p);
+        assert_eq!(Transform::translation(1., 5.).apply(p), Vec2::new(4., 3.));
+        assert_eq!(Transform::scale(2., 3.).apply(p), Vec2::new(6., -6.));
+        // a quarter turn takes right to down
+        assert_vec_close(
+            Transform::rotation(FRAC_PI_2).apply(Vec2::new(1., 0.)),
+            Vec2::new(0., 1.),
+        );
+
+        // composed, the right hand side goes first
+        let move_then_scale = Transform::scale(2., 2.) * Transform::translation(1., 0.);
+        assert_eq!(move_then_scale.apply(Vec2::zero()), Vec2::new(2., 0.));
+        let scale_then_move = Transform::translation(1., 0.) * Transform::scale(2., 2.);
+        assert_eq!(scale_then_move.apply(Vec2::zero()), Vec2::new(1., 0.));
+
+        // a child placed relative to a turned parent
+        let parent = Transform::new(Vec2::new(10., 10.), FRAC_PI_2, Vec2::new(1., 1.));
+        let child = Transform::translation(4., 0.);
+        let world = parent.compose(&child);
+        assert_vec_close(world.apply(Vec2::zero()), Vec2::new(10., 14.));
+        assert_close(world.angle(), FRAC_PI_2);
+
+        let t = Transform::new(Vec2::new(-7., 3.), 0.6, Vec2::new(2., 0.5));
+        let inverse = t.inverse().unwrap();
+        assert_vec_close(inverse.apply(t.apply(p)), p);
+        assert_vec_close(t.apply(inverse.apply(p)), p);
+        assert_vec_close((t * inverse).apply(p), p);
+        // vectors ignore the translation
+        assert_vec_close(Transform::translation(5., 5.).apply_vector(p), p);
+        assert_eq!(Transform::scale(0., 1.).inverse(), None);
+    }
+
+    // the deterministic trig has to stay close to std's
+    #[test]
+    fn trig_matches_std() {
+        for i in -100..=100 {
+            let x = i as f32 * 0.1;
+            assert!((sin(x) - x.sin()).abs() < 1e-4, "sin({})", x);
+            assert!((cos(x) - x.cos()).abs() < 1e-4, "cos({})", x);
+        }
+        for i in -10..=10 {
+            for j in -10..=10 {
+                let (y, x) = (i as f32, j as f32);
+                if x == 0. && y == 0. {
+                    continue;
+                }
+                assert!(
+                    (atan2(y, x) - y.atan2(x)).abs() < 1e-4,
+                    "atan2({}, {})",
+                    y,
+                    x
+                );
+            }
+        }
+        assert_eq!(atan2(0., 0.), 0.);
+    }
 }
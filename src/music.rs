@@ -0,0 +1,226 @@
+// background music from MUSIC_PATH. every mood has a track and a level can bring its own
+// track for any mood, falling back to the default one. a track is a stack of looping
+// layers that start together so they stay in time, the first layer always plays and the
+// others come in as the fight heats up:
+//
+//   [combat]
+//   layers = music_combat music_combat_lead
+//
+//   [exploration cellar]
+//   layers = music_cellar
+//
+// switching tracks crossfades, the old one keeps looping until it's silent
+
+use ecs::{Resource, World};
+
+use crate::{
+    audio::Audio,
+    components::{Boss, Enemy, Health, Pos},
+    Ctx,
+};
+
+pub const MUSIC_PATH: &str = "data/music.txt";
+const CROSSFADE_TICKS: f32 = 90.;
+// enemies closer than this are fighting the player
+const COMBAT_RANGE: f32 = 300.;
+const BOSS_RANGE: f32 = 500.;
+// enemies in range for every layer to play
+const FULL_INTENSITY_ENEMIES: usize = 4;
+// so a fight doesn't flicker in and out as enemies come and go
+const COMBAT_LINGER_TICKS: u32 = 180;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mood {
+    Exploration,
+    Combat,
+    Boss,
+}
+
+impl Mood {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "exploration" => Some(Mood::Exploration),
+            "combat" => Some(Mood::Combat),
+            "boss" => Some(Mood::Boss),
+            _ => None,
+        }
+    }
+}
+
+struct Track {
+    mood: Mood,
+    // None for the default track
+    level: Option<String>,
+    // sound names
+    layers: Vec<String>,
+    volume: f32,
+}
+
+// a track that's playing or fading out
+struct Playing {
+    track: usize,
+    voices: Vec<Option<u32>>,
+    volumes: Vec<f32>,
+    is_current: bool,
+}
+
+#[derive(Resource)]
+pub struct Music {
+    tracks: Vec<Track>,
+    playing: Vec<Playing>,
+    pub mood: Mood,
+    // 0 to 1, how many layers play
+    pub intensity: f32,
+    linger: u32,
+}
+
+impl Music {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+        let mut tracks: Vec<Track> = Vec::new();
+        for (idx, line) in data.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |what: &str| format!("{}:{}: {}", path, idx + 1, what);
+
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                let mut words = header.split_whitespace();
+                let Some(mood) = words.next().and_then(Mood::parse) else {
+                    return Err(error(&format!("unknown mood in [{}]", header)));
+                };
+                let level = words.next().map(String::from);
+                if tracks.iter().any(|t| t.mood == mood && t.level == level) {
+                    return Err(error(&format!("[{}] defined twice", header)));
+                }
+                tracks.push(Track {
+                    mood,
+                    level,
+                    layers: Vec::new(),
+                    volume: 1.,
+                });
+                continue;
+            }
+            let Some(track) = tracks.last_mut() else {
+                return Err(error("expected a [mood] first"));
+            };
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error(&format!("expected key = value, got '{}'", line)));
+            };
+            match (key.trim(), value.trim()) {
+                ("layers", value) => {
+                    track.layers = value.split_whitespace().map(String::from).collect()
+                }
+                ("volume", value) => {
+                    track.volume = value
+                        .parse()
+                        .map_err(|_| error(&format!("bad volume '{}'", value)))?
+                }
+                (key, _) => return Err(error(&format!("unknown key {}", key))),
+            }
+        }
+        if tracks.iter().any(|t| t.layers.is_empty()) {
+            return Err(format!("{}: a track without layers", path));
+        }
+
+        Ok(Music {
+            tracks,
+            playing: Vec::new(),
+            mood: Mood::Exploration,
+            intensity: 0.,
+            linger: 0,
+        })
+    }
+
+    fn find(&self, mood: Mood, level: &str) -> Option<usize> {
+        let find = |level: Option<&str>| {
+            self.tracks
+                .iter()
+                .position(|t| t.mood == mood && t.level.as_deref() == level)
+        };
+        find(Some(level)).or_else(|| find(None))
+    }
+}
+
+pub fn update(world: &World) {
+    let music = world.resource_mut::<Music>().unwrap();
+    let audio = world.resource_mut::<Audio>().unwrap();
+    let ctx = world.resource::<Ctx>().unwrap();
+
+    let mut boss = None;
+    world.run(|_: &Boss, pos: &Pos, health: &Health| {
+        if ctx.player_pos.distance(pos) < BOSS_RANGE {
+            boss = Some(1. - health.hp as f32 / health.max_hp as f32);
+        }
+    });
+    let mut enemies = 0;
+    world.run(|_: &Enemy, pos: &Pos| {
+        if ctx.player_pos.distance(pos) < COMBAT_RANGE {
+            enemies += 1;
+        }
+    });
+    if enemies > 0 {
+        music.linger = COMBAT_LINGER_TICKS;
+    } else {
+        music.linger = music.linger.saturating_sub(1);
+    }
+    // the boss track builds up as it loses health
+    (music.mood, music.intensity) = match boss {
+        Some(damage) => (Mood::Boss, damage),
+        None if enemies > 0 => (
+            Mood::Combat,
+            (enemies as f32 / FULL_INTENSITY_ENEMIES as f32).min(1.),
+        ),
+        // lingering keeps the last intensity
+        None if music.linger > 0 => (Mood::Combat, music.intensity),
+        None => (Mood::Exploration, 0.),
+    };
+
+    let track = music.find(music.mood, ctx.level_name);
+    let current = music.playing.iter().find(|p| p.is_current).map(|p| p.track);
+    if current != track {
+        for playing in &mut music.playing {
+            playing.is_current = false;
+        }
+        if let Some(track) = track {
+            let layers = &music.tracks[track].layers;
+            music.playing.push(Playing {
+                track,
+                voices: audio.play_loops(layers, 0.),
+                volumes: vec![0.; layers.len()],
+                is_current: true,
+            });
+        }
+    }
+
+    for playing in &mut music.playing {
+        let track = &music.tracks[playing.track];
+        let step = track.volume / CROSSFADE_TICKS;
+        let layers = track.layers.len() as f32;
+        for (layer, volume) in playing.volumes.iter_mut().enumerate() {
+            let is_heard = playing.is_current && music.intensity >= layer as f32 / layers;
+            let target = if is_heard { track.volume } else { 0. };
+            *volume = if *volume < target {
+                (*volume + step).min(target)
+            } else {
+                (*volume - step).max(target)
+            };
+            if let Some(id) = playing.voices[layer] {
+                audio.update_voice(id, *volume, 0.);
+            }
+        }
+    }
+
+    // faded out
+    music.playing.retain(|playing| {
+        let is_silent = !playing.is_current && playing.volumes.iter().all(|v| *v <= 0.);
+        if is_silent {
+            for id in playing.voices.iter().flatten() {
+                audio.stop(*id);
+            }
+        }
+        !is_silent
+    });
+}
@@ -0,0 +1,759 @@
+// two player co-op over udp on a lan, the host is a listen server:
+//
+//   game --host [port]
+//   game --join <addr>[:port]
+//
+// or from the lobby. the client says hello with its PROTOCOL_VERSION and content hash until
+// the host welcomes or rejects it, a host only takes one partner. animations are sent by id,
+// so both sides need the same mods and animations for them to mean the same thing. either
+// side says bye when it quits, and gives up on the other after TIMEOUT_TICKS of silence
+//
+// the host plays as usual and simulates everything. the partner is a second character it
+// drives with the input the client sends every tick, and every tick it sends back what
+// changed about the entities with a sprite. the client doesn't simulate at all, it mirrors
+// the host's entities and interpolates them between snapshots the way it would between
+// ticks
+//
+// one message per datagram, little endian:
+//
+//   hello     kind, version u32, content u32
+//   welcome   kind
+//   reject    kind, reason u8, host version u32
+//   bye       kind
+//   input     kind, tick u32, pressed u32, just_pressed u32, aim x f32, aim y f32
+//   snapshot  kind, tick u32, you u32, then entities until the end of the datagram:
+//             id u32, x f32, y f32, x_offset i16, y_offset i16, width u16, height u16,
+//             anim u16, frame u16, alpha u8, flags u8, [z_offset i16],
+//             [radius u16, r g b u8, intensity f32]
+//   despawn   kind, ids u32 until the end of the datagram
+//
+// an entity with an animation or frame the client doesn't have is dropped.
+// nothing is resent on loss. an entity that didn't change is still sent every
+// RESEND_TICKS so a lost snapshot heals, and the client drops what it hasn't heard about
+// in STALE_TICKS in case a despawn got lost
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::{SocketAddr, UdpSocket},
+};
+
+use ecs::{Component, Entity, Resource, World};
+use sdl2::pixels::Color;
+
+use crate::{
+    assets,
+    components::{AnimatedSprite, Floor, Light, Pos, PrevPos, Prop},
+    AnimationId, AnimationRepository, Ctx, InputState,
+};
+
+pub const DEFAULT_PORT: u16 = 7777;
+// bumped whenever a message changes
+const PROTOCOL_VERSION: u32 = 2;
+const HELLO_TICKS: u32 = 30;
+// stays under the usual mtu
+const MAX_PACKET: usize = 1200;
+const RESEND_TICKS: u32 = 60;
+const STALE_TICKS: u32 = 150;
+// the other side is gone after this long without a word
+const TIMEOUT_TICKS: u32 = 300;
+
+const MSG_INPUT: u8 = 1;
+const MSG_SNAPSHOT: u8 = 2;
+const MSG_DESPAWN: u8 = 3;
+const MSG_HELLO: u8 = 4;
+const MSG_WELCOME: u8 = 5;
+const MSG_REJECT: u8 = 6;
+const MSG_BYE: u8 = 7;
+
+const REJECT_VERSION: u8 = 1;
+const REJECT_FULL: u8 = 2;
+const REJECT_CONTENT: u8 = 3;
+
+const FLAG_FLIP: u8 = 1;
+const FLAG_FLOOR: u8 = 1 << 1;
+const FLAG_PROP: u8 = 1 << 2;
+const FLAG_Z_OFFSET: u8 = 1 << 3;
+const FLAG_LIGHT: u8 = 1 << 4;
+const FLAG_EMISSIVE: u8 = 1 << 5;
+
+// what the client sees of an entity, light cones aren't sent
+#[derive(Clone, Copy, PartialEq)]
+struct EntityState {
+    pos: (f32, f32),
+    rect: (i16, i16, u16, u16),
+    anim: u16,
+    frame: u16,
+    alpha: u8,
+    flags: u8,
+    z_offset: i16,
+    light: (u16, [u8; 3], f32),
+}
+
+// what happened to the connection, for the lobby
+pub enum Notice {
+    // the partner joined the host, or the host welcomed the client
+    Joined,
+    // the other side quit
+    Left,
+    // the other side went silent
+    Lost,
+    // the host's protocol version
+    WrongVersion(u32),
+    // the host has other mods or animations
+    WrongContent,
+    Full,
+    NoAnswer,
+}
+
+// the partner's input on the host
+pub struct RemoteInput {
+    pub pressed: InputState,
+    // everything pressed since the last tick, so a press isn't missed when two inputs
+    // arrive at once
+    pub just_pressed: InputState,
+    // the partner's cursor, in world space
+    pub aim: Pos,
+}
+
+pub struct Host {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+    pub partner: Option<Entity>,
+    pub input: RemoteInput,
+    last_input: u32,
+    silent_ticks: u32,
+    // stable across the wire, never reused
+    ids: HashMap<Entity, u32>,
+    next_id: u32,
+    sent: HashMap<u32, EntityState>,
+    notices: Vec<Notice>,
+}
+
+pub struct Client {
+    socket: UdpSocket,
+    is_connected: bool,
+    // the proxy following the partner the host simulates for us
+    you: Option<u32>,
+    // entity and the last tick it was heard about
+    proxies: HashMap<u32, (Entity, u32)>,
+    last_snapshot: u32,
+    silent_ticks: u32,
+    notices: Vec<Notice>,
+}
+
+#[derive(Resource)]
+pub enum Net {
+    Offline,
+    Host(Box<Host>),
+    Client(Box<Client>),
+}
+
+impl RemoteInput {
+    fn new() -> Self {
+        RemoteInput {
+            pressed: InputState::default(),
+            just_pressed: InputState::default(),
+            aim: Pos::zero(),
+        }
+    }
+}
+
+impl Host {
+    pub fn has_peer(&self) -> bool {
+        self.peer.is_some()
+    }
+}
+
+impl Net {
+    pub fn host(port: u16) -> Result<Self, String> {
+        let socket = UdpSocket::bind(("0.0.0.0", port)).map_err(|e| e.to_string())?;
+        socket.set_nonblocking(true).unwrap();
+        println!("hosting on port {}", port);
+        Ok(Net::Host(Box::new(Host {
+            socket,
+            peer: None,
+            partner: None,
+            input: RemoteInput::new(),
+            last_input: 0,
+            silent_ticks: 0,
+            ids: HashMap::new(),
+            next_id: 1,
+            sent: HashMap::new(),
+            notices: Vec::new(),
+        })))
+    }
+
+    pub fn join(addr: &str) -> Result<Self, String> {
+        let addr = if addr.contains(':') {
+            addr.to_string()
+        } else {
+            format!("{}:{}", addr, DEFAULT_PORT)
+        };
+        let socket = UdpSocket::bind(("0.0.0.0", 0)).map_err(|e| e.to_string())?;
+        socket
+            .connect(&addr)
+            .map_err(|e| format!("{}: {}", addr, e))?;
+        socket.set_nonblocking(true).unwrap();
+        println!("joining {}", addr);
+        Ok(Net::Client(Box::new(Client {
+            socket,
+            is_connected: false,
+            you: None,
+            proxies: HashMap::new(),
+            last_snapshot: 0,
+            silent_ticks: 0,
+            notices: Vec::new(),
+        })))
+    }
+
+    pub fn is_client(&self) -> bool {
+        matches!(self, Net::Client(_))
+    }
+
+    pub fn take_notices(&mut self) -> Vec<Notice> {
+        match self {
+            Net::Offline => Vec::new(),
+            Net::Host(host) => std::mem::take(&mut host.notices),
+            Net::Client(client) => std::mem::take(&mut client.notices),
+        }
+    }
+
+    // tells the other side right away instead of leaving it to time out
+    pub fn leave(&mut self) {
+        let bye = Writer::new(MSG_BYE);
+        match self {
+            Net::Offline => {}
+            Net::Host(host) => {
+                if let Some(peer) = host.peer {
+                    let _ = host.socket.send_to(&bye.0, peer);
+                }
+            }
+            Net::Client(client) => {
+                let _ = client.socket.send(&bye.0);
+            }
+        }
+        *self = Net::Offline;
+    }
+}
+
+// the entity the client's camera follows
+pub fn you(world: &World) -> Option<Entity> {
+    let Net::Client(client) = world.resource::<Net>().unwrap() else {
+        return None;
+    };
+    client.proxies.get(&client.you?).map(|(entity, _)| *entity)
+}
+
+// early in the tick, after the previous positions are stored
+pub fn receive(world: &World) {
+    match world.resource_mut::<Net>().unwrap() {
+        Net::Offline => {}
+        Net::Host(host) => receive_inputs(world, host),
+        Net::Client(client) => receive_snapshots(world, client),
+    }
+}
+
+// at the end of the tick
+pub fn send(world: &World) {
+    let ctx = world.resource::<Ctx>().unwrap();
+    match world.resource_mut::<Net>().unwrap() {
+        Net::Offline => {}
+        Net::Host(host) => send_snapshots(world, host, ctx.ticks),
+        // until the host answers
+        Net::Client(client) if !client.is_connected => {
+            if client.silent_ticks % HELLO_TICKS == 1 {
+                let mut msg = Writer::new(MSG_HELLO);
+                msg.u32(PROTOCOL_VERSION);
+                msg.u32(content_hash(world));
+                let _ = client.socket.send(&msg.0);
+            }
+        }
+        Net::Client(client) => {
+            let mut msg = Writer::new(MSG_INPUT);
+            let aim = ctx.cursor_world_pos();
+            msg.u32(ctx.ticks);
+            msg.u32(ctx.input.pressed.to_bits());
+            msg.u32(ctx.input.just_pressed.to_bits());
+            msg.f32(aim.x);
+            msg.f32(aim.y);
+            // fails while nobody listens on the other end, the timeout reports that
+            let _ = client.socket.send(&msg.0);
+        }
+    }
+}
+
+fn receive_inputs(world: &World, host: &mut Host) {
+    let mut just_pressed = 0;
+    let mut buf = [0; MAX_PACKET];
+    while let Ok((len, from)) = host.socket.recv_from(&mut buf) {
+        let mut msg = Reader::new(&buf[..len]);
+        let kind = msg.u8();
+        if kind == Some(MSG_HELLO) {
+            greet(world, host, from, msg.u32(), msg.u32());
+            continue;
+        }
+        if host.peer != Some(from) {
+            continue;
+        }
+        if kind == Some(MSG_BYE) {
+            println!("{} left", from);
+            drop_peer(host, Notice::Left);
+            return;
+        }
+        let (Some(MSG_INPUT), Some(tick), Some(pressed), Some(just), Some(x), Some(y)) =
+            (kind, msg.u32(), msg.u32(), msg.u32(), msg.f32(), msg.f32())
+        else {
+            continue;
+        };
+        host.silent_ticks = 0;
+        just_pressed |= just;
+        // late packets only count for their presses
+        if tick >= host.last_input {
+            host.last_input = tick;
+            host.input.pressed = InputState::from_bits(pressed);
+            host.input.aim = Pos::new(x, y);
+        }
+    }
+    host.input.just_pressed = InputState::from_bits(just_pressed);
+
+    if host.peer.is_some() {
+        host.silent_ticks += 1;
+        if host.silent_ticks > TIMEOUT_TICKS {
+            println!("{} timed out", host.peer.unwrap());
+            drop_peer(host, Notice::Lost);
+        }
+    }
+}
+
+// a client that says hello again didn't hear the welcome
+fn greet(
+    world: &World,
+    host: &mut Host,
+    from: SocketAddr,
+    version: Option<u32>,
+    content: Option<u32>,
+) {
+    let reject = |reason: u8| {
+        let mut msg = Writer::new(MSG_REJECT);
+        msg.u8(reason);
+        msg.u32(PROTOCOL_VERSION);
+        msg
+    };
+    let reply = if version != Some(PROTOCOL_VERSION) {
+        reject(REJECT_VERSION)
+    } else if content != Some(content_hash(world)) {
+        reject(REJECT_CONTENT)
+    } else if host.peer.is_some_and(|peer| peer != from) {
+        reject(REJECT_FULL)
+    } else {
+        if host.peer.is_none() {
+            println!("{} joined", from);
+            host.peer = Some(from);
+            host.silent_ticks = 0;
+            host.sent.clear();
+            host.notices.push(Notice::Joined);
+        }
+        Writer::new(MSG_WELCOME)
+    };
+    let _ = host.socket.send_to(&reply.0, from);
+}
+
+fn drop_peer(host: &mut Host, notice: Notice) {
+    host.peer = None;
+    host.last_input = 0;
+    host.input = RemoteInput::new();
+    host.notices.push(notice);
+}
+
+fn send_snapshots(world: &World, host: &mut Host, tick: u32) {
+    let Some(peer) = host.peer else {
+        return;
+    };
+    let you = host
+        .partner
+        .and_then(|partner| host.ids.get(&partner).copied());
+
+    let mut seen = HashSet::new();
+    let mut changed = Vec::new();
+    world.run(|entity: &Entity, pos: &Pos, sprite: &AnimatedSprite| {
+        let id = *host.ids.entry(*entity).or_insert_with(|| {
+            host.next_id += 1;
+            host.next_id
+        });
+        seen.insert(id);
+        let state = entity_state(world, *entity, pos, sprite);
+        // staggered so the static ones don't all go out in the same tick
+        let is_due = tick.wrapping_add(id).is_multiple_of(RESEND_TICKS);
+        if is_due || host.sent.get(&id) != Some(&state) {
+            host.sent.insert(id, state);
+            changed.push((id, state));
+        }
+    });
+
+    let header = |msg: &mut Writer| {
+        msg.u32(tick);
+        msg.u32(you.unwrap_or(0));
+    };
+    let mut msg = Writer::new(MSG_SNAPSHOT);
+    header(&mut msg);
+    for (id, state) in changed {
+        if msg.0.len() + ENTITY_SIZE > MAX_PACKET {
+            let _ = host.socket.send_to(&msg.0, peer);
+            msg = Writer::new(MSG_SNAPSHOT);
+            header(&mut msg);
+        }
+        write_entity(&mut msg, id, &state);
+    }
+    let _ = host.socket.send_to(&msg.0, peer);
+
+    let mut gone = Vec::new();
+    host.ids.retain(|_, id| {
+        let is_alive = seen.contains(id);
+        if !is_alive {
+            gone.push(*id);
+        }
+        is_alive
+    });
+    for chunk in gone.chunks((MAX_PACKET - 1) / 4) {
+        let mut msg = Writer::new(MSG_DESPAWN);
+        for id in chunk {
+            host.sent.remove(id);
+            msg.u32(*id);
+        }
+        let _ = host.socket.send_to(&msg.0, peer);
+    }
+}
+
+fn entity_state(world: &World, entity: Entity, pos: &Pos, sprite: &AnimatedSprite) -> EntityState {
+    let mut flags = 0;
+    let mut set = |flag: u8, is_set: bool| {
+        if is_set {
+            flags |= flag;
+        }
+    };
+    set(FLAG_FLIP, sprite.flip_horizontal);
+    set(FLAG_FLOOR, world.has_component::<Floor>(entity));
+    set(FLAG_PROP, world.has_component::<Prop>(entity));
+    set(FLAG_Z_OFFSET, sprite.z_offset.is_some());
+    set(FLAG_EMISSIVE, sprite.is_emissive);
+    let light = world.component::<Light>(entity);
+    set(FLAG_LIGHT, light.is_some());
+    EntityState {
+        pos: (pos.x, pos.y),
+        rect: (
+            sprite.x_offset,
+            sprite.y_offset,
+            sprite.width as u16,
+            sprite.height as u16,
+        ),
+        anim: sprite.anim().0 as u16,
+        frame: sprite.frame as u16,
+        alpha: sprite.alpha,
+        flags,
+        z_offset: sprite.z_offset.unwrap_or(0),
+        light: light.map_or((0, [0; 3], 0.), |light| {
+            let color = [light.color.r, light.color.g, light.color.b];
+            (light.radius, color, light.intensity)
+        }),
+    }
+}
+
+// the largest an entity gets
+const ENTITY_SIZE: usize = 4 + 8 + 8 + 4 + 2 + 2 + 2 + 9;
+
+fn write_entity(msg: &mut Writer, id: u32, state: &EntityState) {
+    msg.u32(id);
+    msg.f32(state.pos.0);
+    msg.f32(state.pos.1);
+    msg.u16(state.rect.0 as u16);
+    msg.u16(state.rect.1 as u16);
+    msg.u16(state.rect.2);
+    msg.u16(state.rect.3);
+    msg.u16(state.anim);
+    msg.u16(state.frame);
+    msg.u8(state.alpha);
+    msg.u8(state.flags);
+    if state.flags & FLAG_Z_OFFSET != 0 {
+        msg.u16(state.z_offset as u16);
+    }
+    if state.flags & FLAG_LIGHT != 0 {
+        let (radius, color, intensity) = state.light;
+        msg.u16(radius);
+        for channel in color {
+            msg.u8(channel);
+        }
+        msg.f32(intensity);
+    }
+}
+
+fn read_entity(msg: &mut Reader) -> Option<(u32, EntityState)> {
+    let id = msg.u32()?;
+    let pos = (msg.f32()?, msg.f32()?);
+    let rect = (msg.u16()? as i16, msg.u16()? as i16, msg.u16()?, msg.u16()?);
+    let (anim, frame, alpha, flags) = (msg.u16()?, msg.u16()?, msg.u8()?, msg.u8()?);
+    let z_offset = if flags & FLAG_Z_OFFSET != 0 {
+        msg.u16()? as i16
+    } else {
+        0
+    };
+    let light = if flags & FLAG_LIGHT != 0 {
+        (msg.u16()?, [msg.u8()?, msg.u8()?, msg.u8()?], msg.f32()?)
+    } else {
+        (0, [0; 3], 0.)
+    };
+    Some((
+        id,
+        EntityState {
+            pos,
+            rect,
+            anim,
+            frame,
+            alpha,
+            flags,
+            z_offset,
+            light,
+        },
+    ))
+}
+
+fn receive_snapshots(world: &World, client: &mut Client) {
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    let tick = ctx.ticks;
+    let mut buf = [0; MAX_PACKET];
+    while let Ok(len) = client.socket.recv(&mut buf) {
+        let mut msg = Reader::new(&buf[..len]);
+        match msg.u8() {
+            Some(MSG_WELCOME) if !client.is_connected => {
+                println!("connected");
+                client.is_connected = true;
+                client.silent_ticks = 0;
+                client.notices.push(Notice::Joined);
+                // the host's level replaces ours, player included
+                let mut local = Vec::new();
+                world.run(|entity: &Entity, _: &Pos| local.push(*entity));
+                for entity in local {
+                    world.despawn(entity);
+                }
+            }
+            Some(MSG_REJECT) if !client.is_connected => {
+                let notice = match (msg.u8(), msg.u32()) {
+                    (Some(REJECT_FULL), _) => Notice::Full,
+                    (Some(REJECT_CONTENT), _) => Notice::WrongContent,
+                    (_, version) => Notice::WrongVersion(version.unwrap_or(0)),
+                };
+                client.notices.push(notice);
+                return;
+            }
+            Some(MSG_BYE) if client.is_connected => {
+                println!("the host left");
+                disconnect(world, client, Notice::Left);
+                return;
+            }
+            Some(MSG_SNAPSHOT) if client.is_connected => {
+                let (Some(host_tick), Some(you)) = (msg.u32(), msg.u32()) else {
+                    continue;
+                };
+                client.silent_ticks = 0;
+                client.you = Some(you).filter(|you| *you != 0);
+                // an older snapshot that arrived late would pull things back
+                let is_late = host_tick < client.last_snapshot;
+                client.last_snapshot = client.last_snapshot.max(host_tick);
+                let animations = world.resource::<AnimationRepository>().unwrap();
+                while let Some((id, state)) = read_entity(&mut msg) {
+                    let anim = AnimationId(state.anim as usize);
+                    if !animations.has_frame(anim, state.frame as u32) {
+                        continue;
+                    }
+                    match client.proxies.get_mut(&id) {
+                        Some((entity, heard)) => {
+                            *heard = tick;
+                            if !is_late {
+                                apply(world, *entity, &state);
+                            }
+                        }
+                        None => {
+                            client
+                                .proxies
+                                .insert(id, (spawn_proxy(world, &state), tick));
+                        }
+                    }
+                }
+            }
+            Some(MSG_DESPAWN) => {
+                while let Some(id) = msg.u32() {
+                    if let Some((entity, _)) = client.proxies.remove(&id) {
+                        world.despawn(entity);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    client.proxies.retain(|_, (entity, heard)| {
+        let is_stale = tick.wrapping_sub(*heard) > STALE_TICKS;
+        if is_stale {
+            world.despawn(*entity);
+        }
+        !is_stale
+    });
+
+    let you = client.you.and_then(|id| client.proxies.get(&id));
+    if let Some(pos) = you.and_then(|(you, _)| world.component::<Pos>(*you)) {
+        // for the audio listener
+        ctx.player_pos = *pos;
+    }
+
+    client.silent_ticks += 1;
+    if client.silent_ticks == TIMEOUT_TICKS {
+        println!("no word from the host");
+        let notice = if client.is_connected {
+            Notice::Lost
+        } else {
+            Notice::NoAnswer
+        };
+        disconnect(world, client, notice);
+    }
+}
+
+// fnv-1a over what the two sides have to agree on for animation ids to match: the enabled
+// mods and the name and length of every animation
+fn content_hash(world: &World) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    let mut add = |bytes: &[u8]| {
+        for byte in bytes {
+            hash = (hash ^ *byte as u32).wrapping_mul(0x0100_0193);
+        }
+    };
+    for name in assets::enabled() {
+        add(name.as_bytes());
+        add(&[0]);
+    }
+    for (name, frames) in world.resource::<AnimationRepository>().unwrap().summary() {
+        add(name.as_bytes());
+        add(&(frames as u32).to_le_bytes());
+    }
+    hash
+}
+
+// the mirrored entities go with the connection
+fn disconnect(world: &World, client: &mut Client, notice: Notice) {
+    for (_, (entity, _)) in client.proxies.drain() {
+        world.despawn(entity);
+    }
+    client.you = None;
+    client.notices.push(notice);
+}
+
+fn spawn_proxy(world: &World, state: &EntityState) -> Entity {
+    let pos = Pos::new(state.pos.0, state.pos.1);
+    let (x, y, w, h) = state.rect;
+    let z_offset = Some(state.z_offset).filter(|_| state.flags & FLAG_Z_OFFSET != 0);
+    let mut sprite = AnimatedSprite::new(
+        (x, y, w as u32, h as u32),
+        0,
+        AnimationId(state.anim as usize),
+        z_offset,
+    );
+    sprite.frame = state.frame as u32;
+    sprite.alpha = state.alpha;
+    sprite.flip_horizontal = state.flags & FLAG_FLIP != 0;
+    sprite.is_emissive = state.flags & FLAG_EMISSIVE != 0;
+    let light = light(state);
+
+    let prev_pos = PrevPos(pos);
+    let mut components: Vec<&dyn Component> = vec![&pos, &prev_pos, &sprite];
+    if state.flags & FLAG_FLOOR != 0 {
+        components.push(&Floor {});
+    }
+    if state.flags & FLAG_PROP != 0 {
+        components.push(&Prop {});
+    }
+    if state.flags & FLAG_LIGHT != 0 {
+        components.push(&light);
+    }
+    world.spawn(&components)
+}
+
+fn apply(world: &World, entity: Entity, state: &EntityState) {
+    if let Some(pos) = world.component_mut::<Pos>(entity) {
+        *pos = Pos::new(state.pos.0, state.pos.1);
+    }
+    if let Some(sprite) = world.component_mut::<AnimatedSprite>(entity) {
+        sprite.switch_anim(AnimationId(state.anim as usize), 0);
+        sprite.frame = state.frame as u32;
+        sprite.alpha = state.alpha;
+        sprite.flip_horizontal = state.flags & FLAG_FLIP != 0;
+        sprite.is_emissive = state.flags & FLAG_EMISSIVE != 0;
+    }
+    if let Some(light) = world.component_mut::<Light>(entity) {
+        *light = self::light(state);
+    }
+}
+
+fn light(state: &EntityState) -> Light {
+    let (radius, [r, g, b], intensity) = state.light;
+    Light {
+        radius,
+        color: Color::RGB(r, g, b),
+        intensity,
+        cone: None,
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn new(kind: u8) -> Self {
+        Writer(vec![kind])
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+// None once the datagram runs out
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (bytes, rest) = self.data.split_first_chunk::<N>()?;
+        self.data = rest;
+        Some(*bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes::<1>().map(|[byte]| byte)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.bytes().map(f32::from_le_bytes)
+    }
+}
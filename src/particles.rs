@@ -0,0 +1,348 @@
+// particle presets from PARTICLES_PATH. a preset either bursts a number of particles at
+// once or keeps emitting at a rate while its emitter is active, each particle flies off
+// in the preset's cone and runs through its color gradient over its lifetime:
+//
+//   [torch_smoke]
+//   rate = 1 8
+//   angle = 270 30
+//   colors = 90,90,90,120 60,60,60,0
+//
+// particles are drawn with one of the preset's sprites or, without any, as a small light
+
+use ecs::{Component, Entity, Resource, World};
+use rand::Rng;
+use sdl2::pixels::Color;
+
+use crate::{
+    commands::CommandBuffer,
+    components::{
+        AnimatedSprite, Collider, ColliderGroup, Light, Particle, ParticleEmitter, Pos, PrevPos,
+        Timer,
+    },
+    layers::CollisionLayers,
+    math::Vec2,
+    rng::GameRng,
+    AnimationRepository,
+};
+
+pub const PARTICLES_PATH: &str = "data/particles.txt";
+
+#[derive(Clone, Copy)]
+pub enum Emission {
+    Burst(u32),
+    // count particles every so many ticks
+    Rate(u32, u32),
+}
+
+pub struct Preset {
+    pub name: String,
+    pub emission: Emission,
+    // degrees, 0 points right and 90 down
+    pub angle: f32,
+    // width of the cone around angle, 360 is all around
+    pub spread: f32,
+    // pixels per tick
+    pub speed: (f32, f32),
+    pub lifetime: (u32, u32),
+    // fraction of the velocity kept every tick
+    pub drag: f32,
+    // spread evenly over the lifetime
+    pub colors: Vec<Color>,
+    // animations, one is picked per particle
+    pub sprites: Vec<String>,
+    pub size: u32,
+    pub light: u16,
+    // stops at the first wall or hitbox it touches
+    pub collides: bool,
+}
+
+impl Preset {
+    fn new(name: &str) -> Self {
+        Preset {
+            name: name.to_string(),
+            emission: Emission::Burst(1),
+            angle: 0.,
+            spread: 360.,
+            speed: (1., 1.),
+            lifetime: (30, 30),
+            drag: 1.,
+            colors: vec![Color::WHITE],
+            sprites: Vec::new(),
+            size: 8,
+            light: 2,
+            collides: false,
+        }
+    }
+
+    fn color_at(&self, t: f32) -> Color {
+        let last = self.colors.len() - 1;
+        let at = t.clamp(0., 1.) * last as f32;
+        let idx = (at.floor() as usize).min(last.saturating_sub(1));
+        let (from, to) = (self.colors[idx], self.colors[(idx + 1).min(last)]);
+        let t = at - idx as f32;
+        let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::RGBA(
+            lerp(from.r, to.r),
+            lerp(from.g, to.g),
+            lerp(from.b, to.b),
+            lerp(from.a, to.a),
+        )
+    }
+}
+
+#[derive(Resource)]
+pub struct ParticlePresets {
+    presets: Vec<Preset>,
+}
+
+impl ParticlePresets {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+
+        let mut presets: Vec<Preset> = Vec::new();
+        for (idx, line) in data.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |what: &str| format!("{}:{}: {}", path, idx + 1, what);
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if presets.iter().any(|p| p.name == name) {
+                    return Err(error(&format!("preset {} defined twice", name)));
+                }
+                presets.push(Preset::new(name));
+                continue;
+            }
+            let Some(preset) = presets.last_mut() else {
+                return Err(error("expected a [preset] first"));
+            };
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error(&format!("expected key = value, got '{}'", line)));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            parse_key(preset, key, value)
+                .ok_or_else(|| error(&format!("bad {} '{}'", key, value)))?;
+        }
+        Ok(ParticlePresets { presets })
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.presets.iter().position(|p| p.name == name)
+    }
+
+    pub fn get(&self, idx: usize) -> &Preset {
+        &self.presets[idx]
+    }
+}
+
+fn pair<T: std::str::FromStr + Copy>(value: &str) -> Option<(T, T)> {
+    let mut words = value.split_whitespace().map(|w| w.parse().ok());
+    let first = words.next()??;
+    let second = match words.next() {
+        Some(second) => second?,
+        None => first,
+    };
+    Some((first, second))
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let channels: Vec<u8> = value
+        .split(',')
+        .map(|c| c.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match channels[..] {
+        [r, g, b] => Some(Color::RGB(r, g, b)),
+        [r, g, b, a] => Some(Color::RGBA(r, g, b, a)),
+        _ => None,
+    }
+}
+
+fn parse_key(preset: &mut Preset, key: &str, value: &str) -> Option<()> {
+    match key {
+        "burst" => preset.emission = Emission::Burst(value.parse().ok()?),
+        "rate" => {
+            let (count, every) = pair::<u32>(value)?;
+            preset.emission = Emission::Rate(count, every.max(1));
+        }
+        "angle" => (preset.angle, preset.spread) = pair(value)?,
+        "speed" => preset.speed = pair(value)?,
+        "lifetime" => preset.lifetime = pair(value)?,
+        "drag" => preset.drag = value.parse().ok()?,
+        "colors" => {
+            preset.colors = value
+                .split_whitespace()
+                .map(parse_color)
+                .collect::<Option<_>>()?;
+            if preset.colors.is_empty() {
+                return None;
+            }
+        }
+        "sprites" => preset.sprites = value.split_whitespace().map(String::from).collect(),
+        "size" => preset.size = value.parse().ok()?,
+        "light" => preset.light = value.parse().ok()?,
+        "collides" => preset.collides = value.parse().ok()?,
+        _ => return None,
+    }
+    Some(())
+}
+
+fn preset_idx(world: &World, name: &str) -> usize {
+    match world.resource::<ParticlePresets>().unwrap().find(name) {
+        Some(idx) => idx,
+        None => panic!("no particle preset {}", name),
+    }
+}
+
+pub fn emitter(world: &World, preset: &str, is_active: bool) -> ParticleEmitter {
+    let idx = preset_idx(world, preset);
+    let every = match world
+        .resource::<ParticlePresets>()
+        .unwrap()
+        .get(idx)
+        .emission
+    {
+        Emission::Rate(_, every) => every,
+        Emission::Burst(_) => 1,
+    };
+    ParticleEmitter {
+        is_active,
+        preset: idx,
+        cadence: Timer::repeating(every),
+        has_burst: false,
+    }
+}
+
+// the whole burst at once, direction turns the preset's cone to point along it
+pub fn burst(world: &World, preset: &str, pos: Pos, direction: Option<Vec2<f32>>) {
+    let idx = preset_idx(world, preset);
+    let count = match world
+        .resource::<ParticlePresets>()
+        .unwrap()
+        .get(idx)
+        .emission
+    {
+        Emission::Burst(count) | Emission::Rate(count, _) => count,
+    };
+    spawn(world, idx, pos, count, direction);
+}
+
+fn spawn(world: &World, idx: usize, pos: Pos, count: u32, direction: Option<Vec2<f32>>) {
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    let preset = world.resource::<ParticlePresets>().unwrap().get(idx);
+    let commands = world.resource_mut::<CommandBuffer>().unwrap();
+    let rng = world.resource_mut::<GameRng>().unwrap();
+
+    let angle = match direction {
+        Some(dir) => dir.angle().to_degrees(),
+        None => preset.angle,
+    };
+    for _ in 0..count {
+        let spread = rng.gen_range(-0.5..=0.5) * preset.spread;
+        let theta = (angle + spread).to_radians();
+        let speed = rng.gen_range(preset.speed.0..=preset.speed.1);
+        let lifetime = rng.gen_range(preset.lifetime.0..=preset.lifetime.1).max(1);
+        let color = preset.colors[0];
+
+        let mut components: Vec<Box<dyn Component>> = vec![
+            Box::new(pos),
+            Box::new(PrevPos(pos)),
+            Box::new(Particle {
+                preset: idx,
+                velocity: Vec2::from_angle(theta) * speed,
+                age: 0,
+                lifetime,
+            }),
+        ];
+        if !preset.sprites.is_empty() {
+            let name = &preset.sprites[rng.gen_range(0..preset.sprites.len())];
+            let Some(anim) = world.resource::<AnimationRepository>().unwrap().get(name) else {
+                panic!("no animation {} for particle preset {}", name, preset.name);
+            };
+            let half = preset.size as i16 / 2;
+            let mut sprite =
+                AnimatedSprite::new((-half, -half, preset.size, preset.size), 5, anim, None);
+            sprite.color_mod = Color::RGB(color.r, color.g, color.b);
+            sprite.alpha = color.a;
+            components.push(Box::new(sprite));
+        }
+        if preset.light > 0 {
+            components.push(Box::new(Light {
+                radius: preset.light,
+                color: Color::RGB(color.r, color.g, color.b),
+                intensity: color.a as f32 / 255.,
+                cone: None,
+            }));
+        }
+        if preset.collides {
+            components.push(Box::new(ColliderGroup {
+                nav: Some(
+                    Collider::on_layer(layers, "projectile", (-2, -2, 4, 4)).with_on_collide(
+                        |world: &World, me: Entity, _: Entity| {
+                            world.component_mut::<Particle>(me).unwrap().velocity = Vec2::zero();
+                        },
+                    ),
+                ),
+                hitbox: None,
+            }));
+        }
+        commands.spawn(components);
+    }
+}
+
+pub fn update_emitters(world: &World) {
+    let presets = world.resource::<ParticlePresets>().unwrap();
+    let mut bursts = Vec::new();
+    world.run(
+        |entity: &Entity, emitter: &mut ParticleEmitter, pos: &Pos| {
+            // an unlit torch doesn't smoke
+            let is_lit = world
+                .component::<Light>(*entity)
+                .is_none_or(|light| light.intensity > 0.);
+            if !emitter.is_active || !is_lit {
+                emitter.has_burst = false;
+                return;
+            }
+            match presets.get(emitter.preset).emission {
+                // once each time the emitter turns on
+                Emission::Burst(count) if !emitter.has_burst => {
+                    emitter.has_burst = true;
+                    bursts.push((emitter.preset, *pos, count));
+                }
+                Emission::Rate(count, _) if emitter.cadence.tick() => {
+                    bursts.push((emitter.preset, *pos, count));
+                }
+                _ => {}
+            }
+        },
+    );
+    for (idx, pos, count) in bursts {
+        spawn(world, idx, pos, count, None);
+    }
+}
+
+pub fn update(world: &World) {
+    let presets = world.resource::<ParticlePresets>().unwrap();
+    let commands = world.resource_mut::<CommandBuffer>().unwrap();
+    world.run(|entity: &Entity, particle: &mut Particle, pos: &mut Pos| {
+        particle.age += 1;
+        if particle.age >= particle.lifetime {
+            commands.despawn(*entity);
+            return;
+        }
+        let preset = presets.get(particle.preset);
+        pos.x += particle.velocity.x;
+        pos.y += particle.velocity.y;
+        particle.velocity.scale(preset.drag);
+
+        let color = preset.color_at(particle.age as f32 / particle.lifetime as f32);
+        if let Some(sprite) = world.component_mut::<AnimatedSprite>(*entity) {
+            sprite.color_mod = Color::RGB(color.r, color.g, color.b);
+            sprite.alpha = color.a;
+        }
+        if let Some(light) = world.component_mut::<Light>(*entity) {
+            light.color = Color::RGB(color.r, color.g, color.b);
+            light.intensity = color.a as f32 / 255.;
+        }
+    });
+}
@@ -0,0 +1,191 @@
+// collider queries for gameplay code: what's at a point, in a rect or a circle, and what a
+// ray runs into first. every enabled collider, nav and hitbox alike, goes into the
+// broadphase grid once collisions are resolved each tick, so answers are where things ended
+// up last physics step and something spawned since isn't found yet
+//
+// mask is a set of channel bits, a collider counts when it's on any of them. an entity comes
+// back once however many of its colliders match, sorted so the order is the same every run.
+// sensors are found like anything else, callers skip them when they only want solid things
+
+use ecs::{Entity, Resource, World};
+use sdl2::rect::Rect;
+
+use crate::{
+    broadphase::Grid,
+    components::{ColliderGroup, Pos},
+    math::Vec2,
+};
+
+struct Shape {
+    entity: Entity,
+    bounds: Rect,
+    channels: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct RayHit {
+    pub entity: Entity,
+    // along the ray to where it enters the collider
+    pub distance: f32,
+}
+
+#[derive(Resource)]
+pub struct Physics {
+    shapes: Vec<Shape>,
+    grid: Grid,
+}
+
+impl Physics {
+    pub fn new() -> Self {
+        Physics {
+            shapes: Vec::new(),
+            grid: Grid::new(),
+        }
+    }
+
+    // the shapes on mask in the grid cells area touches, still to be tested exactly
+    fn candidates(&self, area: Rect, mask: usize) -> impl Iterator<Item = &Shape> {
+        self.grid
+            .query(area)
+            .into_iter()
+            .map(|idx| &self.shapes[idx])
+            .filter(move |shape| shape.channels & mask != 0)
+    }
+
+    fn collect<'a>(shapes: impl Iterator<Item = &'a Shape>) -> Vec<Entity> {
+        let mut entities: Vec<Entity> = shapes.map(|shape| shape.entity).collect();
+        entities.sort_unstable();
+        entities.dedup();
+        entities
+    }
+
+    pub fn entities_at_point(&self, point: Pos, mask: usize) -> Vec<Entity> {
+        let area = Rect::new(point.x.floor() as i32, point.y.floor() as i32, 1, 1);
+        Self::collect(
+            self.candidates(area, mask)
+                .filter(|shape| contains(shape.bounds, point)),
+        )
+    }
+
+    pub fn entities_in_rect(&self, rect: Rect, mask: usize) -> Vec<Entity> {
+        Self::collect(
+            self.candidates(rect, mask)
+                .filter(|shape| shape.bounds.has_intersection(rect)),
+        )
+    }
+
+    // anything whose collider reaches into the circle, not just the ones centered in it
+    pub fn entities_in_circle(&self, center: Pos, radius: f32, mask: usize) -> Vec<Entity> {
+        let area = Rect::new(
+            (center.x - radius).floor() as i32,
+            (center.y - radius).floor() as i32,
+            (radius * 2.).ceil() as u32 + 1,
+            (radius * 2.).ceil() as u32 + 1,
+        );
+        Self::collect(
+            self.candidates(area, mask)
+                .filter(|shape| closest_point(shape.bounds, center).distance(&center) <= radius),
+        )
+    }
+
+    // the first collider on mask along the ray, up to max_distance away. a ray doesn't hit
+    // the ones it starts inside of, so casting from something's own center skips it
+    pub fn raycast(
+        &self,
+        from: Pos,
+        mut direction: Vec2<f32>,
+        max_distance: f32,
+        mask: usize,
+    ) -> Option<RayHit> {
+        if direction.magnitude() == 0. {
+            return None;
+        }
+        let direction = direction.normalized();
+        let to = Pos::new(
+            from.x + direction.x * max_distance,
+            from.y + direction.y * max_distance,
+        );
+        // every cell the ray's bounding box touches, plenty for the lengths gameplay casts
+        let area = Rect::new(
+            from.x.min(to.x).floor() as i32,
+            from.y.min(to.y).floor() as i32,
+            (from.x - to.x).abs().ceil() as u32 + 1,
+            (from.y - to.y).abs().ceil() as u32 + 1,
+        );
+
+        self.candidates(area, mask)
+            .filter(|shape| !contains(shape.bounds, from))
+            .filter_map(|shape| {
+                let distance = ray_distance(from, direction, max_distance, shape.bounds)?;
+                Some(RayHit {
+                    entity: shape.entity,
+                    distance,
+                })
+            })
+            // ties go to the lower entity, same as the other queries' order
+            .min_by(|a, b| {
+                a.distance
+                    .total_cmp(&b.distance)
+                    .then(a.entity.cmp(&b.entity))
+            })
+    }
+}
+
+fn contains(rect: Rect, point: Pos) -> bool {
+    point.x >= rect.left() as f32
+        && point.x < rect.right() as f32
+        && point.y >= rect.top() as f32
+        && point.y < rect.bottom() as f32
+}
+
+fn closest_point(rect: Rect, point: Pos) -> Pos {
+    Pos::new(
+        point.x.clamp(rect.left() as f32, rect.right() as f32),
+        point.y.clamp(rect.top() as f32, rect.bottom() as f32),
+    )
+}
+
+// how far along the ray it enters rect, direction is normalized
+fn ray_distance(from: Pos, direction: Vec2<f32>, max_distance: f32, rect: Rect) -> Option<f32> {
+    let mut near = 0.;
+    let mut far = max_distance;
+    for (start, step, min, max) in [
+        (from.x, direction.x, rect.left() as f32, rect.right() as f32),
+        (from.y, direction.y, rect.top() as f32, rect.bottom() as f32),
+    ] {
+        if step == 0. {
+            // parallel to these sides, it's either between them the whole way or never
+            if start < min || start > max {
+                return None;
+            }
+            continue;
+        }
+        let (a, b) = ((min - start) / step, (max - start) / step);
+        near = a.min(b).max(near);
+        far = a.max(b).min(far);
+        if near > far {
+            return None;
+        }
+    }
+    Some(near)
+}
+
+pub fn rebuild(world: &World) {
+    let physics = world.resource_mut::<Physics>().unwrap();
+    physics.shapes.clear();
+    physics.grid.clear();
+    world.run(|entity: &Entity, cg: &ColliderGroup| {
+        for collider in [cg.nav.as_ref(), cg.hitbox.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter(|c| c.is_enabled)
+        {
+            physics.grid.insert(physics.shapes.len(), collider.bounds);
+            physics.shapes.push(Shape {
+                entity: *entity,
+                bounds: collider.bounds,
+                channels: collider.channels,
+            });
+        }
+    });
+}
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+
+use ecs::{Entity, Resource, World};
+
+use crate::components::Pos;
+
+// spawns the archetype with its default values at pos
+pub type PrefabFn = fn(&World, Pos) -> Entity;
+
+#[derive(Resource)]
+pub struct PrefabRegistry {
+    prefabs: HashMap<&'static str, PrefabFn>,
+}
+
+impl PrefabRegistry {
+    pub fn new() -> Self {
+        PrefabRegistry {
+            prefabs: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, name: &'static str, spawn: PrefabFn) {
+        assert!(
+            self.prefabs.insert(name, spawn).is_none(),
+            "prefab {} registered twice",
+            name
+        );
+    }
+
+    pub fn get(&self, name: &str) -> Option<PrefabFn> {
+        self.prefabs.get(name).copied()
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.prefabs.keys().copied().collect();
+        names.sort();
+        names
+    }
+}
+
+pub fn spawn_prefab(world: &World, name: &str, pos: Pos) -> Option<Entity> {
+    let spawn = world.resource::<PrefabRegistry>().unwrap().get(name)?;
+    Some(spawn(world, pos))
+}
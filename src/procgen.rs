@@ -0,0 +1,218 @@
+// rooms-and-corridors dungeons, rooms are carved out of a binary space partition
+
+use rand::Rng;
+
+use crate::{components::HazardKind, math};
+
+const MIN_LEAF_SIZE: i32 = 10;
+const MIN_ROOM_SIZE: i32 = 5;
+const MAX_SPLIT_DEPTH: u32 = 4;
+const HAZARD_CHANCE: f64 = 0.5;
+// hazard noise features per tile, about one every 25 tiles
+const HAZARD_NOISE_SCALE: f32 = 0.04;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Empty,
+    Floor,
+    Wall,
+    Hazard(HazardKind),
+}
+
+impl Tile {
+    // hazards are floors with extra properties
+    pub fn is_walkable(&self) -> bool {
+        matches!(self, Tile::Floor | Tile::Hazard(_))
+    }
+}
+
+// tile coordinates
+#[derive(Clone, Copy)]
+pub struct Room {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl Room {
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.w / 2, self.y + self.h / 2)
+    }
+}
+
+pub struct Dungeon {
+    pub width: i32,
+    pub height: i32,
+    pub tiles: Vec<Tile>,
+    // in generation order, neighbours are connected by a corridor
+    pub rooms: Vec<Room>,
+}
+
+impl Dungeon {
+    pub fn get(&self, x: i32, y: i32) -> Tile {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            Tile::Empty
+        } else {
+            self.tiles[(y * self.width + x) as usize]
+        }
+    }
+
+    fn set(&mut self, x: i32, y: i32, tile: Tile) {
+        if x >= 0 && y >= 0 && x < self.width && y < self.height {
+            self.tiles[(y * self.width + x) as usize] = tile;
+        }
+    }
+
+    fn carve(&mut self, room: &Room) {
+        for y in room.y..room.y + room.h {
+            for x in room.x..room.x + room.w {
+                self.set(x, y, Tile::Floor);
+            }
+        }
+    }
+
+    fn carve_corridor(&mut self, from: (i32, i32), to: (i32, i32), horizontal_first: bool) {
+        let corner = if horizontal_first {
+            (to.0, from.1)
+        } else {
+            (from.0, to.1)
+        };
+
+        for (a, b) in [(from, corner), (corner, to)] {
+            for x in a.0.min(b.0)..=a.0.max(b.0) {
+                for y in a.1.min(b.1)..=a.1.max(b.1) {
+                    self.set(x, y, Tile::Floor);
+                }
+            }
+        }
+    }
+}
+
+pub fn generate(width: i32, height: i32, rng: &mut impl Rng) -> Dungeon {
+    let mut dungeon = Dungeon {
+        width,
+        height,
+        tiles: vec![Tile::Empty; (width * height) as usize],
+        rooms: Vec::new(),
+    };
+
+    // keep a tile of margin for the outer walls
+    let bounds = Room {
+        x: 1,
+        y: 1,
+        w: width - 2,
+        h: height - 2,
+    };
+    split(bounds, MAX_SPLIT_DEPTH, rng, &mut dungeon.rooms);
+
+    for room in dungeon.rooms.clone() {
+        dungeon.carve(&room);
+    }
+
+    for idx in 1..dungeon.rooms.len() {
+        let from = dungeon.rooms[idx - 1].center();
+        let to = dungeon.rooms[idx].center();
+        dungeon.carve_corridor(from, to, rng.gen_bool(0.5));
+    }
+
+    // wall off every empty tile that touches a floor
+    for y in 0..height {
+        for x in 0..width {
+            if dungeon.get(x, y) != Tile::Empty {
+                continue;
+            }
+
+            let touches_floor = (-1..=1)
+                .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+                .any(|(dx, dy)| dungeon.get(x + dx, y + dy) == Tile::Floor);
+            if touches_floor {
+                dungeon.set(x, y, Tile::Wall);
+            }
+        }
+    }
+
+    // a patch of hazards in some rooms, clear of the torch and lever spots. the kind comes
+    // from a noise field over the whole map, so rooms near each other tend to share one and
+    // a level gets a fiery end and an icy end rather than a patchwork
+    let seed = rng.gen();
+    for room in dungeon.rooms.clone().iter().skip(1) {
+        if room.w < 5 || room.h < 5 || !rng.gen_bool(HAZARD_CHANCE) {
+            continue;
+        }
+
+        let (cx, cy) = room.center();
+        let heat = math::fbm(
+            cx as f32 * HAZARD_NOISE_SCALE,
+            cy as f32 * HAZARD_NOISE_SCALE,
+            seed,
+            2,
+        );
+        let kind = match heat {
+            heat if heat > 0.2 => HazardKind::Lava,
+            heat if heat > 0. => HazardKind::Spikes,
+            heat if heat > -0.2 => HazardKind::Water,
+            _ => HazardKind::Ice,
+        };
+        let size = rng.gen_range(2..=3.min(room.w - 3).min(room.h - 3));
+        let x = rng.gen_range(room.x + 1..=room.x + room.w - 2 - size);
+        let y = rng.gen_range(room.y + 2..=room.y + room.h - 1 - size);
+        for ty in y..y + size {
+            for tx in x..x + size {
+                dungeon.set(tx, ty, Tile::Hazard(kind));
+            }
+        }
+    }
+
+    dungeon
+}
+
+fn split(leaf: Room, depth: u32, rng: &mut impl Rng, rooms: &mut Vec<Room>) {
+    let can_split_h = leaf.w >= MIN_LEAF_SIZE * 2;
+    let can_split_v = leaf.h >= MIN_LEAF_SIZE * 2;
+
+    if depth == 0 || !(can_split_h || can_split_v) {
+        // leave a tile between the room and the leaf edge for walls
+        let w = rng.gen_range(MIN_ROOM_SIZE..=(leaf.w - 2).max(MIN_ROOM_SIZE));
+        let h = rng.gen_range(MIN_ROOM_SIZE..=(leaf.h - 2).max(MIN_ROOM_SIZE));
+        let x = leaf.x + rng.gen_range(1..=(leaf.w - w - 1).max(1));
+        let y = leaf.y + rng.gen_range(1..=(leaf.h - h - 1).max(1));
+        rooms.push(Room { x, y, w, h });
+        return;
+    }
+
+    // split across the longer side so leaves stay roughly square
+    let split_h = if can_split_h && can_split_v {
+        leaf.w >= leaf.h
+    } else {
+        can_split_h
+    };
+
+    if split_h {
+        let at = rng.gen_range(MIN_LEAF_SIZE..=leaf.w - MIN_LEAF_SIZE);
+        split(Room { w: at, ..leaf }, depth - 1, rng, rooms);
+        split(
+            Room {
+                x: leaf.x + at,
+                w: leaf.w - at,
+                ..leaf
+            },
+            depth - 1,
+            rng,
+            rooms,
+        );
+    } else {
+        let at = rng.gen_range(MIN_LEAF_SIZE..=leaf.h - MIN_LEAF_SIZE);
+        split(Room { h: at, ..leaf }, depth - 1, rng, rooms);
+        split(
+            Room {
+                y: leaf.y + at,
+                h: leaf.h - at,
+                ..leaf
+            },
+            depth - 1,
+            rng,
+            rooms,
+        );
+    }
+}
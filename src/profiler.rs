@@ -0,0 +1,155 @@
+use std::{collections::VecDeque, time::Duration};
+
+use ecs::Resource;
+use sdl2::pixels::Color;
+
+use crate::{
+    scheduler::Scheduler,
+    ui::{Anchor, Ui},
+};
+
+// frames of history
+const HISTORY: usize = 120;
+const TOP_SYSTEMS: usize = 8;
+const GRAPH_HEIGHT: u32 = 80;
+const GRAPH_BAR_WIDTH: u32 = 2;
+// frame time at the top of the graph
+const GRAPH_MAX: Duration = Duration::from_micros(33_333);
+const FRAME_BUDGET: Duration = Duration::from_micros(16_667);
+
+#[derive(Resource)]
+pub struct Profiler {
+    pub is_visible: bool,
+    frames: VecDeque<Duration>,
+    // per system, in scheduler order
+    systems: Vec<(&'static str, VecDeque<Duration>)>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            is_visible: false,
+            frames: VecDeque::with_capacity(HISTORY),
+            systems: Vec::new(),
+        }
+    }
+
+    // call once per frame, before the scheduler's end_frame
+    pub fn record(&mut self, frame_time: Duration, scheduler: &Scheduler) {
+        push_sample(&mut self.frames, frame_time);
+
+        for system in scheduler.systems() {
+            let idx = match self.systems.iter().position(|(n, _)| *n == system.name) {
+                Some(idx) => idx,
+                None => {
+                    self.systems
+                        .push((system.name, VecDeque::with_capacity(HISTORY)));
+                    self.systems.len() - 1
+                }
+            };
+            push_sample(&mut self.systems[idx].1, system.frame_time);
+        }
+    }
+
+    // (name, average, worst) over the history
+    fn slowest_systems(&self) -> Vec<(&'static str, Duration, Duration)> {
+        let mut stats: Vec<_> = self
+            .systems
+            .iter()
+            .map(|(name, samples)| {
+                let total: Duration = samples.iter().sum();
+                let worst = samples.iter().max().copied().unwrap_or_default();
+                (*name, total / samples.len().max(1) as u32, worst)
+            })
+            .collect();
+        stats.sort_by_key(|(_, average, _)| std::cmp::Reverse(*average));
+        stats.truncate(TOP_SYSTEMS);
+        stats
+    }
+}
+
+fn push_sample(samples: &mut VecDeque<Duration>, sample: Duration) {
+    if samples.len() == HISTORY {
+        samples.pop_front();
+    }
+    samples.push_back(sample);
+}
+
+fn ms(duration: Duration) -> f32 {
+    duration.as_secs_f32() * 1000.
+}
+
+pub fn draw(profiler: &Profiler, ui: &mut Ui) {
+    let width = HISTORY as u32 * GRAPH_BAR_WIDTH + 16;
+    let height = GRAPH_HEIGHT + 40 + 20 * TOP_SYSTEMS as u32;
+
+    ui.fill(
+        Anchor::TopRight,
+        (-4, 24),
+        (width.max(340), height),
+        Color::RGBA(0, 0, 0, 200),
+        |ui| {
+            let latest = profiler.frames.back().copied().unwrap_or_default();
+            let worst = profiler.frames.iter().max().copied().unwrap_or_default();
+            ui.changing_label(
+                Anchor::TopLeft,
+                (8, 4),
+                &format!("frame {:.2}ms, worst {:.2}ms", ms(latest), ms(worst)),
+                Color::WHITE,
+                None,
+            );
+
+            ui.fill(
+                Anchor::TopLeft,
+                (8, 28),
+                (HISTORY as u32 * GRAPH_BAR_WIDTH, GRAPH_HEIGHT),
+                Color::RGBA(40, 40, 40, 255),
+                |ui| {
+                    for (idx, frame) in profiler.frames.iter().enumerate() {
+                        let fraction = (frame.as_secs_f32() / GRAPH_MAX.as_secs_f32()).min(1.);
+                        let bar_height = ((fraction * GRAPH_HEIGHT as f32) as u32).max(1);
+                        let color = if *frame > GRAPH_MAX {
+                            Color::RGB(255, 60, 60)
+                        } else if *frame > FRAME_BUDGET {
+                            Color::RGB(255, 200, 0)
+                        } else {
+                            Color::RGB(80, 220, 80)
+                        };
+                        ui.fill(
+                            Anchor::BottomLeft,
+                            ((idx as u32 * GRAPH_BAR_WIDTH) as i32, 0),
+                            (GRAPH_BAR_WIDTH, bar_height),
+                            color,
+                            |_| {},
+                        );
+                    }
+
+                    // the 60fps line
+                    let budget = FRAME_BUDGET.as_secs_f32() / GRAPH_MAX.as_secs_f32();
+                    ui.fill(
+                        Anchor::BottomLeft,
+                        (0, -((budget * GRAPH_HEIGHT as f32) as i32)),
+                        (HISTORY as u32 * GRAPH_BAR_WIDTH, 1),
+                        Color::RGBA(255, 255, 255, 120),
+                        |_| {},
+                    );
+                },
+            );
+
+            for (idx, (name, average, worst)) in profiler.slowest_systems().iter().enumerate() {
+                ui.changing_label(
+                    Anchor::TopLeft,
+                    (8, 36 + GRAPH_HEIGHT as i32 + 20 * idx as i32),
+                    &format!(
+                        "{:<16} {:.2}ms avg {:.2}ms max",
+                        name,
+                        ms(*average),
+                        ms(*worst)
+                    ),
+                    Color::RGB(200, 200, 200),
+                    None,
+                );
+            }
+        },
+    );
+}
@@ -0,0 +1,207 @@
+// records the input of every tick so a run can be played back exactly,
+// together with the rng seed that's all the simulation depends on
+//
+// file format: "seed <n>", with " coop" when a second local player took part, followed by
+// one line per tick,
+//
+//   <pressed> <just_pressed> <mouse x> <mouse y> <hash> <p2 pressed> <p2 just_pressed>
+//
+// the input states as hex bitmasks, p2 is the second local player. the hash is
+// game::state_hash after the tick, playback compares against it and reports the first tick
+// that went differently. older replays without the hash play back unchecked, and without
+// the second player's input it stands still
+
+use ecs::Resource;
+
+use crate::{Input, InputState};
+
+#[derive(Clone, Copy)]
+pub struct TickInput {
+    pressed: u32,
+    just_pressed: u32,
+    mouse_pos: (i32, i32),
+    hash: Option<u64>,
+    // pressed and just_pressed
+    second: (u32, u32),
+}
+
+#[derive(Resource)]
+pub enum Replay {
+    Idle,
+    Recording {
+        path: String,
+        seed: u64,
+        is_coop: bool,
+        ticks: Vec<TickInput>,
+    },
+    Playing {
+        ticks: Vec<TickInput>,
+        next: usize,
+        is_desynced: bool,
+        is_coop: bool,
+    },
+}
+
+impl Replay {
+    pub fn record(path: &str, seed: u64, is_coop: bool) -> Self {
+        println!("recording to {}", path);
+        Replay::Recording {
+            path: path.to_string(),
+            seed,
+            is_coop,
+            ticks: Vec::new(),
+        }
+    }
+
+    // returns the seed the replay was recorded with
+    pub fn load(path: &str) -> Result<(Self, u64), String> {
+        let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut lines = data.lines();
+        let header = lines.next().and_then(|line| line.strip_prefix("seed "));
+        let is_coop = header.is_some_and(|header| header.ends_with(" coop"));
+        let seed = header
+            .map(|header| header.trim_end_matches(" coop"))
+            .and_then(|seed| seed.parse().ok())
+            .ok_or_else(|| format!("{} doesn't start with a seed", path))?;
+
+        let ticks = lines
+            .enumerate()
+            .map(|(idx, line)| {
+                // the seed is line 1
+                parse_tick(line).ok_or_else(|| format!("{}:{}: bad tick '{}'", path, idx + 2, line))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        println!("playing {} ticks from {}", ticks.len(), path);
+        Ok((
+            Replay::Playing {
+                ticks,
+                next: 0,
+                is_desynced: false,
+                is_coop,
+            },
+            seed,
+        ))
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self, Replay::Playing { .. })
+    }
+
+    // whether the second local player has to be there for the replay to play back
+    pub fn is_coop(&self) -> bool {
+        match self {
+            Replay::Idle => false,
+            Replay::Recording { is_coop, .. } | Replay::Playing { is_coop, .. } => *is_coop,
+        }
+    }
+
+    // once per tick, after the real input of both local players has been read
+    pub fn tick(&mut self, input: &mut Input, second: &mut Input) {
+        match self {
+            Replay::Idle => {}
+            Replay::Recording { ticks, .. } => ticks.push(TickInput {
+                pressed: input.pressed.to_bits(),
+                just_pressed: input.just_pressed.to_bits(),
+                mouse_pos: input.mouse_pos,
+                hash: None,
+                second: (second.pressed.to_bits(), second.just_pressed.to_bits()),
+            }),
+            Replay::Playing { ticks, next, .. } => {
+                let Some(tick) = ticks.get(*next) else {
+                    println!("replay finished after {} ticks", next);
+                    *self = Replay::Idle;
+                    return;
+                };
+                input.pressed = InputState::from_bits(tick.pressed);
+                input.just_pressed = InputState::from_bits(tick.just_pressed);
+                input.mouse_pos = tick.mouse_pos;
+                second.pressed = InputState::from_bits(tick.second.0);
+                second.just_pressed = InputState::from_bits(tick.second.1);
+                *next += 1;
+            }
+        }
+    }
+
+    // once per tick, after the update, with game::state_hash
+    pub fn check(&mut self, hash: u64) {
+        match self {
+            Replay::Idle => {}
+            Replay::Recording { ticks, .. } => {
+                if let Some(tick) = ticks.last_mut() {
+                    tick.hash = Some(hash);
+                }
+            }
+            Replay::Playing {
+                ticks,
+                next,
+                is_desynced,
+                ..
+            } => {
+                let played = next.saturating_sub(1);
+                let expected = ticks.get(played).and_then(|tick| tick.hash);
+                if !*is_desynced && expected.is_some_and(|expected| expected != hash) {
+                    println!("replay desynced at tick {}", played);
+                    *is_desynced = true;
+                }
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Replay::Recording {
+            path,
+            seed,
+            is_coop,
+            ticks,
+        } = self
+        else {
+            return;
+        };
+
+        let coop = if *is_coop { " coop" } else { "" };
+        let mut data = format!("seed {}{}\n", seed, coop);
+        for tick in ticks {
+            data += &format!(
+                "{:x} {:x} {} {} {:x} {:x} {:x}\n",
+                tick.pressed,
+                tick.just_pressed,
+                tick.mouse_pos.0,
+                tick.mouse_pos.1,
+                tick.hash.unwrap_or_default(),
+                tick.second.0,
+                tick.second.1
+            );
+        }
+        if let Err(e) = std::fs::write(path, data) {
+            println!("failed to save {}: {}", path, e);
+            return;
+        }
+        println!("saved {} ticks to {}", ticks.len(), path);
+    }
+}
+
+fn parse_tick(line: &str) -> Option<TickInput> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let hex = |part: &str| u32::from_str_radix(part, 16).ok();
+    let (pressed, just_pressed, x, y, rest) = match parts[..] {
+        [pressed, just_pressed, x, y, ref rest @ ..] => (pressed, just_pressed, x, y, rest),
+        _ => return None,
+    };
+    let (hash, second) = match *rest {
+        [] => (None, (0, 0)),
+        [hash] => (Some(u64::from_str_radix(hash, 16).ok()?), (0, 0)),
+        [hash, pressed, just_pressed] => (
+            Some(u64::from_str_radix(hash, 16).ok()?),
+            (hex(pressed)?, hex(just_pressed)?),
+        ),
+        _ => return None,
+    };
+    Some(TickInput {
+        pressed: hex(pressed)?,
+        just_pressed: hex(just_pressed)?,
+        mouse_pos: (x.parse().ok()?, y.parse().ok()?),
+        hash,
+        second,
+    })
+}
@@ -0,0 +1,70 @@
+// xorshift64*, small and the same on every platform, unlike thread_rng
+
+use ecs::Resource;
+use rand::RngCore;
+
+#[derive(Resource)]
+pub struct GameRng {
+    seed: u64,
+    state: u64,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = GameRng { seed, state: 0 };
+        rng.reseed(seed);
+        rng
+    }
+
+    // a seed from the clock, for when none was asked for
+    pub fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        GameRng::new(nanos as u64)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // for checking two runs haven't drifted apart
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        // splitmix so nearby seeds don't start out correlated, xorshift can't have a zero state
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        self.state = (z ^ (z >> 31)).max(1);
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
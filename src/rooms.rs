@@ -0,0 +1,198 @@
+// room files, one prefab per line on the tile grid with optional properties after it:
+//
+//   torch 5 6 radius=120 intensity=0.8
+//   lever 7 8 channel=3 script=open_vault
+//
+// the properties override whatever the prefab spawned with, a script has to be one of the
+// hooks in scripts.rs
+
+use ecs::{Entity, World};
+
+use crate::{
+    assets,
+    components::{Interactable, Light, SignalEmitter, SignalReceiver, TriggerZone},
+    game::{light_base_mut, tile_to_pos},
+    prefabs::{spawn_prefab, PrefabRegistry},
+    scripts::Scripts,
+    tween::{LightIntensity, Tween},
+};
+
+pub const ROOM_DIR: &str = "rooms";
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Props {
+    pub radius: Option<u16>,
+    pub intensity: Option<f32>,
+    pub channel: Option<u16>,
+    // interactables run it when used, trigger zones when entered
+    pub script: Option<&'static str>,
+}
+
+pub struct Placement {
+    pub prefab: &'static str,
+    pub tile: (i32, i32),
+    pub props: Props,
+    pub entity: Entity,
+}
+
+// rooms are saved into the base assets, never into a mod
+pub fn room_path(name: &str) -> String {
+    format!("{}/{}/{}.txt", assets::BASE_DIR, ROOM_DIR, name)
+}
+
+pub fn place(world: &World, prefab: &str, tile: (i32, i32), props: Props) -> Option<Placement> {
+    // the registry hands out the 'static name
+    let prefab = world
+        .resource::<PrefabRegistry>()
+        .unwrap()
+        .names()
+        .into_iter()
+        .find(|name| *name == prefab)?;
+    let entity = spawn_prefab(world, prefab, tile_to_pos(tile.0, tile.1))?;
+    apply_props(world, entity, props);
+    Some(Placement {
+        prefab,
+        tile,
+        props,
+        entity,
+    })
+}
+
+// properties the entity doesn't have are ignored
+pub fn apply_props(world: &World, entity: Entity, props: Props) {
+    if world.has_component::<Light>(entity) {
+        let light = light_base_mut(world, entity);
+        if let Some(radius) = props.radius {
+            light.radius = radius;
+        }
+        if let Some(intensity) = props.intensity {
+            light.intensity = intensity;
+            // fades would put the old intensity back
+            if let Some(fade) = world.component_mut::<Tween<LightIntensity>>(entity) {
+                *fade = Tween::idle(LightIntensity(intensity));
+            }
+        }
+    }
+    if let Some(channel) = props.channel {
+        if let Some(emitter) = world.component_mut::<SignalEmitter>(entity) {
+            emitter.channel = channel;
+        }
+        if let Some(receiver) = world.component_mut::<SignalReceiver>(entity) {
+            receiver.channel = channel;
+        }
+    }
+    if let Some(script) = props.script {
+        if let Some(interactable) = world.component_mut::<Interactable>(entity) {
+            interactable.script = Some(script);
+        }
+        if let Some(trigger) = world.component_mut::<TriggerZone>(entity) {
+            trigger.script = Some(script);
+        }
+    }
+}
+
+// the current value of every property the entity has, None for the rest
+pub fn props_of(world: &World, entity: Entity) -> Props {
+    let mut props = Props::default();
+    if world.has_component::<Light>(entity) {
+        let light = light_base_mut(world, entity);
+        props.radius = Some(light.radius);
+        props.intensity = Some(light.intensity);
+    }
+    props.channel = world
+        .component::<SignalEmitter>(entity)
+        .map(|emitter| emitter.channel)
+        .or_else(|| {
+            world
+                .component::<SignalReceiver>(entity)
+                .map(|receiver| receiver.channel)
+        });
+    props.script = world
+        .component::<Interactable>(entity)
+        .and_then(|interactable| interactable.script)
+        .or_else(|| {
+            world
+                .component::<TriggerZone>(entity)
+                .and_then(|trigger| trigger.script)
+        });
+    props
+}
+
+// the hook's 'static name
+pub fn parse_script(world: &World, name: &str) -> Option<&'static str> {
+    world.resource::<Scripts>().unwrap().find(name)
+}
+
+// spawns everything in the file next to the current level, see editor::replace_level
+pub fn load(world: &World, name: &str) -> Result<Vec<Placement>, String> {
+    let path = assets::path(&format!("{}/{}.txt", ROOM_DIR, name));
+    let data = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path, e))?;
+
+    let mut placements = Vec::new();
+    for (idx, line) in data.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |what: &str| format!("{}:{}: {}", path, idx + 1, what);
+
+        let mut words = line.split_whitespace();
+        let (Some(prefab), Some(x), Some(y)) = (words.next(), words.next(), words.next()) else {
+            return Err(error("expected a prefab and a tile"));
+        };
+        let tile = match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => (x, y),
+            _ => return Err(error("bad tile")),
+        };
+
+        let mut props = Props::default();
+        for prop in words {
+            let parsed = match prop.split_once('=') {
+                Some(("radius", v)) => v.parse().map(|v| props.radius = Some(v)).is_ok(),
+                Some(("intensity", v)) => v.parse().map(|v| props.intensity = Some(v)).is_ok(),
+                Some(("channel", v)) => v.parse().map(|v| props.channel = Some(v)).is_ok(),
+                Some(("script", v)) => parse_script(world, v)
+                    .map(|v| props.script = Some(v))
+                    .is_some(),
+                _ => false,
+            };
+            if !parsed {
+                return Err(error(&format!("bad property '{}'", prop)));
+            }
+        }
+
+        let placement = place(world, prefab, tile, props)
+            .ok_or_else(|| error(&format!("no prefab '{}'", prefab)))?;
+        placements.push(placement);
+    }
+    Ok(placements)
+}
+
+pub fn save(name: &str, placements: &[Placement]) -> Result<(), String> {
+    let mut data = String::from("# prefab tile_x tile_y [radius= intensity= channel= script=]\n");
+    for placement in placements {
+        data += &format!(
+            "{} {} {}",
+            placement.prefab, placement.tile.0, placement.tile.1
+        );
+        let props = placement.props;
+        if let Some(radius) = props.radius {
+            data += &format!(" radius={}", radius);
+        }
+        if let Some(intensity) = props.intensity {
+            data += &format!(" intensity={:.2}", intensity);
+        }
+        if let Some(channel) = props.channel {
+            data += &format!(" channel={}", channel);
+        }
+        if let Some(script) = props.script {
+            data += &format!(" script={}", script);
+        }
+        data.push('\n');
+    }
+
+    let path = room_path(name);
+    std::fs::create_dir_all(format!("{}/{}", assets::BASE_DIR, ROOM_DIR))
+        .map_err(|e| e.to_string())?;
+    std::fs::write(&path, data).map_err(|e| format!("{}: {}", path, e))
+}
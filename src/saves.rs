@@ -0,0 +1,423 @@
+// save slots under the platform data dir, each one a directory with the saved state
+// and a thumbnail of the screen at the time
+//
+// levels aren't serializable yet, so a save holds the player's progress and loading
+// puts the player back into whatever level is currently loaded
+
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ecs::{Resource, With, World};
+use sdl2::{
+    image::{LoadTexture, SaveSurface},
+    pixels::{Color, PixelFormatEnum},
+    rect::Rect,
+    render::Texture,
+    surface::Surface,
+};
+
+use crate::{
+    checkpoints::Checkpoints,
+    components::{Player, Pos, PrevPos},
+    locale::{tr, tr_args},
+    toasts,
+    ui::{Anchor, Ui},
+    Ctx, TICKS_PER_SECOND,
+};
+
+pub const NUM_SLOTS: usize = 3;
+// listed after the manual slots, never the quicksave target
+pub const AUTOSAVE_SLOT: usize = NUM_SLOTS;
+const SAVE_FILE: &str = "save.toml";
+const THUMBNAIL_FILE: &str = "thumbnail.png";
+const THUMBNAIL_SIZE: (u32, u32) = (128, 72);
+const ROW_HEIGHT: i32 = 84;
+
+pub fn data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("gaem")
+}
+
+fn slot_dir(slot: usize) -> PathBuf {
+    let name = match slot {
+        AUTOSAVE_SLOT => "autosave".to_string(),
+        _ => format!("slot{}", slot + 1),
+    };
+    data_dir().join("saves").join(name)
+}
+
+fn slot_name(slot: usize) -> String {
+    match slot {
+        AUTOSAVE_SLOT => tr("saves-autosave").to_string(),
+        _ => tr_args("saves-slot", &[("slot", &(slot + 1).to_string())]),
+    }
+}
+
+#[derive(Clone)]
+pub struct SaveMeta {
+    // unix seconds
+    pub timestamp: u64,
+    pub play_ticks: u64,
+    pub room: String,
+}
+
+struct SaveData {
+    meta: SaveMeta,
+    player_pos: Pos,
+    coins: u32,
+}
+
+impl SaveData {
+    // None if the slot is empty or unreadable
+    fn load(dir: &Path) -> Option<Self> {
+        let path = dir.join(SAVE_FILE);
+        let data = std::fs::read_to_string(&path).ok()?;
+
+        let mut save = SaveData {
+            meta: SaveMeta {
+                timestamp: 0,
+                play_ticks: 0,
+                room: String::new(),
+            },
+            player_pos: Pos::new(0., 0.),
+            coins: 0,
+        };
+        for line in data
+            .lines()
+            .map(|line| line.split('#').next().unwrap().trim())
+            .filter(|line| !line.is_empty())
+        {
+            let Some((key, value)) = line.split_once('=') else {
+                println!("{}: ignoring '{}'", path.display(), line);
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            let parsed = match key.trim() {
+                "timestamp" => value.parse().map(|v| save.meta.timestamp = v).is_ok(),
+                "play_ticks" => value.parse().map(|v| save.meta.play_ticks = v).is_ok(),
+                "room" => {
+                    save.meta.room = value.to_string();
+                    true
+                }
+                "player_x" => value.parse().map(|v| save.player_pos.x = v).is_ok(),
+                "player_y" => value.parse().map(|v| save.player_pos.y = v).is_ok(),
+                "coins" => value.parse().map(|v| save.coins = v).is_ok(),
+                _ => false,
+            };
+            if !parsed {
+                println!("{}: ignoring '{}'", path.display(), line);
+            }
+        }
+
+        Some(save)
+    }
+
+    fn save(&self, dir: &Path) {
+        let data = format!(
+            "timestamp = {}\n\
+             play_ticks = {}\n\
+             room = \"{}\"\n\
+             player_x = {:.1}\n\
+             player_y = {:.1}\n\
+             coins = {}\n",
+            self.meta.timestamp,
+            self.meta.play_ticks,
+            self.meta.room,
+            self.player_pos.x,
+            self.player_pos.y,
+            self.coins,
+        );
+        let path = dir.join(SAVE_FILE);
+        if let Err(e) = std::fs::write(&path, data) {
+            println!("failed to save {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct SaveSlots {
+    pub is_open: bool,
+    selected: usize,
+    // quicksaves go to the slot last saved to or loaded from
+    current: usize,
+    save_requested: Option<usize>,
+    // read whenever the menu opens
+    metas: Vec<Option<SaveMeta>>,
+    thumbnails: Vec<Option<Texture>>,
+    // play time of the loaded save and the tick it was loaded at
+    play_ticks_base: u64,
+    loaded_at_tick: u32,
+}
+
+impl SaveSlots {
+    pub fn new() -> Self {
+        SaveSlots {
+            is_open: false,
+            selected: 0,
+            current: 0,
+            save_requested: None,
+            metas: vec![None; NUM_SLOTS + 1],
+            thumbnails: (0..=NUM_SLOTS).map(|_| None).collect(),
+            play_ticks_base: 0,
+            loaded_at_tick: 0,
+        }
+    }
+
+    // None is the current slot. written once the frame is drawn, for the thumbnail
+    pub fn request_save(&mut self, slot: Option<usize>) {
+        self.save_requested = Some(slot.unwrap_or(self.current));
+    }
+
+    fn play_ticks(&self, ticks: u32) -> u64 {
+        self.play_ticks_base + ticks.wrapping_sub(self.loaded_at_tick) as u64
+    }
+}
+
+pub fn open(world: &World) {
+    let slots = world.resource_mut::<SaveSlots>().unwrap();
+    let ctx = world.resource::<Ctx>().unwrap();
+    let texture_creator = ctx.canvas.texture_creator();
+
+    for slot in 0..=NUM_SLOTS {
+        let dir = slot_dir(slot);
+        slots.metas[slot] = SaveData::load(&dir).map(|save| save.meta);
+        if let Some(old) = slots.thumbnails[slot].take() {
+            unsafe { old.destroy() };
+        }
+        slots.thumbnails[slot] = texture_creator.load_texture(dir.join(THUMBNAIL_FILE)).ok();
+    }
+    slots.selected = slots.current;
+    slots.is_open = true;
+}
+
+pub fn close(world: &World) {
+    let slots = world.resource_mut::<SaveSlots>().unwrap();
+    slots.is_open = false;
+    for thumbnail in slots.thumbnails.iter_mut() {
+        if let Some(texture) = thumbnail.take() {
+            unsafe { texture.destroy() };
+        }
+    }
+}
+
+pub fn update_menu(world: &World) {
+    let ctx = world.resource::<Ctx>().unwrap();
+    let slots = world.resource_mut::<SaveSlots>().unwrap();
+    let input = &ctx.input.just_pressed;
+
+    let count = NUM_SLOTS + 1;
+    if input.up {
+        slots.selected = (slots.selected + count - 1) % count;
+    }
+    if input.down {
+        slots.selected = (slots.selected + 1) % count;
+    }
+    if input.tab {
+        close(world);
+    } else if input.interact && slots.metas[slots.selected].is_some() {
+        load(world, slots.selected);
+        close(world);
+    }
+}
+
+fn load(world: &World, slot: usize) {
+    let Some(save) = SaveData::load(&slot_dir(slot)) else {
+        return;
+    };
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    let slots = world.resource_mut::<SaveSlots>().unwrap();
+
+    world.run(|pos: &mut Pos, prev_pos: &mut PrevPos, _: With<Player>| {
+        *pos = save.player_pos;
+        prev_pos.0 = save.player_pos;
+    });
+    ctx.player_pos = save.player_pos;
+    ctx.coins = save.coins;
+    // wherever the save was made is safe to come back to
+    world.resource_mut::<Checkpoints>().unwrap().respawn_pos = save.player_pos;
+
+    if slot != AUTOSAVE_SLOT {
+        slots.current = slot;
+    }
+    slots.play_ticks_base = save.meta.play_ticks;
+    slots.loaded_at_tick = ctx.ticks;
+
+    toasts::push(
+        world,
+        &tr_args("toast-game-loaded", &[("slot", &slot_name(slot))]),
+    );
+}
+
+// called after the frame is drawn and before present, the screen becomes the thumbnail
+pub fn write_pending(world: &World) {
+    let slots = world.resource_mut::<SaveSlots>().unwrap();
+    let Some(slot) = slots.save_requested.take() else {
+        return;
+    };
+    let ctx = world.resource::<Ctx>().unwrap();
+
+    let dir = slot_dir(slot);
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        println!("failed to create {}: {}", dir.display(), e);
+        return;
+    }
+
+    let save = SaveData {
+        meta: SaveMeta {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            play_ticks: slots.play_ticks(ctx.ticks),
+            room: ctx.level_name.to_string(),
+        },
+        player_pos: ctx.player_pos,
+        coins: ctx.coins,
+    };
+    save.save(&dir);
+
+    let (width, height) = ctx.canvas.output_size().unwrap();
+    let mut pixels = ctx
+        .canvas
+        .read_pixels(None, PixelFormatEnum::RGB24)
+        .unwrap();
+    let screen = Surface::from_data(
+        &mut pixels,
+        width,
+        height,
+        width * 3,
+        PixelFormatEnum::RGB24,
+    )
+    .unwrap();
+    let mut thumbnail =
+        Surface::new(THUMBNAIL_SIZE.0, THUMBNAIL_SIZE.1, PixelFormatEnum::RGB24).unwrap();
+    screen.blit_scaled(None, &mut thumbnail, None).unwrap();
+    if let Err(e) = thumbnail.save(dir.join(THUMBNAIL_FILE)) {
+        println!("failed to save thumbnail: {}", e);
+    }
+
+    if slot != AUTOSAVE_SLOT {
+        slots.current = slot;
+    }
+    toasts::push(
+        world,
+        &tr_args("toast-game-saved", &[("slot", &slot_name(slot))]),
+    );
+}
+
+fn format_play_time(ticks: u64) -> String {
+    let seconds = ticks / TICKS_PER_SECOND as u64;
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// utc, there's no timezone database to ask
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    // days to civil date, from howard hinnant's date algorithms
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60
+    )
+}
+
+pub fn draw(slots: &SaveSlots, ui: &mut Ui) {
+    let height = ROW_HEIGHT * (NUM_SLOTS + 1) as i32 + 40;
+
+    ui.fill(
+        Anchor::Center,
+        (0, 0),
+        (420, height as u32),
+        Color::RGBA(0, 0, 0, 220),
+        |ui| {
+            ui.label(
+                Anchor::TopLeft,
+                (12, 8),
+                tr("saves-title"),
+                Color::WHITE,
+                None,
+            );
+
+            for slot in 0..=NUM_SLOTS {
+                let y = 32 + ROW_HEIGHT * slot as i32;
+                let color = if slot == slots.selected {
+                    Color::RGB(255, 255, 0)
+                } else {
+                    Color::RGB(200, 200, 200)
+                };
+
+                let viewport = ui.viewport();
+                let frame = Rect::new(
+                    viewport.x() + 12,
+                    viewport.y() + y,
+                    THUMBNAIL_SIZE.0,
+                    THUMBNAIL_SIZE.1,
+                );
+                let canvas = ui.canvas();
+                match &slots.thumbnails[slot] {
+                    Some(thumbnail) => canvas.copy(thumbnail, None, frame).unwrap(),
+                    None => {
+                        canvas.set_draw_color(Color::RGB(30, 30, 30));
+                        canvas.fill_rect(frame).unwrap();
+                    }
+                }
+                if slot == slots.selected {
+                    canvas.set_draw_color(color);
+                    canvas.draw_rect(frame).unwrap();
+                }
+
+                let x = 12 + THUMBNAIL_SIZE.0 as i32 + 12;
+                ui.label(Anchor::TopLeft, (x, y), &slot_name(slot), color, None);
+                let Some(meta) = &slots.metas[slot] else {
+                    ui.label(
+                        Anchor::TopLeft,
+                        (x, y + 20),
+                        tr("saves-empty"),
+                        Color::RGB(140, 140, 140),
+                        None,
+                    );
+                    continue;
+                };
+                for (line, text) in [
+                    meta.room.clone(),
+                    format_play_time(meta.play_ticks),
+                    format_timestamp(meta.timestamp),
+                ]
+                .iter()
+                .enumerate()
+                {
+                    ui.label(
+                        Anchor::TopLeft,
+                        (x, y + 20 * (line as i32 + 1)),
+                        text,
+                        Color::RGB(140, 140, 140),
+                        None,
+                    );
+                }
+            }
+        },
+    );
+}
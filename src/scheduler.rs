@@ -0,0 +1,359 @@
+// systems are grouped into stages, within a stage they run after their
+// dependencies. systems can declare what they read and write, two in the same stage that
+// touch the same thing, one of them writing it, have to be ordered with after. that keeps
+// the order they share the rng and the like in from hanging on registration order, so a
+// tick always runs the same way and replays and co-op stay in step. nothing checks the
+// declarations against what a system actually touches.
+//
+// the world isn't thread safe, so systems given the world run on the main thread one at a
+// time. systems added with add_job only ever get their one resource: the scheduler borrows
+// it on the main thread and neighbouring jobs on different resources run side by side on
+// scoped threads. their access is declared by the type, so it can't be wrong
+
+use std::{
+    any::TypeId,
+    time::{Duration, Instant},
+};
+
+use ecs::{Resource, World};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Stage {
+    Input,
+    Ai,
+    Physics,
+    PostPhysics,
+    Render,
+    Ui,
+}
+
+impl Stage {
+    // run once per tick
+    pub const UPDATE: [Stage; 4] = [Stage::Input, Stage::Ai, Stage::Physics, Stage::PostPhysics];
+}
+
+pub type SystemFn = fn(&World);
+
+// what a job system borrowed out of the world, it can run on any thread
+type Job<'w> = Box<dyn FnOnce() + Send + 'w>;
+type JobFn = Box<dyn for<'w> Fn(&'w World) -> Job<'w>>;
+
+enum Run {
+    World(SystemFn),
+    Job(JobFn),
+}
+
+#[derive(Default)]
+struct Access {
+    reads: Vec<TypeId>,
+    writes: Vec<TypeId>,
+}
+
+impl Access {
+    fn conflicts_with(&self, other: &Access) -> bool {
+        self.writes
+            .iter()
+            .any(|t| other.reads.contains(t) || other.writes.contains(t))
+            || other.writes.iter().any(|t| self.reads.contains(t))
+    }
+}
+
+pub struct System {
+    pub name: &'static str,
+    pub stage: Stage,
+    run: Run,
+    after: Vec<&'static str>,
+    condition: Option<fn(&World) -> bool>,
+    // None when nothing was declared, it isn't checked against anything then
+    access: Option<Access>,
+    // how long the last run took, zero when it was skipped
+    pub time: Duration,
+    // summed over every run since the last end_frame, update systems can run several times a frame
+    pub frame_time: Duration,
+}
+
+impl System {
+    // once anything is declared, every component and resource the system touches should be
+    pub fn reads<T: 'static>(&mut self) -> &mut Self {
+        let access = self.access.get_or_insert_with(Access::default);
+        access.reads.push(TypeId::of::<T>());
+        self
+    }
+
+    pub fn writes<T: 'static>(&mut self) -> &mut Self {
+        let access = self.access.get_or_insert_with(Access::default);
+        access.writes.push(TypeId::of::<T>());
+        self
+    }
+
+    fn run_timed(&mut self, world: &World) {
+        let start = Instant::now();
+        match &self.run {
+            Run::World(run) => run(world),
+            Run::Job(borrow) => borrow(world)(),
+        }
+        self.record(Instant::now().duration_since(start));
+    }
+
+    fn record(&mut self, time: Duration) {
+        self.time = time;
+        self.frame_time += time;
+    }
+
+    fn is_job(&self) -> bool {
+        matches!(self.run, Run::Job(_))
+    }
+
+    fn should_run(&mut self, world: &World) -> bool {
+        if self.condition.is_some_and(|condition| !condition(world)) {
+            self.time = Duration::ZERO;
+            return false;
+        }
+        true
+    }
+
+    pub fn after(&mut self, name: &'static str) -> &mut Self {
+        self.after.push(name);
+        self
+    }
+
+    pub fn run_if(&mut self, condition: fn(&World) -> bool) -> &mut Self {
+        self.condition = Some(condition);
+        self
+    }
+}
+
+#[derive(Resource)]
+pub struct Scheduler {
+    systems: Vec<System>,
+    is_sorted: bool,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Scheduler {
+            systems: Vec::new(),
+            is_sorted: true,
+        }
+    }
+
+    pub fn add(&mut self, stage: Stage, name: &'static str, run: SystemFn) -> &mut System {
+        self.push(stage, name, Run::World(run))
+    }
+
+    // a system that only touches R, it writes it as far as conflicts go
+    pub fn add_job<R: Resource + Send>(
+        &mut self,
+        stage: Stage,
+        name: &'static str,
+        run: fn(&mut R),
+    ) -> &mut System {
+        let borrow: JobFn = Box::new(move |world| {
+            let resource = world.resource_mut::<R>().unwrap();
+            Box::new(move || run(resource))
+        });
+        self.push(stage, name, Run::Job(borrow)).writes::<R>()
+    }
+
+    fn push(&mut self, stage: Stage, name: &'static str, run: Run) -> &mut System {
+        assert!(
+            self.systems.iter().all(|s| s.name != name),
+            "system {} registered twice",
+            name
+        );
+
+        self.is_sorted = false;
+        self.systems.push(System {
+            name,
+            stage,
+            run,
+            after: Vec::new(),
+            condition: None,
+            access: None,
+            time: Duration::ZERO,
+            frame_time: Duration::ZERO,
+        });
+        self.systems.last_mut().unwrap()
+    }
+
+    pub fn systems(&self) -> &[System] {
+        &self.systems
+    }
+
+    pub fn end_frame(&mut self) {
+        for system in self.systems.iter_mut() {
+            system.frame_time = Duration::ZERO;
+        }
+    }
+
+    pub fn run(&mut self, world: &World, stage: Stage) {
+        if !self.is_sorted {
+            self.sort();
+        }
+
+        let start = self
+            .systems
+            .iter()
+            .position(|s| s.stage == stage)
+            .unwrap_or(self.systems.len());
+        let len = self.systems[start..]
+            .iter()
+            .take_while(|s| s.stage == stage)
+            .count();
+
+        let mut systems = &mut self.systems[start..start + len];
+        while !systems.is_empty() {
+            let (batch, rest) = systems.split_at_mut(batch_len(systems));
+            run_batch(world, batch);
+            systems = rest;
+        }
+    }
+
+    // registration order breaks ties. mistakes in the order are reported and left out rather
+    // than stopping the game
+    fn sort(&mut self) {
+        let mut pending = std::mem::take(&mut self.systems);
+        pending.sort_by_key(|s| s.stage);
+
+        for idx in 0..pending.len() {
+            let (name, stage) = (pending[idx].name, pending[idx].stage);
+            let mut after = std::mem::take(&mut pending[idx].after);
+            after.retain(|dep| {
+                let is_known = pending.iter().any(|p| p.name == *dep && p.stage <= stage);
+                if !is_known {
+                    println!(
+                        "{} runs after {}, which isn't in the same or an earlier stage",
+                        name, dep
+                    );
+                }
+                is_known
+            });
+            pending[idx].after = after;
+        }
+
+        while !pending.is_empty() {
+            let stage = pending[0].stage;
+            let is_done = |dep: &&str| self.systems.iter().any(|d| d.name == *dep);
+            let ready = pending
+                .iter()
+                .take_while(|s| s.stage == stage)
+                .position(|s| s.after.iter().all(is_done));
+            let ready = match ready {
+                Some(ready) => ready,
+                None => {
+                    // the first one waiting goes next, it'll run before the one it waits on
+                    let system = &mut pending[0];
+                    let dep = *system.after.iter().find(|dep| !is_done(dep)).unwrap();
+                    println!(
+                        "dependency cycle in stage {:?}, {} and {} wait on each other",
+                        stage, system.name, dep
+                    );
+                    system.after.retain(|d| *d != dep);
+                    continue;
+                }
+            };
+
+            self.systems.push(pending.remove(ready));
+        }
+
+        self.check_access();
+        self.is_sorted = true;
+    }
+
+    fn check_access(&self) {
+        for (idx, system) in self.systems.iter().enumerate() {
+            let Some(access) = system.access.as_ref() else {
+                continue;
+            };
+            for other in self.systems[..idx]
+                .iter()
+                .filter(|o| o.stage == system.stage)
+            {
+                let Some(other_access) = other.access.as_ref() else {
+                    continue;
+                };
+                if access.conflicts_with(other_access) && !self.runs_after(system, other.name) {
+                    println!(
+                        "{} and {} touch the same thing, one has to run after the other",
+                        other.name, system.name
+                    );
+                }
+            }
+        }
+    }
+
+    // whether system is ordered after name, directly or through its dependencies
+    fn runs_after(&self, system: &System, name: &str) -> bool {
+        system.after.iter().any(|dep| {
+            *dep == name
+                || self
+                    .systems
+                    .iter()
+                    .find(|s| s.name == *dep && s.stage == system.stage)
+                    .is_some_and(|dep| self.runs_after(dep, name))
+        })
+    }
+}
+
+// how many of the leading systems can run at the same time, only jobs on different
+// resources that aren't ordered among themselves
+fn batch_len(systems: &[System]) -> usize {
+    if !systems[0].is_job() {
+        return 1;
+    }
+
+    let mut len = 1;
+    while let Some(next) = systems.get(len) {
+        if !next.is_job() {
+            break;
+        }
+        // jobs always have access, add_job declares it
+        let access = next.access.as_ref().unwrap();
+        let is_independent = systems[..len].iter().all(|s| {
+            !next.after.contains(&s.name) && !s.access.as_ref().unwrap().conflicts_with(access)
+        });
+        if !is_independent {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
+
+fn run_batch(world: &World, batch: &mut [System]) {
+    if let [system] = batch {
+        if system.should_run(world) {
+            system.run_timed(world);
+        }
+        return;
+    }
+
+    // conditions and borrows need the world, so they're done here on the main thread
+    let mut jobs = Vec::new();
+    for (idx, system) in batch.iter_mut().enumerate() {
+        if !system.should_run(world) {
+            continue;
+        }
+        let Run::Job(borrow) = &system.run else {
+            unreachable!();
+        };
+        jobs.push((idx, borrow(world)));
+    }
+
+    let times: Vec<(usize, Duration)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = jobs
+            .into_iter()
+            .map(|(idx, job)| {
+                scope.spawn(move || {
+                    let start = Instant::now();
+                    job();
+                    (idx, Instant::now().duration_since(start))
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    for (idx, time) in times {
+        batch[idx].record(time);
+    }
+}
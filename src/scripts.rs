@@ -0,0 +1,203 @@
+// rhai scripts from SCRIPT_DIR, so room files can hook interactables and trigger zones
+// up to behaviour without new code:
+//
+//   lever 7 8 script=open_vault
+//
+//   fn open_vault(me, other) {
+//       toggle_light(me);
+//       spawn_prefab("chest", 12, 4);
+//   }
+//
+// a hook gets the entity it's on and the one that set it off, the player for
+// interactables. only functions are kept, top level code in a file never runs
+//
+// scripts can't touch the world themselves, their calls are queued and applied once the
+// function returns, and a runaway loop is cut off after MAX_OPERATIONS
+
+use std::{cell::RefCell, collections::BTreeSet, rc::Rc, sync::Mutex};
+
+use ecs::{Entity, Resource, World};
+use rhai::{Dynamic, Engine, Scope, AST};
+
+use crate::{
+    assets, audio,
+    components::{Light, Pos, PrevPos},
+    game::{self, tile_to_pos},
+    prefabs::spawn_prefab,
+    signals::{Signal, SignalBroker},
+};
+
+pub const SCRIPT_DIR: &str = "scripts";
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+const SOUND_VOLUME: f32 = 0.8;
+
+// every hook name seen so far. components hold on to the names, so they're leaked, but only
+// the first time, reloading the same scripts doesn't leak again
+static HOOK_NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+// what a script asked for, see the api in Scripts::load
+enum Call {
+    Spawn(String, (i32, i32)),
+    // None toggles
+    SetLight(Entity, Option<bool>),
+    // to the hook's other
+    GiveItem(String),
+    Move(Entity, (i32, i32)),
+    PlaySound(String),
+    Signal(u16, bool),
+}
+
+#[derive(Resource)]
+pub struct Scripts {
+    engine: Engine,
+    ast: AST,
+    calls: Rc<RefCell<Vec<Call>>>,
+    // functions taking (me, other), see HOOK_NAMES
+    hooks: Vec<&'static str>,
+}
+
+impl Scripts {
+    // the api without any hooks
+    pub fn new() -> Self {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+        engine.register_type_with_name::<Entity>("Entity");
+
+        let queue = calls.clone();
+        engine.register_fn("spawn_prefab", move |prefab: &str, x: i64, y: i64| {
+            let tile = (x as i32, y as i32);
+            queue
+                .borrow_mut()
+                .push(Call::Spawn(prefab.to_string(), tile));
+        });
+        let queue = calls.clone();
+        engine.register_fn("toggle_light", move |entity: Entity| {
+            queue.borrow_mut().push(Call::SetLight(entity, None));
+        });
+        let queue = calls.clone();
+        engine.register_fn("set_light", move |entity: Entity, is_on: bool| {
+            queue.borrow_mut().push(Call::SetLight(entity, Some(is_on)));
+        });
+        // the item goes to whoever set the hook off
+        let queue = calls.clone();
+        engine.register_fn("give_item", move |name: &str| {
+            queue.borrow_mut().push(Call::GiveItem(name.to_string()));
+        });
+        let queue = calls.clone();
+        engine.register_fn("move_entity", move |entity: Entity, x: i64, y: i64| {
+            queue
+                .borrow_mut()
+                .push(Call::Move(entity, (x as i32, y as i32)));
+        });
+        let queue = calls.clone();
+        engine.register_fn("play_sound", move |name: &str| {
+            queue.borrow_mut().push(Call::PlaySound(name.to_string()));
+        });
+        let queue = calls.clone();
+        engine.register_fn("signal", move |channel: i64, is_on: bool| {
+            queue.borrow_mut().push(Call::Signal(channel as u16, is_on));
+        });
+
+        Scripts {
+            engine,
+            ast: AST::empty(),
+            calls,
+            hooks: Vec::new(),
+        }
+    }
+
+    pub fn load(dir: &str) -> Result<Self, String> {
+        let mut scripts = Scripts::new();
+        // sorted so a function defined twice always resolves the same way
+        for path in assets::files(dir, "rhai") {
+            let source =
+                std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            let file = scripts
+                .engine
+                .compile(&source)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+            scripts.ast = scripts.ast.merge(&file.clone_functions_only());
+        }
+
+        scripts.hooks = scripts
+            .ast
+            .iter_functions()
+            .filter(|f| f.params.len() == 2)
+            .map(|f| intern(f.name))
+            .collect();
+        Ok(scripts)
+    }
+
+    // the 'static name of a hook
+    pub fn find(&self, name: &str) -> Option<&'static str> {
+        self.hooks.iter().copied().find(|hook| *hook == name)
+    }
+}
+
+fn intern(name: &str) -> &'static str {
+    let mut names = HOOK_NAMES.lock().unwrap();
+    if let Some(name) = names.get(name) {
+        return name;
+    }
+    let name: &'static str = Box::leak(name.into());
+    names.insert(name);
+    name
+}
+
+pub fn call(world: &World, name: &str, me: Entity, other: Entity) {
+    let scripts = world.resource::<Scripts>().unwrap();
+    let result =
+        scripts
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &scripts.ast, name, (me, other));
+    if let Err(e) = result {
+        println!("script {}: {}", name, e);
+    }
+    // whatever ran before an error still happens
+    let calls: Vec<_> = scripts.calls.borrow_mut().drain(..).collect();
+    for call in calls {
+        apply(world, call, other);
+    }
+}
+
+fn apply(world: &World, call: Call, other: Entity) {
+    match call {
+        Call::Spawn(prefab, (x, y)) => {
+            if spawn_prefab(world, &prefab, tile_to_pos(x, y)).is_none() {
+                println!("script: no prefab {}", prefab);
+            }
+        }
+        Call::SetLight(entity, is_on) => {
+            if world.has_component::<Light>(entity) {
+                let is_on =
+                    is_on.unwrap_or_else(|| game::light_base_mut(world, entity).intensity <= 0.);
+                game::set_light(world, entity, is_on);
+            }
+        }
+        Call::GiveItem(name) => match game::give_item(world, other, &name) {
+            Some(true) => {}
+            Some(false) => println!("script: no room for {}", name),
+            None => println!("script: no item {}", name),
+        },
+        Call::Move(entity, (x, y)) => {
+            let to = tile_to_pos(x, y);
+            if let Some(pos) = world.component_mut::<Pos>(entity) {
+                *pos = to;
+            }
+            // no interpolating across the room
+            if let Some(prev) = world.component_mut::<PrevPos>(entity) {
+                prev.0 = to;
+            }
+        }
+        Call::PlaySound(name) => audio::play(world, &name, SOUND_VOLUME),
+        Call::Signal(channel, is_on) => {
+            world
+                .resource_mut::<SignalBroker>()
+                .unwrap()
+                .send(Signal { channel, is_on });
+        }
+    }
+}
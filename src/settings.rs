@@ -0,0 +1,417 @@
+use ecs::{Resource, World};
+use sdl2::{pixels::Color, video::FullscreenType};
+
+use crate::{
+    assets,
+    audio::Audio,
+    haptics::Haptics,
+    locale::{self, tr, LANGUAGES},
+    toasts,
+    ui::{Anchor, Ui},
+    Ctx,
+};
+
+pub const CONFIG_PATH: &str = "config.toml";
+
+// logical resolutions to cycle through, None is the window resolution
+const RESOLUTIONS: [Option<(u32, u32)>; 4] =
+    [None, Some((640, 360)), Some((480, 270)), Some((400, 400))];
+
+// names in the config and string table keys, indexed by bloom quality
+const BLOOM_QUALITIES: [(&str, &str); 3] = [
+    ("off", "settings-off"),
+    ("low", "settings-low"),
+    ("high", "settings-high"),
+];
+
+// string table keys
+const ROWS: [&str; 10] = [
+    "settings-fullscreen",
+    "settings-vsync",
+    "settings-resolution",
+    "settings-integer-scaling",
+    "settings-volume",
+    "settings-shadows",
+    "settings-bloom",
+    "settings-rumble",
+    "settings-language",
+    "settings-quit",
+];
+
+const KEY_BINDINGS: [&str; 8] = [
+    "keys-move",
+    "keys-shoot",
+    "keys-sprint",
+    "keys-dash",
+    "keys-interact",
+    "keys-items",
+    "keys-inventory",
+    "keys-console",
+];
+
+const ROW_HEIGHT: i32 = 22;
+
+enum Row {
+    Setting(&'static str),
+    // index into Settings::installed
+    Mod(usize),
+}
+
+#[derive(Resource)]
+pub struct Settings {
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub resolution: Option<(u32, u32)>,
+    pub integer_scale: bool,
+    // 0 to 1
+    pub volume: f32,
+    pub shadows_enabled: bool,
+    // index into BLOOM_QUALITIES
+    pub bloom: usize,
+    pub rumble: bool,
+    pub language: &'static str,
+    // enabled, in load order
+    pub mods: Vec<String>,
+    // every mod in the mods directory, listed before the quit row
+    installed: Vec<String>,
+
+    pub is_open: bool,
+    pub quit_requested: bool,
+    selected: usize,
+}
+
+impl Settings {
+    // missing or unknown keys keep their defaults
+    pub fn load(path: &str) -> Self {
+        let mut settings = Settings {
+            fullscreen: false,
+            vsync: true,
+            resolution: None,
+            integer_scale: false,
+            volume: 0.8,
+            shadows_enabled: true,
+            bloom: 1,
+            rumble: true,
+            language: LANGUAGES[0],
+            mods: Vec::new(),
+            installed: assets::installed(),
+            is_open: false,
+            quit_requested: false,
+            selected: 0,
+        };
+
+        let Ok(data) = std::fs::read_to_string(path) else {
+            return settings;
+        };
+        for line in data
+            .lines()
+            .map(|line| line.split('#').next().unwrap().trim())
+            .filter(|line| !line.is_empty())
+        {
+            let Some((key, value)) = line.split_once('=') else {
+                println!("{}: ignoring '{}'", path, line);
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            let parsed = match key.trim() {
+                "fullscreen" => value.parse().map(|v| settings.fullscreen = v).is_ok(),
+                "vsync" => value.parse().map(|v| settings.vsync = v).is_ok(),
+                "integer_scale" => value.parse().map(|v| settings.integer_scale = v).is_ok(),
+                "shadows" => value.parse().map(|v| settings.shadows_enabled = v).is_ok(),
+                "rumble" => value.parse().map(|v| settings.rumble = v).is_ok(),
+                "volume" => value
+                    .parse::<f32>()
+                    .map(|v| settings.volume = v.clamp(0., 1.))
+                    .is_ok(),
+                "bloom" => match BLOOM_QUALITIES.iter().position(|(name, _)| *name == value) {
+                    Some(bloom) => {
+                        settings.bloom = bloom;
+                        true
+                    }
+                    None => false,
+                },
+                "language" => match LANGUAGES.into_iter().find(|l| *l == value) {
+                    Some(language) => {
+                        settings.language = language;
+                        true
+                    }
+                    None => false,
+                },
+                "mods" => {
+                    settings.mods = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(String::from)
+                        .collect();
+                    true
+                }
+                "resolution" => match parse_resolution(value) {
+                    Some(resolution) => {
+                        settings.resolution = resolution;
+                        true
+                    }
+                    None => false,
+                },
+                _ => false,
+            };
+            if !parsed {
+                println!("{}: ignoring '{}'", path, line);
+            }
+        }
+
+        settings
+    }
+
+    pub fn save(&self, path: &str) {
+        let data = format!(
+            "# rewritten whenever the settings change in game\n\
+             fullscreen = {}\n\
+             vsync = {}\n\
+             resolution = \"{}\"\n\
+             integer_scale = {}\n\
+             volume = {:.1}\n\
+             shadows = {}\n\
+             bloom = \"{}\"\n\
+             rumble = {}\n\
+             language = \"{}\"\n\
+             mods = \"{}\"\n",
+            self.fullscreen,
+            self.vsync,
+            resolution_name(self.resolution),
+            self.integer_scale,
+            self.volume,
+            self.shadows_enabled,
+            BLOOM_QUALITIES[self.bloom].0,
+            self.rumble,
+            self.language,
+            self.mods.join(","),
+        );
+        if let Err(e) = std::fs::write(path, data) {
+            println!("failed to save {}: {}", path, e);
+        }
+    }
+
+    fn rows(&self) -> usize {
+        ROWS.len() + self.installed.len()
+    }
+
+    fn row(&self, idx: usize) -> Row {
+        let quit = ROWS.len() - 1;
+        match idx.checked_sub(quit) {
+            Some(idx) if idx < self.installed.len() => Row::Mod(idx),
+            Some(_) => Row::Setting(ROWS[quit]),
+            None => Row::Setting(ROWS[idx]),
+        }
+    }
+
+    fn value(&self, row: usize) -> String {
+        let on_off = |b: bool| tr(if b { "settings-on" } else { "settings-off" }).to_string();
+        let name = match self.row(row) {
+            Row::Setting(name) => name,
+            // enabled ones show where they are in the load order
+            Row::Mod(idx) => {
+                return match self.mods.iter().position(|m| *m == self.installed[idx]) {
+                    Some(order) => format!("{} {}", order + 1, on_off(true)),
+                    None => on_off(false),
+                }
+            }
+        };
+        match name {
+            "settings-fullscreen" => on_off(self.fullscreen),
+            "settings-vsync" => on_off(self.vsync),
+            "settings-resolution" => match self.resolution {
+                Some(_) => resolution_name(self.resolution),
+                None => tr("settings-native").to_string(),
+            },
+            "settings-integer-scaling" => on_off(self.integer_scale),
+            "settings-volume" => format!("{:.0}%", self.volume * 100.),
+            "settings-shadows" => on_off(self.shadows_enabled),
+            "settings-bloom" => tr(BLOOM_QUALITIES[self.bloom].1).to_string(),
+            "settings-rumble" => on_off(self.rumble),
+            "settings-language" => tr("language-name").to_string(),
+            _ => String::new(),
+        }
+    }
+
+    // returns whether anything changed
+    fn change(&mut self, row: usize, offset: i32) -> bool {
+        let name = match self.row(row) {
+            Row::Setting(name) => name,
+            // enabling puts it on top of the others
+            Row::Mod(idx) => {
+                let name = &self.installed[idx];
+                match self.mods.iter().position(|m| m == name) {
+                    Some(order) => {
+                        self.mods.remove(order);
+                    }
+                    None => self.mods.push(name.clone()),
+                }
+                return true;
+            }
+        };
+        match name {
+            "settings-fullscreen" => self.fullscreen = !self.fullscreen,
+            "settings-vsync" => self.vsync = !self.vsync,
+            "settings-integer-scaling" => self.integer_scale = !self.integer_scale,
+            "settings-shadows" => self.shadows_enabled = !self.shadows_enabled,
+            "settings-rumble" => self.rumble = !self.rumble,
+            "settings-bloom" => {
+                let len = BLOOM_QUALITIES.len() as i32;
+                self.bloom = (self.bloom as i32 + offset).rem_euclid(len) as usize;
+            }
+            "settings-volume" => self.volume = (self.volume + offset as f32 * 0.1).clamp(0., 1.),
+            "settings-language" => {
+                let idx = LANGUAGES
+                    .iter()
+                    .position(|l| *l == self.language)
+                    .unwrap_or(0) as i32;
+                let len = LANGUAGES.len() as i32;
+                self.language = LANGUAGES[(idx + offset).rem_euclid(len) as usize];
+            }
+            "settings-resolution" => {
+                let idx = RESOLUTIONS
+                    .iter()
+                    .position(|r| *r == self.resolution)
+                    .unwrap_or(0) as i32;
+                let len = RESOLUTIONS.len() as i32;
+                self.resolution = RESOLUTIONS[(idx + offset).rem_euclid(len) as usize];
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn parse_resolution(value: &str) -> Option<Option<(u32, u32)>> {
+    if value == "native" {
+        return Some(None);
+    }
+    let (w, h) = value.split_once('x')?;
+    Some(Some((w.parse().ok()?, h.parse().ok()?)))
+}
+
+fn resolution_name(resolution: Option<(u32, u32)>) -> String {
+    match resolution {
+        Some((w, h)) => format!("{}x{}", w, h),
+        None => "native".to_string(),
+    }
+}
+
+// pushes the settings into the window and renderer
+pub fn apply(world: &World) {
+    let settings = world.resource::<Settings>().unwrap();
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+
+    let fullscreen = if settings.fullscreen {
+        FullscreenType::Desktop
+    } else {
+        FullscreenType::Off
+    };
+    if ctx.canvas.window().fullscreen_state() != fullscreen {
+        ctx.canvas.window_mut().set_fullscreen(fullscreen).unwrap();
+    }
+    unsafe { sdl2::sys::SDL_RenderSetVSync(ctx.canvas.raw(), settings.vsync as i32) };
+    // also recreates the lightmap and ui texture for the new size
+    ctx.set_logical_size(settings.resolution, settings.integer_scale);
+    ctx.shadows_enabled = settings.shadows_enabled;
+    ctx.bloom_quality = settings.bloom;
+    world.resource_mut::<Haptics>().unwrap().enabled = settings.rumble;
+    world
+        .resource_mut::<Audio>()
+        .unwrap()
+        .set_volume(settings.volume);
+    locale::set_language(settings.language);
+}
+
+pub fn update(world: &World) {
+    let ctx = world.resource::<Ctx>().unwrap();
+    let settings = world.resource_mut::<Settings>().unwrap();
+    let input = &ctx.input.just_pressed;
+
+    let rows = settings.rows();
+    if input.up {
+        settings.selected = (settings.selected + rows - 1) % rows;
+    }
+    if input.down {
+        settings.selected = (settings.selected + 1) % rows;
+    }
+
+    let offset = match (input.left, input.right || input.interact) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => return,
+    };
+    let row = settings.row(settings.selected);
+    if matches!(row, Row::Setting("settings-quit")) {
+        settings.quit_requested = input.interact;
+        return;
+    }
+    if settings.change(settings.selected, offset) {
+        settings.save(CONFIG_PATH);
+        apply(world);
+        if let Row::Mod(_) = row {
+            toasts::push(world, tr("toast-mods-restart"));
+        }
+    }
+}
+
+pub fn draw(settings: &Settings, ui: &mut Ui) {
+    let rows = settings.rows();
+    let height = ROW_HEIGHT * (rows + KEY_BINDINGS.len()) as i32 + 48;
+
+    ui.fill(
+        Anchor::Center,
+        (0, 0),
+        (360, height as u32),
+        Color::RGBA(0, 0, 0, 220),
+        |ui| {
+            ui.label(
+                Anchor::TopLeft,
+                (12, 8),
+                tr("settings-title"),
+                Color::WHITE,
+                None,
+            );
+
+            for idx in 0..rows {
+                let y = 8 + ROW_HEIGHT * (idx as i32 + 1);
+                let name = match settings.row(idx) {
+                    Row::Setting(name) => tr(name),
+                    Row::Mod(idx) => &settings.installed[idx],
+                };
+                let (color, marker) = if idx == settings.selected {
+                    (Color::RGB(255, 255, 0), ">")
+                } else {
+                    (Color::RGB(200, 200, 200), " ")
+                };
+                ui.label(
+                    Anchor::TopLeft,
+                    (12, y),
+                    &format!("{} {}", marker, name),
+                    color,
+                    None,
+                );
+                ui.label(
+                    Anchor::TopRight,
+                    (-12, y),
+                    &settings.value(idx),
+                    color,
+                    None,
+                );
+            }
+
+            // key bindings aren't rebindable yet, this is just for reference
+            for (idx, binding) in KEY_BINDINGS.iter().enumerate() {
+                let y = 24 + ROW_HEIGHT * (rows + idx + 1) as i32;
+                ui.label(
+                    Anchor::TopLeft,
+                    (12, y),
+                    tr(binding),
+                    Color::RGB(140, 140, 140),
+                    None,
+                );
+            }
+        },
+    );
+}
@@ -0,0 +1,165 @@
+// the vendor sells what's in its Container, one of each thing on the price list to start
+// with, see spawn_vendor
+
+use ecs::{Entity, Resource};
+use sdl2::pixels::Color;
+
+use crate::{
+    components::Inventory,
+    locale::tr,
+    tween::{Ease, Tween},
+    ui::{Anchor, Ui},
+};
+
+const ROW_HEIGHT: i32 = 24;
+const FEEDBACK_TICKS: u32 = 90;
+// the panel drops in from above
+const SLIDE_TICKS: u32 = 24;
+const SLIDE_HEIGHT: f32 = 120.;
+
+pub struct ShopEntry {
+    // same names as the give command
+    pub item: String,
+    pub price: u32,
+}
+
+#[derive(Resource)]
+pub struct Shop {
+    // (the vendor, the player buying)
+    pub open: Option<(Entity, Entity)>,
+    // into what the vendor has left
+    pub selected: usize,
+    // the price list
+    pub entries: Vec<ShopEntry>,
+    feedback: Option<(String, Color, u32)>,
+    slide: Tween<f32>,
+}
+
+impl Shop {
+    // one "<item> <price>" pair per line, # starts a comment. bad lines are reported and
+    // skipped, without the file the shop is empty
+    pub fn load(path: &str) -> Self {
+        let data = std::fs::read_to_string(path).unwrap_or_else(|_| {
+            println!("no shop in {}", path);
+            String::new()
+        });
+        let entries = data
+            .lines()
+            .map(|line| line.split('#').next().unwrap().trim())
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| {
+                let entry = line
+                    .split_once(char::is_whitespace)
+                    .and_then(|(item, price)| {
+                        Some(ShopEntry {
+                            item: item.to_string(),
+                            price: price.trim().parse().ok()?,
+                        })
+                    });
+                if entry.is_none() {
+                    println!("{}: ignoring '{}'", path, line);
+                }
+                entry
+            })
+            .collect();
+
+        Shop {
+            open: None,
+            selected: 0,
+            entries,
+            feedback: None,
+            slide: Tween::idle(1.),
+        }
+    }
+
+    pub fn open(&mut self, vendor: Entity, customer: Entity) {
+        self.open = Some((vendor, customer));
+        self.selected = 0;
+        self.feedback = None;
+        self.slide = Tween::new(0., 1., SLIDE_TICKS, Ease::Elastic);
+    }
+
+    pub fn price(&self, item: &str) -> Option<u32> {
+        self.entries
+            .iter()
+            .find(|entry| entry.item == item)
+            .map(|entry| entry.price)
+    }
+
+    // len is how many things are left
+    pub fn select_offset(&mut self, offset: i32, len: usize) {
+        if len == 0 {
+            self.selected = 0;
+            return;
+        }
+        self.selected = (self.selected as i32 + offset).rem_euclid(len as i32) as usize;
+    }
+
+    pub fn set_feedback(&mut self, text: impl Into<String>, color: Color) {
+        self.feedback = Some((text.into(), color, FEEDBACK_TICKS));
+    }
+
+    pub fn tick(&mut self) {
+        self.slide.tick();
+        if let Some((_, _, ticks_left)) = self.feedback.as_mut() {
+            *ticks_left -= 1;
+            if *ticks_left == 0 {
+                self.feedback = None;
+            }
+        }
+    }
+}
+
+pub fn draw(shop: &Shop, stock: &Inventory, coins: u32, ui: &mut Ui) {
+    let height = ROW_HEIGHT * (stock.items().count() as i32 + 3);
+
+    let slide = ((1. - shop.slide.value()) * -SLIDE_HEIGHT).round() as i32;
+
+    ui.fill(
+        Anchor::Center,
+        (0, slide),
+        (320, height as u32),
+        Color::RGBA(0, 0, 0, 220),
+        |ui| {
+            ui.label(
+                Anchor::TopLeft,
+                (12, 8),
+                tr("shop-title"),
+                Color::WHITE,
+                None,
+            );
+
+            for (idx, (_, item)) in stock.items().enumerate() {
+                let y = 8 + ROW_HEIGHT * (idx as i32 + 1);
+                let price = shop.price(item.name());
+                let color = if price.is_none_or(|price| price > coins) {
+                    Color::RGB(120, 120, 120)
+                } else if idx == shop.selected {
+                    Color::RGB(255, 255, 0)
+                } else {
+                    Color::RGB(200, 200, 200)
+                };
+                let marker = if idx == shop.selected { ">" } else { " " };
+
+                ui.label(
+                    Anchor::TopLeft,
+                    (12, y),
+                    &format!("{} {}", marker, item.name()),
+                    color,
+                    None,
+                );
+                ui.label(
+                    Anchor::TopRight,
+                    (-12, y),
+                    &price.map_or("-".to_string(), |price| price.to_string()),
+                    color,
+                    None,
+                );
+            }
+
+            if let Some((text, color, _)) = shop.feedback.as_ref() {
+                ui.label(Anchor::BottomLeft, (12, -8), text, *color, None);
+            }
+        },
+    );
+}
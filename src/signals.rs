@@ -0,0 +1,48 @@
+use ecs::{Entity, Resource, World};
+
+use crate::components::{SignalEmitter, SignalReceiver};
+
+#[derive(Clone, Copy)]
+pub struct Signal {
+    pub channel: u16,
+    pub is_on: bool,
+}
+
+#[derive(Resource)]
+pub struct SignalBroker {
+    pending: Vec<Signal>,
+}
+
+impl SignalBroker {
+    pub fn new() -> Self {
+        SignalBroker {
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn send(&mut self, signal: Signal) {
+        self.pending.push(signal);
+    }
+}
+
+pub fn emit(world: &World, emitter: Entity) {
+    if let Some(emitter) = world.component::<SignalEmitter>(emitter) {
+        world.resource_mut::<SignalBroker>().unwrap().send(Signal {
+            channel: emitter.channel,
+            is_on: emitter.is_on,
+        });
+    }
+}
+
+pub fn dispatch(world: &World) {
+    // signals sent by receivers are delivered on the next tick
+    let signals = std::mem::take(&mut world.resource_mut::<SignalBroker>().unwrap().pending);
+
+    for signal in signals {
+        world.run(|entity: &Entity, receiver: &SignalReceiver| {
+            if receiver.channel == signal.channel {
+                (receiver.on_signal)(world, *entity, signal.is_on);
+            }
+        });
+    }
+}
@@ -0,0 +1,269 @@
+// pushes colliders out of what they overlap. everything a collider touches is looked at
+// together for a few passes instead of one pair at a time, so a body wedged into a corner or
+// a crowd settles instead of being shoved back and forth between its contacts
+//
+// bodies come to rest overlapping what they lean on by SKIN, that's how the contact flags
+// stay set while something stands against a wall
+
+use sdl2::rect::Rect;
+
+use crate::components::Side;
+
+pub const SKIN: i32 = 1;
+// after this many a body that's still stuck tries again next tick
+const PASSES: usize = 4;
+
+#[derive(Clone, Copy)]
+pub struct Contact {
+    pub bounds: Rect,
+    pub one_way: Option<Side>,
+    // walls don't give, two bodies in each other's way split the push
+    pub is_static: bool,
+}
+
+// the side of other that bounds gets pushed out through, whichever is the shallowest way
+// out. None when it's just as deep every way
+pub fn push_side(bounds: Rect, other: Rect) -> Option<Side> {
+    let d_bottom = other.bottom() - bounds.top();
+    let d_top = bounds.bottom() - other.top();
+    let d_left = bounds.right() - other.left();
+    let d_right = other.right() - bounds.left();
+
+    if d_top < d_bottom && d_top < d_left && d_top < d_right {
+        Some(Side::Top)
+    } else if d_bottom < d_top && d_bottom < d_left && d_bottom < d_right {
+        Some(Side::Bottom)
+    } else if d_left < d_right && d_left < d_top && d_left < d_bottom {
+        Some(Side::Left)
+    } else if d_right < d_left && d_right < d_top && d_right < d_bottom {
+        Some(Side::Right)
+    } else {
+        None
+    }
+}
+
+// how far bounds has to move to rest against its contacts instead of being inside them
+pub fn solve(bounds: Rect, contacts: &[Contact]) -> (i32, i32) {
+    let mut moved = (0, 0);
+    for _ in 0..PASSES {
+        let at = Rect::new(
+            bounds.x() + moved.0,
+            bounds.y() + moved.1,
+            bounds.width(),
+            bounds.height(),
+        );
+
+        // the deepest push each way, tiles along the same wall only push once together
+        let (mut left, mut right, mut up, mut down) = (0, 0, 0, 0);
+        for contact in contacts {
+            if !at.has_intersection(contact.bounds) {
+                continue;
+            }
+            let Some(side) = push_side(at, contact.bounds) else {
+                continue;
+            };
+            if contact.one_way.is_some_and(|blocks| blocks != side) {
+                continue;
+            }
+            let depth = match side {
+                Side::Top => at.bottom() - contact.bounds.top(),
+                Side::Bottom => contact.bounds.bottom() - at.top(),
+                Side::Left => at.right() - contact.bounds.left(),
+                Side::Right => contact.bounds.right() - at.left(),
+            } - SKIN;
+            if depth <= 0 {
+                continue;
+            }
+            let depth = if contact.is_static {
+                depth
+            } else {
+                (depth + 1) / 2
+            };
+            match side {
+                Side::Top => up = up.max(depth),
+                Side::Bottom => down = down.max(depth),
+                Side::Left => left = left.max(depth),
+                Side::Right => right = right.max(depth),
+            }
+        }
+
+        if left == 0 && right == 0 && up == 0 && down == 0 {
+            break;
+        }
+        moved.0 += right - left;
+        moved.1 += down - up;
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILE: i32 = 32;
+
+    fn wall(tile_x: i32, tile_y: i32) -> Contact {
+        Contact {
+            bounds: Rect::new(tile_x * TILE, tile_y * TILE, TILE as u32, TILE as u32),
+            one_way: None,
+            is_static: true,
+        }
+    }
+
+    fn overlap(a: Rect, b: Rect) -> (i32, i32) {
+        (
+            a.right().min(b.right()) - a.left().max(b.left()),
+            a.bottom().min(b.bottom()) - a.top().max(b.top()),
+        )
+    }
+
+    fn at(bounds: Rect, x: i32, y: i32) -> Rect {
+        Rect::new(x, y, bounds.width(), bounds.height())
+    }
+
+    // moves a body by velocity every tick and solves it against the walls like
+    // detect_collisions does, returns where it was after each tick
+    fn simulate(
+        mut body: Rect,
+        velocity: (i32, i32),
+        walls: &[Contact],
+        ticks: usize,
+    ) -> Vec<(i32, i32)> {
+        let mut path = Vec::new();
+        for _ in 0..ticks {
+            body = at(body, body.x() + velocity.0, body.y() + velocity.1);
+            let (dx, dy) = solve(body, walls);
+            body = at(body, body.x() + dx, body.y() + dy);
+            path.push((body.x(), body.y()));
+        }
+        path
+    }
+
+    fn assert_not_inside(body: Rect, walls: &[Contact]) {
+        for wall in walls {
+            let (w, h) = overlap(body, wall.bounds);
+            assert!(
+                w <= SKIN || h <= SKIN,
+                "{:?} is {}x{} inside {:?}",
+                body,
+                w,
+                h,
+                wall.bounds
+            );
+        }
+    }
+
+    #[test]
+    fn resting_contact_stays_put() {
+        let walls = [wall(1, 0)];
+        let body = Rect::new(TILE - 20 + SKIN, 4, 20, 16);
+        assert_eq!(solve(body, &walls), (0, 0));
+    }
+
+    #[test]
+    fn pushed_out_the_shallow_way() {
+        let walls = [wall(1, 0)];
+        let body = Rect::new(TILE - 20 + 6, 4, 20, 16);
+        assert_eq!(solve(body, &walls), (-5, 0));
+    }
+
+    // running diagonally into the corner of an L of walls, each a separate tile
+    #[test]
+    fn corner_settles_without_jitter() {
+        let walls = [wall(2, 0), wall(2, 1), wall(0, 2), wall(1, 2), wall(2, 2)];
+        let body = Rect::new(10, 10, 26, 16);
+        let path = simulate(body, (3, 3), &walls, 40);
+
+        let rest = *path.last().unwrap();
+        assert_eq!(rest, (2 * TILE - 26 + SKIN, 2 * TILE - 16 + SKIN));
+        // it stops once it's there
+        assert!(path[path.len() - 10..].iter().all(|pos| *pos == rest));
+        assert_not_inside(at(body, rest.0, rest.1), &walls);
+    }
+
+    // sliding along a wall made of tiles doesn't catch on the seams between them
+    #[test]
+    fn sliding_along_seams() {
+        let walls: Vec<Contact> = (0..8).map(|x| wall(x, 1)).collect();
+        let body = Rect::new(0, TILE - 16 + SKIN, 26, 16);
+        let path = simulate(body, (2, 1), &walls, 60);
+
+        for (idx, (x, y)) in path.iter().enumerate() {
+            assert_eq!(*x, 2 * (idx as i32 + 1), "caught at a seam");
+            assert_eq!(*y, TILE - 16 + SKIN);
+        }
+    }
+
+    // squeezed down a corridor just wide enough, it isn't shoved into either wall
+    #[test]
+    fn tunnel_keeps_to_the_middle() {
+        let walls: Vec<Contact> = (0..6).flat_map(|y| [wall(0, y), wall(2, y)]).collect();
+        let body = Rect::new(TILE - SKIN, 0, TILE as u32 + 2 * SKIN as u32, 16);
+        let path = simulate(body, (0, 3), &walls, 40);
+
+        assert!(path.iter().all(|(x, _)| *x == TILE - SKIN));
+        assert_eq!(path.last().unwrap().1, 40 * 3);
+    }
+
+    // walking into a wall off center, a bit too far into the corridor's side
+    #[test]
+    fn tunnel_entrance_pushes_back_in() {
+        let walls: Vec<Contact> = (0..6).flat_map(|y| [wall(0, y), wall(2, y)]).collect();
+        let body = Rect::new(TILE - 6, 40, 20, 16);
+        let (dx, dy) = solve(body, &walls);
+        assert_eq!((dx, dy), (5, 0));
+        assert_not_inside(at(body, body.x() + dx, body.y() + dy), &walls);
+    }
+
+    // bodies piled on top of each other spread out and then stay apart
+    #[test]
+    fn crowd_spreads_out() {
+        let mut bodies: Vec<Rect> = (0..5)
+            .map(|i| Rect::new(100 + i * 3, 100 + i, 20, 16))
+            .collect();
+        let mut history = Vec::new();
+        for _ in 0..60 {
+            // everyone solves against where the others were at the start of the tick
+            let snapshot = bodies.clone();
+            for (idx, body) in bodies.iter_mut().enumerate() {
+                let others: Vec<Contact> = snapshot
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != idx)
+                    .map(|(_, bounds)| Contact {
+                        bounds: *bounds,
+                        one_way: None,
+                        is_static: false,
+                    })
+                    .collect();
+                let (dx, dy) = solve(*body, &others);
+                *body = at(*body, body.x() + dx, body.y() + dy);
+            }
+            history.push(bodies.clone());
+        }
+
+        for (idx, body) in bodies.iter().enumerate() {
+            for other in &bodies[idx + 1..] {
+                let (w, h) = overlap(*body, *other);
+                assert!(w <= SKIN || h <= SKIN, "{:?} still in {:?}", body, other);
+            }
+        }
+        // nobody keeps drifting once it's sorted out
+        assert_eq!(history[history.len() - 1], history[history.len() - 2]);
+    }
+
+    #[test]
+    fn one_way_only_blocks_one_side() {
+        let ledge = Contact {
+            bounds: Rect::new(0, 28, 32, 8),
+            one_way: Some(Side::Bottom),
+            is_static: true,
+        };
+        // coming up from below it's pushed back down
+        let from_below = Rect::new(8, 34, 20, 16);
+        assert_eq!(solve(from_below, &[ledge]), (0, 1));
+        // coming down from above it walks right through
+        let from_above = Rect::new(8, 20, 20, 16);
+        assert_eq!(solve(from_above, &[ledge]), (0, 0));
+    }
+}
@@ -0,0 +1,209 @@
+// lifetime counters fed from the event bus, and the achievements unlocked by them
+
+use std::path::{Path, PathBuf};
+
+use ecs::{Resource, World};
+use sdl2::pixels::Color;
+
+use crate::{
+    components::Pos,
+    events::{EnemyDied, EventReader, Events, ItemUsed, PlayerDied, ShotFired},
+    locale::{tr, tr_args},
+    saves,
+    toasts::Toasts,
+    Ctx,
+};
+
+// kept next to the save slots
+pub fn stats_path() -> PathBuf {
+    saves::data_dir().join("stats.toml")
+}
+// anything further in a tick is a teleport, not a walk
+const MAX_STEP: f32 = 32.;
+
+pub struct Achievement {
+    pub id: &'static str,
+    // string table key
+    pub name: &'static str,
+    pub is_unlocked: fn(&Stats) -> bool,
+}
+
+pub const ACHIEVEMENTS: [Achievement; 5] = [
+    Achievement {
+        id: "first_blood",
+        name: "achievement-first-blood",
+        is_unlocked: |stats| stats.enemies_killed >= 1,
+    },
+    Achievement {
+        id: "exterminator",
+        name: "achievement-exterminator",
+        is_unlocked: |stats| stats.enemies_killed >= 100,
+    },
+    Achievement {
+        id: "trigger_happy",
+        name: "achievement-trigger-happy",
+        is_unlocked: |stats| stats.shots_fired >= 1000,
+    },
+    Achievement {
+        id: "wanderer",
+        name: "achievement-wanderer",
+        // about 300 tiles
+        is_unlocked: |stats| stats.distance_walked >= 10_000.,
+    },
+    Achievement {
+        id: "handy",
+        name: "achievement-handy",
+        is_unlocked: |stats| stats.items_used >= 50,
+    },
+];
+
+#[derive(Resource)]
+pub struct Stats {
+    pub enemies_killed: u32,
+    pub shots_fired: u32,
+    // in pixels
+    pub distance_walked: f32,
+    pub items_used: u32,
+    pub deaths: u32,
+    // achievement ids
+    pub unlocked: Vec<&'static str>,
+
+    last_player_pos: Option<Pos>,
+    enemy_died: EventReader<EnemyDied>,
+    shot_fired: EventReader<ShotFired>,
+    item_used: EventReader<ItemUsed>,
+    player_died: EventReader<PlayerDied>,
+}
+
+impl Stats {
+    // missing or unknown keys start from zero
+    pub fn load(path: &Path) -> Self {
+        let mut stats = Stats {
+            enemies_killed: 0,
+            shots_fired: 0,
+            distance_walked: 0.,
+            items_used: 0,
+            deaths: 0,
+            unlocked: Vec::new(),
+            last_player_pos: None,
+            enemy_died: EventReader::new(),
+            shot_fired: EventReader::new(),
+            item_used: EventReader::new(),
+            player_died: EventReader::new(),
+        };
+
+        let Ok(data) = std::fs::read_to_string(path) else {
+            return stats;
+        };
+        for line in data
+            .lines()
+            .map(|line| line.split('#').next().unwrap().trim())
+            .filter(|line| !line.is_empty())
+        {
+            let Some((key, value)) = line.split_once('=') else {
+                println!("{}: ignoring '{}'", path.display(), line);
+                continue;
+            };
+            let value = value.trim().trim_matches('"');
+            let parsed = match key.trim() {
+                "enemies_killed" => value.parse().map(|v| stats.enemies_killed = v).is_ok(),
+                "shots_fired" => value.parse().map(|v| stats.shots_fired = v).is_ok(),
+                "distance_walked" => value.parse().map(|v| stats.distance_walked = v).is_ok(),
+                "items_used" => value.parse().map(|v| stats.items_used = v).is_ok(),
+                "deaths" => value.parse().map(|v| stats.deaths = v).is_ok(),
+                "unlocked" => {
+                    stats.unlocked = ACHIEVEMENTS
+                        .iter()
+                        .map(|a| a.id)
+                        .filter(|id| value.split(',').any(|v| v.trim() == *id))
+                        .collect();
+                    true
+                }
+                _ => false,
+            };
+            if !parsed {
+                println!("{}: ignoring '{}'", path.display(), line);
+            }
+        }
+
+        stats
+    }
+
+    pub fn save(&self, path: &Path) {
+        let data = format!(
+            "# rewritten on exit and whenever an achievement unlocks\n\
+             enemies_killed = {}\n\
+             shots_fired = {}\n\
+             distance_walked = {:.0}\n\
+             items_used = {}\n\
+             deaths = {}\n\
+             unlocked = \"{}\"\n",
+            self.enemies_killed,
+            self.shots_fired,
+            self.distance_walked,
+            self.items_used,
+            self.deaths,
+            self.unlocked.join(","),
+        );
+        if let Some(dir) = path.parent() {
+            if let Err(e) = std::fs::create_dir_all(dir) {
+                println!("failed to save {}: {}", path.display(), e);
+                return;
+            }
+        }
+        if let Err(e) = std::fs::write(path, data) {
+            println!("failed to save {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains(&id)
+    }
+}
+
+pub fn update(world: &World) {
+    let stats = world.resource_mut::<Stats>().unwrap();
+    let ctx = world.resource::<Ctx>().unwrap();
+
+    stats.enemies_killed += stats
+        .enemy_died
+        .read(world.resource::<Events<EnemyDied>>().unwrap())
+        .count() as u32;
+    stats.shots_fired += stats
+        .shot_fired
+        .read(world.resource::<Events<ShotFired>>().unwrap())
+        .count() as u32;
+    stats.items_used += stats
+        .item_used
+        .read(world.resource::<Events<ItemUsed>>().unwrap())
+        .count() as u32;
+    stats.deaths += stats
+        .player_died
+        .read(world.resource::<Events<PlayerDied>>().unwrap())
+        .count() as u32;
+
+    if let Some(last) = stats.last_player_pos {
+        let step = last.distance(&ctx.player_pos);
+        if step < MAX_STEP {
+            stats.distance_walked += step;
+        }
+    }
+    stats.last_player_pos = Some(ctx.player_pos);
+
+    let mut unlocked_any = false;
+    for achievement in ACHIEVEMENTS.iter() {
+        if stats.is_unlocked(achievement.id) || !(achievement.is_unlocked)(stats) {
+            continue;
+        }
+        stats.unlocked.push(achievement.id);
+        let text = tr_args("achievement-unlocked", &[("name", tr(achievement.name))]);
+        world
+            .resource_mut::<Toasts>()
+            .unwrap()
+            .push(&text, Color::RGB(255, 220, 80));
+        unlocked_any = true;
+    }
+    if unlocked_any {
+        stats.save(&stats_path());
+    }
+}
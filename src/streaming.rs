@@ -0,0 +1,183 @@
+// spritesheet textures are decoded on a worker thread and uploaded a few per frame, so loading
+// never blocks the window. the built in sheets are needed before anything can be drawn, the
+// game shows a loading bar until they're in. the aseprite sheets are only asked for once
+// something in the world uses them, a room with new art streams it in behind the transition
+// and its sprites pop in when they arrive instead of stalling the room change
+//
+// fonts open straight away, they're small and the loading screen needs one
+
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use ecs::{Resource, World};
+use sdl2::{
+    image::LoadSurface,
+    pixels::{Color, PixelFormatEnum},
+    render::BlendMode,
+    surface::Surface,
+};
+
+use crate::{
+    components::AnimatedSprite,
+    locale::tr,
+    ui::{Anchor, TextCache, Ui},
+    AnimationRepository, Ctx, SPRITESHEETS, SPRITESHEET_TEXTURE,
+};
+
+// texture uploads have to happen on the main thread, this much of every frame goes to them
+const UPLOAD_BUDGET: Duration = Duration::from_millis(4);
+const BAR_SIZE: (u32, u32) = (300, 12);
+
+// pixels ready for a texture, rgba in memory order
+struct Decoded {
+    path: String,
+    width: u32,
+    height: u32,
+    pitch: usize,
+    pixels: Vec<u8>,
+}
+
+#[derive(Resource)]
+pub struct Streamer {
+    requests: Sender<String>,
+    decoded: Receiver<Result<Decoded, String>>,
+    // for the loading bar
+    requested: usize,
+    uploaded: usize,
+}
+
+impl Streamer {
+    pub fn new() -> Self {
+        let (requests, jobs) = mpsc::channel::<String>();
+        let (results, decoded) = mpsc::channel();
+        std::thread::spawn(move || {
+            for path in jobs {
+                if results.send(decode(path)).is_err() {
+                    break;
+                }
+            }
+        });
+        Streamer {
+            requests,
+            decoded,
+            requested: 0,
+            uploaded: 0,
+        }
+    }
+
+    fn request(&mut self, path: &str) {
+        self.requested += 1;
+        self.requests.send(path.to_string()).unwrap();
+    }
+}
+
+fn decode(path: String) -> Result<Decoded, String> {
+    let surface = Surface::from_file(&path).map_err(|e| format!("{}: {}", path, e))?;
+    let surface = surface
+        .convert_format(PixelFormatEnum::ABGR8888)
+        .map_err(|e| format!("{}: {}", path, e))?;
+    let pitch = surface.pitch() as usize;
+    let pixels = surface.with_lock(|pixels| pixels.to_vec());
+    Ok(Decoded {
+        width: surface.width(),
+        height: surface.height(),
+        path,
+        pitch,
+        pixels,
+    })
+}
+
+// the built in sheets aren't all in yet
+pub fn is_loading(ctx: &Ctx) -> bool {
+    ctx.spritesheets[..SPRITESHEETS.len()]
+        .iter()
+        .any(|sheet| !sheet.is_loaded())
+}
+
+// once a frame, asks for the sheets that are needed and uploads what's been decoded
+pub fn update(world: &World) {
+    let streamer = world.resource_mut::<Streamer>().unwrap();
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    let animations = world.resource::<AnimationRepository>().unwrap();
+
+    let mut is_needed = vec![false; ctx.spritesheets.len()];
+    is_needed[..SPRITESHEETS.len()].fill(true);
+    world.run(|anim: &AnimatedSprite| {
+        is_needed[*animations.get_sheet(anim.anim())] = true;
+    });
+    for (sheet, is_needed) in ctx.spritesheets.iter_mut().zip(is_needed) {
+        if is_needed && !sheet.is_requested {
+            sheet.is_requested = true;
+            streamer.request(&sheet.path);
+            if let Some(path) = &sheet.specular_path {
+                streamer.request(path);
+            }
+        }
+    }
+
+    // at least one a frame, however slow the upload
+    let start = Instant::now();
+    while let Ok(decoded) = streamer.decoded.try_recv() {
+        let decoded = decoded.unwrap_or_else(|e| panic!("Failed to load texture {}", e));
+        let mut texture = ctx
+            .canvas
+            .texture_creator()
+            .create_texture_static(PixelFormatEnum::ABGR8888, decoded.width, decoded.height)
+            .unwrap();
+        texture
+            .update(None, &decoded.pixels, decoded.pitch)
+            .unwrap();
+        texture.set_blend_mode(BlendMode::Blend);
+        streamer.uploaded += 1;
+
+        // a sheet reloaded while it was decoding may have been asked for twice
+        let sheet = ctx
+            .spritesheets
+            .iter_mut()
+            .find(|sheet| sheet.wants(&decoded.path));
+        match sheet {
+            Some(sheet) => sheet.receive(&decoded.path, texture),
+            None => unsafe { texture.destroy() },
+        }
+
+        if start.elapsed() >= UPLOAD_BUDGET {
+            break;
+        }
+    }
+}
+
+pub fn draw_loading_screen(world: &World) {
+    let streamer = world.resource::<Streamer>().unwrap();
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    let progress = streamer.uploaded as f32 / streamer.requested.max(1) as f32;
+
+    ctx.canvas.set_draw_color(Color::RGB(0, 0, 0));
+    ctx.canvas.clear();
+    let mut ui = Ui::new(
+        &mut ctx.canvas,
+        &ctx.spritesheets[*SPRITESHEET_TEXTURE],
+        &ctx.font,
+        world.resource_mut::<TextCache>().unwrap(),
+    );
+    ui.label(Anchor::Center, (0, -24), tr("loading"), Color::WHITE, None);
+    ui.fill(
+        Anchor::Center,
+        (0, 0),
+        BAR_SIZE,
+        Color::RGB(60, 60, 60),
+        |ui| {
+            let width = (BAR_SIZE.0 as f32 * progress.min(1.)) as u32;
+            ui.fill(
+                Anchor::TopLeft,
+                (0, 0),
+                (width.max(1), BAR_SIZE.1),
+                Color::RGB(255, 200, 0),
+                |_| {},
+            );
+        },
+    );
+    drop(ui);
+    ctx.canvas.present();
+}
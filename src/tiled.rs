@@ -0,0 +1,247 @@
+// imports maps made in Tiled (https://www.mapeditor.org). tiles become prefabs through
+// a "prefab" property on the tileset's tiles, or on the layer for a layer full of the
+// same thing. objects use their "prefab" property, or their class if it names a prefab
+// (or one of ALIASES). radius, intensity and channel properties become room properties
+//
+// only csv encoded, finite maps are supported, that's what Tiled saves by default
+
+use std::{collections::HashMap, path::Path};
+
+use ecs::World;
+use roxmltree::{Document, Node};
+
+use crate::rooms::{self, Placement, Props};
+
+// Tiled class to prefab
+const ALIASES: [(&str, &str); 4] = [
+    ("light", "torch"),
+    ("spawner", "enemy"),
+    ("trigger", "pressure_plate"),
+    ("switch", "lever"),
+];
+
+// the top bits of a gid are flip flags
+const GID_MASK: u32 = 0x0fff_ffff;
+
+struct Map {
+    tile_size: (f32, f32),
+    // gid to prefab
+    tiles: HashMap<u32, String>,
+}
+
+pub fn load(world: &World, path: &str) -> Result<Vec<Placement>, String> {
+    let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let doc = Document::parse(&data).map_err(|e| format!("{}: {}", path, e))?;
+    let root = doc.root_element();
+    if root.attribute("infinite") == Some("1") {
+        return Err(format!("{}: infinite maps aren't supported", path));
+    }
+
+    let mut map = Map {
+        tile_size: (number(root, "tilewidth")?, number(root, "tileheight")?),
+        tiles: HashMap::new(),
+    };
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    for tileset in root.children().filter(|n| n.has_tag_name("tileset")) {
+        load_tileset(&mut map, tileset, dir)?;
+    }
+
+    let mut placements = Vec::new();
+    load_layers(world, &map, root, &mut placements).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(placements)
+}
+
+fn number(node: Node, name: &str) -> Result<f32, String> {
+    node.attribute(name)
+        .unwrap_or("0")
+        .parse()
+        .map_err(|_| format!("bad {} on line {}", name, line_of(node)))
+}
+
+fn line_of(node: Node) -> u32 {
+    node.document().text_pos_at(node.range().start).row
+}
+
+fn properties<'a>(node: Node<'a, '_>) -> impl Iterator<Item = (&'a str, &'a str)> {
+    node.children()
+        .filter(|n| n.has_tag_name("properties"))
+        .flat_map(|n| n.children())
+        .filter(|n| n.has_tag_name("property"))
+        .filter_map(|n| Some((n.attribute("name")?, n.attribute("value")?)))
+}
+
+fn property<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    properties(node).find(|(n, _)| *n == name).map(|(_, v)| v)
+}
+
+fn load_tileset(map: &mut Map, tileset: Node, dir: &Path) -> Result<(), String> {
+    let first_gid = number(tileset, "firstgid")? as u32;
+
+    // external tilesets live in their own .tsx file next to the map
+    let Some(source) = tileset.attribute("source") else {
+        add_tiles(map, tileset, first_gid);
+        return Ok(());
+    };
+    let path = dir.join(source);
+    let data = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let doc = Document::parse(&data).map_err(|e| format!("{}: {}", path.display(), e))?;
+    add_tiles(map, doc.root_element(), first_gid);
+    Ok(())
+}
+
+fn add_tiles(map: &mut Map, tileset: Node, first_gid: u32) {
+    for tile in tileset.children().filter(|n| n.has_tag_name("tile")) {
+        let Some(id) = tile.attribute("id").and_then(|id| id.parse::<u32>().ok()) else {
+            continue;
+        };
+        let prefab = property(tile, "prefab")
+            .or_else(|| tile.attribute("class"))
+            .or_else(|| tile.attribute("type"));
+        if let Some(prefab) = prefab {
+            map.tiles.insert(first_gid + id, prefab.to_string());
+        }
+    }
+}
+
+// groups are flattened, their layers are placed like any other
+fn load_layers(
+    world: &World,
+    map: &Map,
+    parent: Node,
+    placements: &mut Vec<Placement>,
+) -> Result<(), String> {
+    for node in parent.children() {
+        if node.attribute("visible") == Some("0") {
+            continue;
+        }
+        match node.tag_name().name() {
+            "layer" => load_tile_layer(world, map, node, placements)?,
+            "objectgroup" => load_object_layer(world, map, node, placements)?,
+            "group" => load_layers(world, map, node, placements)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn load_tile_layer(
+    world: &World,
+    map: &Map,
+    layer: Node,
+    placements: &mut Vec<Placement>,
+) -> Result<(), String> {
+    let width = number(layer, "width")? as i32;
+    let layer_prefab = property(layer, "prefab");
+    let Some(data) = layer.children().find(|n| n.has_tag_name("data")) else {
+        return Ok(());
+    };
+    if data.attribute("encoding") != Some("csv") {
+        return Err(format!(
+            "layer on line {} isn't csv encoded, change the tile layer format in the map properties",
+            line_of(layer)
+        ));
+    }
+
+    let csv = data.text().unwrap_or("");
+    for (idx, gid) in csv.split(',').enumerate() {
+        let gid = gid
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("bad tile data on line {}", line_of(data)))?
+            & GID_MASK;
+        if gid == 0 {
+            continue;
+        }
+        let Some(prefab) = map.tiles.get(&gid).map(|p| p.as_str()).or(layer_prefab) else {
+            continue;
+        };
+        let tile = (idx as i32 % width, idx as i32 / width);
+        placements.push(place(world, prefab, tile, Props::default(), layer)?);
+    }
+    Ok(())
+}
+
+fn load_object_layer(
+    world: &World,
+    map: &Map,
+    layer: Node,
+    placements: &mut Vec<Placement>,
+) -> Result<(), String> {
+    for object in layer.children().filter(|n| n.has_tag_name("object")) {
+        let gid = object
+            .attribute("gid")
+            .and_then(|gid| gid.parse::<u32>().ok())
+            .map(|gid| gid & GID_MASK);
+        let class = object
+            .attribute("class")
+            .or_else(|| object.attribute("type"));
+        let prefab = property(object, "prefab")
+            .or_else(|| {
+                class.map(|class| {
+                    ALIASES
+                        .iter()
+                        .find(|(alias, _)| *alias == class)
+                        .map_or(class, |(_, prefab)| prefab)
+                })
+            })
+            .or_else(|| gid.and_then(|gid| map.tiles.get(&gid)).map(|p| p.as_str()));
+        let Some(prefab) = prefab else {
+            println!(
+                "skipping object on line {}, it has no prefab",
+                line_of(object)
+            );
+            continue;
+        };
+
+        // tile objects hang up from their position, everything else down
+        let (x, y) = (number(object, "x")?, number(object, "y")?);
+        let (w, h) = (number(object, "width")?, number(object, "height")?);
+        let center = if gid.is_some() {
+            (x + w / 2., y - h / 2.)
+        } else {
+            (x + w / 2., y + h / 2.)
+        };
+        let tile = (
+            (center.0 / map.tile_size.0).floor() as i32,
+            (center.1 / map.tile_size.1).floor() as i32,
+        );
+
+        let mut props = Props::default();
+        for (name, value) in properties(object) {
+            let parsed = match name {
+                "prefab" => true,
+                "radius" => value.parse().map(|v| props.radius = Some(v)).is_ok(),
+                "intensity" => value.parse().map(|v| props.intensity = Some(v)).is_ok(),
+                "channel" => value.parse().map(|v| props.channel = Some(v)).is_ok(),
+                "script" => rooms::parse_script(world, value)
+                    .map(|v| props.script = Some(v))
+                    .is_some(),
+                _ => {
+                    println!("ignoring property '{}' on line {}", name, line_of(object));
+                    true
+                }
+            };
+            if !parsed {
+                return Err(format!(
+                    "bad {} '{}' on line {}",
+                    name,
+                    value,
+                    line_of(object)
+                ));
+            }
+        }
+        placements.push(place(world, prefab, tile, props, object)?);
+    }
+    Ok(())
+}
+
+fn place(
+    world: &World,
+    prefab: &str,
+    tile: (i32, i32),
+    props: Props,
+    node: Node,
+) -> Result<Placement, String> {
+    rooms::place(world, prefab, tile, props)
+        .ok_or_else(|| format!("no prefab '{}' on line {}", prefab, line_of(node)))
+}
@@ -0,0 +1,81 @@
+// player facing notifications, stacked in the bottom right corner. they slide in,
+// stay a while and fade out, anything past the visible limit waits its turn
+
+use std::collections::VecDeque;
+
+use ecs::{Resource, World};
+use sdl2::pixels::Color;
+
+use crate::{
+    tween::{Ease, Tween},
+    ui::{Anchor, Ui},
+};
+
+const MAX_VISIBLE: usize = 4;
+const TOAST_TICKS: u32 = 180;
+const SLIDE_TICKS: u32 = 20;
+const SLIDE_DISTANCE: f32 = 200.;
+const FADE_TICKS: u32 = 40;
+const LINE_HEIGHT: i32 = 22;
+
+struct Toast {
+    text: String,
+    color: Color,
+    ticks_left: u32,
+    // 0 is in place, 1 is off screen
+    slide: Tween<f32>,
+}
+
+#[derive(Resource)]
+pub struct Toasts {
+    // oldest first
+    queue: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Toasts {
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, text: &str, color: Color) {
+        self.queue.push_back(Toast {
+            text: text.to_string(),
+            color,
+            ticks_left: TOAST_TICKS,
+            slide: Tween::new(1., 0., SLIDE_TICKS, Ease::InOut),
+        });
+    }
+}
+
+pub fn push(world: &World, text: &str) {
+    world
+        .resource_mut::<Toasts>()
+        .unwrap()
+        .push(text, Color::WHITE);
+}
+
+// only the visible ones count down, so waiting toasts get their full time
+pub fn update(toasts: &mut Toasts) {
+    for toast in toasts.queue.iter_mut().take(MAX_VISIBLE) {
+        toast.slide.tick();
+        toast.ticks_left = toast.ticks_left.saturating_sub(1);
+    }
+    toasts.queue.retain(|toast| toast.ticks_left > 0);
+}
+
+pub fn draw(toasts: &Toasts, ui: &mut Ui) {
+    for (i, toast) in toasts.queue.iter().take(MAX_VISIBLE).enumerate() {
+        let slide = (toast.slide.value() * SLIDE_DISTANCE).round() as i32;
+        let alpha = (toast.ticks_left as f32 / FADE_TICKS as f32).min(1.);
+        let alpha = (alpha * 255.).round() as u8;
+        ui.label(
+            Anchor::BottomRight,
+            (-8 + slide, -56 - i as i32 * LINE_HEIGHT),
+            &toast.text,
+            Color::RGBA(toast.color.r, toast.color.g, toast.color.b, alpha),
+            Some(Color::RGBA(0, 0, 0, 255)),
+        );
+    }
+}
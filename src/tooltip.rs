@@ -0,0 +1,68 @@
+// the panel next to the cursor when an item is hovered in the inventory or a container: name
+// in its rarity's color, then what it is and does. it's kept inside the window
+//
+// drawn after everything else on the screen so nothing covers it
+
+use sdl2::pixels::Color;
+
+use crate::{
+    components::Item,
+    locale::{tr, tr_args},
+    ui::{Anchor, Ui},
+    TICKS_PER_SECOND,
+};
+
+const PADDING: i32 = 8;
+const LINE_HEIGHT: i32 = 20;
+// between the cursor and the panel
+const OFFSET: i32 = 16;
+
+pub fn draw(ui: &mut Ui, item: &dyn Item, mouse_pos: (i32, i32)) {
+    let meta = item.meta();
+    let grey = Color::RGB(160, 160, 160);
+    let mut lines = vec![
+        (item.name().replace('_', " "), meta.rarity.color()),
+        (tr(meta.rarity.name()).to_string(), grey),
+        (tr(meta.description).to_string(), Color::WHITE),
+    ];
+    if meta.use_cooldown > 0 {
+        let seconds = meta.use_cooldown as f32 / TICKS_PER_SECOND as f32;
+        let seconds = format!("{:.2}", seconds);
+        lines.push((tr_args("item-cooldown", &[("seconds", &seconds)]), grey));
+    }
+    if meta.stack_size > 1 {
+        let count = meta.stack_size.to_string();
+        lines.push((tr_args("item-stack", &[("count", &count)]), grey));
+    }
+
+    let width = lines
+        .iter()
+        .map(|(text, _)| ui.text_size(text).0 as i32)
+        .max()
+        .unwrap()
+        + PADDING * 2;
+    let height = lines.len() as i32 * LINE_HEIGHT + PADDING * 2;
+    let viewport = ui.viewport();
+    let x = (mouse_pos.0 + OFFSET).min(viewport.width() as i32 - width);
+    let y = (mouse_pos.1 + OFFSET).min(viewport.height() as i32 - height);
+
+    let rect = ui.fill(
+        Anchor::TopLeft,
+        (x, y),
+        (width as u32, height as u32),
+        Color::RGBA(0, 0, 0, 230),
+        |ui| {
+            for (idx, (text, color)) in lines.iter().enumerate() {
+                ui.label(
+                    Anchor::TopLeft,
+                    (PADDING, PADDING + idx as i32 * LINE_HEIGHT),
+                    text,
+                    *color,
+                    None,
+                );
+            }
+        },
+    );
+    ui.canvas().set_draw_color(meta.rarity.color());
+    ui.canvas().draw_rect(rect).unwrap();
+}
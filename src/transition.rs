@@ -0,0 +1,188 @@
+// full screen fades and wipes between game states. a white texture is tinted to the
+// transition's color and drawn over the lit scene, under the ui, with its coverage eased
+// by a tween. covering can run an action once the screen is fully hidden and uncover
+// again, that's how rooms swap without the player seeing the old one go
+//
+// menus dim the screen the same way, on a separate layer so a fade can run behind them
+
+use std::mem::MaybeUninit;
+
+use ecs::{Resource, World};
+use sdl2::{
+    pixels::Color,
+    rect::Rect,
+    render::{BlendMode, Canvas, Texture},
+    video::Window,
+};
+
+use crate::{
+    tween::{Alpha, Ease, Tween},
+    Ctx,
+};
+
+pub const ROOM_TICKS: u32 = 20;
+pub const CUTSCENE_TICKS: u32 = 15;
+const DIM_TICKS: u32 = 8;
+// how dark the screen gets behind a menu
+const DIM_AMOUNT: f32 = 0.5;
+
+#[derive(Clone, Copy)]
+pub enum Style {
+    Fade(Color),
+    // slides in from the left and out to the right
+    Wipe(Color),
+}
+
+type Action = Box<dyn FnOnce(&World)>;
+
+#[derive(Resource)]
+pub struct ScreenTransition {
+    texture: MaybeUninit<Texture>,
+    style: Style,
+    // 0 is clear, 1 fully covered
+    cover: Tween<Alpha>,
+    // runs once covered, then it uncovers over the same ticks
+    on_covered: Option<Action>,
+    ticks: u32,
+    // wipes leave out the other side
+    is_uncovering: bool,
+    dim: Tween<Alpha>,
+    is_dimmed: bool,
+}
+
+impl ScreenTransition {
+    pub fn new(canvas: &mut Canvas<Window>) -> Self {
+        let (w, h) = canvas.output_size().unwrap();
+        let mut texture = canvas
+            .texture_creator()
+            .create_texture_target(canvas.default_pixel_format(), w.max(1), h.max(1))
+            .unwrap();
+        texture.set_blend_mode(BlendMode::Blend);
+        canvas
+            .with_texture_canvas(&mut texture, |canvas| {
+                canvas.set_draw_color(Color::WHITE);
+                canvas.clear();
+            })
+            .unwrap();
+
+        ScreenTransition {
+            texture: MaybeUninit::new(texture),
+            style: Style::Fade(Color::BLACK),
+            cover: Tween::idle(Alpha(0.)),
+            on_covered: None,
+            ticks: 1,
+            is_uncovering: false,
+            dim: Tween::idle(Alpha(0.)),
+            is_dimmed: false,
+        }
+    }
+
+    pub fn is_covered(&self) -> bool {
+        self.cover.value().0 >= 1.
+    }
+
+    fn texture(&self) -> Texture {
+        unsafe { self.texture.assume_init_read() }
+    }
+
+    fn uncover(&mut self, ticks: u32) {
+        self.is_uncovering = true;
+        self.cover.to(Alpha(0.), ticks, Ease::InOut);
+    }
+}
+
+impl Drop for ScreenTransition {
+    fn drop(&mut self) {
+        unsafe { self.texture.assume_init_read().destroy() }
+    }
+}
+
+// covers the screen and stays covered until uncovered
+pub fn cover(world: &World, style: Style, ticks: u32) {
+    let transition = world.resource_mut::<ScreenTransition>().unwrap();
+    transition.style = style;
+    transition.ticks = ticks;
+    transition.is_uncovering = false;
+    transition.cover.to(Alpha(1.), ticks, Ease::InOut);
+}
+
+// covers the screen, runs the action while nothing shows and uncovers again
+pub fn cover_then(world: &World, style: Style, ticks: u32, action: impl FnOnce(&World) + 'static) {
+    cover(world, style, ticks);
+    world.resource_mut::<ScreenTransition>().unwrap().on_covered = Some(Box::new(action));
+}
+
+pub fn uncover(world: &World, ticks: u32) {
+    world
+        .resource_mut::<ScreenTransition>()
+        .unwrap()
+        .uncover(ticks);
+}
+
+// snaps to covered and uncovers, for things that already happened
+pub fn reveal(world: &World, style: Style, ticks: u32) {
+    let transition = world.resource_mut::<ScreenTransition>().unwrap();
+    transition.style = style;
+    transition.cover = Tween::idle(Alpha(1.));
+    transition.uncover(ticks);
+}
+
+pub fn set_dimmed(world: &World, is_dimmed: bool) {
+    let transition = world.resource_mut::<ScreenTransition>().unwrap();
+    if transition.is_dimmed != is_dimmed {
+        transition.is_dimmed = is_dimmed;
+        let amount = if is_dimmed { DIM_AMOUNT } else { 0. };
+        transition.dim.to(Alpha(amount), DIM_TICKS, Ease::InOut);
+    }
+}
+
+// runs while paused too, menus dim and the pause itself can fade
+pub fn update(world: &World) {
+    let transition = world.resource_mut::<ScreenTransition>().unwrap();
+    transition.cover.tick();
+    transition.dim.tick();
+
+    if transition.is_covered() && !transition.is_uncovering {
+        if let Some(action) = transition.on_covered.take() {
+            action(world);
+            transition.uncover(transition.ticks);
+        }
+    }
+}
+
+pub fn draw(world: &World) {
+    let transition = world.resource::<ScreenTransition>().unwrap();
+    let ctx = world.resource_mut::<Ctx>().unwrap();
+    let (w, h) = ctx.render_size();
+    let mut texture = transition.texture();
+
+    let dim = transition.dim.value().0.clamp(0., 1.);
+    if dim > 0. {
+        texture.set_color_mod(0, 0, 0);
+        texture.set_alpha_mod((dim * 255.).round() as u8);
+        ctx.canvas.copy(&texture, None, None).unwrap();
+    }
+
+    let cover = transition.cover.value().0.clamp(0., 1.);
+    if cover <= 0. {
+        return;
+    }
+    let (color, dst) = match transition.style {
+        Style::Fade(color) => {
+            texture.set_alpha_mod((cover * 255.).round() as u8);
+            (color, Rect::new(0, 0, w, h))
+        }
+        Style::Wipe(color) => {
+            texture.set_alpha_mod(255);
+            let covered = (cover * w as f32).round() as u32;
+            let x = if transition.is_uncovering {
+                (w - covered) as i32
+            } else {
+                0
+            };
+            (color, Rect::new(x, 0, covered.max(1), h))
+        }
+    };
+    texture.set_color_mod(color.r, color.g, color.b);
+    ctx.canvas.copy(&texture, None, dst).unwrap();
+}
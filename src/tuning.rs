@@ -0,0 +1,72 @@
+// gameplay numbers from TUNING_PATH, reloaded with F12 so they can be tweaked while the
+// game runs. missing or unknown keys keep their defaults, a value that doesn't parse is
+// reported and skipped
+
+use ecs::Resource;
+
+pub const TUNING_PATH: &str = "data/tuning.toml";
+
+#[derive(Resource)]
+pub struct Tuning {
+    // pixels per tick
+    pub player_speed: f32,
+    pub enemy_speed: f32,
+    pub bullet_speed: f32,
+    // ticks
+    pub bullet_lifetime: u32,
+    pub player_fire_cooldown: u32,
+}
+
+impl Tuning {
+    pub fn load(path: &str) -> Self {
+        let mut tuning = Tuning {
+            player_speed: 3.0,
+            enemy_speed: 1.2,
+            bullet_speed: 4.0,
+            bullet_lifetime: 60,
+            player_fire_cooldown: 20,
+        };
+        tuning.reload(path);
+        tuning
+    }
+
+    // keeps the current values when the file is gone
+    pub fn reload(&mut self, path: &str) {
+        let Ok(data) = std::fs::read_to_string(path) else {
+            println!("no tuning in {}", path);
+            return;
+        };
+        for line in data
+            .lines()
+            .map(|line| line.split('#').next().unwrap().trim())
+            .filter(|line| !line.is_empty())
+        {
+            let Some((key, value)) = line.split_once('=') else {
+                println!("{}: ignoring '{}'", path, line);
+                continue;
+            };
+            if self.set(key.trim(), value.trim()).is_err() {
+                println!("{}: ignoring '{}'", path, line);
+            }
+        }
+    }
+
+    // also used by the console
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "player_speed" => self.player_speed = parse(value)?,
+            "enemy_speed" => self.enemy_speed = parse(value)?,
+            "bullet_speed" => self.bullet_speed = parse(value)?,
+            "bullet_lifetime" => self.bullet_lifetime = parse(value)?,
+            "player_fire_cooldown" => self.player_fire_cooldown = parse(value)?,
+            _ => return Err(format!("unknown value '{}'", name)),
+        }
+        Ok(())
+    }
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("'{}' is not a number", value))
+}
@@ -0,0 +1,166 @@
+// eases a value between two points over a number of ticks, as a component the target
+// property is picked by the value type, e.g. Tween<Alpha> drives the sprite alpha
+
+use ecs::{Component, Entity, World};
+
+use crate::{
+    components::AnimatedSprite,
+    game::light_base_mut,
+    math::{self, Vec2},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Ease {
+    Linear,
+    // starts slow
+    In,
+    // ends slow
+    Out,
+    InOut,
+    // overshoots and wobbles into place
+    Elastic,
+    // lands and bounces
+    Bounce,
+}
+
+impl Ease {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Ease::Linear => t,
+            Ease::In => math::ease_in(t),
+            Ease::Out => math::ease_out(t),
+            Ease::InOut => math::ease_in_out(t),
+            Ease::Elastic => math::ease_out_elastic(t),
+            Ease::Bounce => math::ease_out_bounce(t),
+        }
+    }
+}
+
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Vec2<f32> {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Vec2::new(self.x.lerp(to.x, t), self.y.lerp(to.y, t))
+    }
+}
+
+// added to where the sprite is drawn, Pos itself doesn't move
+#[derive(Clone, Copy)]
+pub struct Offset(pub Vec2<f32>);
+
+// these go on the light's base if it has an animator
+#[derive(Clone, Copy)]
+pub struct LightRadius(pub f32);
+
+#[derive(Clone, Copy)]
+pub struct LightIntensity(pub f32);
+
+// 0 to 1
+#[derive(Clone, Copy)]
+pub struct Alpha(pub f32);
+
+#[derive(Clone, Copy)]
+pub struct Scale(pub Vec2<f32>);
+
+macro_rules! lerp_newtype {
+    ($($name:ident),*) => {
+        $(impl Lerp for $name {
+            fn lerp(self, to: Self, t: f32) -> Self {
+                $name(self.0.lerp(to.0, t))
+            }
+        })*
+    };
+}
+
+lerp_newtype!(Offset, LightRadius, LightIntensity, Alpha, Scale);
+
+#[derive(Clone, Copy)]
+pub struct Tween<T: Lerp> {
+    from: T,
+    to: T,
+    ease: Ease,
+    ticks: u32,
+    duration: u32,
+    // swaps from and to at the end instead of stopping
+    ping_pong: bool,
+}
+
+impl<T: Lerp + 'static> Component for Tween<T> {}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: u32, ease: Ease) -> Self {
+        Tween {
+            from,
+            to,
+            ease,
+            ticks: 0,
+            duration: duration.max(1),
+            ping_pong: false,
+        }
+    }
+
+    // sits at the value until retargeted
+    pub fn idle(value: T) -> Self {
+        let mut tween = Tween::new(value, value, 1, Ease::Linear);
+        tween.ticks = 1;
+        tween
+    }
+
+    pub fn ping_pong(mut self) -> Self {
+        self.ping_pong = true;
+        self
+    }
+
+    // starts over from wherever it is now
+    pub fn to(&mut self, to: T, duration: u32, ease: Ease) {
+        *self = Tween {
+            ping_pong: self.ping_pong,
+            ..Tween::new(self.value(), to, duration, ease)
+        };
+    }
+
+    pub fn tick(&mut self) {
+        if self.ticks < self.duration {
+            self.ticks += 1;
+        } else if self.ping_pong {
+            std::mem::swap(&mut self.from, &mut self.to);
+            self.ticks = 1;
+        }
+    }
+
+    pub fn value(&self) -> T {
+        let t = self.ticks as f32 / self.duration as f32;
+        self.from.lerp(self.to, self.ease.apply(t))
+    }
+}
+
+pub fn update(world: &World) {
+    world.run(|tween: &mut Tween<Offset>| tween.tick());
+
+    world.run(|entity: &Entity, tween: &mut Tween<LightRadius>| {
+        tween.tick();
+        light_base_mut(world, *entity).radius = tween.value().0.max(0.).round() as u16;
+    });
+    world.run(|entity: &Entity, tween: &mut Tween<LightIntensity>| {
+        tween.tick();
+        light_base_mut(world, *entity).intensity = tween.value().0.max(0.);
+    });
+    // floating text reads its alpha tween without a sprite
+    world.run(|tween: &mut Tween<Alpha>| tween.tick());
+    world.run(|tween: &Tween<Alpha>, sprite: &mut AnimatedSprite| {
+        sprite.alpha = (tween.value().0.clamp(0., 1.) * 255.).round() as u8;
+    });
+    world.run(|tween: &mut Tween<Scale>, sprite: &mut AnimatedSprite| {
+        tween.tick();
+        sprite.scale = tween.value().0;
+    });
+}
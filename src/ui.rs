@@ -0,0 +1,409 @@
+// immediate mode ui, the widget tree is rebuilt every frame through nested closures
+
+use std::collections::HashMap;
+
+use ecs::Resource;
+use sdl2::{
+    pixels::Color,
+    rect::Rect,
+    render::{BlendMode, Canvas, Texture, TextureQuery},
+    ttf::Font,
+    video::Window,
+};
+
+use crate::{DrawOptions, Sprite, Spritesheet};
+
+// enough for every label on screen, changing numbers are drawn from the glyphs instead
+const TEXT_CACHE_CAPACITY: usize = 256;
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // places a rect of the given size inside parent, offset is applied after anchoring
+    pub fn resolve(&self, parent: Rect, size: (u32, u32), offset: (i32, i32)) -> Rect {
+        use Anchor::*;
+
+        let x = match self {
+            TopLeft | Left | BottomLeft => parent.x(),
+            Top | Center | Bottom => parent.x() + (parent.width() as i32 - size.0 as i32) / 2,
+            TopRight | Right | BottomRight => parent.right() - size.0 as i32,
+        };
+        let y = match self {
+            TopLeft | Top | TopRight => parent.y(),
+            Left | Center | Right => parent.y() + (parent.height() as i32 - size.1 as i32) / 2,
+            BottomLeft | Bottom | BottomRight => parent.bottom() - size.1 as i32,
+        };
+
+        Rect::new(x + offset.0, y + offset.1, size.0, size.1)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct TextStyle<'a> {
+    pub font: &'a Font<'a, 'a>,
+    pub color: Color,
+    pub background: Option<Color>,
+}
+
+impl<'a> TextStyle<'a> {
+    pub fn new(font: &'a Font<'a, 'a>, color: Color) -> Self {
+        TextStyle {
+            font,
+            color,
+            background: None,
+        }
+    }
+
+    pub fn with_background(mut self, background: Option<Color>) -> Self {
+        self.background = background;
+        self
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct TextKey {
+    text: String,
+    color: Color,
+    background: Option<Color>,
+    // the font's line height, stands in for its size
+    size: i32,
+}
+
+struct CachedText {
+    texture: Texture,
+    last_used: u64,
+}
+
+// rendered text textures, the least recently used one goes once it's full. text that
+// changes every frame, numbers mostly, is drawn a character at a time from white glyphs
+// tinted when drawn instead, so it doesn't render a texture every frame
+#[derive(Resource)]
+pub struct TextCache {
+    entries: HashMap<TextKey, CachedText>,
+    uses: u64,
+    // by the font's line height, like TextKey
+    glyphs: HashMap<(char, i32), Texture>,
+}
+
+impl TextCache {
+    pub fn new() -> Self {
+        TextCache {
+            entries: HashMap::new(),
+            uses: 0,
+            glyphs: HashMap::new(),
+        }
+    }
+
+    fn glyph(&mut self, canvas: &Canvas<Window>, font: &Font, c: char) -> &mut Texture {
+        self.glyphs.entry((c, font.height())).or_insert_with(|| {
+            let surface = font.render_char(c).blended(Color::WHITE).unwrap();
+            canvas
+                .texture_creator()
+                .create_texture_from_surface(&surface)
+                .unwrap()
+        })
+    }
+
+    // what draw_glyphs takes up
+    pub fn glyphs_size(&mut self, canvas: &Canvas<Window>, font: &Font, text: &str) -> (u32, u32) {
+        let mut size = (0, 0);
+        for c in text.chars() {
+            let TextureQuery { width, height, .. } = self.glyph(canvas, font, c).query();
+            size = (size.0 + width, size.1.max(height));
+        }
+        size
+    }
+
+    // rendered opaque, the color's alpha is applied when drawing
+    fn get(&mut self, canvas: &Canvas<Window>, text: &str, style: &TextStyle) -> &mut Texture {
+        self.uses += 1;
+        let color = Color::RGB(style.color.r, style.color.g, style.color.b);
+        let key = TextKey {
+            text: text.to_string(),
+            color,
+            background: style.background,
+            size: style.font.height(),
+        };
+
+        if !self.entries.contains_key(&key) {
+            if self.entries.len() >= TEXT_CACHE_CAPACITY {
+                self.evict();
+            }
+            let surface = match style.background {
+                Some(background) => style.font.render(text).shaded(color, background),
+                None => style.font.render(text).blended(color),
+            }
+            .unwrap();
+            let mut texture = canvas
+                .texture_creator()
+                .create_texture_from_surface(&surface)
+                .unwrap();
+            // shaded text has no alpha channel and wouldn't fade otherwise
+            texture.set_blend_mode(BlendMode::Blend);
+            return &mut self
+                .entries
+                .entry(key)
+                .or_insert(CachedText {
+                    texture,
+                    last_used: self.uses,
+                })
+                .texture;
+        }
+
+        let entry = self.entries.get_mut(&key).unwrap();
+        entry.last_used = self.uses;
+        &mut entry.texture
+    }
+
+    fn evict(&mut self) {
+        let Some(oldest) = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone())
+        else {
+            return;
+        };
+        let entry = self.entries.remove(&oldest).unwrap();
+        unsafe { entry.texture.destroy() };
+    }
+}
+
+// top left at pos, returns where it was drawn. the color's alpha fades the whole
+// text, background included
+pub fn draw_text(
+    canvas: &mut Canvas<Window>,
+    cache: &mut TextCache,
+    text: &str,
+    pos: (i32, i32),
+    style: &TextStyle,
+) -> Rect {
+    if text.is_empty() {
+        return Rect::new(pos.0, pos.1, 0, 0);
+    }
+    let texture = cache.get(canvas, text, style);
+    texture.set_alpha_mod(style.color.a);
+    let TextureQuery { width, height, .. } = texture.query();
+    let rect = Rect::new(pos.0, pos.1, width, height);
+    canvas.copy(texture, None, rect).unwrap();
+    rect
+}
+
+// like draw_text, for text that changes every frame
+pub fn draw_glyphs(
+    canvas: &mut Canvas<Window>,
+    cache: &mut TextCache,
+    text: &str,
+    pos: (i32, i32),
+    style: &TextStyle,
+) -> Rect {
+    let (width, height) = cache.glyphs_size(canvas, style.font, text);
+    let rect = Rect::new(pos.0, pos.1, width, height);
+    if let Some(background) = style.background.filter(|_| width > 0) {
+        let blend_mode = canvas.blend_mode();
+        canvas.set_blend_mode(BlendMode::Blend);
+        canvas.set_draw_color(Color::RGBA(
+            background.r,
+            background.g,
+            background.b,
+            style.color.a,
+        ));
+        canvas.fill_rect(rect).unwrap();
+        canvas.set_blend_mode(blend_mode);
+    }
+
+    let mut x = pos.0;
+    for c in text.chars() {
+        let glyph = cache.glyph(canvas, style.font, c);
+        glyph.set_color_mod(style.color.r, style.color.g, style.color.b);
+        glyph.set_alpha_mod(style.color.a);
+        let TextureQuery { width, height, .. } = glyph.query();
+        canvas
+            .copy(glyph, None, Rect::new(x, pos.1, width, height))
+            .unwrap();
+        x += width as i32;
+    }
+    rect
+}
+
+pub struct Ui<'a> {
+    canvas: &'a mut Canvas<Window>,
+    spritesheet: &'a Spritesheet,
+    font: &'a Font<'a, 'a>,
+    text_cache: &'a mut TextCache,
+    // innermost container last, widgets are laid out relative to it
+    parents: Vec<Rect>,
+}
+
+impl<'a> Ui<'a> {
+    pub fn new(
+        canvas: &'a mut Canvas<Window>,
+        spritesheet: &'a Spritesheet,
+        font: &'a Font<'a, 'a>,
+        text_cache: &'a mut TextCache,
+    ) -> Self {
+        let root = canvas.viewport();
+        Ui {
+            canvas,
+            spritesheet,
+            font,
+            text_cache,
+            parents: vec![root],
+        }
+    }
+
+    fn parent(&self) -> Rect {
+        *self.parents.last().unwrap()
+    }
+
+    // rect of the innermost container
+    pub fn viewport(&self) -> Rect {
+        self.parent()
+    }
+
+    fn with_parent(&mut self, rect: Rect, build: impl FnOnce(&mut Ui<'a>)) {
+        self.parents.push(rect);
+        build(self);
+        self.parents.pop();
+    }
+
+    // invisible container
+    #[allow(dead_code)]
+    pub fn group(
+        &mut self,
+        anchor: Anchor,
+        offset: (i32, i32),
+        size: (u32, u32),
+        build: impl FnOnce(&mut Ui<'a>),
+    ) -> Rect {
+        let rect = anchor.resolve(self.parent(), size, offset);
+        self.with_parent(rect, build);
+        rect
+    }
+
+    // solid color container, alpha blended
+    pub fn fill(
+        &mut self,
+        anchor: Anchor,
+        offset: (i32, i32),
+        size: (u32, u32),
+        color: Color,
+        build: impl FnOnce(&mut Ui<'a>),
+    ) -> Rect {
+        let rect = anchor.resolve(self.parent(), size, offset);
+        let blend_mode = self.canvas.blend_mode();
+        self.canvas.set_blend_mode(BlendMode::Blend);
+        self.canvas.set_draw_color(color);
+        self.canvas.fill_rect(rect).unwrap();
+        self.canvas.set_blend_mode(blend_mode);
+        self.with_parent(rect, build);
+        rect
+    }
+
+    // nine-slice panel, border is in spritesheet pixels
+    pub fn panel(
+        &mut self,
+        anchor: Anchor,
+        offset: (i32, i32),
+        size: (u32, u32),
+        sprite: Sprite,
+        border: u32,
+        build: impl FnOnce(&mut Ui<'a>),
+    ) -> Rect {
+        let rect = anchor.resolve(self.parent(), size, offset);
+        self.spritesheet
+            .draw_nine_slice(self.canvas, sprite, border, rect, Color::WHITE);
+        self.with_parent(rect, build);
+        rect
+    }
+
+    pub fn icon(
+        &mut self,
+        anchor: Anchor,
+        offset: (i32, i32),
+        sprite: Sprite,
+        options: DrawOptions,
+    ) -> Rect {
+        let size = self.spritesheet.sprite_size(sprite);
+        let rect = anchor.resolve(self.parent(), size, offset);
+        self.spritesheet
+            .draw_to_canvas(self.canvas, sprite, rect, options);
+        rect
+    }
+
+    pub fn label(
+        &mut self,
+        anchor: Anchor,
+        offset: (i32, i32),
+        text: &str,
+        color: Color,
+        background: Option<Color>,
+    ) -> Rect {
+        if text.is_empty() {
+            return anchor.resolve(self.parent(), (0, 0), offset);
+        }
+
+        let style = TextStyle::new(self.font, color).with_background(background);
+        let TextureQuery { width, height, .. } =
+            self.text_cache.get(self.canvas, text, &style).query();
+
+        let rect = anchor.resolve(self.parent(), (width, height), offset);
+        draw_text(
+            self.canvas,
+            self.text_cache,
+            text,
+            (rect.x(), rect.y()),
+            &style,
+        )
+    }
+
+    // a label for text that changes every frame, like counters and timings
+    pub fn changing_label(
+        &mut self,
+        anchor: Anchor,
+        offset: (i32, i32),
+        text: &str,
+        color: Color,
+        background: Option<Color>,
+    ) -> Rect {
+        let size = self.text_cache.glyphs_size(self.canvas, self.font, text);
+        let rect = anchor.resolve(self.parent(), size, offset);
+        let style = TextStyle::new(self.font, color).with_background(background);
+        draw_glyphs(
+            self.canvas,
+            self.text_cache,
+            text,
+            (rect.x(), rect.y()),
+            &style,
+        )
+    }
+
+    // what label would take up, for sizing a container around text
+    pub fn text_size(&self, text: &str) -> (u32, u32) {
+        if text.is_empty() {
+            return (0, 0);
+        }
+        self.font.size_of(text).unwrap()
+    }
+
+    // for drawing that doesn't fit the widgets, in the root's coordinates
+    pub fn canvas(&mut self) -> &mut Canvas<Window> {
+        self.canvas
+    }
+
+    pub fn spritesheet(&self) -> &'a Spritesheet {
+        self.spritesheet
+    }
+}
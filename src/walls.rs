@@ -0,0 +1,131 @@
+// wall tiles don't collide on their own, every run of them is covered by one big collider
+// instead. adjacent tiles are merged greedily into rectangles, rows first and then as many
+// rows down as are just as wide, so there are no seams to catch on and far fewer colliders
+// for the broadphase. whenever the walls change, a room loading or the editor placing or
+// removing one, the merged colliders are thrown away and built again
+//
+// walls off the tile grid keep a collider of their own
+
+use ecs::{Entity, Resource, World};
+
+use crate::{
+    components::{Collider, ColliderGroup, Pos, Static, Wall, WallCollider},
+    game::{tile_to_pos, TILE_SIZE},
+    layers::CollisionLayers,
+};
+
+// a wall tile's collider, relative to its center. the top couple of pixels are left open so
+// things can stand right up against a wall's face
+const TILE_COLLIDER: (i32, i32, u32, u32) = (-16, -14, 32, 30);
+
+#[derive(Resource)]
+pub struct WallColliders {
+    // the walls the colliders were built for, sorted
+    walls: Vec<(i32, i32)>,
+}
+
+impl WallColliders {
+    pub fn new() -> Self {
+        WallColliders { walls: Vec::new() }
+    }
+}
+
+pub fn update(world: &World) {
+    let merged = world.resource_mut::<WallColliders>().unwrap();
+    let mut walls = Vec::new();
+    world.run(|_: &Wall, pos: &Pos| {
+        walls.push((pos.x.round() as i32, pos.y.round() as i32));
+    });
+    walls.sort();
+    if walls == merged.walls {
+        return;
+    }
+
+    let mut old = Vec::new();
+    world.run(|entity: &Entity, _: &WallCollider| old.push(*entity));
+    for entity in old {
+        world.despawn(entity);
+    }
+
+    let layers = world.resource::<CollisionLayers>().unwrap();
+    for (x, y, w, h) in merge(&walls) {
+        let (dx, dy, tile_w, tile_h) = TILE_COLLIDER;
+        let size = (
+            tile_w + (w - 1) * TILE_SIZE as u32,
+            tile_h + (h - 1) * TILE_SIZE as u32,
+        );
+        world.spawn(&[
+            &WallCollider {},
+            &Static {},
+            &tile_to_pos(x, y),
+            &ColliderGroup {
+                nav: Some(Collider::on_layer(layers, "wall", (dx, dy, size.0, size.1))),
+                hitbox: None,
+            },
+        ]);
+    }
+    for (x, y) in walls.iter().filter(|wall| tile_at(**wall).is_none()) {
+        world.spawn(&[
+            &WallCollider {},
+            &Static {},
+            &Pos::new(*x as f32, *y as f32),
+            &ColliderGroup {
+                nav: Some(Collider::on_layer(layers, "wall", TILE_COLLIDER)),
+                hitbox: None,
+            },
+        ]);
+    }
+
+    merged.walls = walls;
+}
+
+// the tile a wall sits in the middle of
+fn tile_at((x, y): (i32, i32)) -> Option<(i32, i32)> {
+    let tile = (
+        x.div_euclid(TILE_SIZE as i32),
+        y.div_euclid(TILE_SIZE as i32),
+    );
+    let center = tile_to_pos(tile.0, tile.1);
+    (center.x as i32 == x && center.y as i32 == y).then_some(tile)
+}
+
+// (x, y, w, h) in tiles, covering every wall on the grid exactly once
+fn merge(walls: &[(i32, i32)]) -> Vec<(i32, i32, u32, u32)> {
+    // row by row, so runs are found left to right
+    let mut tiles: Vec<(i32, i32)> = walls
+        .iter()
+        .filter_map(|wall| tile_at(*wall))
+        .map(|(x, y)| (y, x))
+        .collect();
+    tiles.sort();
+    tiles.dedup();
+    let is_wall = |x: i32, y: i32| tiles.binary_search(&(y, x)).is_ok();
+    let mut is_taken = vec![false; tiles.len()];
+    let taken = |x: i32, y: i32, is_taken: &[bool]| {
+        tiles.binary_search(&(y, x)).is_ok_and(|idx| is_taken[idx])
+    };
+
+    let mut rects = Vec::new();
+    for idx in 0..tiles.len() {
+        if is_taken[idx] {
+            continue;
+        }
+        let (y, x) = tiles[idx];
+        let mut w = 1;
+        while is_wall(x + w, y) && !taken(x + w, y, &is_taken) {
+            w += 1;
+        }
+        let mut h = 1;
+        while (x..x + w).all(|x| is_wall(x, y + h) && !taken(x, y + h, &is_taken)) {
+            h += 1;
+        }
+        for ty in y..y + h {
+            for tx in x..x + w {
+                let idx = tiles.binary_search(&(ty, tx)).unwrap();
+                is_taken[idx] = true;
+            }
+        }
+        rects.push((x, y, w as u32, h as u32));
+    }
+    rects
+}